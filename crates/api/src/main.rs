@@ -15,13 +15,14 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tower_http::cors::{Any, CorsLayer};
-use tracing::{info, Level};
+use tracing::{info, warn, Level};
 use tracing_subscriber::FmtSubscriber;
 
 use solana_arb_core::{
     arbitrage::ArbitrageDetector,
     config::Config,
     dex::{jupiter::JupiterProvider, orca::OrcaProvider, raydium::RaydiumProvider, DexProvider},
+    http::pool::{HostRateLimiter, HttpTimeouts},
     ArbitrageConfig, DexType, PriceData, TokenPair,
 };
 use tokio::sync::broadcast;
@@ -121,19 +122,28 @@ async fn main() -> anyhow::Result<()> {
     info!("Starting Solana Arbitrage API Server");
 
     // Load configuration
-    let config = Config::from_env().unwrap_or_default();
-
-    // Initialize DEX providers
-    let providers: Vec<Box<dyn DexProvider>> = vec![
-        Box::new(JupiterProvider::new()),
-        Box::new(RaydiumProvider::new()),
-        Box::new(OrcaProvider::new()),
+    let config = Config::from_env()?;
+
+    // Initialize DEX providers, sharing one per-host rate limiter
+    let timeouts = HttpTimeouts::from_config(&config);
+    let rate_limiter = Arc::new(HostRateLimiter::from_config(&config));
+    let mut providers: Vec<Box<dyn DexProvider>> = vec![
+        Box::new(JupiterProvider::with_http(timeouts, rate_limiter.clone())),
+        Box::new(RaydiumProvider::with_http(timeouts, rate_limiter.clone())),
+        Box::new(OrcaProvider::with_http(timeouts, rate_limiter)),
     ];
+    providers.retain(|provider| config.is_dex_enabled(provider.dex_type()));
 
     // Initialize detector
     let arb_config = ArbitrageConfig {
         min_profit_threshold: rust_decimal::Decimal::try_from(config.min_profit_threshold)
             .unwrap_or_default(),
+        jupiter_price_source: config.jupiter_price_source,
+        max_price_deviation_pct: rust_decimal::Decimal::try_from(config.max_price_deviation_pct)
+            .unwrap_or(rust_decimal::Decimal::TEN),
+        min_liquidity_usd: rust_decimal::Decimal::try_from(config.min_liquidity_usd)
+            .unwrap_or_default(),
+        allow_unknown_liquidity: config.allow_unknown_liquidity,
         ..Default::default()
     };
     let detector = RwLock::new(ArbitrageDetector::new(arb_config));
@@ -165,7 +175,10 @@ async fn main() -> anyhow::Result<()> {
     // Spawn background price collector
     let collector_state = state.clone();
     tokio::spawn(async move {
-        let pairs = default_pairs();
+        let pairs: Vec<TokenPair> = default_pairs()
+            .into_iter()
+            .filter(|pair| collector_state.config.is_pair_enabled(pair))
+            .collect();
         let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(500));
 
         loop {
@@ -182,61 +195,65 @@ async fn main() -> anyhow::Result<()> {
             for provider in &collector_state.providers {
                 let dex_name = format!("{:?}", provider.dex_type());
 
-                match provider.get_prices(&pairs).await {
-                    Ok(prices) => {
-                        let mut detector = collector_state.detector.write().await;
-                        detector.update_prices(prices.clone());
-                        detector.clear_stale_prices(collector_state.max_price_age_seconds);
-
-                        // Update DEX health - success
-                        let mut health = collector_state.dex_health.write().await;
-                        health.insert(
-                            dex_name.clone(),
-                            DexHealthStatus {
-                                name: dex_name,
-                                last_success_at: Some(Utc::now()),
-                                consecutive_errors: 0,
-                                status: "green".to_string(),
-                            },
-                        );
-
-                        // Broadcast price updates
-                        let _ = collector_state
-                            .tx
-                            .send(WebSocketMessage::PriceUpdate(prices));
-
-                        // Check for new opportunities
-                        let new_opps = collector_state
-                            .detector
-                            .read()
-                            .await
-                            .find_all_opportunities();
-                        if !new_opps.is_empty() {
-                            info!("🔔 Found {} opportunities", new_opps.len());
-                            for opp in new_opps {
-                                let _ = collector_state
-                                    .tx
-                                    .send(WebSocketMessage::NewOpportunity(opp));
-                            }
-                        }
-                    }
-                    Err(_e) => {
-                        // Update DEX health - error
-                        let mut health = collector_state.dex_health.write().await;
-                        let entry = health.entry(dex_name.clone()).or_insert(DexHealthStatus {
-                            name: dex_name.clone(),
-                            last_success_at: None,
+                let batch = provider.get_prices(&pairs).await;
+                for (pair, e) in &batch.errors {
+                    warn!("{} price fetch failed for {}: {}", dex_name, pair, e);
+                }
+
+                if !batch.is_total_failure() {
+                    let prices = batch.prices;
+                    let mut detector = collector_state.detector.write().await;
+                    detector.update_prices(prices.clone());
+                    detector.clear_stale_prices(collector_state.max_price_age_seconds);
+                    detector.sanitize_prices();
+
+                    // Update DEX health - success
+                    let mut health = collector_state.dex_health.write().await;
+                    health.insert(
+                        dex_name.clone(),
+                        DexHealthStatus {
+                            name: dex_name,
+                            last_success_at: Some(Utc::now()),
                             consecutive_errors: 0,
-                            status: "red".to_string(),
-                        });
-                        entry.consecutive_errors += 1;
-                        entry.status = if entry.consecutive_errors >= 5 {
-                            "red"
-                        } else {
-                            "yellow"
+                            status: "green".to_string(),
+                        },
+                    );
+
+                    // Broadcast price updates
+                    let _ = collector_state
+                        .tx
+                        .send(WebSocketMessage::PriceUpdate(prices));
+
+                    // Check for new opportunities
+                    let new_opps = collector_state
+                        .detector
+                        .read()
+                        .await
+                        .find_all_opportunities();
+                    if !new_opps.is_empty() {
+                        info!("🔔 Found {} opportunities", new_opps.len());
+                        for opp in new_opps {
+                            let _ = collector_state
+                                .tx
+                                .send(WebSocketMessage::NewOpportunity(opp));
                         }
-                        .to_string();
                     }
+                } else {
+                    // Update DEX health - error
+                    let mut health = collector_state.dex_health.write().await;
+                    let entry = health.entry(dex_name.clone()).or_insert(DexHealthStatus {
+                        name: dex_name.clone(),
+                        last_success_at: None,
+                        consecutive_errors: 0,
+                        status: "red".to_string(),
+                    });
+                    entry.consecutive_errors += 1;
+                    entry.status = if entry.consecutive_errors >= 5 {
+                        "red"
+                    } else {
+                        "yellow"
+                    }
+                    .to_string();
                 }
             }
 
@@ -298,9 +315,13 @@ async fn validate_dex_coverage(state: &Arc<AppState>) {
     }
 
     for pair in default_pairs() {
+        if !state.config.is_pair_enabled(&pair) {
+            continue;
+        }
         let seen = coverage.get(&pair.symbol());
         let missing: Vec<_> = DexType::all()
             .iter()
+            .filter(|dex| state.config.is_dex_enabled(**dex))
             .filter(|dex| seen.is_none_or(|set| !set.contains(dex)))
             .collect();
 
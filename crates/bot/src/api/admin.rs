@@ -0,0 +1,154 @@
+//! Token-protected admin API for operator intervention
+//!
+//! Lets an operator pause and resume trading and inspect risk state without
+//! restarting the process. Every route requires `Authorization: Bearer <token>`.
+
+use crate::BotState;
+use axum::{
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::warn;
+
+#[derive(Clone)]
+struct AdminContext {
+    state: Arc<RwLock<BotState>>,
+    token: Arc<String>,
+}
+
+pub(crate) fn admin_routes(state: Arc<RwLock<BotState>>, token: String) -> Router {
+    Router::new()
+        .route("/admin/pause", post(pause_handler))
+        .route("/admin/resume", post(resume_handler))
+        .route("/admin/status", get(status_handler))
+        .layer(Extension(AdminContext {
+            state,
+            token: Arc::new(token),
+        }))
+}
+
+/// Check the bearer token without short-circuiting on the first mismatched byte
+fn is_authorized(headers: &HeaderMap, token: &str) -> bool {
+    let Some(provided) = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+
+    provided.len() == token.len()
+        && provided
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+async fn pause_handler(
+    Extension(ctx): Extension<AdminContext>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    set_paused(ctx, headers, true).await
+}
+
+async fn resume_handler(
+    Extension(ctx): Extension<AdminContext>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    set_paused(ctx, headers, false).await
+}
+
+async fn set_paused(
+    ctx: AdminContext,
+    headers: HeaderMap,
+    paused: bool,
+) -> (StatusCode, Json<serde_json::Value>) {
+    if !is_authorized(&headers, &ctx.token) {
+        return unauthorized();
+    }
+
+    ctx.state.write().await.trading_paused = paused;
+    if paused {
+        warn!("⏸️ Trading paused via admin API");
+    } else {
+        warn!("▶️ Trading resumed via admin API");
+    }
+
+    (StatusCode::OK, Json(json!({ "trading_paused": paused })))
+}
+
+async fn status_handler(
+    Extension(ctx): Extension<AdminContext>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if !is_authorized(&headers, &ctx.token) {
+        return unauthorized();
+    }
+
+    let state = ctx.state.read().await;
+    let status = state.risk_manager.status().await;
+    let circuit_state = state.risk_manager.circuit_breaker.state().await;
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "trading_paused": state.trading_paused,
+            "dry_run": state.dry_run,
+            "paper_portfolio": state.paper_portfolio,
+            "circuit_breaker": format!("{:?}", circuit_state),
+            "disabled_providers": state
+                .dex_manager
+                .disabled_providers()
+                .iter()
+                .map(|dex| dex.to_string())
+                .collect::<Vec<_>>(),
+            "risk": {
+                "total_exposure": status.total_exposure.to_string(),
+                "daily_pnl": status.daily_pnl.to_string(),
+                "portfolio_var": status.portfolio_var.to_string(),
+                "var_confidence_level": status.var_confidence_level,
+                "var_unestimated_pairs": status.var_unestimated_pairs,
+                "trades_today": status.trades_today,
+                "is_paused": status.is_paused,
+                "positions": status
+                    .positions
+                    .iter()
+                    .map(|(pair, size)| (pair.clone(), size.to_string()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+            },
+        })),
+    )
+}
+
+fn unauthorized() -> (StatusCode, Json<serde_json::Value>) {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(json!({ "error": "missing or invalid bearer token" })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers_with(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_bearer_token_check() {
+        assert!(is_authorized(&headers_with("Bearer s3cret"), "s3cret"));
+        assert!(!is_authorized(&headers_with("Bearer s3cre"), "s3cret"));
+        assert!(!is_authorized(&headers_with("Bearer s3creT"), "s3cret"));
+        assert!(!is_authorized(&headers_with("s3cret"), "s3cret"));
+        assert!(!is_authorized(&HeaderMap::new(), "s3cret"));
+    }
+}
@@ -0,0 +1,171 @@
+//! Read-only JSON endpoints for dashboards
+//!
+//! Served next to `/metrics`, so a dashboard can poll the opportunities the
+//! detector is currently tracking and the risk manager's status without
+//! scraping logs. Nothing here changes bot state.
+
+use crate::paper_portfolio::PaperPortfolio;
+use crate::BotState;
+use axum::{extract::Query, routing::get, Extension, Json, Router};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use solana_arb_core::{risk::RiskStatus, ArbitrageOpportunity};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Opportunities returned when no `limit` is given
+const DEFAULT_LIMIT: usize = 50;
+/// Most opportunities returned, whatever `limit` asks for
+const MAX_LIMIT: usize = 200;
+
+pub(crate) fn dashboard_routes(state: Arc<RwLock<BotState>>) -> Router {
+    Router::new()
+        .route("/opportunities", get(opportunities_handler))
+        .route("/status", get(status_handler))
+        .layer(Extension(state))
+}
+
+/// Filters for `/opportunities`
+#[derive(Debug, Deserialize)]
+struct OpportunityQuery {
+    /// Only this pair, e.g. `SOL/USDC` (case-insensitive)
+    pair: Option<String>,
+    /// Minimum net profit in percent
+    min_profit: Option<Decimal>,
+    /// Maximum number of results, capped at [`MAX_LIMIT`]
+    limit: Option<usize>,
+}
+
+/// Active opportunities matching `query`, most profitable first
+async fn opportunities_handler(
+    Extension(state): Extension<Arc<RwLock<BotState>>>,
+    Query(query): Query<OpportunityQuery>,
+) -> Json<Vec<ArbitrageOpportunity>> {
+    let state = state.read().await;
+    let mut opportunities: Vec<ArbitrageOpportunity> = state
+        .detector
+        .active_opportunities()
+        .filter(|opp| {
+            query
+                .pair
+                .as_ref()
+                .is_none_or(|pair| opp.pair.symbol().eq_ignore_ascii_case(pair))
+        })
+        .filter(|opp| query.min_profit.is_none_or(|min| opp.net_profit_pct >= min))
+        .cloned()
+        .collect();
+
+    opportunities.sort_by_key(|opp| std::cmp::Reverse(opp.net_profit_pct));
+    opportunities.truncate(query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT));
+    Json(opportunities)
+}
+
+/// Body of `/status`
+#[derive(Debug, Serialize)]
+struct StatusResponse {
+    #[serde(flatten)]
+    risk: RiskStatus,
+    /// Simulated balances, in dry runs only
+    paper_portfolio: Option<PaperPortfolio>,
+}
+
+async fn status_handler(
+    Extension(state): Extension<Arc<RwLock<BotState>>>,
+) -> Json<StatusResponse> {
+    let state = state.read().await;
+    Json(StatusResponse {
+        risk: state.risk_manager.status().await,
+        paper_portfolio: state.paper_portfolio.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::AlertManager;
+    use crate::config_manager::ConfigManager;
+    use crate::metrics::prometheus::MetricsCollector;
+    use crate::SystemHealth;
+    use chrono::Utc;
+    use solana_arb_core::{config::Config, DexType, TokenPair, Uuid};
+
+    fn opportunity(pair: TokenPair, net_profit_pct: Decimal) -> ArbitrageOpportunity {
+        ArbitrageOpportunity {
+            id: Uuid::new_v4(),
+            pair,
+            buy_dex: DexType::Raydium,
+            sell_dex: DexType::Orca,
+            buy_price: Decimal::ONE_HUNDRED,
+            sell_price: Decimal::from(101),
+            gross_profit_pct: net_profit_pct,
+            net_profit_pct,
+            estimated_profit_usd: None,
+            recommended_size: None,
+            detected_at: Utc::now(),
+            expired_at: None,
+            path: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_opportunities_route_filters_and_caps() {
+        let config_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../config/trading_config.json"
+        );
+        let state = BotState::new(
+            &Config::default(),
+            true,
+            Arc::new(MetricsCollector::new().unwrap()),
+            AlertManager::new(None, None),
+            Arc::new(RwLock::new(SystemHealth::default())),
+            Arc::new(ConfigManager::new(config_path).unwrap()),
+        );
+        let state = Arc::new(RwLock::new(state));
+        state.write().await.detector.track(&[
+            opportunity(TokenPair::new("SOL", "USDC"), Decimal::new(5, 1)),
+            opportunity(TokenPair::new("SOL", "USDC"), Decimal::new(12, 1)),
+            opportunity(TokenPair::new("RAY", "USDC"), Decimal::new(9, 1)),
+        ]);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, dashboard_routes(state)).await });
+
+        let get = |path: &str| {
+            let url = format!("{}{}", base, path);
+            async move {
+                reqwest::get(url)
+                    .await
+                    .unwrap()
+                    .json::<serde_json::Value>()
+                    .await
+                    .unwrap()
+            }
+        };
+        let profits = |body: serde_json::Value| -> Vec<String> {
+            body.as_array()
+                .unwrap()
+                .iter()
+                .map(|opp| opp["net_profit_pct"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        assert_eq!(profits(get("/opportunities").await), ["1.2", "0.9", "0.5"]);
+        assert_eq!(
+            profits(get("/opportunities?pair=sol/usdc").await),
+            ["1.2", "0.5"]
+        );
+        assert_eq!(
+            profits(get("/opportunities?min_profit=0.8").await),
+            ["1.2", "0.9"]
+        );
+        assert_eq!(profits(get("/opportunities?limit=1").await), ["1.2"]);
+
+        let status = get("/status").await;
+        assert_eq!(status["trades_today"], 0);
+        assert_eq!(status["is_paused"], false);
+        assert_eq!(status["paper_portfolio"]["balances"]["USDC"], "10000");
+        assert_eq!(status["paper_portfolio"]["trades"], 0);
+    }
+}
@@ -29,3 +29,94 @@ async fn metrics_handler(
         buffer,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+    use solana_arb_core::pricing::parallel_fetcher::{DexFetchStats, FetchReport};
+    use solana_arb_core::{ArbitrageOpportunity, DexType, TokenPair, Uuid};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_per_dex_fetch_metrics_are_scraped() {
+        let metrics = Arc::new(MetricsCollector::new().unwrap());
+        let mut report = FetchReport::default();
+        report.per_dex.insert(
+            DexType::Orca,
+            DexFetchStats {
+                ok: 3,
+                error: 1,
+                latency: Duration::from_millis(40),
+                ..Default::default()
+            },
+        );
+        report.per_dex.insert(
+            DexType::Raydium,
+            DexFetchStats {
+                timeout: 4,
+                latency: Duration::from_millis(1_500),
+                ..Default::default()
+            },
+        );
+        metrics.record_fetch_report(&report);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/metrics", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, metrics_routes(metrics)).await });
+        let body = reqwest::get(url).await.unwrap().text().await.unwrap();
+
+        for line in [
+            r#"arb_dex_prices_returned{dex="Orca"} 3"#,
+            r#"arb_dex_prices_returned{dex="Raydium"} 0"#,
+            r#"arb_dex_fetch_errors_total{dex="Orca"} 1"#,
+            r#"arb_dex_fetch_errors_total{dex="Raydium"} 4"#,
+            r#"arb_dex_fetch_latency_seconds_bucket{dex="Orca",le="0.05"} 1"#,
+            r#"arb_dex_fetch_latency_seconds_bucket{dex="Raydium",le="1"} 0"#,
+            r#"arb_dex_fetch_latency_seconds_count{dex="Raydium"} 1"#,
+        ] {
+            assert!(body.contains(line), "missing {}", line);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_opportunity_gauges_are_scraped() {
+        let metrics = Arc::new(MetricsCollector::new().unwrap());
+        let opportunity = |net_profit_pct: Decimal| ArbitrageOpportunity {
+            id: Uuid::new_v4(),
+            pair: TokenPair::new("SOL", "USDC"),
+            buy_dex: DexType::Raydium,
+            sell_dex: DexType::Orca,
+            buy_price: Decimal::ONE_HUNDRED,
+            sell_price: Decimal::from(101),
+            gross_profit_pct: net_profit_pct,
+            net_profit_pct,
+            estimated_profit_usd: None,
+            recommended_size: None,
+            detected_at: Utc::now(),
+            expired_at: None,
+            path: None,
+        };
+        metrics.record_tick_opportunities(&[
+            opportunity(Decimal::new(4, 1)),
+            opportunity(Decimal::new(125, 2)),
+        ]);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/metrics", listener.local_addr().unwrap());
+        let collector = metrics.clone();
+        tokio::spawn(async move { axum::serve(listener, metrics_routes(collector)).await });
+        let scrape = || async { reqwest::get(&url).await.unwrap().text().await.unwrap() };
+
+        let body = scrape().await;
+        assert!(body.contains("arb_opportunities_active 2"));
+        assert!(body.contains("arb_best_opportunity_profit_pct 1.25"));
+
+        // A tick with nothing found resets both
+        metrics.record_tick_opportunities(&[]);
+        let body = scrape().await;
+        assert!(body.contains("arb_opportunities_active 0"));
+        assert!(body.contains("arb_best_opportunity_profit_pct 0"));
+    }
+}
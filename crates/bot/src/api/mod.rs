@@ -1 +1,4 @@
+pub mod admin;
+pub mod dashboard;
 pub mod metrics;
+pub mod opportunity_feed;
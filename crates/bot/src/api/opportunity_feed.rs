@@ -0,0 +1,166 @@
+//! Live opportunity feed over WebSocket
+//!
+//! The trading loop publishes every opportunity it detects to a broadcast
+//! channel; each client connected to `/ws/opportunities` gets them as JSON
+//! text messages. A client that falls behind loses the messages it missed
+//! rather than holding up the trading loop.
+
+use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    response::IntoResponse,
+    routing::get,
+    Extension, Router,
+};
+use solana_arb_core::ArbitrageOpportunity;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, info};
+
+/// Opportunities buffered per client before the oldest are dropped
+const FEED_CAPACITY: usize = 256;
+
+/// Broadcast channel of newly detected opportunities; cheap to clone
+#[derive(Clone)]
+pub struct OpportunityFeed {
+    tx: broadcast::Sender<ArbitrageOpportunity>,
+}
+
+impl Default for OpportunityFeed {
+    fn default() -> Self {
+        Self::new(FEED_CAPACITY)
+    }
+}
+
+impl OpportunityFeed {
+    /// Feed buffering up to `capacity` opportunities per client
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        Self { tx }
+    }
+
+    /// Send `opportunity` to every connected client. Never waits: with no
+    /// clients it is dropped, and slow clients skip what they can't keep up with.
+    pub fn publish(&self, opportunity: &ArbitrageOpportunity) -> usize {
+        self.tx.send(opportunity.clone()).unwrap_or(0)
+    }
+
+    /// Number of connected clients
+    pub fn client_count(&self) -> usize {
+        self.tx.receiver_count()
+    }
+}
+
+pub(crate) fn feed_routes(feed: OpportunityFeed) -> Router {
+    Router::new()
+        .route("/ws/opportunities", get(feed_handler))
+        .layer(Extension(feed))
+}
+
+async fn feed_handler(
+    ws: WebSocketUpgrade,
+    Extension(feed): Extension<OpportunityFeed>,
+) -> impl IntoResponse {
+    // Subscribe before upgrading so nothing published after the handshake is missed
+    let rx = feed.tx.subscribe();
+    ws.on_upgrade(move |socket| stream_opportunities(socket, rx, feed))
+}
+
+/// Forward opportunities to one client until it disconnects
+async fn stream_opportunities(
+    mut socket: WebSocket,
+    mut rx: broadcast::Receiver<ArbitrageOpportunity>,
+    feed: OpportunityFeed,
+) {
+    info!(
+        "📡 Opportunity feed client connected ({} connected)",
+        feed.client_count()
+    );
+
+    loop {
+        tokio::select! {
+            received = rx.recv() => match received {
+                Ok(opportunity) => {
+                    let Ok(json) = serde_json::to_string(&opportunity) else {
+                        continue;
+                    };
+                    if socket.send(Message::Text(json)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    debug!("Opportunity feed client lagged, skipped {} messages", skipped);
+                }
+                Err(RecvError::Closed) => break,
+            },
+            // Only a close (or a dropped connection) matters from the client
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    drop(rx);
+    info!(
+        "📡 Opportunity feed client disconnected ({} connected)",
+        feed.client_count()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use futures_util::StreamExt;
+    use rust_decimal::Decimal;
+    use solana_arb_core::{DexType, TokenPair, Uuid};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_connected_client_receives_published_opportunity() {
+        let feed = OpportunityFeed::default();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws/opportunities", listener.local_addr().unwrap());
+        let app = feed_routes(feed.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let (mut client, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        assert_eq!(feed.client_count(), 1);
+
+        let opportunity = ArbitrageOpportunity {
+            id: Uuid::new_v4(),
+            pair: TokenPair::new("SOL", "USDC"),
+            buy_dex: DexType::Raydium,
+            sell_dex: DexType::Orca,
+            buy_price: Decimal::ONE_HUNDRED,
+            sell_price: Decimal::from(101),
+            gross_profit_pct: Decimal::ONE,
+            net_profit_pct: Decimal::new(5, 1),
+            estimated_profit_usd: None,
+            recommended_size: None,
+            detected_at: Utc::now(),
+            expired_at: None,
+            path: None,
+        };
+        assert_eq!(feed.publish(&opportunity), 1);
+
+        let message = tokio::time::timeout(Duration::from_secs(5), client.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let received: ArbitrageOpportunity =
+            serde_json::from_str(message.to_text().unwrap()).unwrap();
+        assert_eq!(received.id, opportunity.id);
+        assert_eq!(received.net_profit_pct, opportunity.net_profit_pct);
+
+        // The server notices the disconnect and stops counting the client
+        drop(client);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while feed.client_count() > 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
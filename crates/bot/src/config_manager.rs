@@ -2,9 +2,14 @@
 //!
 //! Provides a `ConfigManager` that loads trading configuration from a JSON file,
 //! validates all values on load, and supports hot-reloading via file change detection.
+//!
+//! Only runtime-safe settings live here. Anything else found in the file (the RPC
+//! URL, the wallet) is kept in [`DynamicConfig::restart_only`] so a reload can say
+//! it was ignored rather than silently dropping it.
 #![allow(dead_code)]
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -23,6 +28,9 @@ pub struct DynamicConfig {
     pub performance: PerformanceConfig,
     /// Alert configuration
     pub alerts: AlertConfig,
+    /// Any other top-level keys: settings that only take effect on restart
+    #[serde(flatten)]
+    pub restart_only: BTreeMap<String, serde_json::Value>,
 }
 
 /// Trading-specific configuration
@@ -36,6 +44,12 @@ pub struct TradingConfig {
     pub min_profit_bps: f64,
     /// Maximum allowed slippage in basis points
     pub max_slippage_bps: u64,
+    /// Static priority fee in micro-lamports per compute unit (unset = keep the env value)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority_fee_micro_lamports: Option<u64>,
+    /// Pairs to trade, e.g. `SOL/USDC` (unset = every configured pair)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled_pairs: Option<Vec<String>>,
 }
 
 /// Risk management configuration
@@ -104,17 +118,27 @@ impl DynamicConfig {
     }
 }
 
+/// A reload that changed the configuration
+#[derive(Debug, Clone)]
+pub struct ConfigChange {
+    pub previous: DynamicConfig,
+    pub current: DynamicConfig,
+}
+
 /// Manages dynamic configuration with hot-reload support
 pub struct ConfigManager {
     config: Arc<RwLock<DynamicConfig>>,
     config_path: PathBuf,
+    /// File contents last loaded, to tell whether the file changed
+    loaded_contents: RwLock<String>,
 }
 
 impl ConfigManager {
     /// Load configuration from a JSON file
     pub fn new(config_path: impl AsRef<Path>) -> Result<Self, String> {
         let path = config_path.as_ref().to_path_buf();
-        let config = Self::load_config(&path)?;
+        let contents = Self::read_config(&path)?;
+        let config = Self::parse_config(&contents)?;
         config.validate()?;
 
         info!("Configuration loaded from {:?} (version: {})", path, config.version);
@@ -122,6 +146,7 @@ impl ConfigManager {
         Ok(Self {
             config: Arc::new(RwLock::new(config)),
             config_path: path,
+            loaded_contents: RwLock::new(contents),
         })
     }
 
@@ -132,7 +157,8 @@ impl ConfigManager {
 
     /// Reload configuration from disk
     pub async fn reload(&self) -> Result<(), String> {
-        let new_config = Self::load_config(&self.config_path)?;
+        let contents = Self::read_config(&self.config_path)?;
+        let new_config = Self::parse_config(&contents)?;
         new_config.validate()?;
 
         let old_version = {
@@ -142,6 +168,7 @@ impl ConfigManager {
 
         let mut config = self.config.write().await;
         *config = new_config;
+        *self.loaded_contents.write().await = contents;
 
         info!(
             "Configuration reloaded: {} → {}",
@@ -151,19 +178,39 @@ impl ConfigManager {
         Ok(())
     }
 
+    /// Reload if the file changed since it was last loaded.
+    ///
+    /// Returns the configuration before and after, or `None` when the file is
+    /// unchanged. An invalid file is rejected and the current configuration kept.
+    pub async fn reload_if_changed(&self) -> Result<Option<ConfigChange>, String> {
+        let contents = Self::read_config(&self.config_path)?;
+        if *self.loaded_contents.read().await == contents {
+            return Ok(None);
+        }
+        let current = Self::parse_config(&contents)?;
+        current.validate()?;
+
+        let previous = std::mem::replace(&mut *self.config.write().await, current.clone());
+        *self.loaded_contents.write().await = contents;
+        info!(
+            "Configuration reloaded: {} → {}",
+            previous.version, current.version
+        );
+        Ok(Some(ConfigChange { previous, current }))
+    }
+
     /// Get a cloneable reference to the config Arc for sharing
     pub fn shared(&self) -> Arc<RwLock<DynamicConfig>> {
         self.config.clone()
     }
 
-    fn load_config(path: &Path) -> Result<DynamicConfig, String> {
-        let data = std::fs::read_to_string(path)
-            .map_err(|e| format!("Failed to read config from {:?}: {}", path, e))?;
-
-        let config: DynamicConfig = serde_json::from_str(&data)
-            .map_err(|e| format!("Failed to parse config JSON: {}", e))?;
+    fn read_config(path: &Path) -> Result<String, String> {
+        std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config from {:?}: {}", path, e))
+    }
 
-        Ok(config)
+    fn parse_config(data: &str) -> Result<DynamicConfig, String> {
+        serde_json::from_str(data).map_err(|e| format!("Failed to parse config JSON: {}", e))
     }
 }
 
@@ -179,6 +226,8 @@ mod tests {
                 max_position_size: 1000,
                 min_profit_bps: 50.0,
                 max_slippage_bps: 100,
+                priority_fee_micro_lamports: None,
+                enabled_pairs: None,
             },
             risk: RiskConfig {
                 circuit_breaker_enabled: true,
@@ -197,6 +246,7 @@ mod tests {
                 alert_on_profit: 50.0,
                 alert_on_loss: 10.0,
             },
+            restart_only: BTreeMap::new(),
         }
     }
 
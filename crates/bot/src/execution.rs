@@ -8,33 +8,43 @@ use anyhow::{anyhow, Result};
 use base64::engine::general_purpose::STANDARD as BASE64_ENGINE;
 use base64::Engine;
 use reqwest::Client;
-use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use solana_rpc_client::nonblocking::rpc_client::RpcClient;
-use solana_rpc_client_api::config::RpcSendTransactionConfig;
+use solana_rpc_client_api::config::{
+    RpcSendTransactionConfig, RpcSimulateTransactionConfig, RpcTransactionConfig,
+};
+use solana_rpc_client_api::request::RpcRequest;
+use solana_rpc_client_api::response::RpcSimulateTransactionResult;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
 use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::compute_budget::{self, ComputeBudgetInstruction};
+use solana_sdk::instruction::{CompiledInstruction, Instruction};
+use solana_sdk::message::VersionedMessage;
 use solana_sdk::transaction::VersionedTransaction;
-use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 
 use crate::wallet::Wallet;
-use solana_arb_core::jito::JitoClient;
-use solana_arb_core::types::TradeResult;
-use solana_arb_core::ArbitrageOpportunity;
+use solana_arb_core::jito::{BundleStatus, JitoClient};
+use solana_arb_core::math::to_atoms;
+use solana_arb_core::priority_fee::{PriorityFeeConfig, PriorityFeeOracle};
+use solana_arb_core::token_registry::{TokenRegistry, SOL_MINT};
+use solana_arb_core::types::{LatencyBreakdown, TradeResult};
+use solana_arb_core::{ArbitrageError, ArbitrageOpportunity, ArbitrageResult, JupiterError};
 
 use crate::flash_loan_tx_builder::FlashLoanTxBuilder;
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::signature::Keypair;
+use solana_sdk::signature::Signature;
 use std::str::FromStr;
 
 const JUPITER_API_URL: &str = "https://quote-api.jup.ag/v6";
 
-// Token Mints (Mainnet)
-pub const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
-pub const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
-pub const RAY_MINT: &str = "4k3Dyjzvzp8eMZWUXbBCjEvwSkkk59S5iCNLY3QrkX6R";
-pub const ORCA_MINT: &str = "orcaEKTdK7LKz57vaAYr9QeNsVEPfiu6QeMU1kektZE";
+/// Most compute units a single transaction may request.
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// First wait after Jupiter rate-limits a quote request, doubled on each retry
+const JUPITER_RATE_LIMIT_BACKOFF_MS: u64 = 1_000;
 
 /// Configuration for trade execution parameters.
 #[derive(Debug, Clone)]
@@ -42,14 +52,60 @@ pub const ORCA_MINT: &str = "orcaEKTdK7LKz57vaAYr9QeNsVEPfiu6QeMU1kektZE";
 pub struct ExecutionConfig {
     /// Priority fee to add to transactions (in micro-lamports).
     pub priority_fee_micro_lamports: u64,
-    /// Compute unit limit for transactions.
+    /// Compute unit limit for transactions whose usage could not be simulated.
     pub compute_unit_limit: u32,
+    /// Headroom (in basis points) added to simulated compute-unit usage when
+    /// setting a transaction's compute unit limit.
+    pub compute_unit_margin_bps: u64,
     /// Slippage tolerance in basis points.
     pub slippage_bps: u64,
     /// Maximum number of retries for failed transactions.
     pub max_retries: u32,
     /// RPC commitment level (e.g., "confirmed", "finalized").
     pub rpc_commitment: String,
+    /// Maximum age of a quote (in milliseconds) before it is re-fetched ahead of
+    /// flash-loan transaction assembly.
+    pub max_quote_age_ms: u64,
+    /// HTTP timeout for Jupiter quote requests (in milliseconds).
+    pub quote_timeout_ms: u64,
+    /// HTTP timeout for Jupiter swap / swap-instructions requests (in milliseconds).
+    pub swap_build_timeout_ms: u64,
+    /// Restrict flash-loan quotes to direct (single-pool) routes.
+    ///
+    /// Split routes usually fill better, but every extra hop adds accounts and
+    /// instructions to an already large atomic transaction. Direct routes keep
+    /// the flash-loan transaction small and its legs simple, at the cost of a
+    /// somewhat worse price on thin pairs. Simple swaps are never restricted.
+    pub flash_loan_direct_routes_only: bool,
+    /// Derive the priority fee from `getRecentPrioritizationFees` for the route's
+    /// accounts instead of always paying `priority_fee_micro_lamports`.
+    pub dynamic_priority_fee: bool,
+    /// Percentile (0-100) of recent fees to pay when `dynamic_priority_fee` is set.
+    pub priority_fee_percentile: u8,
+    /// Lower bound for a dynamic priority fee (in micro-lamports).
+    pub min_priority_fee_micro_lamports: u64,
+    /// Upper bound for a dynamic priority fee (in micro-lamports).
+    pub max_priority_fee_micro_lamports: u64,
+    /// How long a dynamic fee estimate is reused (in milliseconds).
+    pub priority_fee_cache_ms: u64,
+    /// Margin (in basis points of the amount spent) the return leg of a non-atomic
+    /// trade must still clear, re-quoted after the first leg fills, or it is aborted.
+    pub leg_divergence_margin_bps: u64,
+    /// How long to wait for a Jito bundle to land before counting the
+    /// submission as failed (in milliseconds).
+    pub jito_bundle_timeout_ms: u64,
+    /// Abort a standard (non-Jito) submission when its simulation fails, instead
+    /// of sending it with `skip_preflight` and paying for the failure on chain.
+    pub simulate_before_submit: bool,
+}
+
+/// Routing restriction applied to a Jupiter quote request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteConstraint {
+    /// Let Jupiter split across any number of pools and hops.
+    Any,
+    /// Only single-pool routes (`onlyDirectRoutes=true`).
+    DirectOnly,
 }
 
 impl Default for ExecutionConfig {
@@ -57,16 +113,35 @@ impl Default for ExecutionConfig {
         Self {
             priority_fee_micro_lamports: 50_000,
             compute_unit_limit: 200_000,
+            compute_unit_margin_bps: 1_500,
             slippage_bps: 50,
             max_retries: 3,
             rpc_commitment: "confirmed".to_string(),
+            max_quote_age_ms: 2_000,
+            quote_timeout_ms: 2_000,
+            swap_build_timeout_ms: 5_000,
+            flash_loan_direct_routes_only: false,
+            dynamic_priority_fee: false,
+            priority_fee_percentile: 75,
+            min_priority_fee_micro_lamports: 10_000,
+            max_priority_fee_micro_lamports: 1_000_000,
+            priority_fee_cache_ms: 2_000,
+            leg_divergence_margin_bps: 10,
+            jito_bundle_timeout_ms: 15_000,
+            simulate_before_submit: false,
         }
     }
 }
 
 use solana_arb_core::alt::AltManager;
-use solana_arb_core::rate_limiter::RateLimiter;
-use std::sync::Arc;
+use solana_arb_core::rate_limiter::{ConcurrencyLimiter, ConcurrencyPermit, RateLimiter};
+use std::sync::{Arc, OnceLock};
+#[cfg(any(test, feature = "test-utils"))]
+use rust_decimal::prelude::ToPrimitive;
+#[cfg(any(test, feature = "test-utils"))]
+use std::collections::HashMap;
+#[cfg(any(test, feature = "test-utils"))]
+use std::sync::Mutex;
 
 /// Main execution component responsible for processing trades.
 ///
@@ -77,8 +152,8 @@ use std::sync::Arc;
 pub struct Executor {
     /// HTTP client for making API requests.
     client: Client,
-    /// Cache of token mint addresses.
-    token_map: HashMap<String, String>,
+    /// Mint and decimals of every tradable token.
+    tokens: TokenRegistry,
     /// Execution configuration.
     config: ExecutionConfig,
     /// Builder for flash loan transactions.
@@ -87,10 +162,177 @@ pub struct Executor {
     flash_loans_enabled: bool,
     /// Optional Address Lookup Table (ALT) manager.
     alt_manager: Option<Arc<AltManager>>,
+    /// ALT manager built from the trade's RPC URL when none was set, for
+    /// routes that use lookup tables.
+    default_alt_manager: OnceLock<Arc<AltManager>>,
     /// Rate limiter for RPC requests.
     pub rpc_rate_limiter: Option<Arc<RateLimiter>>,
     /// Rate limiter for Jupiter API requests.
     pub jupiter_rate_limiter: Option<Arc<RateLimiter>>,
+    /// Cap on concurrent RPC requests, shared with the rest of the bot.
+    pub rpc_concurrency: Option<Arc<ConcurrencyLimiter>>,
+    /// Compute-unit price estimation from recent network fees.
+    priority_fees: PriorityFeeOracle,
+    /// Base URL of the Jupiter swap API.
+    jupiter_api_url: String,
+    /// Replaces Jupiter as the source of dry-run quotes, when set.
+    quote_source: Option<Arc<dyn QuoteSource>>,
+}
+
+/// Where [`Executor::quote_only`] gets its quotes instead of Jupiter, e.g. a
+/// [`MockExecutor`] so the dry-run path can be driven without the network.
+#[async_trait::async_trait]
+pub trait QuoteSource: Send + Sync + std::fmt::Debug {
+    /// Quotes swapping `amount` atoms of `input_mint` for `output_mint`.
+    async fn quote(
+        &self,
+        input_mint: &str,
+        output_mint: &str,
+        amount: u64,
+    ) -> ArbitrageResult<QuoteSummary>;
+}
+
+/// Scripted stand-in for Jupiter on the dry-run path: quotes each swap at a
+/// fixed rate and remembers every quote it was asked for.
+#[cfg(any(test, feature = "test-utils"))]
+#[derive(Debug, Default)]
+pub struct MockExecutor {
+    /// Atoms received per atom spent, by input and output mint.
+    rates: HashMap<(String, String), Decimal>,
+    /// Input mint, output mint and amount of each quote requested.
+    requests: Mutex<Vec<(String, String, u64)>>,
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl MockExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Quotes swaps of `input_mint` for `output_mint` at `out_per_in` atoms
+    /// received per atom spent.
+    pub fn with_rate(
+        mut self,
+        input_mint: impl Into<String>,
+        output_mint: impl Into<String>,
+        out_per_in: Decimal,
+    ) -> Self {
+        self.rates
+            .insert((input_mint.into(), output_mint.into()), out_per_in);
+        self
+    }
+
+    /// Every quote requested so far, oldest first.
+    pub fn requests(&self) -> Vec<(String, String, u64)> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+#[async_trait::async_trait]
+impl QuoteSource for MockExecutor {
+    async fn quote(
+        &self,
+        input_mint: &str,
+        output_mint: &str,
+        amount: u64,
+    ) -> ArbitrageResult<QuoteSummary> {
+        let route = (input_mint.to_string(), output_mint.to_string());
+        self.requests
+            .lock()
+            .unwrap()
+            .push((route.0.clone(), route.1.clone(), amount));
+        let rate = self.rates.get(&route).ok_or_else(|| {
+            JupiterError::NoRoute(format!("{} -> {}", input_mint, output_mint))
+        })?;
+        let out_amount = Decimal::from(amount)
+            .checked_mul(*rate)
+            .and_then(|out| out.floor().to_u64())
+            .ok_or_else(|| ArbitrageError::ArithmeticOverflow(format!("quoting {}", amount)))?;
+        Ok(QuoteSummary {
+            input_mint: input_mint.to_string(),
+            output_mint: output_mint.to_string(),
+            in_amount: amount,
+            out_amount,
+            price_impact_pct: Decimal::ZERO,
+            route: vec!["Mock".to_string()],
+        })
+    }
+}
+
+/// What a Jupiter quote promises for a trade, without building a transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuoteSummary {
+    /// Mint of the token spent.
+    pub input_mint: String,
+    /// Mint of the token received.
+    pub output_mint: String,
+    /// Amount spent, in atomic units.
+    pub in_amount: u64,
+    /// Expected amount received before slippage, in atomic units.
+    pub out_amount: u64,
+    /// Price impact of the route (1.0 = 1%).
+    pub price_impact_pct: Decimal,
+    /// Label of each AMM the route passes through, in order.
+    pub route: Vec<String>,
+}
+
+impl QuoteSummary {
+    /// Extracts the summary from a Jupiter `/quote` response.
+    fn from_quote(quote: &serde_json::Value) -> ArbitrageResult<Self> {
+        let field = |name: &str| {
+            quote
+                .get(name)
+                .ok_or_else(|| ArbitrageError::JupiterApiError(format!("quote missing {}", name)))
+        };
+        let text = |name: &str| -> ArbitrageResult<String> {
+            let value = field(name)?;
+            value
+                .as_str()
+                .map(str::to_string)
+                .or_else(|| value.as_number().map(|n| n.to_string()))
+                .ok_or_else(|| {
+                    ArbitrageError::JupiterApiError(format!("invalid {} in quote: {}", name, value))
+                })
+        };
+        let amount = |name: &str| -> ArbitrageResult<u64> {
+            text(name)?
+                .parse()
+                .map_err(|_| ArbitrageError::JupiterApiError(format!("invalid {} in quote", name)))
+        };
+
+        // Jupiter reports impact as a fraction (0.01 = 1%)
+        let price_impact_pct = match quote.get("priceImpactPct") {
+            Some(_) => {
+                Decimal::from_str(&text("priceImpactPct")?).map_err(|e| {
+                    ArbitrageError::JupiterApiError(format!("invalid priceImpactPct: {}", e))
+                })? * Decimal::ONE_HUNDRED
+            }
+            None => Decimal::ZERO,
+        };
+        let route = quote
+            .get("routePlan")
+            .and_then(|plan| plan.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|step| {
+                let info = step.get("swapInfo")?;
+                info.get("label")
+                    .or_else(|| info.get("ammKey"))?
+                    .as_str()
+                    .map(str::to_string)
+            })
+            .collect();
+
+        Ok(Self {
+            input_mint: text("inputMint")?,
+            output_mint: text("outputMint")?,
+            in_amount: amount("inAmount")?,
+            out_amount: amount("outAmount")?,
+            price_impact_pct,
+            route,
+        })
+    }
 }
 
 /// Request body for Jupiter /swap endpoint (full transaction mode)
@@ -111,6 +353,83 @@ struct SwapResponse {
     swap_transaction: String,
 }
 
+/// A Jupiter swap transaction and what it was quoted from, so it can be
+/// re-quoted and rebuilt if it goes stale before landing.
+#[derive(Debug)]
+struct PreparedSwap {
+    input_token: String,
+    output_token: String,
+    amount_atoms: u64,
+    /// A rebuild quoting less than this is abandoned: the trade no longer pays.
+    min_out_atoms: u64,
+    /// The quote `transaction` was built from.
+    quote: serde_json::Value,
+    /// Base64-encoded transaction from `/swap`.
+    transaction: String,
+}
+
+/// What a failed submission says about retrying it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SubmitFailure {
+    /// The transaction went stale (expired blockhash, price moved past slippage);
+    /// sending it again can't work, but a freshly quoted one might.
+    Stale,
+    /// Network trouble, timeouts and the like: the same transaction may still land.
+    Transient,
+    /// Retrying can't help, e.g. the wallet lacks the funds.
+    Fatal,
+}
+
+/// Jupiter's `SlippageToleranceExceeded` program error
+const JUPITER_SLIPPAGE_ERROR: u32 = 0x1771;
+/// SPL Token's `InsufficientFunds` program error
+const TOKEN_INSUFFICIENT_FUNDS_ERROR: u32 = 0x1;
+
+impl SubmitFailure {
+    /// Classifies a submission error by its message, as reported by the RPC node.
+    fn classify(message: &str) -> Self {
+        let message = message.to_lowercase();
+        let program_error = message
+            .split("custom program error: 0x")
+            .nth(1)
+            .and_then(|rest| {
+                let hex: String = rest.chars().take_while(char::is_ascii_hexdigit).collect();
+                u32::from_str_radix(&hex, 16).ok()
+            });
+
+        match program_error {
+            Some(JUPITER_SLIPPAGE_ERROR) => return Self::Stale,
+            Some(TOKEN_INSUFFICIENT_FUNDS_ERROR) => return Self::Fatal,
+            _ => {}
+        }
+        if [
+            "blockhash not found",
+            "blockhashnotfound",
+            "block height exceeded",
+            "transactionexpired",
+        ]
+        .iter()
+        .any(|stale| message.contains(stale))
+        {
+            Self::Stale
+        } else if [
+            "insufficient funds",
+            "insufficient lamports",
+            "no record of a prior credit",
+            "no keypair",
+            "signature verification",
+            "without a tip",
+        ]
+        .iter()
+        .any(|fatal| message.contains(fatal))
+        {
+            Self::Fatal
+        } else {
+            Self::Transient
+        }
+    }
+}
+
 /// Request body for Jupiter /swap-instructions endpoint (structured instructions mode)
 #[derive(Debug, Serialize)]
 struct SwapInstructionsRequest {
@@ -176,46 +495,79 @@ impl Executor {
                 .unwrap_or_default()
                 .contains("devnet");
 
-        let mut token_map = HashMap::new();
-        if is_devnet {
-            // Devnet Mints
-            // Solend Devnet USDC: zVzi5VAf4qMEwzv7NXECVx5v2pQ7xnqVVjCXZwS9XzA
-            token_map.insert("SOL".to_string(), SOL_MINT.to_string());
-            token_map.insert(
-                "USDC".to_string(),
-                "zVzi5VAf4qMEwzv7NXECVx5v2pQ7xnqVVjCXZwS9XzA".to_string(),
-            );
-            // Other mints (RAY, ORCA) might not work on Devnet or use different addresses.
-            // Leaving them pointing to Mainnet but users should be aware.
-            token_map.insert("RAY".to_string(), RAY_MINT.to_string());
-            token_map.insert("ORCA".to_string(), ORCA_MINT.to_string());
+        let tokens = if is_devnet {
+            TokenRegistry::devnet()
         } else {
-            token_map.insert("SOL".to_string(), SOL_MINT.to_string());
-            token_map.insert("USDC".to_string(), USDC_MINT.to_string());
-            token_map.insert("RAY".to_string(), RAY_MINT.to_string());
-            token_map.insert("ORCA".to_string(), ORCA_MINT.to_string());
-        }
+            TokenRegistry::mainnet()
+        };
 
+        // Flash loans are paid for by the wallet; a simulated one has no real key
         let wallet = crate::wallet::Wallet::new().expect("Failed to load wallet for executor");
-        let keypair = if let Some(kp) = wallet.signer() {
-            Keypair::from_bytes(&kp.to_bytes()).expect("Failed to clone keypair")
-        } else {
-            Keypair::new()
-        };
+        let payer = Pubkey::from_str(&wallet.pubkey()).unwrap_or_else(|_| Pubkey::new_unique());
 
         Self {
             client: Client::new(),
-            token_map,
+            tokens,
             config: config.clone(),
-            flash_loan_builder: FlashLoanTxBuilder::new(keypair, is_devnet),
+            flash_loan_builder: FlashLoanTxBuilder::new(payer, is_devnet),
             flash_loans_enabled: std::env::var("ENABLE_FLASH_LOANS").unwrap_or("false".to_string())
                 == "true",
             alt_manager: None,
+            default_alt_manager: OnceLock::new(),
             rpc_rate_limiter: None,
             jupiter_rate_limiter: None,
+            rpc_concurrency: None,
+            priority_fees: PriorityFeeOracle::new(PriorityFeeConfig {
+                dynamic: config.dynamic_priority_fee,
+                percentile: config.priority_fee_percentile,
+                min_fee: config.min_priority_fee_micro_lamports,
+                max_fee: config.max_priority_fee_micro_lamports,
+                cache_ttl: Duration::from_millis(config.priority_fee_cache_ms),
+                static_fee: config.priority_fee_micro_lamports,
+            }),
+            jupiter_api_url: JUPITER_API_URL.to_string(),
+            quote_source: None,
         }
     }
 
+    /// Mints and decimals the executor trades with.
+    pub fn tokens(&self) -> &TokenRegistry {
+        &self.tokens
+    }
+
+    /// Registers `symbol` for trading, reading its decimals from the `mint` account.
+    pub async fn register_token(&mut self, symbol: &str, mint: &str, rpc_url: &str) -> Result<u32> {
+        let client = RpcClient::new(rpc_url.to_string());
+        Ok(self.tokens.register_from_chain(&client, symbol, mint).await?)
+    }
+
+    /// Points the executor at a different Jupiter API (e.g. a self-hosted instance).
+    pub fn set_jupiter_api_url(&mut self, url: impl Into<String>) {
+        self.jupiter_api_url = url.into();
+    }
+
+    /// Takes dry-run quotes from `source` instead of Jupiter. Live execution
+    /// still goes through Jupiter.
+    pub fn set_quote_source(&mut self, source: Arc<dyn QuoteSource>) {
+        self.quote_source = Some(source);
+    }
+
+    /// Slippage tolerance quotes are requested with, in basis points.
+    pub fn slippage_bps(&self) -> u64 {
+        self.config.slippage_bps
+    }
+
+    /// Changes the slippage tolerance for quotes requested from now on.
+    pub fn set_slippage_bps(&mut self, bps: u64) {
+        self.config.slippage_bps = bps;
+    }
+
+    /// Changes the static priority fee (also the fallback when fees are estimated).
+    pub fn set_priority_fee(&mut self, micro_lamports: u64) {
+        self.config.priority_fee_micro_lamports = micro_lamports;
+        self.priority_fees.set_static_fee(micro_lamports);
+    }
+
     /// Sets the address lookup table manager for optimizing transaction size.
     pub fn set_alt_manager(&mut self, manager: Arc<AltManager>) {
         self.alt_manager = Some(manager);
@@ -231,6 +583,66 @@ impl Executor {
         self.jupiter_rate_limiter = jupiter;
     }
 
+    /// Sets the limiter that bounds how many RPC requests may be in flight at once.
+    pub fn set_rpc_concurrency_limiter(&mut self, limiter: Option<Arc<ConcurrencyLimiter>>) {
+        self.rpc_concurrency = limiter;
+    }
+
+    /// Waits for an RPC slot; hold the returned permit for the duration of the call.
+    async fn acquire_rpc_slot(&self) -> Option<ConcurrencyPermit> {
+        match &self.rpc_concurrency {
+            Some(limiter) => Some(limiter.acquire().await),
+            None => None,
+        }
+    }
+
+    /// Compute-unit price for a transaction executing `quote`.
+    ///
+    /// Uses recent prioritization fees for the route's pool accounts when dynamic
+    /// estimation is enabled, falling back to the static fee if the query fails.
+    async fn priority_fee(&self, rpc_url: &str, quote: &serde_json::Value) -> u64 {
+        if !self.priority_fees.is_dynamic() {
+            return self.priority_fees.fallback();
+        }
+
+        let accounts = Self::route_accounts(quote);
+        if let Some(fee) = self.priority_fees.cached(&accounts) {
+            return fee;
+        }
+
+        let client = RpcClient::new(rpc_url.to_string());
+        let result = {
+            let _rpc_slot = self.acquire_rpc_slot().await;
+            self.priority_fees.fetch(&client, &accounts).await
+        };
+        match result {
+            Ok(fee) => {
+                debug!("⛽ Priority fee estimate: {} micro-lamports/CU", fee);
+                fee
+            }
+            Err(e) => {
+                warn!("Priority fee estimation failed, using static fee: {}", e);
+                self.priority_fees.fallback()
+            }
+        }
+    }
+
+    /// Pool accounts (`ammKey`) touched by a Jupiter quote's route plan, sorted
+    /// and deduplicated so equivalent routes share a cache entry.
+    fn route_accounts(quote: &serde_json::Value) -> Vec<Pubkey> {
+        let mut accounts: Vec<Pubkey> = quote
+            .get("routePlan")
+            .and_then(|plan| plan.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|step| step.pointer("/swapInfo/ammKey")?.as_str())
+            .filter_map(|key| Pubkey::from_str(key).ok())
+            .collect();
+        accounts.sort();
+        accounts.dedup();
+        accounts
+    }
+
     /// Fetches a swap quote from the Jupiter API.
     ///
     /// # Arguments
@@ -238,25 +650,110 @@ impl Executor {
     /// * `input_mint` - Mint address of the token to swap from
     /// * `output_mint` - Mint address of the token to swap to
     /// * `amount` - Amount of input token in atomic units
+    /// * `route` - Routing restriction for this execution path
+    ///
+    /// A rate-limited request is retried after a backoff, up to `max_retries` times;
+    /// other failures come back as an [`ArbitrageError::Jupiter`] straight away.
     pub async fn get_quote(
         &self,
         input_mint: &str,
         output_mint: &str,
         amount: u64,
+        route: RouteConstraint,
     ) -> Result<serde_json::Value> {
-        let url = format!(
-            "{}/quote?inputMint={}&outputMint={}&amount={}&slippageBps={}",
-            JUPITER_API_URL, input_mint, output_mint, amount, self.config.slippage_bps
+        let url = Self::quote_url(
+            &self.jupiter_api_url,
+            input_mint,
+            output_mint,
+            amount,
+            self.config.slippage_bps,
+            route,
         );
 
         debug!("Fetching quote from {}", url);
-        let response = self.client.get(&url).send().await?;
-        if !response.status().is_success() {
-            let err_text = response.text().await?;
-            return Err(anyhow!("Jupiter quote failed: {}", err_text));
+        let mut attempt = 0;
+        loop {
+            let response = self
+                .client
+                .get(&url)
+                .timeout(Duration::from_millis(self.config.quote_timeout_ms))
+                .send()
+                .await?;
+            if response.status().is_success() {
+                let quote: serde_json::Value = response.json().await?;
+                return Ok(quote);
+            }
+
+            let error = Self::jupiter_error(response).await;
+            attempt += 1;
+            if error != JupiterError::RateLimited || attempt >= self.config.max_retries {
+                return Err(ArbitrageError::from(error).into());
+            }
+            let delay_ms = JUPITER_RATE_LIMIT_BACKOFF_MS * 2u64.pow(attempt - 1);
+            warn!("⚠️ Jupiter rate-limited the quote request, retrying in {}ms", delay_ms);
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+        }
+    }
+
+    /// Parses a failed Jupiter response into what went wrong.
+    async fn jupiter_error(response: reqwest::Response) -> JupiterError {
+        let status = response.status().as_u16();
+        let body = response.text().await.unwrap_or_default();
+        JupiterError::from_response(status, &body)
+    }
+
+    /// Builds the Jupiter `/quote` URL, adding `onlyDirectRoutes` when routing is restricted.
+    fn quote_url(
+        api_url: &str,
+        input_mint: &str,
+        output_mint: &str,
+        amount: u64,
+        slippage_bps: u64,
+        route: RouteConstraint,
+    ) -> String {
+        let mut url = format!(
+            "{}/quote?inputMint={}&outputMint={}&amount={}&slippageBps={}",
+            api_url, input_mint, output_mint, amount, slippage_bps
+        );
+        if route == RouteConstraint::DirectOnly {
+            url.push_str("&onlyDirectRoutes=true");
+        }
+        url
+    }
+
+    /// Routing restriction for flash-loan (atomic) quotes.
+    fn flash_loan_route(&self) -> RouteConstraint {
+        if self.config.flash_loan_direct_routes_only {
+            RouteConstraint::DirectOnly
+        } else {
+            RouteConstraint::Any
+        }
+    }
+
+    /// Quotes buying `opp`'s base token with `amount_usd` of its quote token.
+    ///
+    /// Only calls Jupiter's `/quote`: no transaction is requested or built, so this
+    /// is what dry runs and backtests use to see what a trade would return.
+    pub async fn quote_only(
+        &self,
+        opp: &ArbitrageOpportunity,
+        amount_usd: Decimal,
+    ) -> ArbitrageResult<QuoteSummary> {
+        let input_mint = self.tokens.mint(&opp.pair.quote)?;
+        let output_mint = self.tokens.mint(&opp.pair.base)?;
+        let amount_atoms = to_atoms(amount_usd, self.tokens.decimals(&opp.pair.quote)?)?;
+        if let Some(source) = &self.quote_source {
+            return source.quote(input_mint, output_mint, amount_atoms).await;
         }
-        let quote: serde_json::Value = response.json().await?;
-        Ok(quote)
+
+        let quote = self
+            .get_quote(input_mint, output_mint, amount_atoms, RouteConstraint::Any)
+            .await
+            .map_err(|e| {
+                e.downcast::<ArbitrageError>()
+                    .unwrap_or_else(|e| ArbitrageError::JupiterQuoteFailed(e.to_string()))
+            })?;
+        QuoteSummary::from_quote(&quote)
     }
 
     /// Checks the SOL balance of the provided wallet.
@@ -264,6 +761,7 @@ impl Executor {
         let client = RpcClient::new(rpc_url.to_string());
         let pubkey = Pubkey::from_str(&wallet.pubkey())
             .map_err(|e| anyhow!("Invalid wallet pubkey: {}", e))?;
+        let _rpc_slot = self.acquire_rpc_slot().await;
         Ok(client.get_balance(&pubkey).await?)
     }
 
@@ -276,9 +774,10 @@ impl Executor {
     /// * `wallet` - The wallet to sign the transaction
     /// * `opp` - The arbitrage opportunity details
     /// * `amount_usd` - The trade size in USD
-    /// * `submit` - If true, submits the transaction; otherwise, simulates
+    /// * `submit` - If true, submits the transaction; otherwise only quotes it
     /// * `rpc_url` - The RPC URL to use
     /// * `jito_client` - Optional Jito client for MEV protection
+    #[tracing::instrument(name = "execution", skip_all, fields(amount_usd = %amount_usd, submit))]
     pub async fn execute(
         &self,
         wallet: &Wallet,
@@ -288,6 +787,10 @@ impl Executor {
         rpc_url: &str,
         jito_client: Option<&JitoClient>,
     ) -> Result<TradeResult> {
+        if !submit {
+            return Ok(self.dry_run(opp, amount_usd).await.0);
+        }
+
         let flash_loan_threshold = Decimal::from(1000);
         let use_flash_loan = self.flash_loans_enabled && amount_usd > flash_loan_threshold;
 
@@ -301,6 +804,50 @@ impl Executor {
             .await
     }
 
+    /// Dry-run outcome of a trade from its quote alone, skipping the `/swap` round-trip.
+    /// Also returns the quote, when one was had, so the caller can paper-trade it.
+    pub async fn dry_run(
+        &self,
+        opp: &ArbitrageOpportunity,
+        amount_usd: Decimal,
+    ) -> (TradeResult, Option<QuoteSummary>) {
+        let quote_start = Instant::now();
+        let result = self.quote_only(opp, amount_usd).await;
+        let latency = LatencyBreakdown {
+            detected_at: Some(opp.detected_at),
+            quote_ms: Some(quote_start.elapsed().as_millis() as u64),
+            ..Default::default()
+        };
+
+        let (error, summary) = match result {
+            Ok(summary) => {
+                info!(
+                    "📊 [DRY RUN] Quote: {} → {} (impact {}%, route: {})",
+                    summary.in_amount,
+                    summary.out_amount,
+                    summary.price_impact_pct.round_dp(4),
+                    summary.route.join(" → ")
+                );
+                (None, Some(summary))
+            }
+            Err(e) => {
+                warn!("Dry-run quote failed: {}", e);
+                (Some(e.to_string()), None)
+            }
+        };
+        let result = TradeResult {
+            opportunity_id: opp.id,
+            signature: None,
+            success: error.is_none(),
+            actual_profit: None,
+            executed_at: chrono::Utc::now(),
+            error,
+            latency: Some(latency),
+            realized_slippage: None,
+        };
+        (result, summary)
+    }
+
     /// Executes a standard (non-flash-loan) arbitrage trade.
     ///
     /// Fetches a quote, gets swap instructions, checks balance, and submits the transaction.
@@ -316,14 +863,22 @@ impl Executor {
     ) -> Result<TradeResult> {
         let (input_token, output_token) = (&opp.pair.quote, &opp.pair.base);
 
-        let amount_atoms = (amount_usd * Decimal::from(1_000_000))
-            .to_u64()
-            .unwrap_or(1_000_000);
+        // Input is the quote token
+        let quote_decimals = self.tokens.decimals(input_token)?;
+        let amount_atoms = to_atoms(amount_usd, quote_decimals)?;
 
-        let quote = match self
-            .get_quote(input_token, output_token, amount_atoms)
-            .await
-        {
+        let mut latency = LatencyBreakdown {
+            detected_at: Some(opp.detected_at),
+            ..Default::default()
+        };
+
+        let quote_start = Instant::now();
+        let quote_result = self
+            .get_quote(input_token, output_token, amount_atoms, RouteConstraint::Any)
+            .await;
+        latency.quote_ms = Some(quote_start.elapsed().as_millis() as u64);
+
+        let quote = match quote_result {
             Ok(q) => {
                 if let Some(out_amount) = q.get("outAmount") {
                     info!(
@@ -343,33 +898,41 @@ impl Executor {
                     opportunity_id: opp.id,
                     signature: None,
                     success: false,
-                    actual_profit: Decimal::ZERO,
+                    actual_profit: Some(Decimal::ZERO),
                     executed_at: chrono::Utc::now(),
                     error: Some(format!("Failed to get quote: {}", e)),
+                    latency: Some(latency),
+                    realized_slippage: None,
                 });
             }
         };
 
+        let quoted_out = Self::quote_out_amount(&quote).ok();
+
+        let build_start = Instant::now();
+        let compute_unit_price = if submit {
+            Some(self.priority_fee(rpc_url, &quote).await)
+        } else {
+            None
+        };
         let swap_req = SwapRequest {
             user_public_key: wallet.pubkey(),
-            quote_response: quote,
-            compute_unit_price_micro_lamports: if submit {
-                Some(self.config.priority_fee_micro_lamports)
-            } else {
-                None
-            },
+            quote_response: quote.clone(),
+            compute_unit_price_micro_lamports: compute_unit_price,
         };
 
         debug!("Requesting swap instruction...");
         let response = self
             .client
-            .post(format!("{}/swap", JUPITER_API_URL))
+            .post(format!("{}/swap", self.jupiter_api_url))
+            .timeout(Duration::from_millis(self.config.swap_build_timeout_ms))
             .json(&swap_req)
             .send()
             .await?;
 
         if response.status().is_success() {
             let swap_resp: SwapResponse = response.json().await?;
+            latency.build_ms = Some(build_start.elapsed().as_millis() as u64);
             info!(
                 "✅ Received swap transaction (Base64 length: {})",
                 swap_resp.swap_transaction.len()
@@ -383,39 +946,134 @@ impl Executor {
                             opportunity_id: opp.id,
                             signature: None,
                             success: false,
-                            actual_profit: Decimal::ZERO,
+                            actual_profit: Some(Decimal::ZERO),
                             executed_at: chrono::Utc::now(),
                             error: Some("Insufficient SOL balance".to_string()),
+                            latency: Some(latency),
+                            realized_slippage: None,
                         });
                     }
                 }
 
 
-                match self.submit_with_retry(
+                // A rebuild may re-quote within slippage of the original, but no worse
+                let min_out_atoms = quoted_out.map_or(0, |out| {
+                    let kept_bps = 10_000u64.saturating_sub(self.config.slippage_bps);
+                    (out as u128 * kept_bps as u128 / 10_000) as u64
+                });
+                let mut swap = PreparedSwap {
+                    input_token: input_token.clone(),
+                    output_token: output_token.clone(),
+                    amount_atoms,
+                    min_out_atoms,
+                    quote,
+                    transaction: swap_resp.swap_transaction,
+                };
+
+                let submit_start = Instant::now();
+                let submitted = self.submit_with_retry(
                     wallet,
-                    &swap_resp.swap_transaction,
+                    &mut swap,
                     rpc_url,
                     jito_client,
-                ).await {
-                    Ok(signature) => {
+                ).await;
+                let submit_total_ms = submit_start.elapsed().as_millis() as u64;
+                // What the transaction that was sent promised, rebuilt or not
+                let expected_out = Self::quote_out_amount(&swap.quote).ok();
+
+                match submitted {
+                    Ok((signature, confirm_ms)) => {
                         info!("✅ Swap submitted: {}", signature);
+                        latency.confirm_ms = confirm_ms;
+                        latency.submit_ms =
+                            Some(submit_total_ms.saturating_sub(confirm_ms.unwrap_or(0)));
+
+                        // Reconcile quoted vs. actual output (only for confirmed txs)
+                        let actual_out = match (expected_out, confirm_ms) {
+                            (Some(_), Some(_)) => {
+                                let output_mint =
+                                    self.tokens.mint(output_token).unwrap_or(output_token);
+                                self.reconcile_output_amount(
+                                    rpc_url,
+                                    &signature,
+                                    &wallet.pubkey(),
+                                    output_mint,
+                                )
+                                .await
+                            }
+                            _ => None,
+                        };
+                        let realized_slippage = expected_out
+                            .zip(actual_out)
+                            .and_then(|(expected, actual)| {
+                                Self::realized_slippage_bps(expected, actual)
+                            });
+
+                        // Leg two: sell what leg one delivered, unless the legs diverged
+                        let received = actual_out.or(expected_out).unwrap_or_default();
+                        let return_signature = match self
+                            .execute_return_leg(
+                                wallet,
+                                output_token,
+                                input_token,
+                                amount_atoms,
+                                received,
+                                rpc_url,
+                                jito_client,
+                            )
+                            .await
+                        {
+                            Ok(return_signature) => return_signature,
+                            Err(e) => {
+                                return Ok(TradeResult {
+                                    opportunity_id: opp.id,
+                                    signature: Some(signature),
+                                    success: false,
+                                    actual_profit: None,
+                                    executed_at: chrono::Utc::now(),
+                                    error: Some(format!("Return leg not executed: {}", e)),
+                                    latency: Some(latency),
+                                    realized_slippage,
+                                });
+                            }
+                        };
+
+                        // Profit is what both legs together did to the quote token balance
+                        let input_mint = self.tokens.mint(input_token).unwrap_or(input_token);
+                        let actual_profit = self
+                            .realized_change(
+                                rpc_url,
+                                &[&signature, &return_signature],
+                                &wallet.pubkey(),
+                                input_mint,
+                            )
+                            .await
+                            .and_then(|atoms| Self::atoms_to_ui(atoms, quote_decimals));
+
                         Ok(TradeResult {
                             opportunity_id: opp.id,
                             signature: Some(signature),
                             success: true,
-                            actual_profit: opp.estimated_profit_usd.unwrap_or_default(),
+                            actual_profit,
                             executed_at: chrono::Utc::now(),
                             error: None,
+                            latency: Some(latency),
+                            realized_slippage,
+                        })
+                    }
+                    Err(e) => {
+                        latency.submit_ms = Some(submit_total_ms);
+                        Ok(TradeResult {
+                            opportunity_id: opp.id,
+                            signature: None,
+                            success: false,
+                            actual_profit: Some(Decimal::ZERO),
+                            executed_at: chrono::Utc::now(),
+                            error: Some(format!("Submission failed: {}", e)),
+                            latency: Some(latency),
+                            realized_slippage: None,
                         })
                     }
-                    Err(e) => Ok(TradeResult {
-                        opportunity_id: opp.id,
-                        signature: None,
-                        success: false,
-                        actual_profit: Decimal::ZERO,
-                        executed_at: chrono::Utc::now(),
-                        error: Some(format!("Submission failed: {}", e)),
-                    }),
                 }
             } else {
                 info!("📝 [SIMULATION] Transaction would be signed and sent here.");
@@ -423,33 +1081,173 @@ impl Executor {
                     opportunity_id: opp.id,
                     signature: Some("simulated_signature".to_string()),
                     success: true,
-                    actual_profit: opp.estimated_profit_usd.unwrap_or_default(),
+                    actual_profit: None,
                     executed_at: chrono::Utc::now(),
                     error: None,
+                    latency: Some(latency),
+                    realized_slippage: None,
                 })
             }
         } else {
-            let error_text = response.text().await?;
-            warn!("Failed to get swap transaction: {}", error_text);
+            let error = Self::jupiter_error(response).await;
+            warn!("Failed to get swap transaction: {}", error);
             Ok(TradeResult {
                 opportunity_id: opp.id,
                 signature: None,
                 success: false,
-                actual_profit: Decimal::ZERO,
+                actual_profit: Some(Decimal::ZERO),
                 executed_at: chrono::Utc::now(),
-                error: Some(format!("Failed to get swap transaction: {}", error_text)),
+                error: Some(format!("Failed to get swap transaction: {}", error)),
+                latency: Some(latency),
+                realized_slippage: None,
             })
         }
     }
 
-    /// Submits a transaction with exponential backoff retry logic.
-    async fn submit_with_retry(
+    /// Second leg of a non-atomic trade: re-quote selling the `received_atoms` leg one
+    /// actually delivered and submit it only if the round trip still returns at least
+    /// `leg_divergence_margin_bps` more than the `spent_atoms` leg one cost.
+    ///
+    /// Returns the return leg's signature, or an error if the guard aborted it or it
+    /// failed, in which case the leg-one tokens are left in the wallet.
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_return_leg(
         &self,
         wallet: &Wallet,
-        encoded_tx: &str,
+        from_token: &str,
+        to_token: &str,
+        spent_atoms: u64,
+        received_atoms: u64,
         rpc_url: &str,
         jito_client: Option<&JitoClient>,
     ) -> Result<String> {
+        let quote = self
+            .get_quote(from_token, to_token, received_atoms, RouteConstraint::Any)
+            .await?;
+        let returned_atoms = Self::quote_out_amount(&quote)?;
+
+        let margin_bps = self.config.leg_divergence_margin_bps;
+        if !Self::legs_within_margin(spent_atoms, returned_atoms, margin_bps) {
+            warn!(
+                "🛑 Leg divergence guard: aborting {} → {} return leg - {} atoms in would \
+                 return {} for {} spent (needs +{}bps)",
+                from_token, to_token, received_atoms, returned_atoms, spent_atoms, margin_bps
+            );
+            return Err(anyhow!(
+                "leg divergence: round trip returns {} atoms for {} spent (margin {}bps)",
+                returned_atoms,
+                spent_atoms,
+                margin_bps
+            ));
+        }
+
+        debug!(
+            "↩️ Return leg: {} {} → {} {} (spent {})",
+            received_atoms, from_token, returned_atoms, to_token, spent_atoms
+        );
+        let compute_unit_price = Some(self.priority_fee(rpc_url, &quote).await);
+        let transaction = self
+            .fetch_swap_transaction(wallet, &quote, compute_unit_price)
+            .await?;
+        let mut swap = PreparedSwap {
+            input_token: from_token.to_string(),
+            output_token: to_token.to_string(),
+            amount_atoms: received_atoms,
+            // A rebuilt return leg must still clear the divergence guard
+            min_out_atoms: (spent_atoms as u128 * (10_000 + margin_bps as u128))
+                .div_ceil(10_000)
+                .try_into()
+                .unwrap_or(u64::MAX),
+            quote,
+            transaction,
+        };
+
+        let (signature, _) = self
+            .submit_with_retry(wallet, &mut swap, rpc_url, jito_client)
+            .await?;
+        info!("✅ Return leg submitted: {}", signature);
+        Ok(signature)
+    }
+
+    /// Whether getting `returned_atoms` back for `spent_atoms` clears `margin_bps`.
+    fn legs_within_margin(spent_atoms: u64, returned_atoms: u64, margin_bps: u64) -> bool {
+        returned_atoms as u128 * 10_000 >= spent_atoms as u128 * (10_000 + margin_bps as u128)
+    }
+
+    /// Requests a full swap transaction for `quote` from Jupiter's `/swap`.
+    async fn fetch_swap_transaction(
+        &self,
+        wallet: &Wallet,
+        quote: &serde_json::Value,
+        compute_unit_price: Option<u64>,
+    ) -> Result<String> {
+        let swap_req = SwapRequest {
+            user_public_key: wallet.pubkey(),
+            quote_response: quote.clone(),
+            compute_unit_price_micro_lamports: compute_unit_price,
+        };
+        let response = self
+            .client
+            .post(format!("{}/swap", self.jupiter_api_url))
+            .timeout(Duration::from_millis(self.config.swap_build_timeout_ms))
+            .json(&swap_req)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(ArbitrageError::from(Self::jupiter_error(response).await).into());
+        }
+        let swap_resp: SwapResponse = response.json().await?;
+        Ok(swap_resp.swap_transaction)
+    }
+
+    /// Re-quotes `swap` and replaces its transaction with one built on the new quote
+    /// (and so a fresh blockhash). Fails, leaving `swap` as it was, if the new quote
+    /// returns less than `min_out_atoms`.
+    async fn rebuild_swap(
+        &self,
+        wallet: &Wallet,
+        swap: &mut PreparedSwap,
+        rpc_url: &str,
+    ) -> Result<()> {
+        let quote = self
+            .get_quote(
+                &swap.input_token,
+                &swap.output_token,
+                swap.amount_atoms,
+                RouteConstraint::Any,
+            )
+            .await?;
+        let out_atoms = Self::quote_out_amount(&quote)?;
+        if out_atoms < swap.min_out_atoms {
+            return Err(anyhow!(
+                "re-quote returns {} atoms, below the {} the trade needs",
+                out_atoms,
+                swap.min_out_atoms
+            ));
+        }
+        let compute_unit_price = Some(self.priority_fee(rpc_url, &quote).await);
+        swap.transaction = self
+            .fetch_swap_transaction(wallet, &quote, compute_unit_price)
+            .await?;
+        swap.quote = quote;
+        Ok(())
+    }
+
+    /// Submits `swap`, retrying failures with exponential backoff.
+    ///
+    /// A transaction that went stale (expired blockhash, slippage exceeded) is re-quoted
+    /// and rebuilt before the next attempt instead of being resent as is; failures that
+    /// retrying can't fix, like insufficient funds, are returned straight away.
+    ///
+    /// Returns the signature and, when confirmation was awaited, the time spent confirming.
+    #[tracing::instrument(name = "confirmation", skip_all, fields(jito = jito_client.is_some()))]
+    async fn submit_with_retry(
+        &self,
+        wallet: &Wallet,
+        swap: &mut PreparedSwap,
+        rpc_url: &str,
+        jito_client: Option<&JitoClient>,
+    ) -> Result<(String, Option<u64>)> {
         let mut last_error = None;
         
         for attempt in 0..self.config.max_retries {
@@ -458,21 +1256,40 @@ impl Executor {
                 limiter.acquire().await;
             }
 
-            match self.submit_swap_transaction(wallet, encoded_tx, rpc_url, jito_client).await {
-                Ok(sig) => return Ok(sig),
-                Err(e) => {
-                    let delay_ms = 500 * 2u64.pow(attempt);
-                    warn!(
-                        "⚠️ Transaction attempt {}/{} failed: {}. Retrying in {}ms...",
-                        attempt + 1,
-                        self.config.max_retries,
-                        e,
-                        delay_ms
-                    );
-                    tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
-                    last_error = Some(e);
+            let e = match self
+                .submit_swap_transaction(wallet, &swap.transaction, rpc_url, jito_client)
+                .await
+            {
+                Ok(submitted) => return Ok(submitted),
+                Err(e) => e,
+            };
+            let failure = SubmitFailure::classify(&format!("{:#}", e));
+            if failure == SubmitFailure::Fatal {
+                warn!("❌ Transaction failed and can't be retried: {}", e);
+                return Err(e);
+            }
+
+            let delay_ms = 500 * 2u64.pow(attempt);
+            warn!(
+                "⚠️ Transaction attempt {}/{} failed ({:?}): {}. Retrying in {}ms...",
+                attempt + 1,
+                self.config.max_retries,
+                failure,
+                e,
+                delay_ms
+            );
+            tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+
+            if failure == SubmitFailure::Stale && attempt + 1 < self.config.max_retries {
+                info!(
+                    "🔄 Re-quoting {} → {} with a fresh blockhash",
+                    swap.input_token, swap.output_token
+                );
+                if let Err(rebuild_error) = self.rebuild_swap(wallet, swap, rpc_url).await {
+                    return Err(anyhow!("{}; rebuilding the swap failed: {}", e, rebuild_error));
                 }
             }
+            last_error = Some(e);
         }
 
         Err(last_error.unwrap_or_else(|| anyhow!("All retry attempts exhausted")))
@@ -484,29 +1301,64 @@ impl Executor {
         encoded_tx: &str,
         rpc_url: &str,
         jito_client: Option<&JitoClient>,
-    ) -> Result<String> {
-        let signer = wallet
-            .signer()
-            .ok_or_else(|| anyhow!("No keypair available for signing"))?;
+    ) -> Result<(String, Option<u64>)> {
+        if !wallet.can_sign() {
+            return Err(anyhow!("No keypair available for signing"));
+        }
 
         let tx_bytes = BASE64_ENGINE.decode(encoded_tx)?;
-        let tx: VersionedTransaction = bincode::deserialize(&tx_bytes)?;
-        let signed_tx = VersionedTransaction::try_new(tx.message, &[signer])?;
+        let mut tx: VersionedTransaction = bincode::deserialize(&tx_bytes)?;
+
+        let commitment = self.parse_commitment();
+        let client = RpcClient::new_with_commitment(rpc_url.to_string(), commitment);
+
+        // The block engine drops bundles that don't tip; add it before simulating
+        // so the compute budget covers the transfer too
+        if let Some(jito) = jito_client {
+            let payer = Pubkey::from_str(&wallet.pubkey())
+                .map_err(|e| anyhow!("Invalid wallet pubkey: {}", e))?;
+            Self::append_instruction(&mut tx.message, &jito.tip_instruction(&payer)?)?;
+        }
+
+        // Size the compute budget to what the swap actually uses, and don't send
+        // a standard swap the simulation shows failing. Jito bundles skip the
+        // check: they only land (and pay) if they succeed
+        let simulated = self.simulate_unsigned(&client, &tx).await;
+        if self.config.simulate_before_submit && jito_client.is_none() {
+            Self::check_preflight(&simulated)?;
+        }
+        let compute_unit_limit = self.compute_unit_limit_from(&simulated);
+        if !Self::set_compute_unit_limit(&mut tx.message, compute_unit_limit) {
+            debug!("Swap transaction has no compute unit limit instruction; leaving it as built");
+        }
+        let signed_tx = wallet.sign(tx.message).await?;
 
         if let Some(jito) = jito_client {
+            if !jito.has_tip(&signed_tx.message) {
+                return Err(anyhow!("Refusing to send a Jito bundle without a tip"));
+            }
             let signed_tx_bytes = bincode::serialize(&signed_tx)?;
             let signed_tx_base64 = BASE64_ENGINE.encode(signed_tx_bytes);
 
             let bundle_id = tokio::task::block_in_place(|| {
                 tokio::runtime::Handle::current().block_on(jito.send_bundle(&signed_tx_base64))
             })?;
-
             info!("🚀 Sent via Jito! Bundle ID: {}", bundle_id);
-            return Ok(bundle_id);
+
+            // The block engine accepting a bundle doesn't mean it lands
+            let confirm_start = Instant::now();
+            let timeout = Duration::from_millis(self.config.jito_bundle_timeout_ms);
+            return match jito.wait_for_landed(&bundle_id, timeout).await? {
+                BundleStatus::Landed { slot } => {
+                    info!("✅ Jito bundle {} landed in slot {}", bundle_id, slot);
+                    let confirm_ms = confirm_start.elapsed().as_millis() as u64;
+                    Ok((signed_tx.signatures[0].to_string(), Some(confirm_ms)))
+                }
+                status => Err(anyhow!("Jito bundle {} did not land: {:?}", bundle_id, status)),
+            };
         }
 
-        let commitment = self.parse_commitment();
-        let client = RpcClient::new_with_commitment(rpc_url.to_string(), commitment);
+        let _rpc_slot = self.acquire_rpc_slot().await;
 
         let config = RpcSendTransactionConfig {
             skip_preflight: true,
@@ -519,6 +1371,7 @@ impl Executor {
             "📡 Transaction sent: {}. Waiting for confirmation...",
             signature
         );
+        let confirm_start = Instant::now();
         match client.confirm_transaction_with_spinner(
             &signature,
             &client.get_latest_blockhash().await?,
@@ -532,18 +1385,213 @@ impl Executor {
             }
         }
 
-        Ok(signature.to_string())
+        let confirm_ms = confirm_start.elapsed().as_millis() as u64;
+
+        Ok((signature.to_string(), Some(confirm_ms)))
     }
 
-    fn parse_commitment(&self) -> CommitmentConfig {
-        match self.config.rpc_commitment.as_str() {
-            "processed" => CommitmentConfig::processed(),
-            "finalized" => CommitmentConfig::finalized(),
-            _ => CommitmentConfig::confirmed(),
-        }
+    /// Simulate the not yet signed `tx` against the latest blockhash.
+    async fn simulate_unsigned(
+        &self,
+        client: &RpcClient,
+        tx: &VersionedTransaction,
+    ) -> Result<RpcSimulateTransactionResult> {
+        // The transaction is not signed yet, and Jupiter's blockhash may have aged
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            ..Default::default()
+        };
+        let _rpc_slot = self.acquire_rpc_slot().await;
+        Ok(client.simulate_transaction_with_config(tx, config).await?.value)
     }
 
-    /// Execute a flash loan arbitrage trade using Jupiter's `/swap-instructions` API.
+    /// Fails when `simulated` shows the transaction failing, naming the error and
+    /// the program's last log line. A simulation that couldn't be run proves
+    /// nothing either way, so the submission goes ahead.
+    fn check_preflight(simulated: &Result<RpcSimulateTransactionResult>) -> Result<()> {
+        match simulated {
+            Ok(RpcSimulateTransactionResult { err: Some(err), logs, .. }) => {
+                let last_log = logs.iter().flatten().last();
+                Err(anyhow!(
+                    "Preflight simulation failed, not submitting: {}{}",
+                    err,
+                    last_log.map(|log| format!(" ({})", log)).unwrap_or_default()
+                ))
+            }
+            Ok(_) => Ok(()),
+            Err(e) => {
+                warn!("Preflight simulation request failed ({}), submitting anyway", e);
+                Ok(())
+            }
+        }
+    }
+
+    /// Compute unit limit covering the usage `simulated` reports plus
+    /// `compute_unit_margin_bps`. Falls back to the configured `compute_unit_limit`,
+    /// logging why, when the simulation fails or reports no usage.
+    fn compute_unit_limit_from(&self, simulated: &Result<RpcSimulateTransactionResult>) -> u32 {
+        let fallback = self.config.compute_unit_limit;
+        match simulated {
+            Ok(simulation) => match (&simulation.err, simulation.units_consumed) {
+                (None, Some(units)) => {
+                    let limit =
+                        Self::compute_unit_limit_for(units, self.config.compute_unit_margin_bps);
+                    debug!("Simulated {} compute units, requesting {}", units, limit);
+                    limit
+                }
+                (Some(err), _) => {
+                    warn!(
+                        "Compute unit simulation failed ({:?}), using configured limit {}",
+                        err, fallback
+                    );
+                    fallback
+                }
+                (None, None) => {
+                    warn!(
+                        "Simulation did not report compute units, using configured limit {}",
+                        fallback
+                    );
+                    fallback
+                }
+            },
+            Err(e) => {
+                warn!(
+                    "Compute unit simulation request failed ({}), using configured limit {}",
+                    e, fallback
+                );
+                fallback
+            }
+        }
+    }
+
+    /// `message` with placeholder signatures, for simulation without signature checks
+    fn unsigned_transaction(message: VersionedMessage) -> VersionedTransaction {
+        let signatures = usize::from(message.header().num_required_signatures);
+        VersionedTransaction {
+            signatures: vec![Signature::default(); signatures],
+            message,
+        }
+    }
+
+    /// Compute unit limit for a transaction that used `units_consumed` in simulation.
+    fn compute_unit_limit_for(units_consumed: u64, margin_bps: u64) -> u32 {
+        let limit = units_consumed.saturating_mul(10_000 + margin_bps) / 10_000;
+        limit.min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+    }
+
+    /// Rewrite the compute unit limit instruction already in `message` to `limit`.
+    ///
+    /// Returns false when there is none: adding an instruction to a compiled
+    /// message would shift its account indices, so the message is left alone.
+    fn set_compute_unit_limit(message: &mut VersionedMessage, limit: u32) -> bool {
+        let set_limit = ComputeBudgetInstruction::set_compute_unit_limit(limit).data;
+        let account_keys = message.static_account_keys().to_vec();
+        let instructions = match message {
+            VersionedMessage::Legacy(message) => &mut message.instructions,
+            VersionedMessage::V0(message) => &mut message.instructions,
+        };
+
+        let existing = instructions.iter_mut().find(|ix| {
+            account_keys.get(ix.program_id_index as usize) == Some(&compute_budget::id())
+                && ix.data.first() == set_limit.first()
+        });
+        match existing {
+            Some(ix) => {
+                ix.data = set_limit;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Append `ix` to an already compiled `message`, adding the accounts it uses.
+    ///
+    /// New accounts go where their role puts them among the static keys, and every
+    /// index at or past an insertion point (including lookup-table accounts, which
+    /// follow the static keys) is shifted to match. `ix` can't add signers, and an
+    /// account it needs writable must not already be in `message` as read-only.
+    /// Accounts only reachable through a lookup table aren't seen, so would be added
+    /// a second time: don't use this for accounts the swap itself touches.
+    fn append_instruction(message: &mut VersionedMessage, ix: &Instruction) -> Result<()> {
+        let (header, keys, instructions) = match message {
+            VersionedMessage::Legacy(m) => {
+                (&mut m.header, &mut m.account_keys, &mut m.instructions)
+            }
+            VersionedMessage::V0(m) => (&mut m.header, &mut m.account_keys, &mut m.instructions),
+        };
+
+        let metas = ix
+            .accounts
+            .iter()
+            .map(|meta| (meta.pubkey, meta.is_signer, meta.is_writable))
+            .chain(std::iter::once((ix.program_id, false, false)));
+        for (key, is_signer, is_writable) in metas {
+            let num_signers = header.num_required_signatures as usize;
+            let num_readonly_signed = header.num_readonly_signed_accounts as usize;
+            let num_readonly_unsigned = header.num_readonly_unsigned_accounts as usize;
+            if let Some(index) = keys.iter().position(|k| *k == key) {
+                let writable = if index < num_signers {
+                    index < num_signers - num_readonly_signed
+                } else {
+                    index < keys.len() - num_readonly_unsigned
+                };
+                if (is_signer && index >= num_signers) || (is_writable && !writable) {
+                    return Err(anyhow!("{} is in the message with a narrower role", key));
+                }
+                continue;
+            }
+            if is_signer {
+                return Err(anyhow!("Cannot add signer {} to a compiled message", key));
+            }
+
+            let position = if is_writable {
+                keys.len() - num_readonly_unsigned
+            } else {
+                header.num_readonly_unsigned_accounts += 1;
+                keys.len()
+            };
+            let shift = |index: &mut u8| -> Result<()> {
+                if *index as usize >= position {
+                    *index = index
+                        .checked_add(1)
+                        .ok_or_else(|| anyhow!("Message has too many accounts"))?;
+                }
+                Ok(())
+            };
+            for compiled in instructions.iter_mut() {
+                shift(&mut compiled.program_id_index)?;
+                compiled.accounts.iter_mut().try_for_each(shift)?;
+            }
+            keys.insert(position, key);
+        }
+
+        let index_of = |key: &Pubkey| -> Result<u8> {
+            let index = keys.iter().position(|k| k == key).unwrap_or(usize::MAX);
+            u8::try_from(index).map_err(|_| anyhow!("Message has too many accounts"))
+        };
+        let compiled = CompiledInstruction {
+            program_id_index: index_of(&ix.program_id)?,
+            accounts: ix
+                .accounts
+                .iter()
+                .map(|meta| index_of(&meta.pubkey))
+                .collect::<Result<_>>()?,
+            data: ix.data.clone(),
+        };
+        instructions.push(compiled);
+        Ok(())
+    }
+
+    fn parse_commitment(&self) -> CommitmentConfig {
+        match self.config.rpc_commitment.as_str() {
+            "processed" => CommitmentConfig::processed(),
+            "finalized" => CommitmentConfig::finalized(),
+            _ => CommitmentConfig::confirmed(),
+        }
+    }
+
+    /// Execute a flash loan arbitrage trade using Jupiter's `/swap-instructions` API.
     ///
     /// Instead of calling `/swap` to get a full serialized transaction and manually
     /// deserializing it (fragile), this calls `/swap-instructions` which returns
@@ -564,101 +1612,100 @@ impl Executor {
         );
 
         // 1. Resolve mint address
-        let input_mint_str = self
-            .token_map
-            .get(&opp.pair.base)
-            .ok_or_else(|| anyhow!("Unknown base token: {}", opp.pair.base))?;
-        let output_mint_str = self
-            .token_map
-            .get(&opp.pair.quote)
-            .ok_or_else(|| anyhow!("Unknown quote token: {}", opp.pair.quote))?;
+        let input_mint_str = self.tokens.mint(&opp.pair.base)?;
+        let output_mint_str = self.tokens.mint(&opp.pair.quote)?;
         let input_mint = Pubkey::from_str(input_mint_str)?;
 
         // 2. Convert USD amount to token atoms
-        let decimals = if opp.pair.base == "USDC" { 6 } else { 9 };
-        let amount_atoms = (amount_usd * Decimal::from(10u64.pow(decimals)))
-            .to_u64()
-            .unwrap_or(0);
+        let decimals = self.tokens.decimals(&opp.pair.base)?;
+        let amount_atoms = to_atoms(amount_usd, decimals)?;
 
         if amount_atoms == 0 {
             return Err(anyhow!("Invalid flash loan amount: zero atoms"));
         }
 
-        // 3. Get quote from Jupiter
-        let quote = self
-            .get_quote(input_mint_str, output_mint_str, amount_atoms)
-            .await?;
-
-        if let Some(out_amount) = quote.get("outAmount") {
-            debug!(
-                "📊 Flash loan quote: {} {} → {} {}",
-                amount_atoms, input_mint_str, out_amount, output_mint_str
-            );
-        }
+        let mut latency = LatencyBreakdown {
+            detected_at: Some(opp.detected_at),
+            ..Default::default()
+        };
 
-        // 4. Get structured swap instructions (NOT full transaction)
-        let swap_instructions_resp = self
-            .get_swap_instructions(&wallet.pubkey(), &quote)
+        // 3. Quote both legs of the round trip from Jupiter
+        let quote_start = Instant::now();
+        let (mut quote, mut reverse_quote) = self
+            .quote_round_trip(input_mint_str, output_mint_str, amount_atoms)
             .await?;
+        let quote_fetched_at = Instant::now();
+        latency.quote_ms = Some(quote_start.elapsed().as_millis() as u64);
 
-        info!(
-            "📋 Received swap instructions: {} setup + 1 swap + {} cleanup",
-            swap_instructions_resp.setup_instructions.len(),
-            if swap_instructions_resp.cleanup_instruction.is_some() { 1 } else { 0 }
-        );
+        // 4-6. Swap instructions and lookup tables for both legs
+        let build_start = Instant::now();
+        let (mut swap_instructions, mut lookup_tables) = self
+            .prepare_round_trip_instructions(wallet, &quote, &reverse_quote, rpc_url)
+            .await?;
 
-        // 5. Convert Jupiter instructions → solana_sdk::Instruction
-        let mut swap_instructions = Vec::new();
+        // 7. Build flash loan transaction via FlashLoanTxBuilder
+        let rpc_client_instance = RpcClient::new(rpc_url.to_string());
+        let recent_blockhash = {
+            let _rpc_slot = self.acquire_rpc_slot().await;
+            rpc_client_instance.get_latest_blockhash().await?
+        };
 
-        for jup_ix in &swap_instructions_resp.setup_instructions {
-            swap_instructions.push(Self::convert_jupiter_instruction(jup_ix)?);
+        // Quote freshness guard: the quote may have aged while we fetched instructions
+        // and the blockhash, so refresh it before assembling the transaction.
+        let max_quote_age = Duration::from_millis(self.config.max_quote_age_ms);
+        if quote_fetched_at.elapsed() > max_quote_age {
+            debug!(
+                "🔄 Quote is {}ms old (max {}ms), re-fetching before build",
+                quote_fetched_at.elapsed().as_millis(),
+                self.config.max_quote_age_ms
+            );
+            (quote, reverse_quote) = self
+                .quote_round_trip(input_mint_str, output_mint_str, amount_atoms)
+                .await?;
+            (swap_instructions, lookup_tables) = self
+                .prepare_round_trip_instructions(wallet, &quote, &reverse_quote, rpc_url)
+                .await?;
         }
 
-        swap_instructions.push(
-            Self::convert_jupiter_instruction(&swap_instructions_resp.swap_instruction)?
-        );
-
-        if let Some(cleanup) = &swap_instructions_resp.cleanup_instruction {
-            swap_instructions.push(Self::convert_jupiter_instruction(cleanup)?);
-        }
+        // The least the reverse leg may return without failing the transaction;
+        // the builder refuses to assemble one this can't repay
+        let min_return = Self::quote_min_out(&reverse_quote)?;
 
-        // 6. Resolve Address Lookup Tables (if any)
-        let lookup_tables = if !swap_instructions_resp.address_lookup_table_addresses.is_empty() {
-            if let Some(alt_manager) = &self.alt_manager {
-                let table_pubkeys: Vec<Pubkey> = swap_instructions_resp
-                    .address_lookup_table_addresses
-                    .iter()
-                    .filter_map(|addr| Pubkey::from_str(addr).ok())
-                    .collect();
-                alt_manager.get_tables(&table_pubkeys).await?
-            } else {
-                warn!("ALTs returned by Jupiter but AltManager not configured; proceeding without");
-                vec![]
-            }
+        // Without dynamic estimation the builder keeps its size-based heuristic
+        let priority_fee = if self.priority_fees.is_dynamic() {
+            Some(self.priority_fee(rpc_url, &quote).await)
         } else {
-            vec![]
+            None
         };
 
-        // 7. Build flash loan transaction via FlashLoanTxBuilder
-        let rpc_client_instance = RpcClient::new(rpc_url.to_string());
-        let recent_blockhash = rpc_client_instance.get_latest_blockhash().await?;
-
-        let tx = self
-            .flash_loan_builder
-            .build_transaction(
-                opp,
-                amount_atoms,
-                &input_mint,
-                swap_instructions,
-                &lookup_tables,
-                recent_blockhash,
-            )
-            .map_err(|e| anyhow!("Failed to build flash loan tx: {}", e))?;
+        // Built at the maximum limit first; tightened once simulation measures usage
+        let build = |compute_unit_limit: u32| {
+            self.flash_loan_builder
+                .build_message(
+                    opp,
+                    amount_atoms,
+                    min_return,
+                    &input_mint,
+                    swap_instructions.clone(),
+                    &lookup_tables,
+                    recent_blockhash,
+                    priority_fee,
+                    compute_unit_limit,
+                )
+                .map_err(|e| anyhow!("Failed to build flash loan tx: {}", e))
+        };
+        let mut message = build(MAX_COMPUTE_UNIT_LIMIT)?;
+        latency.build_ms = Some(build_start.elapsed().as_millis() as u64);
 
         // 8. Simulate transaction before submission
         if submit {
             debug!("🔍 Simulating flash loan transaction...");
-            let sim_result = rpc_client_instance.simulate_transaction(&tx).await?;
+            let sim_result = {
+                let _rpc_slot = self.acquire_rpc_slot().await;
+                rpc_client_instance
+                    .simulate_transaction(&Self::unsigned_transaction(message.clone()))
+                    .await?
+            };
 
             if let Some(err) = sim_result.value.err {
                 return Err(anyhow!(
@@ -669,10 +1716,11 @@ impl Executor {
             }
 
             let compute_units = sim_result.value.units_consumed.unwrap_or(0);
-            if compute_units > 1_400_000 {
+            if compute_units > MAX_COMPUTE_UNIT_LIMIT as u64 {
                 return Err(anyhow!(
-                    "Compute units {} exceed limit 1,400,000",
-                    compute_units
+                    "Compute units {} exceed limit {}",
+                    compute_units,
+                    MAX_COMPUTE_UNIT_LIMIT
                 ));
             }
 
@@ -680,29 +1728,373 @@ impl Executor {
                 "✅ Simulation passed (compute units: {})",
                 compute_units
             );
+
+            if compute_units > 0 {
+                message = build(Self::compute_unit_limit_for(
+                    compute_units,
+                    self.config.compute_unit_margin_bps,
+                ))?;
+            }
         }
 
         // 9. Submit or simulate
-        let signature = if submit {
+        let (signature, actual_profit) = if submit {
             let client = RpcClient::new(rpc_url.to_string());
-            let sig = client.send_and_confirm_transaction(&tx).await?;
+            let tx = wallet.sign(message).await?;
+            let submit_start = Instant::now();
+            let sig = {
+                let _rpc_slot = self.acquire_rpc_slot().await;
+                client.send_and_confirm_transaction(&tx).await?
+            };
+            // send_and_confirm does not expose the split, so this covers both stages
+            latency.submit_ms = Some(submit_start.elapsed().as_millis() as u64);
             info!("✅ Flash loan transaction confirmed: {}", sig);
-            sig.to_string()
+
+            // The loan is repaid within the transaction, so whatever is left of the
+            // borrowed token is the profit; valued in the quote token at the buy price
+            let sig = sig.to_string();
+            let actual_profit = self
+                .realized_change(rpc_url, &[&sig], &wallet.pubkey(), input_mint_str)
+                .await
+                .and_then(|atoms| Self::atoms_to_ui(atoms, decimals))
+                .and_then(|profit| profit.checked_mul(opp.buy_price));
+            (sig, actual_profit)
         } else {
             info!("📝 [SIMULATION] Flash loan transaction would be submitted here.");
-            "simulated_flash_loan_tx".to_string()
+            ("simulated_flash_loan_tx".to_string(), None)
         };
 
         Ok(TradeResult {
             opportunity_id: opp.id,
             signature: Some(signature),
             success: true,
-            actual_profit: opp.estimated_profit_usd.unwrap_or(Decimal::ZERO),
+            actual_profit,
             executed_at: chrono::Utc::now(),
             error: None,
+            latency: Some(latency),
+            realized_slippage: None,
         })
     }
 
+    /// Fetch Jupiter swap instructions for `quote` and convert them to SDK instructions,
+    /// resolving any address lookup tables Jupiter references.
+    async fn prepare_swap_instructions(
+        &self,
+        wallet: &Wallet,
+        quote: &serde_json::Value,
+        rpc_url: &str,
+    ) -> Result<(Vec<Instruction>, Vec<AddressLookupTableAccount>)> {
+        // Structured swap instructions (NOT full transaction)
+        let swap_instructions_resp = self
+            .get_swap_instructions(&wallet.pubkey(), quote)
+            .await?;
+
+        info!(
+            "📋 Received swap instructions: {} setup + 1 swap + {} cleanup",
+            swap_instructions_resp.setup_instructions.len(),
+            if swap_instructions_resp.cleanup_instruction.is_some() { 1 } else { 0 }
+        );
+
+        // Convert Jupiter instructions → solana_sdk::Instruction
+        let mut swap_instructions = Vec::new();
+
+        for jup_ix in &swap_instructions_resp.setup_instructions {
+            swap_instructions.push(Self::convert_jupiter_instruction(jup_ix)?);
+        }
+
+        swap_instructions.push(
+            Self::convert_jupiter_instruction(&swap_instructions_resp.swap_instruction)?
+        );
+
+        if let Some(cleanup) = &swap_instructions_resp.cleanup_instruction {
+            swap_instructions.push(Self::convert_jupiter_instruction(cleanup)?);
+        }
+
+        let lookup_tables = self
+            .resolve_lookup_tables(&swap_instructions_resp.address_lookup_table_addresses, rpc_url)
+            .await?;
+
+        Ok((swap_instructions, lookup_tables))
+    }
+
+    /// Fetch the address lookup tables a route references. Without a configured
+    /// `AltManager`, one is built from `rpc_url` on first use: the route's
+    /// instructions can't be compiled without its tables.
+    async fn resolve_lookup_tables(
+        &self,
+        addresses: &[String],
+        rpc_url: &str,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        if addresses.is_empty() {
+            return Ok(vec![]);
+        }
+        let alt_manager = match &self.alt_manager {
+            Some(alt_manager) => alt_manager,
+            None => self.default_alt_manager.get_or_init(|| {
+                info!("🗂️ Route uses lookup tables; creating an AltManager for {}", rpc_url);
+                Arc::new(AltManager::new(rpc_url))
+            }),
+        };
+        let table_pubkeys: Vec<Pubkey> = addresses
+            .iter()
+            .filter_map(|addr| Pubkey::from_str(addr).ok())
+            .collect();
+        let _rpc_slot = self.acquire_rpc_slot().await;
+        Ok(alt_manager.get_tables(&table_pubkeys).await?)
+    }
+
+    /// Quote the flash loan's two legs: `borrowed_atoms` of the input token
+    /// into the output token, and back again. The reverse leg sells only what
+    /// the forward leg guarantees (its `otherAmountThreshold`), so it can't
+    /// come up short of input even when the forward leg slips.
+    async fn quote_round_trip(
+        &self,
+        input_mint: &str,
+        output_mint: &str,
+        borrowed_atoms: u64,
+    ) -> Result<(serde_json::Value, serde_json::Value)> {
+        let quote = self
+            .get_quote(input_mint, output_mint, borrowed_atoms, self.flash_loan_route())
+            .await?;
+        let forward_min_out = Self::quote_min_out(&quote)?;
+        let reverse_quote = self
+            .get_quote(output_mint, input_mint, forward_min_out, self.flash_loan_route())
+            .await?;
+
+        debug!(
+            "📊 Flash loan round trip: {} {} → at least {} {} → at least {} back",
+            borrowed_atoms,
+            input_mint,
+            forward_min_out,
+            output_mint,
+            Self::quote_min_out(&reverse_quote)?
+        );
+        Ok((quote, reverse_quote))
+    }
+
+    /// Swap instructions for both legs in order, with the lookup tables of both
+    async fn prepare_round_trip_instructions(
+        &self,
+        wallet: &Wallet,
+        quote: &serde_json::Value,
+        reverse_quote: &serde_json::Value,
+        rpc_url: &str,
+    ) -> Result<(Vec<Instruction>, Vec<AddressLookupTableAccount>)> {
+        let (mut instructions, mut lookup_tables) =
+            self.prepare_swap_instructions(wallet, quote, rpc_url).await?;
+        let (reverse_instructions, reverse_tables) =
+            self.prepare_swap_instructions(wallet, reverse_quote, rpc_url).await?;
+        instructions.extend(reverse_instructions);
+        for table in reverse_tables {
+            if !lookup_tables.iter().any(|t| t.key == table.key) {
+                lookup_tables.push(table);
+            }
+        }
+        Ok((instructions, lookup_tables))
+    }
+
+    /// Fetch a confirmed transaction and return how much of `mint` the `owner` received.
+    ///
+    /// Native SOL is read from the fee payer's lamport balance (fee added back), since
+    /// Jupiter unwraps wSOL output. Returns `None` if the transaction can't be inspected.
+    async fn reconcile_output_amount(
+        &self,
+        rpc_url: &str,
+        signature: &str,
+        owner: &str,
+        mint: &str,
+    ) -> Option<u64> {
+        let signature = Signature::from_str(signature).ok()?;
+        let commitment = self.parse_commitment();
+        let client = RpcClient::new_with_commitment(rpc_url.to_string(), commitment);
+        let config = RpcTransactionConfig {
+            encoding: None,
+            commitment: Some(commitment),
+            max_supported_transaction_version: Some(0),
+        };
+
+        let _rpc_slot = self.acquire_rpc_slot().await;
+        let tx = match client.get_transaction_with_config(&signature, config).await {
+            Ok(tx) => tx,
+            Err(e) => {
+                warn!("Failed to fetch transaction {} for reconciliation: {}", signature, e);
+                return None;
+            }
+        };
+        let meta = tx.transaction.meta?;
+
+        if mint == SOL_MINT {
+            let pre = *meta.pre_balances.first()?;
+            let post = *meta.post_balances.first()?;
+            return (post + meta.fee).checked_sub(pre);
+        }
+
+        let pre: Vec<(Option<String>, String, u64)> =
+            Option::<Vec<_>>::from(meta.pre_token_balances)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|b| (b.owner.into(), b.mint, b.ui_token_amount.amount.parse().unwrap_or(0)))
+                .collect();
+        let post: Vec<(Option<String>, String, u64)> =
+            Option::<Vec<_>>::from(meta.post_token_balances)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|b| (b.owner.into(), b.mint, b.ui_token_amount.amount.parse().unwrap_or(0)))
+                .collect();
+
+        Self::token_balance_delta(&pre, &post, owner, mint)
+    }
+
+    /// Net change in `owner`'s `mint` balance, in atoms, across the confirmed
+    /// transactions `signatures`. `None` if any of them can't be fetched or parsed.
+    async fn realized_change(
+        &self,
+        rpc_url: &str,
+        signatures: &[&str],
+        owner: &str,
+        mint: &str,
+    ) -> Option<i128> {
+        let commitment = self.parse_commitment();
+        let client = RpcClient::new_with_commitment(rpc_url.to_string(), commitment);
+        let mut total = 0i128;
+
+        for signature in signatures {
+            let params = serde_json::json!([
+                signature,
+                {
+                    "encoding": "jsonParsed",
+                    "commitment": commitment.commitment,
+                    "maxSupportedTransactionVersion": 0,
+                }
+            ]);
+            let tx = {
+                let _rpc_slot = self.acquire_rpc_slot().await;
+                client
+                    .send::<serde_json::Value>(RpcRequest::GetTransaction, params)
+                    .await
+            };
+            let change = match tx {
+                Ok(tx) => Self::parse_balance_change(&tx, owner, mint),
+                Err(e) => {
+                    warn!(
+                        "Failed to fetch transaction {} for realized profit: {}",
+                        signature, e
+                    );
+                    None
+                }
+            };
+            match change {
+                Some(change) => total += change,
+                None => {
+                    warn!(
+                        "Realized profit unknown: could not read balances of {}",
+                        signature
+                    );
+                    return None;
+                }
+            }
+        }
+        Some(total)
+    }
+
+    /// Signed change in `owner`'s `mint` balance, in atoms, from a `jsonParsed`
+    /// `getTransaction` result. Native SOL is read from lamport balances, so it
+    /// includes the fee when `owner` paid it. `None` if the transaction wasn't found
+    /// or its metadata is missing.
+    fn parse_balance_change(tx: &serde_json::Value, owner: &str, mint: &str) -> Option<i128> {
+        let meta = tx.get("meta").filter(|m| !m.is_null())?;
+
+        if mint == SOL_MINT {
+            let index = tx
+                .pointer("/transaction/message/accountKeys")?
+                .as_array()?
+                .iter()
+                .position(|key| key.get("pubkey").unwrap_or(key).as_str() == Some(owner))?;
+            let lamports = |field: &str| meta.get(field)?.get(index)?.as_u64();
+            return Some(lamports("postBalances")? as i128 - lamports("preBalances")? as i128);
+        }
+
+        let total = |field: &str| -> Option<i128> {
+            let mut sum = 0i128;
+            for balance in meta.get(field)?.as_array()? {
+                if balance.get("owner").and_then(|o| o.as_str()) != Some(owner)
+                    || balance.get("mint").and_then(|m| m.as_str()) != Some(mint)
+                {
+                    continue;
+                }
+                let amount = balance.pointer("/uiTokenAmount/amount")?.as_str()?;
+                sum += amount.parse::<i128>().ok()?;
+            }
+            Some(sum)
+        };
+        Some(total("postTokenBalances")? - total("preTokenBalances")?)
+    }
+
+    /// `atoms` of a token with `decimals` as a UI amount
+    fn atoms_to_ui(atoms: i128, decimals: u32) -> Option<Decimal> {
+        Decimal::try_from_i128_with_scale(atoms, decimals).ok()
+    }
+
+    /// Increase in `owner`'s `mint` balance between pre and post token balances
+    /// (entries are `(owner, mint, amount)`). `None` if the balance did not increase.
+    fn token_balance_delta(
+        pre: &[(Option<String>, String, u64)],
+        post: &[(Option<String>, String, u64)],
+        owner: &str,
+        mint: &str,
+    ) -> Option<u64> {
+        let total = |balances: &[(Option<String>, String, u64)]| -> u64 {
+            balances
+                .iter()
+                .filter(|(o, m, _)| o.as_deref() == Some(owner) && m == mint)
+                .map(|(_, _, amount)| amount)
+                .sum()
+        };
+
+        match total(post).checked_sub(total(pre)) {
+            Some(0) | None => None,
+            delta => delta,
+        }
+    }
+
+    /// Slippage in basis points of `actual` output relative to the quoted `expected` output.
+    /// Positive when less than quoted was received.
+    fn realized_slippage_bps(expected: u64, actual: u64) -> Option<Decimal> {
+        if expected == 0 {
+            return None;
+        }
+        let expected = Decimal::from(expected);
+        let actual = Decimal::from(actual);
+        Some((expected - actual) / expected * Decimal::from(10_000))
+    }
+
+    /// Extract Jupiter's `outAmount` (a string of atoms) from a quote response.
+    fn quote_out_amount(quote: &serde_json::Value) -> Result<u64> {
+        let out_amount = quote
+            .get("outAmount")
+            .ok_or_else(|| anyhow!("Quote missing outAmount"))?;
+
+        out_amount
+            .as_str()
+            .and_then(|s| s.parse().ok())
+            .or_else(|| out_amount.as_u64())
+            .ok_or_else(|| anyhow!("Invalid outAmount in quote: {}", out_amount))
+    }
+
+    /// Least output the quoted route accepts before failing for slippage
+    /// (`otherAmountThreshold`)
+    fn quote_min_out(quote: &serde_json::Value) -> Result<u64> {
+        let threshold = quote
+            .get("otherAmountThreshold")
+            .ok_or_else(|| anyhow!("Quote missing otherAmountThreshold"))?;
+
+        threshold
+            .as_str()
+            .and_then(|s| s.parse().ok())
+            .or_else(|| threshold.as_u64())
+            .ok_or_else(|| anyhow!("Invalid otherAmountThreshold in quote: {}", threshold))
+    }
+
     /// Call Jupiter's `/swap-instructions` endpoint to get structured swap instructions.
     ///
     /// This returns individual instructions (setup, swap, cleanup) instead of a
@@ -715,20 +2107,22 @@ impl Executor {
         let req = SwapInstructionsRequest {
             user_public_key: user_pubkey.to_string(),
             quote_response: quote.clone(),
-            wrap_and_unwrap_sol: true,
+            // The legs trade the borrowed tokens in their token accounts; wrapping
+            // would spend the payer's own SOL instead
+            wrap_and_unwrap_sol: false,
             compute_unit_price_micro_lamports: None, // Handled by FlashLoanTxBuilder
         };
 
         let response = self
             .client
-            .post(format!("{}/swap-instructions", JUPITER_API_URL))
+            .post(format!("{}/swap-instructions", self.jupiter_api_url))
+            .timeout(Duration::from_millis(self.config.swap_build_timeout_ms))
             .json(&req)
             .send()
             .await?;
 
         if !response.status().is_success() {
-            let err_text = response.text().await?;
-            return Err(anyhow!("Jupiter /swap-instructions failed: {}", err_text));
+            return Err(ArbitrageError::from(Self::jupiter_error(response).await).into());
         }
 
         let resp: SwapInstructionsResponse = response.json().await?;
@@ -780,6 +2174,31 @@ impl Default for Executor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use solana_arb_core::token_registry::{BONK_MINT, RAY_MINT, USDC_MINT};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_quote_url_route_constraint() {
+        let any = Executor::quote_url(
+            JUPITER_API_URL,
+            SOL_MINT,
+            USDC_MINT,
+            1_000,
+            50,
+            RouteConstraint::Any,
+        );
+        assert!(!any.contains("onlyDirectRoutes"));
+
+        let direct = Executor::quote_url(
+            JUPITER_API_URL,
+            SOL_MINT,
+            USDC_MINT,
+            1_000,
+            50,
+            RouteConstraint::DirectOnly,
+        );
+        assert!(direct.ends_with("&slippageBps=50&onlyDirectRoutes=true"));
+    }
 
     #[test]
     fn test_convert_jupiter_instruction_valid() {
@@ -872,4 +2291,543 @@ mod tests {
         assert!(ix.accounts[0].is_signer);
         assert!(!ix.accounts[1].is_signer);
     }
+
+    #[test]
+    fn test_route_accounts_from_route_plan() {
+        let amm = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let quote = serde_json::json!({
+            "routePlan": [
+                { "swapInfo": { "ammKey": amm.to_string() } },
+                { "swapInfo": { "ammKey": other.to_string() } },
+                { "swapInfo": { "ammKey": amm.to_string() } },
+                { "swapInfo": { "ammKey": "not-a-pubkey" } },
+            ]
+        });
+        let mut expected = vec![amm, other];
+        expected.sort();
+        assert_eq!(Executor::route_accounts(&quote), expected);
+        assert!(Executor::route_accounts(&serde_json::json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_quote_out_amount_parses_string_and_number() {
+        let quote = serde_json::json!({ "outAmount": "1500000" });
+        assert_eq!(Executor::quote_out_amount(&quote).unwrap(), 1_500_000);
+
+        let quote = serde_json::json!({ "outAmount": 42 });
+        assert_eq!(Executor::quote_out_amount(&quote).unwrap(), 42);
+
+        let quote = serde_json::json!({ "inAmount": "1" });
+        assert!(Executor::quote_out_amount(&quote).is_err());
+    }
+
+    #[test]
+    fn test_flash_loan_guard_uses_slippage_threshold() {
+        // 1,000,000 borrowed at 3 bps -> 1,000,300 owed. The expected output
+        // would repay it, but the route may slip to 1,000,200 without failing.
+        let quote = serde_json::json!({
+            "outAmount": "1000400",
+            "otherAmountThreshold": "1000200"
+        });
+        let min_return = Executor::quote_min_out(&quote).unwrap();
+        assert_eq!(min_return, 1_000_200);
+        assert!(FlashLoanTxBuilder::ensure_repayable(1_000_000, min_return).is_err());
+
+        let quote = serde_json::json!({ "otherAmountThreshold": 1_000_300 });
+        let min_return = Executor::quote_min_out(&quote).unwrap();
+        assert!(FlashLoanTxBuilder::ensure_repayable(1_000_000, min_return).is_ok());
+
+        assert!(Executor::quote_min_out(&serde_json::json!({ "outAmount": "1" })).is_err());
+    }
+
+    #[test]
+    fn test_legs_within_margin() {
+        // 10 bps on 1_000_000 spent requires at least 1_001_000 back
+        assert!(Executor::legs_within_margin(1_000_000, 1_001_000, 10));
+        assert!(!Executor::legs_within_margin(1_000_000, 1_000_999, 10));
+        assert!(Executor::legs_within_margin(1_000_000, 1_000_000, 0));
+        assert!(!Executor::legs_within_margin(1_000_000, 0, 0));
+        assert!(Executor::legs_within_margin(u64::MAX, u64::MAX, 0));
+    }
+
+    #[test]
+    fn test_realized_slippage_bps() {
+        assert_eq!(
+            Executor::realized_slippage_bps(1_000_000, 995_000),
+            Some(Decimal::from(50))
+        );
+        // Better than quoted shows up as negative slippage
+        assert_eq!(
+            Executor::realized_slippage_bps(1_000_000, 1_001_000),
+            Some(Decimal::from(-10))
+        );
+        assert_eq!(Executor::realized_slippage_bps(0, 10), None);
+    }
+
+    #[test]
+    fn test_token_balance_delta_filters_owner_and_mint() {
+        let owner = "Owner1111".to_string();
+        let pre = vec![
+            (Some(owner.clone()), USDC_MINT.to_string(), 100),
+            (Some("Pool".to_string()), USDC_MINT.to_string(), 5_000),
+        ];
+        let post = vec![
+            (Some(owner.clone()), USDC_MINT.to_string(), 350),
+            (Some("Pool".to_string()), USDC_MINT.to_string(), 4_750),
+        ];
+
+        assert_eq!(
+            Executor::token_balance_delta(&pre, &post, &owner, USDC_MINT),
+            Some(250)
+        );
+        assert_eq!(Executor::token_balance_delta(&pre, &post, &owner, RAY_MINT), None);
+    }
+
+    #[test]
+    fn test_parse_balance_change_from_parsed_transaction() {
+        let owner = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+        let pool = "5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1";
+        // Trimmed `getTransaction` result (jsonParsed): the owner's USDC goes from
+        // 100 to 101.25 (the pool's the other way) and 5,000 lamports go on the fee
+        let tx = serde_json::json!({
+            "slot": 250_000_000u64,
+            "transaction": {
+                "message": {
+                    "accountKeys": [
+                        { "pubkey": owner, "signer": true, "writable": true },
+                        { "pubkey": pool, "signer": false, "writable": true }
+                    ]
+                }
+            },
+            "meta": {
+                "err": null,
+                "fee": 5000,
+                "preBalances": [2_000_000_000u64, 1_000_000],
+                "postBalances": [1_999_995_000u64, 1_000_000],
+                "preTokenBalances": [
+                    { "accountIndex": 2, "mint": USDC_MINT, "owner": owner,
+                      "uiTokenAmount": { "amount": "100000000", "decimals": 6 } },
+                    { "accountIndex": 3, "mint": USDC_MINT, "owner": pool,
+                      "uiTokenAmount": { "amount": "900000000", "decimals": 6 } }
+                ],
+                "postTokenBalances": [
+                    { "accountIndex": 2, "mint": USDC_MINT, "owner": owner,
+                      "uiTokenAmount": { "amount": "101250000", "decimals": 6 } },
+                    { "accountIndex": 3, "mint": USDC_MINT, "owner": pool,
+                      "uiTokenAmount": { "amount": "898750000", "decimals": 6 } }
+                ]
+            }
+        });
+
+        let usdc = Executor::parse_balance_change(&tx, owner, USDC_MINT);
+        assert_eq!(usdc, Some(1_250_000));
+        assert_eq!(
+            usdc.and_then(|atoms| Executor::atoms_to_ui(atoms, 6)),
+            Some(Decimal::new(125, 2))
+        );
+        assert_eq!(
+            Executor::parse_balance_change(&tx, owner, SOL_MINT),
+            Some(-5000)
+        );
+        assert_eq!(
+            Executor::parse_balance_change(&tx, pool, USDC_MINT),
+            Some(-1_250_000)
+        );
+        assert_eq!(
+            Executor::parse_balance_change(&tx, owner, RAY_MINT),
+            Some(0)
+        );
+
+        // Not found (null result) or no metadata: unknown, not zero
+        assert_eq!(
+            Executor::parse_balance_change(&serde_json::Value::Null, owner, USDC_MINT),
+            None
+        );
+        let mut no_meta = tx.clone();
+        no_meta["meta"] = serde_json::Value::Null;
+        assert_eq!(
+            Executor::parse_balance_change(&no_meta, owner, USDC_MINT),
+            None
+        );
+    }
+
+    #[test]
+    fn test_compute_unit_limit_adds_margin_and_caps() {
+        assert_eq!(Executor::compute_unit_limit_for(100_000, 1_500), 115_000);
+        assert_eq!(Executor::compute_unit_limit_for(100_000, 0), 100_000);
+        assert_eq!(
+            Executor::compute_unit_limit_for(1_300_000, 1_500),
+            MAX_COMPUTE_UNIT_LIMIT
+        );
+    }
+
+    #[test]
+    fn test_set_compute_unit_limit_rewrites_existing_instruction() {
+        let payer = Pubkey::new_unique();
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            ComputeBudgetInstruction::set_compute_unit_price(50_000),
+        ];
+        let mut message = VersionedMessage::Legacy(solana_sdk::message::Message::new(
+            &instructions,
+            Some(&payer),
+        ));
+
+        assert!(Executor::set_compute_unit_limit(&mut message, 115_000));
+        let data: Vec<&[u8]> = message.instructions().iter().map(|ix| &ix.data[..]).collect();
+        assert_eq!(
+            data,
+            vec![
+                &ComputeBudgetInstruction::set_compute_unit_limit(115_000).data[..],
+                &ComputeBudgetInstruction::set_compute_unit_price(50_000).data[..],
+            ]
+        );
+
+        // Without a limit instruction the message is left untouched
+        let mut message = VersionedMessage::Legacy(solana_sdk::message::Message::new(
+            &instructions[1..],
+            Some(&payer),
+        ));
+        assert!(!Executor::set_compute_unit_limit(&mut message, 115_000));
+        assert_eq!(message.instructions().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_uses_quote_only() {
+        use axum::{routing::get, Json, Router};
+
+        // Stand-in for Jupiter that only serves quotes: a dry run must not need more
+        let app = Router::new().route(
+            "/quote",
+            get(|| async {
+                Json(serde_json::json!({
+                    "inputMint": USDC_MINT,
+                    "outputMint": SOL_MINT,
+                    "inAmount": "100000000",
+                    "outAmount": "665000000",
+                    "priceImpactPct": "0.0012",
+                    "routePlan": [
+                        { "swapInfo": { "ammKey": "pool-1", "label": "Raydium" } },
+                        { "swapInfo": { "ammKey": "pool-2" } }
+                    ]
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut executor = Executor::new();
+        executor.set_jupiter_api_url(format!("http://{}", addr));
+        let opp = ArbitrageOpportunity {
+            id: Default::default(),
+            pair: solana_arb_core::TokenPair::new("SOL", "USDC"),
+            buy_dex: solana_arb_core::DexType::Raydium,
+            sell_dex: solana_arb_core::DexType::Orca,
+            buy_price: Decimal::new(150, 0),
+            sell_price: Decimal::new(151, 0),
+            gross_profit_pct: Decimal::new(66, 2),
+            net_profit_pct: Decimal::new(5, 1),
+            estimated_profit_usd: None,
+            recommended_size: None,
+            detected_at: chrono::Utc::now(),
+            expired_at: None,
+            path: None,
+        };
+
+        let summary = executor.quote_only(&opp, Decimal::ONE_HUNDRED).await.unwrap();
+        assert_eq!(
+            summary,
+            QuoteSummary {
+                input_mint: USDC_MINT.to_string(),
+                output_mint: SOL_MINT.to_string(),
+                in_amount: 100_000_000,
+                out_amount: 665_000_000,
+                price_impact_pct: Decimal::new(12, 2),
+                route: vec!["Raydium".to_string(), "pool-2".to_string()],
+            }
+        );
+
+        let (result, dry_run_summary) = executor.dry_run(&opp, Decimal::ONE_HUNDRED).await;
+        assert_eq!(dry_run_summary, Some(summary));
+        assert!(result.success, "{:?}", result.error);
+        assert!(result.signature.is_none());
+        assert!(result.latency.unwrap().quote_ms.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_quote_errors_are_classified() {
+        use axum::{extract::Query, http::StatusCode, routing::get, Router};
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        // Rate-limits the first SOL quote, and has no route for anything else
+        static SOL_QUOTES: AtomicU32 = AtomicU32::new(0);
+        let app = Router::new().route(
+            "/quote",
+            get(|Query(params): Query<HashMap<String, String>>| async move {
+                if params["outputMint"] != SOL_MINT {
+                    let body = serde_json::json!({
+                        "error": "Could not find any route",
+                        "errorCode": "COULD_NOT_FIND_ANY_ROUTE"
+                    });
+                    return (StatusCode::BAD_REQUEST, body.to_string());
+                }
+                if SOL_QUOTES.fetch_add(1, Ordering::SeqCst) == 0 {
+                    return (StatusCode::TOO_MANY_REQUESTS, "Too many requests".to_string());
+                }
+                (StatusCode::OK, r#"{"outAmount":"665000000"}"#.to_string())
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut executor = Executor::new();
+        executor.set_jupiter_api_url(format!("http://{}", addr));
+
+        let quote = executor
+            .get_quote(USDC_MINT, SOL_MINT, 100_000_000, RouteConstraint::Any)
+            .await
+            .unwrap();
+        assert_eq!(Executor::quote_out_amount(&quote).unwrap(), 665_000_000);
+        assert_eq!(SOL_QUOTES.load(Ordering::SeqCst), 2);
+
+        let no_route = executor
+            .get_quote(USDC_MINT, BONK_MINT, 100_000_000, RouteConstraint::Any)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            no_route.downcast_ref::<ArbitrageError>(),
+            Some(ArbitrageError::Jupiter(JupiterError::NoRoute(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_failing_simulation_blocks_submission() {
+        use axum::{routing::post, Json, Router};
+        use serde_json::json;
+        use solana_sdk::signature::Keypair;
+        use solana_sdk::signer::Signer;
+
+        // RPC node whose simulations trip Jupiter's slippage check
+        let methods = Arc::new(Mutex::new(Vec::<String>::new()));
+        let seen = methods.clone();
+        let app = Router::new().route(
+            "/",
+            post(move |Json(request): Json<serde_json::Value>| async move {
+                let method = request["method"].as_str().unwrap_or_default().to_string();
+                seen.lock().unwrap().push(method.clone());
+                let result = match method.as_str() {
+                    "getVersion" => json!({ "solana-core": "1.18.26", "feature-set": 0 }),
+                    "simulateTransaction" => json!({
+                        "context": { "slot": 1 },
+                        "value": {
+                            "err": {
+                                "InstructionError": [0, { "Custom": JUPITER_SLIPPAGE_ERROR }]
+                            },
+                            "logs": ["Program log: Error: SlippageToleranceExceeded"],
+                            "unitsConsumed": 40_000
+                        }
+                    }),
+                    _ => serde_json::Value::Null,
+                };
+                Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let keypair = Keypair::new();
+        let payer = keypair.pubkey();
+        let wallet = Wallet::with_signer(crate::signing::LocalSigner::new(keypair));
+        let transfer = solana_sdk::system_instruction::transfer(&payer, &Pubkey::new_unique(), 1);
+        let message = VersionedMessage::Legacy(solana_sdk::message::Message::new(
+            &[transfer],
+            Some(&payer),
+        ));
+        let encoded = BASE64_ENGINE
+            .encode(bincode::serialize(&Executor::unsigned_transaction(message)).unwrap());
+        let sent = || methods.lock().unwrap().iter().any(|m| m == "sendTransaction");
+
+        let executor = Executor::with_config(ExecutionConfig {
+            simulate_before_submit: true,
+            ..Default::default()
+        });
+        let error = executor
+            .submit_swap_transaction(&wallet, &encoded, &rpc_url, None)
+            .await
+            .unwrap_err();
+        let message = format!("{:#}", error);
+        assert!(message.contains("custom program error: 0x1771"), "{}", message);
+        assert!(message.contains("SlippageToleranceExceeded"), "{}", message);
+        // A stale quote: the retry loop re-quotes rather than giving up
+        assert_eq!(SubmitFailure::classify(&message), SubmitFailure::Stale);
+        assert!(!sent());
+
+        // With the check off the doomed transaction goes out
+        let executor = Executor::new();
+        let _ = executor
+            .submit_swap_transaction(&wallet, &encoded, &rpc_url, None)
+            .await;
+        assert!(sent());
+    }
+
+    #[tokio::test]
+    async fn test_lookup_tables_resolve_without_configured_alt_manager() {
+        use axum::{routing::post, Json, Router};
+        use serde_json::json;
+        use solana_sdk::address_lookup_table::state::{AddressLookupTable, LookupTableMeta};
+        use std::borrow::Cow;
+
+        // RPC node holding one lookup table
+        let table_key = Pubkey::new_unique();
+        let entries = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let data = AddressLookupTable {
+            meta: LookupTableMeta::default(),
+            addresses: Cow::Owned(entries.clone()),
+        }
+        .serialize_for_tests()
+        .unwrap();
+        let account = json!({
+            "context": { "slot": 1 },
+            "value": {
+                "data": [BASE64_ENGINE.encode(&data), "base64"],
+                "executable": false,
+                "lamports": 1_000_000,
+                "owner": solana_sdk::address_lookup_table::program::id().to_string(),
+                "rentEpoch": 0,
+                "space": data.len()
+            }
+        });
+        let app = Router::new().route(
+            "/",
+            post(move |Json(request): Json<serde_json::Value>| {
+                let account = account.clone();
+                async move {
+                    let result = match request["method"].as_str() {
+                        Some("getAccountInfo") => account,
+                        Some("getVersion") => {
+                            json!({ "solana-core": "1.18.26", "feature-set": 0 })
+                        }
+                        _ => serde_json::Value::Null,
+                    };
+                    Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let executor = Executor::new();
+        assert!(executor
+            .resolve_lookup_tables(&[], &rpc_url)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(executor.default_alt_manager.get().is_none());
+
+        // Jupiter routed through a table: the executor builds its own manager
+        // instead of compiling the swap without it
+        let tables = executor
+            .resolve_lookup_tables(&[table_key.to_string()], &rpc_url)
+            .await
+            .unwrap();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].key, table_key);
+        assert_eq!(tables[0].addresses, entries);
+        assert!(executor.default_alt_manager.get().is_some());
+    }
+
+    #[test]
+    fn test_append_jito_tip_shifts_lookup_indices() {
+        use solana_sdk::hash::Hash;
+        use solana_sdk::message::v0::MessageAddressTableLookup;
+        use solana_sdk::message::{v0, MessageHeader};
+
+        let payer = Pubkey::new_unique();
+        let pool = Pubkey::new_unique();
+        let program = Pubkey::new_unique();
+        // A swap touching one writable and one read-only lookup-table account (3 and 4)
+        let mut message = VersionedMessage::V0(v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![payer, pool, program],
+            recent_blockhash: Hash::default(),
+            instructions: vec![CompiledInstruction {
+                program_id_index: 2,
+                accounts: vec![0, 1, 3, 4],
+                data: vec![7],
+            }],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0],
+                readonly_indexes: vec![1],
+            }],
+        });
+
+        let jito = JitoClient::new("http://localhost", 10_000);
+        assert!(!jito.has_tip(&message));
+        let tip = jito.tip_instruction(&payer).unwrap();
+        Executor::append_instruction(&mut message, &tip).unwrap();
+
+        assert!(jito.has_tip(&message));
+        assert!(message.sanitize().is_ok());
+        let keys = message.static_account_keys();
+        assert_eq!(keys.len(), 5);
+        assert_eq!(keys[2], tip.accounts[1].pubkey);
+        assert_eq!(keys[4], solana_sdk::system_program::id());
+        assert!(message.is_maybe_writable(2));
+        assert!(!message.is_maybe_writable(4));
+
+        // The swap still points at the same accounts
+        let swap = &message.instructions()[0];
+        assert_eq!(swap.program_id_index, 3);
+        assert_eq!(swap.accounts, vec![0, 1, 5, 6]);
+        let transfer = &message.instructions()[1];
+        assert_eq!(transfer.program_id_index, 4);
+        assert_eq!(transfer.accounts, vec![0, 2]);
+
+        // New signers can't be added once the message is compiled
+        let other = solana_sdk::system_instruction::transfer(&Pubkey::new_unique(), &payer, 1);
+        assert!(Executor::append_instruction(&mut message, &other).is_err());
+    }
+
+    #[test]
+    fn test_classify_submit_errors() {
+        let stale = [
+            "RPC response error -32002: Transaction simulation failed: Blockhash not found",
+            "TransactionError::BlockhashNotFound",
+            "transaction 5Kx... has expired: block height exceeded",
+            "Error processing Instruction 3: custom program error: 0x1771",
+        ];
+        for message in stale {
+            assert_eq!(SubmitFailure::classify(message), SubmitFailure::Stale, "{}", message);
+        }
+
+        let fatal = [
+            "Error processing Instruction 2: custom program error: 0x1",
+            "Attempt to debit an account but found no record of a prior credit.",
+            "Transfer: insufficient lamports 1000, need 5000",
+            "No keypair available for signing",
+        ];
+        for message in fatal {
+            assert_eq!(SubmitFailure::classify(message), SubmitFailure::Fatal, "{}", message);
+        }
+
+        let transient = [
+            "error sending request for url (https://api.mainnet-beta.solana.com/)",
+            "Jito bundle abc did not land: Unknown",
+            // Other program errors aren't mistaken for 0x1
+            "custom program error: 0x17",
+        ];
+        for message in transient {
+            assert_eq!(SubmitFailure::classify(message), SubmitFailure::Transient, "{}", message);
+        }
+    }
 }
@@ -1,17 +1,12 @@
-use solana_arb_core::ArbitrageOpportunity;
+use solana_arb_core::{ArbitrageError, ArbitrageOpportunity, ArbitrageResult};
 use solana_sdk::address_lookup_table::AddressLookupTableAccount;
 use solana_sdk::compute_budget::ComputeBudgetInstruction;
 use solana_sdk::message::{v0, VersionedMessage};
-use solana_sdk::transaction::VersionedTransaction;
-use solana_sdk::{
-    instruction::Instruction,
-    pubkey::Pubkey,
-    signature::{Keypair, Signer},
-};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 
 #[derive(Debug)]
 pub struct FlashLoanTxBuilder {
-    payer: Keypair,
+    payer: Pubkey,
     solend_program_id: Pubkey,
     is_devnet: bool,
 }
@@ -21,7 +16,7 @@ impl FlashLoanTxBuilder {
     pub const SOLEND_PROGRAM_DEVNET: &'static str = "ALend7Ketfx5bxh6ghsCDXAoDrhvEmsXT3cynB6aPLgx";
     pub const FEE_BPS: u64 = 3; // 0.03%
 
-    pub fn new(payer: Keypair, is_devnet: bool) -> Self {
+    pub fn new(payer: Pubkey, is_devnet: bool) -> Self {
         let program_id_str = if is_devnet {
             Self::SOLEND_PROGRAM_DEVNET
         } else {
@@ -38,37 +33,49 @@ impl FlashLoanTxBuilder {
         }
     }
 
-    /// Build complete flash loan transaction (V0 with ALT support)
-    pub fn build_transaction(
+    /// Build the complete flash loan transaction message (V0 with ALT support),
+    /// paid for by the builder's payer. It is left unsigned: sign it with the wallet.
+    /// `min_return` is the least of `token_mint` the swaps hand back without
+    /// failing for slippage; nothing is built unless it covers the repayment.
+    /// `priority_fee` overrides the size-based compute-unit price heuristic.
+    /// `compute_unit_limit` caps the transaction's compute units.
+    #[allow(clippy::too_many_arguments)]
+    pub fn build_message(
         &self,
         opportunity: &ArbitrageOpportunity,
         borrow_amount: u64,
+        min_return: u64,
         token_mint: &Pubkey,
         swap_instructions: Vec<Instruction>,
         lookup_tables: &[AddressLookupTableAccount],
         recent_blockhash: solana_sdk::hash::Hash,
-    ) -> Result<VersionedTransaction, Box<dyn std::error::Error>> {
+        priority_fee: Option<u64>,
+        compute_unit_limit: u32,
+    ) -> Result<VersionedMessage, Box<dyn std::error::Error>> {
+        Self::ensure_repayable(borrow_amount, min_return)?;
+
         let mut all_instructions = Vec::new();
 
         // 1. Compute budget
-        all_instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(1_400_000));
+        all_instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(
+            compute_unit_limit,
+        ));
 
-        let priority_fee = self.calculate_priority_fee(opportunity, borrow_amount);
+        let priority_fee =
+            priority_fee.unwrap_or_else(|| self.calculate_priority_fee(opportunity, borrow_amount));
         all_instructions.push(ComputeBudgetInstruction::set_compute_unit_price(
             priority_fee,
         ));
 
         // 2. Get/Create ATA for Payer
-        let ata = spl_associated_token_account::get_associated_token_address(
-            &self.payer.pubkey(),
-            token_mint,
-        );
+        let ata =
+            spl_associated_token_account::get_associated_token_address(&self.payer, token_mint);
 
         // Create ATA idempotent (if it doesn't exist)
         all_instructions.push(
             spl_associated_token_account::instruction::create_associated_token_account_idempotent(
-                &self.payer.pubkey(),
-                &self.payer.pubkey(),
+                &self.payer,
+                &self.payer,
                 token_mint,
                 &spl_token::id(),
             ),
@@ -85,7 +92,7 @@ impl FlashLoanTxBuilder {
         all_instructions.extend(swap_instructions);
 
         // 5. Flash repay (amount + fee) from ATA
-        let repay_amount = self.calculate_repay_amount(borrow_amount);
+        let repay_amount = Self::repay_amount(borrow_amount)?;
         all_instructions.push(self.build_flash_repay_instruction(
             repay_amount,
             token_mint,
@@ -94,17 +101,13 @@ impl FlashLoanTxBuilder {
 
         // Build V0 Message with ALTs
         let message = v0::Message::try_compile(
-            &self.payer.pubkey(),
+            &self.payer,
             &all_instructions,
             lookup_tables,
             recent_blockhash,
         )?;
 
-        // Build Versioned Transaction
-        let transaction =
-            VersionedTransaction::try_new(VersionedMessage::V0(message), &[&self.payer])?;
-
-        Ok(transaction)
+        Ok(VersionedMessage::V0(message))
     }
 
     fn calculate_priority_fee(
@@ -122,9 +125,32 @@ impl FlashLoanTxBuilder {
         fee.clamp(50_000, 1_000_000)
     }
 
-    fn calculate_repay_amount(&self, borrowed: u64) -> u64 {
+    /// Amount owed back to Solend for a borrow
+    pub fn repay_amount(borrowed: u64) -> ArbitrageResult<u64> {
         // Solend fee: 0.03% (3 basis points)
-        borrowed + (borrowed * Self::FEE_BPS / 10000)
+        borrowed
+            .checked_mul(Self::FEE_BPS)
+            .and_then(|scaled| borrowed.checked_add(scaled / 10000))
+            .ok_or_else(|| {
+                ArbitrageError::ArithmeticOverflow(format!("repaying a borrow of {}", borrowed))
+            })
+    }
+
+    /// Fail with `SlippageExceeded` unless `min_return` atoms of the borrowed
+    /// token cover repaying `borrowed` plus the fee. `min_return` must be what
+    /// the swaps guarantee (Jupiter's `otherAmountThreshold`), not what they
+    /// expect: a shortfall would otherwise be repaid from the payer's own
+    /// token account. Amounts in the error are percentages of the borrow.
+    pub fn ensure_repayable(borrowed: u64, min_return: u64) -> ArbitrageResult<()> {
+        let repay = Self::repay_amount(borrowed)?;
+        if min_return >= repay {
+            return Ok(());
+        }
+        let pct_of_borrow = |atoms: u64| atoms as f64 / borrowed.max(1) as f64 * 100.0;
+        Err(ArbitrageError::SlippageExceeded {
+            expected: pct_of_borrow(repay),
+            actual: pct_of_borrow(min_return),
+        })
     }
 
     fn build_flash_borrow_instruction(
@@ -171,7 +197,7 @@ impl FlashLoanTxBuilder {
             solana_sdk::instruction::AccountMeta::new(reserve.liquidity_supply_pubkey, false),
             solana_sdk::instruction::AccountMeta::new(reserve.reserve_pubkey, false),
             solana_sdk::instruction::AccountMeta::new_readonly(reserve.lending_market, false),
-            solana_sdk::instruction::AccountMeta::new_readonly(self.payer.pubkey(), true),
+            solana_sdk::instruction::AccountMeta::new_readonly(self.payer, true),
             solana_sdk::instruction::AccountMeta::new_readonly(spl_token::id(), false),
         ];
 
@@ -254,12 +280,10 @@ struct SolendReserve {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use solana_sdk::signature::Keypair;
 
     #[test]
     fn test_devnet_builder_init() {
-        let payer = Keypair::new();
-        let builder = FlashLoanTxBuilder::new(payer, true);
+        let builder = FlashLoanTxBuilder::new(Pubkey::new_unique(), true);
         assert!(builder.is_devnet);
         assert_eq!(
             builder.solend_program_id.to_string(),
@@ -269,8 +293,7 @@ mod tests {
 
     #[test]
     fn test_get_solend_reserve_devnet() {
-        let payer = Keypair::new();
-        let builder = FlashLoanTxBuilder::new(payer, true);
+        let builder = FlashLoanTxBuilder::new(Pubkey::new_unique(), true);
         let usdc_devnet_mint: Pubkey = "zVzi5VAf4qMEwzv7NXECVx5v2pQ7xnqVVjCXZwS9XzA"
             .parse()
             .unwrap();
@@ -280,4 +303,55 @@ mod tests {
             "FNNkz4RCQezSSS71rW2tvqZH1LCkTzaiG7Nd1LeA5x5y"
         );
     }
+
+    #[test]
+    fn test_unrepayable_quote_is_not_built() {
+        assert_eq!(FlashLoanTxBuilder::repay_amount(1_000_000).unwrap(), 1_000_300);
+        assert!(matches!(
+            FlashLoanTxBuilder::repay_amount(u64::MAX),
+            Err(ArbitrageError::ArithmeticOverflow(_))
+        ));
+        assert!(FlashLoanTxBuilder::ensure_repayable(u64::MAX, u64::MAX).is_err());
+        assert!(FlashLoanTxBuilder::ensure_repayable(1_000_000, 1_000_300).is_ok());
+        assert!(matches!(
+            FlashLoanTxBuilder::ensure_repayable(1_000_000, 1_000_299),
+            Err(ArbitrageError::SlippageExceeded { .. })
+        ));
+
+        let builder = FlashLoanTxBuilder::new(Pubkey::new_unique(), true);
+        let usdc_devnet_mint: Pubkey = "zVzi5VAf4qMEwzv7NXECVx5v2pQ7xnqVVjCXZwS9XzA"
+            .parse()
+            .unwrap();
+        let opportunity = ArbitrageOpportunity {
+            id: Default::default(),
+            pair: solana_arb_core::TokenPair::new("USDC", "SOL"),
+            buy_dex: solana_arb_core::DexType::Raydium,
+            sell_dex: solana_arb_core::DexType::Orca,
+            buy_price: rust_decimal::Decimal::ONE,
+            sell_price: rust_decimal::Decimal::ONE,
+            gross_profit_pct: rust_decimal::Decimal::ZERO,
+            net_profit_pct: rust_decimal::Decimal::ZERO,
+            estimated_profit_usd: None,
+            recommended_size: None,
+            detected_at: chrono::Utc::now(),
+            expired_at: None,
+            path: None,
+        };
+        let build = |min_return| {
+            builder.build_message(
+                &opportunity,
+                1_000_000,
+                min_return,
+                &usdc_devnet_mint,
+                vec![],
+                &[],
+                Default::default(),
+                None,
+                200_000,
+            )
+        };
+        let err = build(999_000).unwrap_err();
+        assert!(err.downcast_ref::<ArbitrageError>().is_some());
+        assert!(build(1_001_000).is_ok());
+    }
 }
@@ -10,7 +10,7 @@ use rust_decimal::prelude::FromPrimitive; // Needed for from_f64
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, info_span, warn, Instrument};
 
 use solana_arb_core::events::{EventBus, TradingEvent};
 
@@ -23,34 +23,52 @@ pub mod flash_loan_tx_builder;
 pub mod logging;
 pub mod metrics;
 pub mod alerts;
+pub mod paper_portfolio;
+pub mod price_watchdog;
 pub mod safety_checks;
+pub mod shutdown;
+pub mod signing;
 pub mod solend_config;
 
 use crate::alerts::AlertManager;
-use crate::config_manager::ConfigManager;
+use crate::api::opportunity_feed::OpportunityFeed;
+use crate::config_manager::{ConfigChange, ConfigManager, DynamicConfig, TradingConfig};
+use crate::paper_portfolio::{PaperPortfolio, PaperTrade};
+use crate::price_watchdog::{FeedTransition, PriceWatchdog};
 use crate::safety_checks::run_preflight_checks;
+use crate::shutdown::TradeGate;
 use axum::{routing::get, Json, Router};
-use execution::{Executor, ORCA_MINT, RAY_MINT, SOL_MINT, USDC_MINT};
+use execution::Executor;
 use serde_json::json;
 use std::time::Instant;
 use metrics::prometheus::MetricsCollector;
 use solana_arb_core::{
     alt::AltManager,
-    arbitrage::ArbitrageDetector,
+    arbitrage::{rank_by_aged_score, ArbitrageDetector, FeeSchedule},
     config::Config,
-    dex::{jupiter::JupiterProvider, orca::OrcaProvider, raydium::RaydiumProvider, DexManager},
-    history::HistoryRecorder,
+    dex::{
+        jupiter::JupiterProvider, orca::OrcaProvider, raydium::RaydiumProvider, DexManager,
+        DexProvider, ProviderHealth,
+    },
+    http::pool::{HostRateLimiter, HttpTimeouts},
+    database::trade_history::PgHistoryRecorder,
+    history::{HistoryRecorder, TradeRecord},
     jito::JitoClient,
     pathfinding::PathFinder,
-    pricing::parallel_fetcher::ParallelPriceFetcher,
-    rate_limiter::RateLimiter,
-    risk::{RiskConfig, RiskManager, TradeDecision, TradeOutcome},
+    pricing::{
+        oracle::{check_against_oracle, PythPriceFeed},
+        pair_scheduler::PairScheduler,
+        parallel_fetcher::ParallelPriceFetcher,
+        smoothing::PriceSmoother,
+    },
+    rate_limiter::{ConcurrencyLimiter, RateLimiter},
+    risk::{circuit_breaker::CircuitState, RiskConfig, RiskManager, TradeDecision, TradeOutcome},
     types::TradeResult,
-    DexType, TokenPair,
+    ArbitrageConfig, DexType, TokenPair, TxCosts,
 };
-use solana_arb_dex_plugins::{LifinityProvider, MeteoraProvider, PhoenixProvider};
+use solana_arb_dex_plugins::{MeteoraProvider, PhoenixProvider};
 use solana_arb_flash_loans::solend::SolendFlashLoan;
-use solana_arb_flash_loans::FlashLoanProvider;
+use solana_arb_flash_loans::FlashLoanRouter;
 use solana_arb_strategies::{LatencyArbitrage, StatisticalArbitrage, Strategy};
 use solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
@@ -61,6 +79,8 @@ use wallet::Wallet;
 pub struct SystemHealth {
     pub is_running: bool,
     pub last_opportunity_time: Option<Instant>,
+    /// When a price fetch last returned prices
+    pub last_price_update: Option<chrono::DateTime<Utc>>,
     pub total_trades: u64,
     pub circuit_breaker_state: String,
     pub balance_usd: f64,
@@ -72,6 +92,7 @@ impl Default for SystemHealth {
         Self {
             is_running: true,
             last_opportunity_time: None,
+            last_price_update: None,
             total_trades: 0,
             circuit_breaker_state: "Closed".to_string(),
             balance_usd: 0.0,
@@ -80,6 +101,18 @@ impl Default for SystemHealth {
     }
 }
 
+/// How often the trading config file is checked for changes
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often Jupiter routes are re-quoted for the fees charged on its prices
+const JUPITER_FEE_REFRESH_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Trade size (USD) Jupiter routes are quoted at for their fees
+const JUPITER_FEE_QUOTE_USD: i64 = 100;
+
+/// How often Meteora pool fees are re-read for the fees charged on its prices
+const METEORA_FEE_REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
 /// Trading bot state holding all component instances and shared data.
 #[allow(dead_code)]
 struct BotState {
@@ -97,24 +130,51 @@ struct BotState {
     executor: Executor,
     /// Wallet for signing transactions.
     wallet: Wallet,
-    /// Provider for flash loans.
-    flash_loan_provider: Box<dyn FlashLoanProvider>,
+    /// Flash loan providers, quoted for the cheapest loan.
+    flash_loan_router: FlashLoanRouter,
     /// Recorder for trade history.
     history_recorder: HistoryRecorder,
+    /// Database copy of trade history, when enabled (the file is still written).
+    pg_history: Option<PgHistoryRecorder>,
     /// Optional Jito client for MEV protection.
     jito_client: Option<JitoClient>,
     /// Address Lookup Table (ALT) manager.
     alt_manager: Arc<AltManager>,
     /// List of active trading strategies.
     strategies: Vec<Box<dyn Strategy>>,
-    /// Whether the bot is currently running.
-    is_running: bool,
+    /// Whether the bot is running; trades pass through it so shutdown can drain them.
+    trade_gate: Arc<TradeGate>,
     /// Whether the bot is in dry-run mode.
     dry_run: bool,
+    /// Simulated wallet dry-run trades are applied to; `None` when live.
+    paper_portfolio: Option<PaperPortfolio>,
     /// RPC URL for Solana connection.
     rpc_url: String,
     /// Maximum age of price data in seconds.
     max_price_age_seconds: i64,
+    /// Halts trading when the price feeds go quiet.
+    price_watchdog: PriceWatchdog,
+    /// Whether the watchdog opened the circuit breaker, so resuming closes it
+    /// only then and not after trips for failed trades.
+    watchdog_opened_breaker: bool,
+    /// Moving average opportunities are detected on, when smoothing is enabled.
+    price_smoother: Option<PriceSmoother>,
+    /// Jupiter provider re-quoted for the route fees charged on Jupiter prices,
+    /// when Jupiter is a price source.
+    jupiter_fees: Option<Arc<JupiterProvider>>,
+    /// Meteora provider whose pool fees are charged on Meteora prices, when
+    /// Meteora is enabled.
+    meteora_fees: Option<Arc<MeteoraProvider>>,
+    /// Half-life of the age discount applied when ranking opportunities.
+    opportunity_half_life: Duration,
+    /// How long a trade may stay unsettled before it is alerted on.
+    max_position_hold: chrono::Duration,
+    /// Priority fee and Jito tip each trade pays, charged against its profit.
+    tx_costs: TxCosts,
+    /// Independent prices trades are sanity-checked against, when enabled.
+    oracle: Option<PythPriceFeed>,
+    /// How far (percent) both of a trade's prices may sit from the oracle.
+    oracle_max_deviation_pct: Decimal,
     /// Metrics collector.
     metrics: Arc<MetricsCollector>,
     /// Alert manager for notifications.
@@ -123,14 +183,22 @@ struct BotState {
     system_health: Arc<RwLock<SystemHealth>>,
     /// Event bus for system-wide events.
     event_bus: Arc<EventBus>,
+    /// Newly detected opportunities, streamed to WebSocket clients.
+    opportunity_feed: OpportunityFeed,
     /// Counter for consecutive errors.
     consecutive_errors: u32,
     /// Rate limiter for RPC requests.
     rpc_rate_limiter: Arc<RateLimiter>,
     /// Rate limiter for Jupiter API requests.
     jupiter_rate_limiter: Arc<RateLimiter>,
+    /// Cap on concurrent RPC requests across the bot.
+    rpc_concurrency: Arc<ConcurrencyLimiter>,
     /// Dynamic configuration manager.
     config_manager: Arc<ConfigManager>,
+    /// Trading paused by an operator via the admin API.
+    trading_paused: bool,
+    /// Pairs the dynamic config limits trading to; `None` trades every pair.
+    enabled_pairs: Option<Vec<String>>,
 }
 
 impl BotState {
@@ -155,52 +223,132 @@ impl BotState {
             max_position_size: Decimal::from(1000),
             max_total_exposure: Decimal::from(5000),
             max_daily_loss: Decimal::from(100),
+            max_drawdown: config
+                .max_drawdown_usd
+                .try_into()
+                .unwrap_or(Decimal::ZERO),
             min_profit_threshold: config
                 .min_profit_threshold
                 .try_into()
                 .unwrap_or(Decimal::new(5, 3)),
+            recovery_size_fraction: config
+                .recovery_size_fraction
+                .try_into()
+                .unwrap_or(Decimal::new(5, 1)),
+            recovery_trades: config.recovery_trades,
+            dedupe_window_seconds: config.dedupe_window_seconds,
+            hard_max_trade_usd: config
+                .hard_max_trade_usd
+                .try_into()
+                .unwrap_or(Decimal::from(2000)),
+            kelly_fraction: config
+                .kelly_fraction
+                .try_into()
+                .unwrap_or(Decimal::new(5, 1)),
+            confidence_level: config.var_confidence_level,
+            circuit_breaker_enabled: config.circuit_breaker_enabled,
+            circuit_failure_threshold: config.max_consecutive_losses as usize,
+            circuit_success_threshold: config.circuit_breaker_success_threshold as usize,
+            circuit_timeout_seconds: config.circuit_breaker_timeout_seconds,
             ..Default::default()
         };
 
-        let mut dex_manager = DexManager::new();
-
-        // Register DEX providers
-        dex_manager.add_provider(Arc::new(JupiterProvider::new()));
-        info!("🔌 Registered DEX provider: Jupiter");
-
-        dex_manager.add_provider(Arc::new(RaydiumProvider::new()));
-        info!("🔌 Registered DEX provider: Raydium");
-
-        dex_manager.add_provider(Arc::new(OrcaProvider::new()));
-        info!("🔌 Registered DEX provider: Orca");
-
-        dex_manager.add_provider(Arc::new(LifinityProvider::new()));
-        info!("🔌 Registered DEX provider: Lifinity");
-
-        dex_manager.add_provider(Arc::new(MeteoraProvider::new()));
-        info!("🔌 Registered DEX provider: Meteora");
-
-        dex_manager.add_provider(Arc::new(PhoenixProvider::new()));
-        info!("🔌 Registered DEX provider: Phoenix");
+        // Disable providers after repeated failures or failed health checks and re-probe
+        // them, reported via metrics
+        let enabled_gauge = metrics.dex_provider_enabled.clone();
+        let state_changes = metrics.dex_provider_state_changes.clone();
+        let mut provider_health = ProviderHealth::new(
+            config.provider_failure_threshold,
+            Duration::from_secs(config.provider_reprobe_interval_secs),
+        )
+        .with_observer(move |dex, enabled| {
+            let dex = dex.to_string();
+            enabled_gauge.with_label_values(&[&dex]).set(enabled as i64);
+            let state = if enabled { "enabled" } else { "disabled" };
+            state_changes.with_label_values(&[&dex, state]).inc();
+        });
+        if config.provider_health_check_interval_secs > 0 {
+            provider_health = provider_health.with_check_interval(Duration::from_secs(
+                config.provider_health_check_interval_secs,
+            ));
+        }
+        let provider_health = Arc::new(provider_health);
+
+        let mut dex_manager = DexManager::new().with_health(provider_health);
+
+        // Register DEX providers, sharing one per-host rate limiter
+        let http_timeouts = HttpTimeouts::from_config(config);
+        let rate_limiter = Arc::new(HostRateLimiter::from_config(config));
+        let jupiter = Arc::new(JupiterProvider::with_http(http_timeouts, rate_limiter.clone()));
+        let meteora = Arc::new(MeteoraProvider::new());
+        let providers: Vec<Arc<dyn DexProvider>> = vec![
+            jupiter.clone(),
+            Arc::new(RaydiumProvider::with_http(http_timeouts, rate_limiter.clone())),
+            Arc::new(
+                OrcaProvider::with_http(http_timeouts, rate_limiter)
+                    .with_rpc(&config.solana_rpc_url),
+            ),
+            // Lifinity is left out until its pool accounts are decoded: every
+            // price it fetched would fail and count against its health
+            meteora.clone(),
+            Arc::new(PhoenixProvider::new().with_rpc(&config.solana_rpc_url)),
+        ];
+        for provider in providers {
+            let dex = provider.dex_type();
+            if !config.is_dex_enabled(dex) {
+                info!("🔌 Skipping blacklisted DEX provider: {}", dex);
+                continue;
+            }
+            dex_manager.add_provider(provider);
+            info!("🔌 Registered DEX provider: {}", dex);
+        }
+        if !config.jupiter_price_source && config.is_dex_enabled(DexType::Jupiter) {
+            info!("🔌 Jupiter is execution-only; its prices are excluded from detection");
+        }
+        // Jupiter's route fees only matter where its prices are compared
+        let jupiter_fees = (config.jupiter_price_source
+            && config.is_dex_enabled(DexType::Jupiter))
+        .then_some(jupiter);
+        let meteora_fees = config.is_dex_enabled(DexType::Meteora).then_some(meteora);
+
+        if config.chaos_mode {
+            #[cfg(debug_assertions)]
+            dex_manager.enable_chaos(solana_arb_core::dex::chaos::ChaosConfig {
+                failure_rate: config.chaos_failure_rate,
+                min_delay: Duration::from_millis(config.chaos_min_delay_ms),
+                max_delay: Duration::from_millis(config.chaos_max_delay_ms),
+            });
+            #[cfg(not(debug_assertions))]
+            warn!("CHAOS_MODE is set but ignored: fault injection is disabled in release builds");
+        }
 
         info!(
             "🔌 DexManager initialized with {} providers",
             dex_manager.providers().len()
         );
 
-        let price_fetcher = ParallelPriceFetcher::new(dex_manager.providers().to_vec());
+        for provider in dex_manager.providers() {
+            metrics
+                .dex_provider_enabled
+                .with_label_values(&[&provider.dex_type().to_string()])
+                .set(1);
+        }
+
+        let price_fetcher = ParallelPriceFetcher::new(dex_manager.providers().to_vec())
+            .with_health(dex_manager.health())
+            .with_provider_timeout(Duration::from_millis(config.price_provider_timeout_ms));
 
-        // Initialize Flash Loan Provider (Solend)
+        // Initialize Flash Loan Providers (Solend; Kamino/MarginFi register here too)
         // For now using USDC reserve placeholder - in prod this would be dynamic or config based
         // Safety: this is a valid base58-encoded Solana pubkey constant
         let usdc_reserve =
             Pubkey::from_str("BgxfHJDzm44T7XG68MYKx7YisTjZu73tVovyZSjJMpmw")
                 .expect("Mainnet USDC reserve is a valid pubkey constant");
-        let flash_loan_provider = Box::new(SolendFlashLoan::new(usdc_reserve));
-        info!(
-            "🏦 Initialized Flash Loan Provider: {}",
-            flash_loan_provider.name()
-        );
+        let flash_loan_router =
+            FlashLoanRouter::new().with_provider(Box::new(SolendFlashLoan::new(usdc_reserve)));
+        for provider in flash_loan_router.providers() {
+            info!("🏦 Initialized Flash Loan Provider: {}", provider.name());
+        }
 
         let temp_session_id = format!("SESSION-{}", Utc::now().format("%Y%m%d-%H%M%S"));
         let history_file = if dry_run {
@@ -211,19 +359,32 @@ impl BotState {
         let history_recorder = HistoryRecorder::new(history_file, &temp_session_id);
         info!("📜 Trade history will be saved to: {}", history_file);
 
+        // Dedupe state lives next to the history so restarts don't re-execute recent trades
+        let dedupe_file = if dry_run {
+            "data/dedupe-sim.json"
+        } else {
+            "data/dedupe-live.json"
+        };
+        // As is the day's P&L, so a restart can't reset the loss limits
+        let risk_state_file = if dry_run {
+            "data/risk-state-sim.json"
+        } else {
+            "data/risk-state-live.json"
+        };
+
         // Initialize Jito Client (Optional)
-        let jito_client = if std::env::var("USE_JITO").unwrap_or("false".to_string()) == "true" {
-            let engine_url = std::env::var("JITO_BLOCK_ENGINE_URL")
-                .unwrap_or("https://mainnet.block-engine.jito.wtf".to_string());
-            let tip = std::env::var("JITO_TIP_LAMPORTS")
-                .unwrap_or("100000".to_string())
-                .parse()
-                .unwrap_or(100000);
+        let jito_client = if config.use_jito {
+            let client = JitoClient::new(&config.jito_block_engine_url, config.jito_tip_lamports)
+                .with_tip_accounts(&config.jito_tip_accounts)
+                .expect("Invalid JITO_TIP_ACCOUNTS");
+            client.validate().expect("Invalid Jito configuration");
             info!(
-                "🛡️ Jito MEV Protection enabled (Engine: {}, Tip: {} lamports)",
-                engine_url, tip
+                "🛡️ Jito MEV Protection enabled (Engine: {}, Tip: {} lamports to {} accounts)",
+                config.jito_block_engine_url,
+                config.jito_tip_lamports,
+                client.tip_accounts().len()
             );
-            Some(JitoClient::new(&engine_url, tip))
+            Some(client)
         } else {
             info!("⚠️ Jito MEV Protection DISABLED");
             None
@@ -247,9 +408,22 @@ impl BotState {
         let mut executor = Executor::with_config(execution::ExecutionConfig {
             priority_fee_micro_lamports: config.priority_fee_micro_lamports,
             compute_unit_limit: config.compute_unit_limit,
+            compute_unit_margin_bps: config.compute_unit_margin_bps,
             slippage_bps: config.slippage_bps,
             max_retries: config.max_retries,
             rpc_commitment: config.rpc_commitment.clone(),
+            max_quote_age_ms: config.max_quote_age_ms,
+            quote_timeout_ms: config.quote_timeout_ms,
+            swap_build_timeout_ms: config.swap_build_timeout_ms,
+            flash_loan_direct_routes_only: config.flash_loan_direct_routes_only,
+            dynamic_priority_fee: config.priority_fee_dynamic,
+            priority_fee_percentile: config.priority_fee_percentile,
+            min_priority_fee_micro_lamports: config.priority_fee_min,
+            max_priority_fee_micro_lamports: config.priority_fee_max,
+            priority_fee_cache_ms: config.priority_fee_cache_ms,
+            leg_divergence_margin_bps: config.leg_divergence_margin_bps,
+            jito_bundle_timeout_ms: config.jito_bundle_timeout_ms,
+            simulate_before_submit: config.simulate_before_submit,
         });
         
         // Initialize Rate Limiters
@@ -263,84 +437,307 @@ impl BotState {
             Some(jupiter_rate_limiter.clone())
         );
 
+        // Shared cap on in-flight RPC requests, reported via the in-flight gauge
+        let in_flight_gauge = metrics.rpc_requests_in_flight.clone();
+        let rpc_concurrency = Arc::new(
+            ConcurrencyLimiter::new(config.max_concurrent_rpc_requests)
+                .with_observer(move |in_flight| in_flight_gauge.set(in_flight as i64)),
+        );
+        executor.set_rpc_concurrency_limiter(Some(rpc_concurrency.clone()));
+
         executor.set_alt_manager(alt_manager.clone());
 
+        // Off unless a smoothing factor is configured; `Config::validated` keeps it in [0, 1]
+        let price_smoother = Decimal::from_f64(config.price_smoothing_factor)
+            .filter(|factor| *factor > Decimal::ZERO)
+            .and_then(|factor| PriceSmoother::new(factor).ok());
+        if let Some(smoother) = &price_smoother {
+            info!("📉 Detecting on prices smoothed with factor {}", smoother.factor());
+        }
+
         Self {
-            detector: ArbitrageDetector::default(),
+            detector: ArbitrageDetector::new(ArbitrageConfig {
+                jupiter_price_source: config.jupiter_price_source,
+                max_price_deviation_pct: config
+                    .max_price_deviation_pct
+                    .try_into()
+                    .unwrap_or(Decimal::TEN),
+                min_liquidity_usd: config.min_liquidity_usd.try_into().unwrap_or_default(),
+                allow_unknown_liquidity: config.allow_unknown_liquidity,
+                ..Default::default()
+            }),
             path_finder: PathFinder::new(4),
-            risk_manager: RiskManager::new(risk_config),
+            risk_manager: RiskManager::new(risk_config)
+                .with_dedupe_file(dedupe_file)
+                .with_state_file(risk_state_file),
             dex_manager,
             price_fetcher,
             executor,
             wallet: Wallet::new().expect("Failed to load wallet"),
-            flash_loan_provider,
+            flash_loan_router,
             history_recorder,
+            pg_history: None,
             jito_client,
             alt_manager,
             strategies,
-            is_running: true,
+            trade_gate: Arc::new(TradeGate::new()),
             dry_run,
+            paper_portfolio: dry_run.then(|| {
+                PaperPortfolio::new(config.paper_starting_balances.iter().map(|(token, amount)| {
+                    (token.clone(), Decimal::from_f64(*amount).unwrap_or_default())
+                }))
+            }),
             rpc_url: config.solana_rpc_url.clone(),
             max_price_age_seconds: config.max_price_age_seconds,
+            price_watchdog: PriceWatchdog::for_max_price_age(config.max_price_age_seconds),
+            watchdog_opened_breaker: false,
+            price_smoother,
+            jupiter_fees,
+            meteora_fees,
+            opportunity_half_life: Duration::from_millis(config.opportunity_half_life_ms),
+            max_position_hold: chrono::Duration::seconds(config.max_position_hold_seconds),
+            tx_costs: TxCosts {
+                priority_fee_micro_lamports: config.priority_fee_micro_lamports,
+                compute_unit_limit: config.compute_unit_limit,
+                jito_tip_lamports: if config.use_jito {
+                    config.jito_tip_lamports
+                } else {
+                    0
+                },
+            },
+            oracle: (config.oracle_max_deviation_pct > 0.0).then(PythPriceFeed::new),
+            oracle_max_deviation_pct: config
+                .oracle_max_deviation_pct
+                .try_into()
+                .unwrap_or(Decimal::ZERO),
             metrics,
             alert_manager,
             system_health,
             event_bus: Arc::new(EventBus::new(1000)),
+            opportunity_feed: OpportunityFeed::default(),
             consecutive_errors: 0,
             rpc_rate_limiter,
             jupiter_rate_limiter,
+            rpc_concurrency,
             config_manager,
+            trading_paused: false,
+            enabled_pairs: None,
+        }
+    }
+
+    /// Fetches prices from `providers` in place of the configured DEXs
+    #[cfg(test)]
+    fn with_providers(mut self, providers: Vec<Arc<dyn DexProvider>>) -> Self {
+        let mut dex_manager = DexManager::new().with_health(self.dex_manager.health());
+        for provider in providers {
+            dex_manager.add_provider(provider);
         }
+        self.price_fetcher = ParallelPriceFetcher::new(dex_manager.providers().to_vec())
+            .with_health(dex_manager.health());
+        self.dex_manager = dex_manager;
+        self.jupiter_fees = None;
+        self.meteora_fees = None;
+        self
+    }
+
+    /// Trades through `executor` in place of the one built from the config
+    #[cfg(test)]
+    fn with_executor(mut self, executor: Executor) -> Self {
+        self.executor = executor;
+        self
     }
     
 
 
-    /// Check risk parameters and calculate position size
-    async fn check_risk_and_size(&self, opp: &solana_arb_core::ArbitrageOpportunity) -> (bool, TradeDecision, String) {
-        let optimal_size = self.risk_manager.calculate_position_size(
-            &opp.pair.symbol(),
-            opp.net_profit_pct,
-            Decimal::from(10000), // Assume high liquidity for now or get from opp
-        );
+    /// Calculate position size and commit it against the live risk state,
+    /// reserving the approved size as exposure until the trade settles
+    async fn commit_risk_and_size(
+        &mut self,
+        opp: &solana_arb_core::ArbitrageOpportunity,
+        max_size: Decimal,
+    ) -> (bool, TradeDecision, String) {
+        let optimal_size = self
+            .risk_manager
+            .calculate_position_size(
+                &opp.pair.symbol(),
+                opp.net_profit_pct,
+                Decimal::from(10000), // Assume high liquidity for now or get from opp
+            )
+            .min(max_size);
 
         let decision = self
             .risk_manager
-            .can_trade(&opp.pair.symbol(), optimal_size)
+            .commit(&opp.pair.symbol(), optimal_size)
             .await;
             
         (self.dry_run, decision, self.rpc_url.clone())
     }
 
-    /// Check if a flash loan is viable and return the quote if so
-    async fn check_flash_loan(&self, opp: &solana_arb_core::ArbitrageOpportunity, size: Decimal) -> Option<solana_arb_flash_loans::FlashLoanQuote> {
-        if let Some(mint) = resolve_mint(&opp.pair.base) {
-            // Assume borrowing base asset
-            match self.flash_loan_provider.get_quote(mint, size).await {
-                Ok(quote) => {
-                    // Simplified: fee is in base token.
-                    let fee_pct = (quote.fee / size) * Decimal::from(100);
-
-                    if opp.net_profit_pct > fee_pct {
-                        info!(
-                            "⚡ Flash Loan Viable! Borrowing {} {} costs {} {} ({:.4}%) - Net edge: {:.4}%",
-                            size, opp.pair.base, quote.fee, opp.pair.base, fee_pct, opp.net_profit_pct - fee_pct
-                        );
-                        Some(quote)
-                    } else {
-                        debug!(
-                            "Flash Loan fee too high: {:.4}% > {:.4}% profit",
-                            fee_pct, opp.net_profit_pct
-                        );
-                        None
-                    }
+    /// Apply the runtime-safe settings that changed in a config reload, all at once
+    /// under the caller's write lock. Restart-only settings are logged and ignored.
+    fn apply_config_change(&mut self, change: &ConfigChange) {
+        let (old, new) = (&change.previous.trading, &change.current.trading);
+
+        if new.min_profit_bps != old.min_profit_bps {
+            let pct =
+                Decimal::from_f64(new.min_profit_bps).unwrap_or_default() / Decimal::ONE_HUNDRED;
+            self.detector.set_min_profit_threshold(pct);
+            info!("🔧 Minimum profit now {}%", pct);
+        }
+        if new.max_slippage_bps != old.max_slippage_bps {
+            self.executor.set_slippage_bps(new.max_slippage_bps);
+            info!("🔧 Slippage tolerance now {} bps", new.max_slippage_bps);
+        }
+        if let Some(fee) = new.priority_fee_micro_lamports {
+            if new.priority_fee_micro_lamports != old.priority_fee_micro_lamports {
+                self.executor.set_priority_fee(fee);
+                self.tx_costs.priority_fee_micro_lamports = fee;
+                info!("🔧 Priority fee now {} micro-lamports/CU", fee);
+            }
+        }
+        if new.enabled_pairs != old.enabled_pairs {
+            self.enabled_pairs = new.enabled_pairs.clone();
+            match &self.enabled_pairs {
+                Some(pairs) => info!("🔧 Trading limited to {}", pairs.join(", ")),
+                None => info!("🔧 Trading every configured pair"),
+            }
+        }
+
+        for (key, value) in &change.current.restart_only {
+            if change.previous.restart_only.get(key) != Some(value) {
+                warn!("⚠️ Config key '{}' only applies after a restart; ignored", key);
+            }
+        }
+    }
+
+    /// The change from the settings the bot was built with (the environment's)
+    /// to `loaded`, so the config file's trading settings apply from startup
+    /// rather than only after its first edit
+    fn startup_config_change(&self, loaded: DynamicConfig) -> ConfigChange {
+        let mut previous = loaded.clone();
+        previous.trading = TradingConfig {
+            min_profit_bps: (self.detector.min_profit_threshold() * Decimal::ONE_HUNDRED)
+                .to_f64()
+                .unwrap_or_default(),
+            max_slippage_bps: self.executor.slippage_bps(),
+            priority_fee_micro_lamports: Some(self.tx_costs.priority_fee_micro_lamports),
+            enabled_pairs: self.enabled_pairs.clone(),
+            ..loaded.trading.clone()
+        };
+        ConfigChange {
+            previous,
+            current: loaded,
+        }
+    }
+
+    /// Whether `pair` may be traded under the dynamic config
+    fn is_pair_enabled(&self, pair: &TokenPair) -> bool {
+        self.enabled_pairs.as_ref().is_none_or(|pairs| {
+            pairs.iter().any(|enabled| enabled.eq_ignore_ascii_case(&pair.symbol()))
+        })
+    }
+
+    /// Run the price watchdog at `now`, returning whether trading may go on.
+    /// A stalled feed opens the circuit breaker and alarms once; trading stays
+    /// halted until a fetch returns prices again. Resuming closes the breaker
+    /// only if the stall opened it.
+    async fn watch_price_feed(&mut self, now: chrono::DateTime<Utc>) -> bool {
+        match self.price_watchdog.check(now) {
+            Some(FeedTransition::Stalled { silent_for }) => {
+                let msg = format!(
+                    "🛑 No prices received for {}s - price feeds stalled, trading halted",
+                    silent_for.num_seconds()
+                );
+                error!(last_update = ?self.price_watchdog.last_update(), "{}", msg);
+                self.metrics.price_feed_stalled.set(1);
+                let breaker = &self.risk_manager.circuit_breaker;
+                self.watchdog_opened_breaker =
+                    matches!(breaker.state().await, CircuitState::Closed);
+                breaker.trip().await;
+                self.alert_manager.send_critical(&msg).await;
+            }
+            Some(FeedTransition::Resumed) => {
+                info!("✅ Prices are arriving again - price watchdog cleared");
+                self.metrics.price_feed_stalled.set(0);
+                // A breaker opened for the stall needn't sit out its cooldown;
+                // one tripped by failed trades recovers the usual way
+                if std::mem::take(&mut self.watchdog_opened_breaker) {
+                    self.risk_manager.circuit_breaker.close().await;
                 }
-                Err(e) => {
-                    warn!("Failed to get flash loan quote: {}", e);
-                    None
+            }
+            None => {}
+        }
+        !self.price_watchdog.is_stalled()
+    }
+
+    /// Compare `opp`'s buy and sell prices with Pyth's, when the oracle check is
+    /// enabled and both tokens have feeds
+    async fn check_oracle(
+        &self,
+        opp: &solana_arb_core::ArbitrageOpportunity,
+    ) -> solana_arb_core::ArbitrageResult<()> {
+        let Some(oracle) = &self.oracle else {
+            return Ok(());
+        };
+        // Multi-hop prices aren't quoted in the pair's tokens
+        if opp.path.is_some()
+            || !oracle.has_feed(&opp.pair.base)
+            || !oracle.has_feed(&opp.pair.quote)
+        {
+            return Ok(());
+        }
+        let price = oracle.pair_price(&opp.pair).await?;
+        check_against_oracle(opp.buy_price, opp.sell_price, &price, self.oracle_max_deviation_pct)
+    }
+
+    /// Check if a flash loan is viable and return the quote if so. A base token
+    /// without a known mint is an error rather than a silently skipped check.
+    async fn check_flash_loan(
+        &self,
+        opp: &solana_arb_core::ArbitrageOpportunity,
+        size: Decimal,
+    ) -> Result<Option<solana_arb_flash_loans::FlashLoanQuote>> {
+        // Assume borrowing base asset
+        let mint = self.executor.tokens().pubkey(&opp.pair.base)?;
+        match self.flash_loan_router.get_best_quote(mint, size).await {
+            Ok((provider, quote)) => {
+                // Simplified: fee is in base token.
+                let fee_pct = (quote.fee / size) * Decimal::from(100);
+
+                if opp.net_profit_pct > fee_pct {
+                    info!(
+                        "⚡ Flash Loan Viable! Borrowing {} {} from {} costs {} {} ({:.4}%) - Net edge: {:.4}%",
+                        size, opp.pair.base, provider, quote.fee, opp.pair.base, fee_pct, opp.net_profit_pct - fee_pct
+                    );
+                    Ok(Some(quote))
+                } else {
+                    debug!(
+                        "Flash Loan fee too high: {:.4}% > {:.4}% profit",
+                        fee_pct, opp.net_profit_pct
+                    );
+                    Ok(None)
                 }
             }
-        } else {
-            None
+            Err(e) => {
+                warn!("Failed to get flash loan quote: {}", e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Let every strategy learn from an execution result (success or failure)
+    async fn notify_strategies(&self, result: &TradeResult) {
+        for strategy in &self.strategies {
+            strategy.on_trade_result(result.opportunity_id, result).await;
+        }
+    }
+
+    /// Append a trade record to the history file, and queue a copy for the
+    /// database when it is enabled
+    async fn write_history(&self, record: TradeRecord) {
+        match &self.pg_history {
+            Some(pg) => pg.record(record).await,
+            None => self.history_recorder.append(&record),
         }
     }
 
@@ -367,62 +764,165 @@ impl BotState {
         } else {
             metrics.trades_failed.inc();
         }
+        if let Some(slippage_bps) = outcome.realized_slippage.and_then(|s| s.to_f64()) {
+            metrics.slippage_distribution.observe(slippage_bps);
+        }
+
+        // Realized PnL where it could be measured. When it couldn't, nothing is
+        // booked: the estimate would feed the loss limits a result that may
+        // never have happened, so the trade is flagged for a manual check instead
+        if success && outcome.actual_profit.is_none() {
+            let msg = format!(
+                "⚠️ Realized profit of trade {} on {} unknown (estimated {:?}); booked as zero, \
+                 check the wallet",
+                opp.id,
+                opp.pair,
+                opp.net_profit_usd(size).ok()
+            );
+            warn!("{}", msg);
+            self.alert_manager.send_critical(&msg).await;
+        }
+        let profit = outcome.actual_profit.unwrap_or(Decimal::ZERO);
+        let quote_usd_price = self.detector.usd_price(&opp.pair.quote);
+        let profit_usd = self.profit_in_usd(opp, profit).unwrap_or_else(|| {
+            warn!(
+                "No USD price for {}; booking trade {} on {} as zero",
+                opp.pair.quote, opp.id, opp.pair
+            );
+            Decimal::ZERO
+        });
 
         // 2. EventBus
-        let profit_usd = if success {
-             opp.estimated_profit_usd.unwrap_or_default().to_f64().unwrap_or(0.0)
-        } else {
-             0.0
-        };
-        
         self.event_bus.publish(TradingEvent::TradeExecuted {
             id: opp.id.to_string(),
             pair: pair_symbol.to_string(),
             success,
-            profit: profit_usd,
+            profit: profit_usd.to_f64().unwrap_or(0.0),
             execution_time_ms,
         });
 
         // 3. History Recorder
-        let (est_profit, tx_sig, error_msg) = if success {
-             (
-                 (size * opp.net_profit_pct) / Decimal::from(100),
-                 outcome.signature.clone(),
-                 None
-             )
+        let (tx_sig, error_msg) = if success {
+            (outcome.signature.clone(), None)
         } else {
-             (
-                 Decimal::ZERO,
-                 None,
-                 outcome.error.clone().or(Some("Unknown error".to_string()))
-             )
+            (
+                None,
+                outcome.error.clone().or(Some("Unknown error".to_string())),
+            )
         };
         
-        self.history_recorder.record_trade(
+        let record = self.history_recorder.build_record(
             opp,
             size,
-            est_profit,
+            profit,
+            quote_usd_price,
             success,
             tx_sig,
             error_msg,
             false,
+            outcome.latency.as_ref(),
         );
+        self.write_history(record).await;
 
         // 4. Return outcome for Risk Manager
         TradeOutcome {
             timestamp: Utc::now(),
             pair: pair_symbol.to_string(),
-            profit_loss: est_profit,
+            profit_loss: profit_usd,
             was_successful: success,
         }
     }
+
+    /// `profit` in `opp`'s quote token converted to USD at the cached price of
+    /// that token, which is what risk limits and events are kept in. `None`
+    /// without a price: quote-token amounts are never counted as dollars.
+    fn profit_in_usd(
+        &self,
+        opp: &solana_arb_core::ArbitrageOpportunity,
+        profit: Decimal,
+    ) -> Option<Decimal> {
+        let quote_usd_price = self.detector.usd_price(&opp.pair.quote);
+        opp.quote_to_usd(profit, quote_usd_price)
+    }
+}
+
+/// Handle on a running bot's state, used to stop it cleanly
+#[derive(Clone)]
+struct BotHandle {
+    state: Arc<RwLock<BotState>>,
+    stopped: Arc<tokio::sync::OnceCell<()>>,
+}
+
+impl BotHandle {
+    fn new(state: Arc<RwLock<BotState>>) -> Self {
+        Self {
+            state,
+            stopped: Arc::new(tokio::sync::OnceCell::new()),
+        }
+    }
+
+    /// Stop trading: refuse new trades, wait for the one in flight to be fully
+    /// recorded, flush queued trade history and log a final risk summary.
+    /// Runs once; concurrent and later calls wait for that run to finish.
+    async fn shutdown(&self, reason: &str) {
+        self.stopped.get_or_init(|| self.stop(reason)).await;
+    }
+
+    async fn stop(&self, reason: &str) {
+        info!("🛑 Shutting down: {}", reason);
+        let (trade_gate, event_bus) = {
+            let state = self.state.read().await;
+            (state.trade_gate.clone(), state.event_bus.clone())
+        };
+        event_bus.publish(TradingEvent::SystemStopping {
+            reason: reason.to_string(),
+        });
+        trade_gate.stop().await;
+
+        let state = self.state.read().await;
+        if let Some(pg) = &state.pg_history {
+            pg.flush().await;
+        }
+
+        let status = state.risk_manager.status().await;
+        info!(
+            "📊 Final risk summary - Exposure: ${:.2}, Open positions: {}, P&L: ${:.2}, Trades: {}, Paused: {}",
+            status.total_exposure,
+            status.positions.len(),
+            status.daily_pnl,
+            status.trades_today,
+            status.is_paused
+        );
+
+        state.system_health.write().await.is_running = false;
+    }
+}
+
+/// Span shared by every log line for one opportunity, from detection through confirmation.
+/// Trade details live here as fields rather than in message text, so JSON logs can be
+/// queried by pair, DEX or profit.
+fn trade_span(opp: &solana_arb_core::ArbitrageOpportunity) -> tracing::Span {
+    info_span!(
+        "trade",
+        opportunity_id = %opp.id,
+        pair = %opp.pair.symbol(),
+        buy_dex = %opp.buy_dex,
+        sell_dex = %opp.sell_dex,
+        profit_pct = %opp.net_profit_pct,
+    )
 }
 
 /// Main trading loop that orchestrates price collection, opportunity detection, and execution.
 ///
 /// Runs indefinitely until a stop signal is received or a critical error occurs.
-async fn run_trading_loop(state: Arc<RwLock<BotState>>, pairs: Vec<TokenPair>) {
+async fn run_trading_loop(state: Arc<RwLock<BotState>>, mut pair_scheduler: PairScheduler) {
     info!("🤖 Trading bot started");
+    if pair_scheduler.is_capped() {
+        info!(
+            "🔄 Monitoring {} pairs with a per-tick cap; rotating the rest",
+            pair_scheduler.pairs().len()
+        );
+    }
 
     // Publish startup event
     {
@@ -461,6 +961,10 @@ async fn run_trading_loop(state: Arc<RwLock<BotState>>, pairs: Vec<TokenPair>) {
 
     let mut tick = 0u64;
     let mut last_balance_check = Instant::now();
+    let mut last_jupiter_fee_refresh: Option<Instant> = None;
+    let mut last_meteora_fee_refresh: Option<Instant> = None;
+    // Stale positions already alerted on, so each is reported once
+    let mut alerted_positions: std::collections::HashSet<String> = std::collections::HashSet::new();
 
     loop {
         // 1. Check Kill Switch
@@ -483,7 +987,7 @@ async fn run_trading_loop(state: Arc<RwLock<BotState>>, pairs: Vec<TokenPair>) {
             // Check if still running (internal state)
             {
                 let state = state.read().await;
-                if !state.is_running {
+                if !state.trade_gate.is_running() {
                     return Ok::<_, anyhow::Error>(false); // Stop signal
                 }
                 
@@ -494,6 +998,12 @@ async fn run_trading_loop(state: Arc<RwLock<BotState>>, pairs: Vec<TokenPair>) {
                     tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
                     return Ok(true);
                 }
+
+                if state.trading_paused {
+                    debug!("⏸️ Trading paused via admin API. Sleeping...");
+                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                    return Ok(true);
+                }
             }
 
             tick += 1;
@@ -503,8 +1013,9 @@ async fn run_trading_loop(state: Arc<RwLock<BotState>>, pairs: Vec<TokenPair>) {
                 let state = state.read().await;
                 let status = state.risk_manager.status().await;
                 info!(
-                    "📊 Status - Exposure: ${:.2}, VaR (95%): ${:.2}, P&L: ${:.2}, Trades: {}, Paused: {}",
+                    "📊 Status - Exposure: ${:.2}, VaR ({:.0}%): ${:.2}, P&L: ${:.2}, Trades: {}, Paused: {}",
                     status.total_exposure,
+                    status.var_confidence_level * 100.0,
                     status.portfolio_var,
                     status.daily_pnl,
                     status.trades_today,
@@ -517,89 +1028,64 @@ async fn run_trading_loop(state: Arc<RwLock<BotState>>, pairs: Vec<TokenPair>) {
                 health.total_trades = status.trades_today as u64;
             }
 
-            let start = std::time::Instant::now();
-
-            // Collect prices
-            let recent_prices = match collect_prices(&state, &pairs).await {
-                Ok(p) => {
-                    // Reset consecutive errors on success
-                    state.write().await.consecutive_errors = 0;
-                    p
-                }
-                Err(e) => {
-                    return Err(anyhow::anyhow!("Failed to collect prices: {}", e));
-                }
-            };
-
+            // Collect prices for this tick's subset of pairs
+            let mut pairs = pair_scheduler.next_batch();
             {
                 let state = state.read().await;
-                state
-                    .metrics
-                    .price_fetch_latency
-                    .observe(start.elapsed().as_secs_f64());
+                pairs.retain(|pair| state.is_pair_enabled(pair));
+            }
+            // Nothing comes back while the price feed is stalled
+            let Some(opportunities) = trading_tick(&state, &pairs).await? else {
+                return Ok(true);
+            };
+            for pair in &pairs {
+                let found = opportunities.iter().filter(|o| &o.pair == pair).count();
+                pair_scheduler.record_opportunities(pair, found);
             }
 
-            // Find and evaluate opportunities
-            let opportunities = {
+            // Flag trades whose legs never all settled
+            {
                 let state = state.read().await;
-                let mut opps = state.detector.find_all_opportunities();
-                let _paths = state.path_finder.find_all_profitable_paths();
-
-                state
-                    .metrics
-                    .opportunities_detected
-                    .inc_by(opps.len() as u64);
-                
-                // Execute Strategies
-                for strategy in &state.strategies {
-                    if let Ok(strategy_opps) = strategy.analyze(&recent_prices).await {
-                         opps.extend(strategy_opps);
+                let stale = state.risk_manager.stale_positions(state.max_position_hold);
+                alerted_positions.retain(|id| stale.iter().any(|p| &p.id == id));
+                for position in stale {
+                    if alerted_positions.insert(position.id.clone()) {
+                        let msg = format!(
+                            "⚠️ Position {} on {} (${}) unsettled since {}; flatten it",
+                            position.id, position.pair, position.size, position.opened_at
+                        );
+                        warn!("{}", msg);
+                        state.alert_manager.send_critical(&msg).await;
                     }
                 }
-                opps
-            };
-
-            if !opportunities.is_empty() {
-                let state_read = state.read().await;
-                let mut health = state_read.system_health.write().await;
-                health.last_opportunity_time = Some(Instant::now());
             }
 
-            // Execute best opportunity
-            for opp in opportunities.iter().take(1) {
-                // ... (Execution logic same as before, calling execute_trade)
-                 let should_execute = {
-                    let state = state.read().await;
-                    let config = state.config_manager.get().await;
-                    let min_profit_bps = Decimal::from_f64(config.trading.min_profit_bps).unwrap_or_default();
-                    let min_profit_pct = min_profit_bps / Decimal::from(100);
-
-                    if opp.net_profit_pct < min_profit_pct {
-                         debug!("Skipping opportunity: Profit {}% < Min {}%", opp.net_profit_pct, min_profit_pct);
-                        false
-                    } else {
-                        let optimal_size = state.risk_manager.calculate_position_size(
-                            &opp.pair.symbol(),
-                            opp.net_profit_pct,
-                            Decimal::from(10000),
-                        );
-                        let decision = state.risk_manager.can_trade(&opp.pair.symbol(), optimal_size).await;
-                        matches!(decision, TradeDecision::Approved { .. } | TradeDecision::Reduced { .. })
-                    }
-                };
+            // Jupiter route fees, once prices can size the quotes
+            if last_jupiter_fee_refresh.is_none_or(|at| at.elapsed() > JUPITER_FEE_REFRESH_INTERVAL)
+            {
+                last_jupiter_fee_refresh = Some(Instant::now());
+                tokio::spawn(refresh_jupiter_fees(state.clone(), pair_scheduler.pairs().to_vec()));
+            }
 
-                if should_execute {
-                    execute_trade(&state, opp).await;
-                }
+            // Meteora pool fees, in place of the static Meteora default
+            if last_meteora_fee_refresh.is_none_or(|at| at.elapsed() > METEORA_FEE_REFRESH_INTERVAL)
+            {
+                last_meteora_fee_refresh = Some(Instant::now());
+                tokio::spawn(refresh_meteora_fees(state.clone(), pair_scheduler.pairs().to_vec()));
             }
 
             // Balance Check
             if last_balance_check.elapsed() > Duration::from_secs(600) {
                  last_balance_check = Instant::now();
                  // Logic to check balance
-                 let (rpc_url, pubkey_str, alert_manager) = {
+                 let (rpc_url, pubkey_str, alert_manager, rpc_concurrency) = {
                      let state = state.read().await;
-                     (state.rpc_url.clone(), state.wallet.pubkey(), state.alert_manager.clone())
+                     (
+                         state.rpc_url.clone(),
+                         state.wallet.pubkey(),
+                         state.alert_manager.clone(),
+                         state.rpc_concurrency.clone(),
+                     )
                  };
                  
                  // Spawn check
@@ -609,7 +1095,11 @@ async fn run_trading_loop(state: Arc<RwLock<BotState>>, pairs: Vec<TokenPair>) {
                      use solana_sdk::pubkey::Pubkey;
                      let client = RpcClient::new(rpc_url);
                      if let Ok(pubkey) = Pubkey::from_str(&pubkey_str) {
-                         if let Ok(balance) = client.get_balance(&pubkey).await {
+                         let balance = {
+                             let _rpc_slot = rpc_concurrency.acquire().await;
+                             client.get_balance(&pubkey).await
+                         };
+                         if let Ok(balance) = balance {
                              let balance_sol = balance as f64 / 1_000_000_000.0;
                              
                              // Get system_health Arc and drop state lock
@@ -675,6 +1165,224 @@ async fn run_trading_loop(state: Arc<RwLock<BotState>>, pairs: Vec<TokenPair>) {
     }
 }
 
+/// One pass over `pairs`: collect their prices, find and rank opportunities and
+/// execute those risk allows. Returns the opportunities found, or `None` while
+/// the price feed is stalled and nothing was traded.
+async fn trading_tick(
+    state: &Arc<RwLock<BotState>>,
+    pairs: &[TokenPair],
+) -> Result<Option<Vec<solana_arb_core::ArbitrageOpportunity>>> {
+    let start = std::time::Instant::now();
+
+    let recent_prices = match collect_prices(state, pairs).await {
+        Ok(p) => {
+            // Reset consecutive errors on success
+            state.write().await.consecutive_errors = 0;
+            p
+        }
+        Err(e) => {
+            return Err(anyhow::anyhow!("Failed to collect prices: {}", e));
+        }
+    };
+
+    {
+        let state = state.read().await;
+        state
+            .metrics
+            .price_fetch_latency
+            .observe(start.elapsed().as_secs_f64());
+    }
+
+    // Keep polling, but trade nothing until prices arrive again
+    if !state.write().await.watch_price_feed(Utc::now()).await {
+        return Ok(None);
+    }
+
+    // Find and evaluate opportunities
+    let opportunities = {
+        let state = state.read().await;
+        let mut opps = state.detector.find_all_opportunities();
+        let _paths = state.path_finder.find_all_profitable_paths();
+
+        state
+            .metrics
+            .opportunities_detected
+            .inc_by(opps.len() as u64);
+        
+        // Execute Strategies
+        for strategy in &state.strategies {
+            // Skip pairs the strategy is still warming up on
+            let mut ready_prices = Vec::with_capacity(recent_prices.len());
+            for price in &recent_prices {
+                if strategy.has_sufficient_history(&price.pair).await {
+                    ready_prices.push(price.clone());
+                }
+            }
+            if ready_prices.is_empty() {
+                continue;
+            }
+
+            if let Ok(strategy_opps) = strategy.analyze(&ready_prices).await {
+                 opps.extend(strategy_opps);
+            }
+        }
+
+        // Best first, discounting older opportunities
+        rank_by_aged_score(&mut opps, state.opportunity_half_life);
+        state.metrics.record_tick_opportunities(&opps);
+        opps
+    };
+
+    if !opportunities.is_empty() {
+        let mut state = state.write().await;
+        state.detector.track(&opportunities);
+        for opp in &opportunities {
+            state.opportunity_feed.publish(opp);
+        }
+    }
+
+    if !opportunities.is_empty() {
+        let state_read = state.read().await;
+        let mut health = state_read.system_health.write().await;
+        health.last_opportunity_time = Some(Instant::now());
+    }
+
+    // One consistent view of risk for every candidate this tick;
+    // execute_trade re-validates against the live state when it commits
+    let risk_snapshot = state.read().await.risk_manager.snapshot().await;
+
+    // Spread the exposure still available over the best opportunities, one per pair.
+    // Nothing is executed while the circuit breaker or kill switch is on
+    let selected = if risk_snapshot.trading_halted {
+        if !opportunities.is_empty() {
+            match &risk_snapshot.kill_switch {
+                Some(reason) => info!(
+                    "🛑 {} - skipping {} opportunities until UTC midnight",
+                    reason,
+                    opportunities.len()
+                ),
+                None => {
+                    let remaining =
+                        risk_snapshot.circuit_cooldown_remaining.unwrap_or_default();
+                    info!(
+                        "🔴 Circuit breaker open - skipping {} opportunities, retry in {}s",
+                        opportunities.len(),
+                        remaining.as_secs()
+                    );
+                }
+            }
+        }
+        Vec::new()
+    } else {
+        state
+            .read()
+            .await
+            .detector
+            .select_opportunities(&opportunities, risk_snapshot.available_exposure())
+    };
+
+    for (opp, allocation) in &selected {
+        // Everything from here to confirmation is logged inside the trade's span
+        async {
+            debug!("Opportunity detected");
+
+            let should_execute = {
+                let state = state.read().await;
+                let config = state.config_manager.get().await;
+                let min_profit_bps = Decimal::from_f64(config.trading.min_profit_bps).unwrap_or_default();
+                let min_profit_pct = min_profit_bps / Decimal::from(100);
+
+                let optimal_size = state
+                    .risk_manager
+                    .calculate_position_size(
+                        &opp.pair.symbol(),
+                        opp.net_profit_pct,
+                        Decimal::from(10000),
+                    )
+                    .min(*allocation);
+
+                // Judge the trade on what is left after gas and tip, not DEX fees alone
+                let breakdown = state.detector.profit_breakdown(opp, optimal_size, &state.tx_costs);
+                let profitable = match breakdown {
+                    Ok(breakdown) if breakdown.net_profit_pct() < min_profit_pct => {
+                        debug!(
+                            min_profit_pct = %min_profit_pct,
+                            net_profit = %breakdown.net_profit,
+                            tx_costs = %breakdown.tx_costs(),
+                            "Skipping opportunity below minimum profit after costs"
+                        );
+                        false
+                    }
+                    Ok(_) => true,
+                    // No SOL price in the quote currency (e.g. SOL/USDC isn't monitored):
+                    // the trade can't be judged after costs, so leave it alone
+                    Err(e) => {
+                        debug!(error = %e, "Skipping opportunity whose costs can't be priced");
+                        false
+                    }
+                };
+                profitable
+                    && matches!(
+                        risk_snapshot.evaluate(optimal_size),
+                        TradeDecision::Approved { .. } | TradeDecision::Reduced { .. }
+                    )
+            };
+
+            if should_execute {
+                execute_trade(state, opp, *allocation).await;
+            }
+        }
+        .instrument(trade_span(opp))
+        .await;
+    }
+
+    Ok(Some(opportunities))
+}
+
+/// Re-quote Jupiter's routes for `pairs` and charge their fees on Jupiter
+/// prices, in place of the zero `DexType::Jupiter` defaults to
+async fn refresh_jupiter_fees(state: Arc<RwLock<BotState>>, pairs: Vec<TokenPair>) {
+    let (jupiter, sized) = {
+        let state = state.read().await;
+        let Some(jupiter) = state.jupiter_fees.clone() else {
+            return;
+        };
+        // Pairs whose quote token has no USD price yet wait for the next refresh
+        let sized: Vec<(TokenPair, Decimal)> = pairs
+            .into_iter()
+            .filter_map(|pair| {
+                let usd = state.detector.usd_price(&pair.quote).filter(|p| !p.is_zero())?;
+                Some((pair, Decimal::from(JUPITER_FEE_QUOTE_USD) / usd))
+            })
+            .collect();
+        (jupiter, sized)
+    };
+    if sized.is_empty() {
+        return;
+    }
+
+    jupiter.refresh_route_fees(&sized).await;
+
+    let mut state = state.write().await;
+    for (pair, _) in sized {
+        if let Some(fee) = jupiter.route_fee(&pair) {
+            debug!("Jupiter route fee for {}: {:.4}%", pair, fee * Decimal::ONE_HUNDRED);
+            state.detector.register_fee(DexType::Jupiter, pair, fee);
+        }
+    }
+}
+
+/// Re-read Meteora's pool fees for `pairs` and charge them on Meteora prices,
+/// in place of the static `DexType::Meteora` default
+async fn refresh_meteora_fees(state: Arc<RwLock<BotState>>, pairs: Vec<TokenPair>) {
+    let Some(meteora) = state.read().await.meteora_fees.clone() else {
+        return;
+    };
+    let mut fees = FeeSchedule::new();
+    meteora.update_fee_schedule(&mut fees, &pairs).await;
+    state.write().await.detector.register_fees(fees);
+}
+
 /// Collects recent price data from all registered DEX providers.
 ///
 /// Updates the local state with new prices, clears stale data, and updates
@@ -687,7 +1395,8 @@ async fn collect_prices(
         let state = state.read().await;
 
         // Use parallel fetcher for all pairs at once!
-        let all_prices = state.price_fetcher.fetch_all_prices(pairs).await;
+        let (all_prices, report) = state.price_fetcher.fetch_all_prices_with_report(pairs).await;
+        state.metrics.record_fetch_report(&report);
         info!(
             "💓 Parallel fetch complete — {} prices collected",
             all_prices.len()
@@ -701,15 +1410,38 @@ async fn collect_prices(
     {
         let mut state = state.write().await;
 
-        // Update detector
-        state.detector.update_prices(prices.clone());
+        if !prices.is_empty() {
+            let now = Utc::now();
+            state.price_watchdog.record_update(now);
+            state.metrics.last_price_update.set(now.timestamp());
+            state.system_health.write().await.last_price_update = Some(now);
+        }
+
+        // Update detector, on smoothed prices if enabled; the rest see them raw
+        let detection_prices = match state.price_smoother.as_mut() {
+            Some(smoother) => smoother.smooth(&prices),
+            None => prices.clone(),
+        };
+        state.detector.update_prices(detection_prices);
         let max_age = state.max_price_age_seconds;
         state.detector.clear_stale_prices(max_age);
+        state.detector.sanitize_prices();
+
+        // Issued opportunities whose prices went stale are no longer tradeable
+        let expired = state
+            .detector
+            .expire_stale(Duration::from_secs(max_age.max(0) as u64));
+        if !expired.is_empty() {
+            debug!("{} opportunities expired", expired.len());
+            state.metrics.opportunities_expired.inc_by(expired.len() as u64);
+        }
 
         // Update pathfinder
         state.path_finder.clear();
         for price in &prices {
-            state.path_finder.add_price(price);
+            if state.detector.is_price_source(price.dex) {
+                state.path_finder.add_price(price);
+            }
         }
 
         // Update risk manager volatility tracking
@@ -759,7 +1491,7 @@ fn validate_dex_coverage(prices: &[solana_arb_core::PriceData], pairs: &[TokenPa
     }
 }
 
-/// Executes a detected arbitrage opportunity.
+/// Executes a detected arbitrage opportunity with at most `max_size` of capital.
 ///
 /// This function handles:
 /// 1. Risk checks and position sizing
@@ -767,27 +1499,75 @@ fn validate_dex_coverage(prices: &[solana_arb_core::PriceData], pairs: &[TokenPa
 /// 3. Dry-run simulation (if enabled)
 /// 4. Actual trade execution via the Executor
 /// 5. Outcome recording (Metrics, History, Risk Manager)
-async fn execute_trade(state: &Arc<RwLock<BotState>>, opp: &solana_arb_core::ArbitrageOpportunity) {
+async fn execute_trade(
+    state: &Arc<RwLock<BotState>>,
+    opp: &solana_arb_core::ArbitrageOpportunity,
+    max_size: Decimal,
+) {
     let start_time = std::time::Instant::now();
     let pair_symbol = opp.pair.symbol();
 
+    // Held until the outcome is recorded, so shutdown waits for this trade
+    let trade_gate = state.read().await.trade_gate.clone();
+    let Some(_in_flight) = trade_gate.enter().await else {
+        debug!("Shutting down - not starting trade");
+        return;
+    };
+
     // We need to release the read lock before acquiring write lock later,
     // AND calling async execution which shouldn't hold locks if possible.
     // However, Executor is stateless (HttpClient) so we can clone data needed.
 
+    // Don't act twice on the same opportunity (or on a blacklisted route)
+    if state.read().await.risk_manager.dedupe.is_duplicate(opp) {
+        debug!("Skipping recently executed opportunity");
+        return;
+    }
+
+    // Both DEXs far from an independent price is a stale feed, not a spread
+    if let Err(e) = state.read().await.check_oracle(opp).await {
+        warn!(error = %e, "Trade rejected by oracle check");
+        return;
+    }
+
+    // Risk limits and PnL are kept in USD, so a pair whose quote token has no
+    // USD price can't be valued and waits until one arrives
+    if state.read().await.profit_in_usd(opp, Decimal::ONE).is_none() {
+        warn!("No USD price for {}; skipping {}", opp.pair.quote, opp.pair);
+        return;
+    }
+
     let (is_dry_run, decision, rpc_url) = {
-        let state = state.read().await;
-        state.check_risk_and_size(opp).await
+        let mut state = state.write().await;
+        state.commit_risk_and_size(opp, max_size).await
     };
 
     let size = match decision {
         TradeDecision::Approved { size } => size,
         TradeDecision::Reduced { new_size, reason } => {
-            info!("Trade size reduced: {}", reason);
+            info!(size_usd = %new_size, reason = %reason, "Trade size reduced");
             new_size
         }
         TradeDecision::Rejected { reason } => {
-            debug!("Trade rejected: {}", reason);
+            debug!(reason = %reason, "Trade rejected");
+            return;
+        }
+    };
+    let position_id = opp.id.to_string();
+    state
+        .write()
+        .await
+        .risk_manager
+        .open_position(position_id.as_str(), &pair_symbol, size);
+
+    // Expected profit at this size; extreme inputs are rejected rather than traded on
+    let expected_profit = match opp.net_profit_usd(size) {
+        Ok(profit) => profit,
+        Err(e) => {
+            warn!(error = %e, "Trade rejected");
+            let mut state = state.write().await;
+            state.risk_manager.close_position(&position_id);
+            state.risk_manager.release(&pair_symbol, size);
             return;
         }
     };
@@ -801,53 +1581,81 @@ async fn execute_trade(state: &Arc<RwLock<BotState>>, opp: &solana_arb_core::Arb
     // Check Flash Loan Viability
     let _flash_loan_quote = {
         let state_read = state.read().await;
-        state_read.check_flash_loan(opp, size).await
+        state_read.check_flash_loan(opp, size).await.unwrap_or_else(|e| {
+            warn!("Flash loan check skipped: {}", e);
+            None
+        })
     };
 
     if is_dry_run {
         // Simulate trade
-        info!(
-            "🔵 [DRY RUN] Would execute: Buy {} on {}, Sell on {} | Size: ${} | Profit: {}%",
-            pair_symbol, opp.buy_dex, opp.sell_dex, size, opp.net_profit_pct
-        );
+        info!(size_usd = %size, "🔵 [DRY RUN] Would execute trade");
 
-        // Fetch quote simulation (optional)
-        {
+        // Quote the trade without building a transaction
+        let (simulation, quote) = {
             let state_read = state.read().await;
-            if let Err(e) = state_read
-                .executor
-                .execute(&state_read.wallet, opp, size, false, &rpc_url, None)
-                .await
-            {
-                warn!("Simulation execution failed: {}", e);
+            state_read.executor.dry_run(opp, size).await
+        };
+
+        // Apply it to the paper portfolio; one it can't afford isn't traded
+        if let Some(quote) = quote {
+            let mut state = state.write().await;
+            if let Some(portfolio) = state.paper_portfolio.as_mut() {
+                match PaperTrade::from_quote(opp, &quote)
+                    .and_then(|trade| portfolio.execute(&trade))
+                {
+                    Ok(pnl) => info!(pnl = %pnl, "📝 [DRY RUN] Paper trade applied"),
+                    Err(e) => {
+                        warn!(error = %e, "📝 [DRY RUN] Paper trade rejected");
+                        state.risk_manager.close_position(&position_id);
+                        state.risk_manager.release(&pair_symbol, size);
+                        return;
+                    }
+                }
             }
         }
 
         // Record simulation history
-        {
+        let expected_profit_usd = {
             let state_read = state.read().await;
-            let est_profit = (size * opp.net_profit_pct) / Decimal::from(100);
+            let latency = simulation.latency.as_ref();
+            let quote_usd_price = state_read.detector.usd_price(&opp.pair.quote);
+            let record = state_read.history_recorder.build_record(
+                opp,
+                size,
+                expected_profit,
+                quote_usd_price,
+                true,
+                None,
+                None,
+                true,
+                latency,
+            );
+            state_read.write_history(record).await;
             state_read
-                .history_recorder
-                .record_trade(opp, size, est_profit, true, None, None, true);
-        }
+                .profit_in_usd(opp, expected_profit)
+                .unwrap_or_else(|| {
+                    warn!("No USD price for {}; booking simulated trade as zero", opp.pair.quote);
+                    Decimal::ZERO
+                })
+        };
 
         // Simulate successful outcome
         let outcome = TradeOutcome {
             timestamp: Utc::now(),
             pair: pair_symbol,
-            profit_loss: size * opp.net_profit_pct / Decimal::from(100),
+            profit_loss: expected_profit_usd,
             was_successful: true,
         };
 
         let mut state = state.write().await;
+        state.risk_manager.dedupe.mark_executed(opp);
         state.risk_manager.record_trade(outcome).await;
+        state.risk_manager.close_position(&position_id);
+        state.risk_manager.release(&opp.pair.symbol(), size);
     } else {
         // Real execution via Jupiter API
-        info!(
-            "🟢 Executing: Buy {} on {}, Sell on {} | Size: ${} | Expected Profit: {}%",
-            pair_symbol, opp.buy_dex, opp.sell_dex, size, opp.net_profit_pct
-        );
+        info!(size_usd = %size, "🟢 Executing trade");
 
         let result: Result<TradeResult> = {
             let state_read = state.read().await;
@@ -868,49 +1676,67 @@ async fn execute_trade(state: &Arc<RwLock<BotState>>, opp: &solana_arb_core::Arb
             Ok(trade_result) => {
                 if trade_result.success {
                     let tx_signature = trade_result.signature.as_deref().unwrap_or("unknown");
-                    info!("✅ Trade submitted! Signature: {}", tx_signature);
+                    info!(signature = %tx_signature, "✅ Trade submitted");
                 } else {
                     let error_msg = trade_result.error.as_deref().unwrap_or("Unknown error");
-                    warn!("❌ Trade execution returned failure: {}", error_msg);
+                    warn!(error = %error_msg, "❌ Trade execution returned failure");
                 }
 
                 // Record outcome
                 let outcome = {
                     let state_read = state.read().await;
+                    state_read.notify_strategies(&trade_result).await;
                     state_read
                         .record_trade_outcome(opp, &pair_symbol, size, &trade_result, start_time)
                         .await
                 };
 
-                // Update Risk Manager
-                // Update Risk Manager
+                // Update Risk Manager. A failure after the first leg landed leaves
+                // that leg's tokens in the wallet, so the position stays open
                 let mut state = state.write().await;
+                state.risk_manager.dedupe.mark_executed(opp);
                 state.risk_manager.record_trade(outcome).await;
+                if !trade_result.success && trade_result.signature.is_some() {
+                    warn!(
+                        "⚠️ Trade {} left a leg unsettled; keeping it open",
+                        position_id
+                    );
+                } else {
+                    state.risk_manager.close_position(&position_id);
+                }
+                state.risk_manager.release(&opp.pair.symbol(), size);
             }
             Err(e) => {
-                error!("❌ Trade failed (Executor Error): {}", e);
+                error!(error = %e, "❌ Trade failed (Executor Error)");
 
                 // Construct failed TradeResult
                 let failed_result = TradeResult {
                     opportunity_id: opp.id,
                     signature: None,
                     success: false,
-                    actual_profit: Decimal::ZERO,
+                    // The error may have come after submission
+                    actual_profit: None,
                     executed_at: Utc::now(),
                     error: Some(e.to_string()),
+                    latency: None,
+                    realized_slippage: None,
                 };
 
                 // Record outcome
                 let outcome = {
                     let state_read = state.read().await;
+                    state_read.notify_strategies(&failed_result).await;
                     state_read
                         .record_trade_outcome(opp, &pair_symbol, size, &failed_result, start_time)
                         .await
                 };
 
-                // Update Risk Manager
+                // Update Risk Manager; quarantine the route so a poison pill isn't retried
                 let mut state = state.write().await;
+                state.risk_manager.dedupe.blacklist(opp);
                 state.risk_manager.record_trade(outcome).await;
+                state.risk_manager.close_position(&position_id);
+                state.risk_manager.release(&opp.pair.symbol(), size);
             }
         }
     }
@@ -930,7 +1756,7 @@ pub async fn run_bot() {
         .expect("Invalid MIN_PROFIT_THRESHOLD value");
 
     // Create config with runtime-loaded value
-    let mut config = Config::from_env().unwrap_or_default();
+    let mut config = Config::from_env().expect("Invalid configuration");
     config.min_profit_threshold = min_profit_threshold;
 
     info!("🚀 Solana Arbitrage Bot starting...");
@@ -1013,6 +1839,7 @@ pub async fn run_bot() {
                     Json(json!({
                         "is_running": h.is_running,
                         "total_trades": h.total_trades,
+                        "last_price_update": h.last_price_update.map(|t| t.to_rfc3339()),
                         "circuit_breaker": h.circuit_breaker_state,
                         "balance_usd": h.balance_usd,
                         "uptime_seconds": h.start_time.elapsed().as_secs()
@@ -1034,34 +1861,17 @@ pub async fn run_bot() {
     });
 
     // Define trading pairs
-    let pairs = vec![
-        TokenPair::new("SOL", "USDC"),
-        TokenPair::new("RAY", "USDC"),
-        TokenPair::new("ORCA", "USDC"),
-        TokenPair::new("JUP", "USDC"),
-    ];
+    let pairs: Vec<TokenPair> = default_pairs()
+        .into_iter()
+        .filter(|pair| config.is_pair_enabled(pair))
+        .collect();
+    if pairs.is_empty() {
+        warn!("⚠️ ENABLED_PAIRS matches none of the configured pairs; nothing will be traded");
+    }
 
     // Initialize metrics
     let metrics = Arc::new(MetricsCollector::new().expect("Failed to initialize metrics"));
 
-    // Start metrics server
-    let metrics_clone = metrics.clone();
-    // Default metrics port from config if possible, or 9090
-    let metrics_port = config.metrics_port;
-    tokio::spawn(async move {
-        let app = api::metrics::metrics_routes(metrics_clone);
-        let addr = std::net::SocketAddr::from(([0, 0, 0, 0], metrics_port));
-        info!("📊 Metrics server running on http://{}/metrics", addr);
-        match tokio::net::TcpListener::bind(addr).await {
-            Ok(listener) => {
-                if let Err(e) = axum::serve(listener, app).await {
-                    error!("Metrics server error: {}", e);
-                }
-            }
-            Err(e) => error!("Failed to bind metrics server on {}: {}", addr, e),
-        }
-    });
-
     // Initialize Config Manager
     let config_path = "config/trading_config.json";
     let config_manager = Arc::new(ConfigManager::new(config_path)
@@ -1072,18 +1882,8 @@ pub async fn run_bot() {
             panic!("Critical: Failed to load {}: {}", config_path, e);
         }));
 
-    // Start Config Watcher (Polling)
-    let cm_clone = config_manager.clone();
-    tokio::spawn(async move {
-        loop {
-            tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
-            if let Err(e) = cm_clone.reload().await {
-                error!("Failed to reload configuration: {}", e);
-            }
-        }
-    });
-
     // Create bot state
+    let metrics_clone = metrics.clone();
     let state = Arc::new(RwLock::new(BotState::new(
         &config,
         dry_run,
@@ -1093,6 +1893,28 @@ pub async fn run_bot() {
         config_manager,
     )));
 
+    // The config file's trading settings apply from the start, not only once it changes
+    {
+        let mut state = state.write().await;
+        let loaded = state.config_manager.get().await;
+        let change = state.startup_config_change(loaded);
+        state.apply_config_change(&change);
+    }
+
+    // Start Config Watcher (Polling): changes apply to the running bot, risk state intact
+    let watched_state = state.clone();
+    tokio::spawn(async move {
+        let config_manager = watched_state.read().await.config_manager.clone();
+        loop {
+            tokio::time::sleep(CONFIG_POLL_INTERVAL).await;
+            match config_manager.reload_if_changed().await {
+                Ok(Some(change)) => watched_state.write().await.apply_config_change(&change),
+                Ok(None) => {}
+                Err(e) => error!("Failed to reload configuration: {}", e),
+            }
+        }
+    });
+
     // Wire EventBus into RiskManager
     {
         let mut s = state.write().await;
@@ -1100,20 +1922,467 @@ pub async fn run_bot() {
         s.risk_manager.set_event_bus(event_bus).await;
     }
 
+    // Start metrics server, with the dashboard's opportunity, risk and live feed endpoints
+    // Default metrics port from config if possible, or 9090
+    let metrics_port = config.metrics_port;
+    let dashboard_state = state.clone();
+    let opportunity_feed = state.read().await.opportunity_feed.clone();
+    tokio::spawn(async move {
+        let app = api::metrics::metrics_routes(metrics_clone)
+            .merge(api::dashboard::dashboard_routes(dashboard_state))
+            .merge(api::opportunity_feed::feed_routes(opportunity_feed));
+        let addr = std::net::SocketAddr::from(([0, 0, 0, 0], metrics_port));
+        info!("📊 Metrics server running on http://{}/metrics", addr);
+        match tokio::net::TcpListener::bind(addr).await {
+            Ok(listener) => {
+                if let Err(e) = axum::serve(listener, app).await {
+                    error!("Metrics server error: {}", e);
+                }
+            }
+            Err(e) => error!("Failed to bind metrics server on {}: {}", addr, e),
+        }
+    });
+
+    // Optional database sink for trade history
+    if config.persist_trade_history {
+        let mut s = state.write().await;
+        match PgHistoryRecorder::new(&config.database_url, s.history_recorder.clone()).await {
+            Ok(pg) => {
+                info!("📜 Trade history will also be written to the database");
+                s.pg_history = Some(pg);
+            }
+            Err(e) => warn!("Trade history database unavailable, using file only: {}", e),
+        }
+    }
+
+    // Start admin API on its own (private by default) address
+    match config.admin_api_token.clone() {
+        Some(token) => {
+            let app = api::admin::admin_routes(state.clone(), token);
+            let bind = format!("{}:{}", config.admin_bind_address, config.admin_port);
+            tokio::spawn(async move {
+                match tokio::net::TcpListener::bind(&bind).await {
+                    Ok(listener) => {
+                        info!("🔐 Admin API running on http://{}/admin", bind);
+                        if let Err(e) = axum::serve(listener, app).await {
+                            error!("Admin API server error: {}", e);
+                        }
+                    }
+                    Err(e) => error!("Failed to bind admin API on {}: {}", bind, e),
+                }
+            });
+        }
+        None => info!("Admin API disabled (ADMIN_API_TOKEN not set)"),
+    }
+
+    // Stop cleanly on Ctrl-C / SIGTERM: the loop exits after the current tick
+    let bot = BotHandle::new(state.clone());
+    tokio::spawn({
+        let bot = bot.clone();
+        async move {
+            shutdown::wait_for_signal().await;
+            bot.shutdown("signal received").await;
+        }
+    });
+
     // Run trading loop
-    run_trading_loop(state, pairs).await;
+    run_trading_loop(state, PairScheduler::new(pairs, config.max_active_pairs)).await;
+    bot.shutdown("trading loop stopped").await;
 }
 
-/// Resolves a token symbol to its Mint Pubkey.
-///
-/// Returns `None` if the symbol is not recognized or the constant is invalid.
-fn resolve_mint(symbol: &str) -> Option<Pubkey> {
-    match symbol {
-        "SOL" => Pubkey::from_str(SOL_MINT).ok(),
-        "USDC" => Pubkey::from_str(USDC_MINT).ok(),
-        "RAY" => Pubkey::from_str(RAY_MINT).ok(),
-        "ORCA" => Pubkey::from_str(ORCA_MINT).ok(),
-        "JUP" => None, // JUP mint not in constants yet, can add later or ignore
-        _ => None,
+/// Default set of monitored trading pairs.
+fn default_pairs() -> Vec<TokenPair> {
+    vec![
+        TokenPair::new("SOL", "USDC"),
+        TokenPair::new("RAY", "USDC"),
+        TokenPair::new("ORCA", "USDC"),
+        TokenPair::new("JUP", "USDC"),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_arb_core::{dex::mock::MockDexProvider, PriceData};
+    use crate::execution::MockExecutor;
+    use solana_arb_core::token_registry::{
+        self, token_info, JUP_MINT, RAY_MINT, SOL_MINT, USDC_MINT,
+    };
+
+    #[test]
+    fn test_monitored_pairs_resolve_to_valid_mints() {
+        let registry = token_registry::TokenRegistry::mainnet();
+        // The bot's pairs and the collector's (SOL/USDT, BONK/SOL and RAY/SOL too)
+        let collector_pairs = [
+            TokenPair::new("SOL", "USDT"),
+            TokenPair::new("RAY", "SOL"),
+            TokenPair::new("BONK", "SOL"),
+        ];
+        for pair in default_pairs().iter().chain(&collector_pairs) {
+            for symbol in [&pair.base, &pair.quote] {
+                let mint = registry.pubkey(symbol);
+                assert!(mint.is_ok(), "{} has no mint: {:?}", symbol, mint);
+            }
+        }
+        assert!(registry.pubkey("NOPE").is_err());
+    }
+
+    #[test]
+    fn test_registry_mints_are_valid_and_unique() {
+        let mut seen = std::collections::HashSet::new();
+        for token in token_registry::TOKEN_REGISTRY {
+            assert!(Pubkey::from_str(token.mint).is_ok(), "{} mint invalid", token.symbol);
+            assert!(seen.insert(token.mint), "{} mint duplicated", token.symbol);
+        }
+        assert_eq!(token_info("BONK").map(|t| t.decimals), Some(5));
+        assert_eq!(token_info("USDT").map(|t| t.decimals), Some(6));
+        // JUP is monitored by default, so its mint must resolve too
+        assert_eq!(token_info("JUP").map(|t| t.mint), Some(JUP_MINT));
+    }
+
+    #[tokio::test]
+    async fn test_config_reload_updates_running_state() {
+        let dir = std::env::temp_dir().join(format!("arb_reload_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("trading_config.json");
+        let template = concat!(env!("CARGO_MANIFEST_DIR"), "/../../config/trading_config.json");
+        let mut file: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(template).unwrap()).unwrap();
+        std::fs::write(&path, file.to_string()).unwrap();
+
+        let manager = Arc::new(ConfigManager::new(&path).unwrap());
+        let mut state = BotState::new(
+            &Config::default(),
+            true,
+            Arc::new(MetricsCollector::new().unwrap()),
+            AlertManager::new(None, None),
+            Arc::new(RwLock::new(SystemHealth::default())),
+            manager.clone(),
+        );
+        assert!(manager.reload_if_changed().await.unwrap().is_none());
+
+        // The file applies from startup: its 100 bps slippage replaces the
+        // environment's 50, while settings it leaves out keep their values
+        let change = state.startup_config_change(manager.get().await);
+        state.apply_config_change(&change);
+        assert_eq!(state.executor.slippage_bps(), 100);
+        assert_eq!(state.detector.min_profit_threshold(), Decimal::new(5, 1));
+        assert_eq!(
+            state.tx_costs.priority_fee_micro_lamports,
+            Config::default().priority_fee_micro_lamports
+        );
+
+        file["trading"]["min_profit_bps"] = json!(80);
+        file["trading"]["max_slippage_bps"] = json!(30);
+        file["trading"]["priority_fee_micro_lamports"] = json!(120_000);
+        file["trading"]["enabled_pairs"] = json!(["SOL/USDC"]);
+        file["solana_rpc_url"] = json!("https://rpc.example.com");
+        std::fs::write(&path, file.to_string()).unwrap();
+
+        let change = manager.reload_if_changed().await.unwrap().unwrap();
+        state.apply_config_change(&change);
+
+        assert_eq!(state.detector.min_profit_threshold(), Decimal::new(8, 1));
+        assert_eq!(state.executor.slippage_bps(), 30);
+        assert_eq!(state.tx_costs.priority_fee_micro_lamports, 120_000);
+        assert!(state.is_pair_enabled(&TokenPair::new("SOL", "USDC")));
+        assert!(!state.is_pair_enabled(&TokenPair::new("RAY", "USDC")));
+        // Restart-only settings are carried along but not applied
+        assert_eq!(state.rpc_url, Config::default().solana_rpc_url);
+        assert!(change.current.restart_only.contains_key("solana_rpc_url"));
+
+        // An invalid edit is refused and the running settings kept
+        file["trading"]["max_slippage_bps"] = json!(0);
+        std::fs::write(&path, file.to_string()).unwrap();
+        assert!(manager.reload_if_changed().await.is_err());
+        assert_eq!(manager.get().await.trading.max_slippage_bps, 30);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_price_feed_outage_halts_trading() {
+        let template = concat!(env!("CARGO_MANIFEST_DIR"), "/../../config/trading_config.json");
+        let pairs = [TokenPair::new("SOL", "USDC")];
+        // A feed that can be cut, standing in for every provider failing
+        let feed = Arc::new(MockDexProvider::new(DexType::Raydium).with_price(
+            PriceData::from_mid(DexType::Raydium, pairs[0].clone(), Decimal::from(150), 0),
+        ));
+        let mut bot = BotState::new(
+            &Config::default(),
+            true,
+            Arc::new(MetricsCollector::new().unwrap()),
+            AlertManager::new(None, None),
+            Arc::new(RwLock::new(SystemHealth::default())),
+            Arc::new(ConfigManager::new(template).unwrap()),
+        );
+        bot.price_fetcher = ParallelPriceFetcher::new(vec![feed.clone()]);
+        let max_silence = Duration::from_secs(
+            (bot.max_price_age_seconds * price_watchdog::STALL_AGE_MULTIPLIER) as u64 + 1,
+        );
+        let state = Arc::new(RwLock::new(bot));
+
+        collect_prices(&state, &pairs).await.unwrap();
+        let fetched_at = state.write().await.price_watchdog.last_update().unwrap();
+        assert!(state.write().await.watch_price_feed(fetched_at).await);
+
+        // Every provider fails: the watchdog fires once the silence outlasts its limit
+        feed.set_down(true);
+        collect_prices(&state, &pairs).await.unwrap();
+        let outage = fetched_at + chrono::Duration::from_std(max_silence).unwrap();
+        {
+            let mut bot = state.write().await;
+            assert_eq!(bot.price_watchdog.last_update(), Some(fetched_at));
+            assert!(!bot.watch_price_feed(outage).await);
+            assert!(bot.risk_manager.is_paused().await);
+            assert_eq!(bot.metrics.price_feed_stalled.get(), 1);
+            assert_eq!(
+                bot.system_health.read().await.last_price_update,
+                Some(fetched_at)
+            );
+        }
+
+        // Prices resume and so does trading
+        feed.set_down(false);
+        collect_prices(&state, &pairs).await.unwrap();
+        let mut bot = state.write().await;
+        assert!(bot.watch_price_feed(Utc::now()).await);
+        assert_eq!(bot.metrics.price_feed_stalled.get(), 0);
+        assert!(matches!(
+            bot.risk_manager.circuit_breaker.state().await,
+            CircuitState::Closed
+        ));
+        assert!(!bot.risk_manager.is_paused().await);
+    }
+
+    #[tokio::test]
+    async fn test_feed_resuming_keeps_breaker_tripped_by_failed_trades() {
+        let template = concat!(env!("CARGO_MANIFEST_DIR"), "/../../config/trading_config.json");
+        let mut bot = BotState::new(
+            &Config::default(),
+            true,
+            Arc::new(MetricsCollector::new().unwrap()),
+            AlertManager::new(None, None),
+            Arc::new(RwLock::new(SystemHealth::default())),
+            Arc::new(ConfigManager::new(template).unwrap()),
+        );
+        for _ in 0..Config::default().max_consecutive_losses {
+            bot.risk_manager
+                .record_trade(TradeOutcome {
+                    timestamp: Utc::now(),
+                    pair: "SOL/USDC".to_string(),
+                    profit_loss: Decimal::ZERO,
+                    was_successful: false,
+                })
+                .await;
+        }
+        assert!(matches!(
+            bot.risk_manager.circuit_breaker.state().await,
+            CircuitState::Open
+        ));
+
+        // The feed stalls and comes back while the breaker is already open
+        let fetched_at = Utc::now();
+        bot.price_watchdog.record_update(fetched_at);
+        let max_silence = chrono::Duration::seconds(
+            bot.max_price_age_seconds * price_watchdog::STALL_AGE_MULTIPLIER + 1,
+        );
+        assert!(!bot.watch_price_feed(fetched_at + max_silence).await);
+        bot.price_watchdog.record_update(fetched_at + max_silence);
+        assert!(bot.watch_price_feed(fetched_at + max_silence).await);
+
+        // The failures' cooldown still applies
+        assert!(matches!(
+            bot.risk_manager.circuit_breaker.state().await,
+            CircuitState::Open
+        ));
+    }
+
+    /// Dry-run bot pricing from `providers` and quoting through `executor`,
+    /// recording its trades to `history` instead of the working tree
+    fn scenario_bot(
+        providers: Vec<Arc<dyn DexProvider>>,
+        executor: Executor,
+        history: &std::path::Path,
+    ) -> Arc<RwLock<BotState>> {
+        let template = concat!(env!("CARGO_MANIFEST_DIR"), "/../../config/trading_config.json");
+        let mut bot = BotState::new(
+            &Config::default(),
+            true,
+            Arc::new(MetricsCollector::new().unwrap()),
+            AlertManager::new(None, None),
+            Arc::new(RwLock::new(SystemHealth::default())),
+            Arc::new(ConfigManager::new(template).unwrap()),
+        )
+        .with_providers(providers)
+        .with_executor(executor);
+        bot.history_recorder = HistoryRecorder::new(history.to_str().unwrap(), "SESSION-TEST");
+        bot.risk_manager = RiskManager::new(RiskConfig::default());
+        Arc::new(RwLock::new(bot))
+    }
+
+    #[tokio::test]
+    async fn test_spread_between_mock_dexes_is_paper_traded() {
+        let history =
+            std::env::temp_dir().join(format!("arb_tick_test_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&history);
+        let pair = TokenPair::new("SOL", "USDC");
+        let quoted = |dex, bid: i64, ask: i64| PriceData {
+            liquidity: Some(Decimal::from(10_000_000)),
+            ..PriceData::new(dex, pair.clone(), Decimal::from(bid), Decimal::from(ask))
+        };
+
+        // SOL is 2% dearer on Orca than Raydium asks
+        let raydium = MockDexProvider::new(DexType::Raydium)
+            .with_price(quoted(DexType::Raydium, 149, 150));
+        let orca = MockDexProvider::new(DexType::Orca).with_price(quoted(DexType::Orca, 153, 154));
+        let providers: Vec<Arc<dyn DexProvider>> = vec![Arc::new(raydium), Arc::new(orca)];
+        // Jupiter fills the buy at Raydium's ask: 1 USDC atom buys 1000/150 SOL atoms
+        let quotes = Arc::new(MockExecutor::new().with_rate(
+            USDC_MINT,
+            SOL_MINT,
+            Decimal::from(1000) / Decimal::from(150),
+        ));
+        let mut executor = Executor::new();
+        executor.set_quote_source(quotes.clone());
+        let state = scenario_bot(providers, executor, &history);
+
+        let opportunities = trading_tick(&state, std::slice::from_ref(&pair))
+            .await
+            .unwrap()
+            .expect("the price feed is live");
+        let opp = opportunities
+            .iter()
+            .find(|o| o.buy_dex == DexType::Raydium && o.sell_dex == DexType::Orca)
+            .expect("the spread is an opportunity");
+
+        let requests = quotes.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!((requests[0].0.as_str(), requests[0].1.as_str()), (USDC_MINT, SOL_MINT));
+
+        let bot = state.read().await;
+        let portfolio = bot.paper_portfolio.as_ref().unwrap();
+        assert_eq!(portfolio.trades(), 1);
+        assert!(portfolio.balance("USDC") > Decimal::from(10_000));
+
+        let records: Vec<TradeRecord> = std::fs::read_to_string(&history)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].trade_type, "SIMULATION");
+        assert_eq!(records[0].pair, "SOL/USDC");
+        assert_eq!(
+            (records[0].buy_dex.as_str(), records[0].sell_dex.as_str()),
+            (opp.buy_dex.to_string().as_str(), opp.sell_dex.to_string().as_str())
+        );
+        let _ = std::fs::remove_file(&history);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_realized_profit_books_zero() {
+        let history =
+            std::env::temp_dir().join(format!("arb_unsettled_test_{}.jsonl", std::process::id()));
+        let state = scenario_bot(Vec::new(), Executor::new(), &history);
+        let mut bot = state.write().await;
+        let pair = TokenPair::new("SOL", "USDC");
+        bot.detector.update_prices(vec![
+            PriceData::new(DexType::Raydium, pair.clone(), Decimal::from(149), Decimal::from(150)),
+            PriceData::new(DexType::Orca, pair.clone(), Decimal::from(153), Decimal::from(154)),
+        ]);
+        let opp = bot.detector.find_all_opportunities().remove(0);
+        assert!(opp.net_profit_usd(Decimal::from(100)).unwrap() > Decimal::ZERO);
+
+        // Landed, but the settlement couldn't be read back
+        let landed = TradeResult {
+            opportunity_id: opp.id,
+            signature: Some("sig".to_string()),
+            success: true,
+            actual_profit: None,
+            executed_at: Utc::now(),
+            error: None,
+            latency: None,
+            realized_slippage: None,
+        };
+        let outcome = bot
+            .record_trade_outcome(&opp, "SOL/USDC", Decimal::from(100), &landed, Instant::now())
+            .await;
+        assert!(outcome.was_successful);
+        assert_eq!(outcome.profit_loss, Decimal::ZERO);
+
+        let realized = TradeResult {
+            actual_profit: Some(Decimal::new(-25, 1)),
+            ..landed
+        };
+        let outcome = bot
+            .record_trade_outcome(&opp, "SOL/USDC", Decimal::from(100), &realized, Instant::now())
+            .await;
+        assert_eq!(outcome.profit_loss, Decimal::new(-25, 1));
+        let _ = std::fs::remove_file(&history);
+    }
+
+    #[tokio::test]
+    async fn test_pair_without_sol_price_is_skipped() {
+        let history =
+            std::env::temp_dir().join(format!("arb_uncosted_test_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&history);
+        // Only RAY/USDC is monitored, so gas and tip can't be priced in USDC
+        let pair = TokenPair::new("RAY", "USDC");
+        let quoted = |dex, bid: i64, ask: i64| PriceData {
+            liquidity: Some(Decimal::from(10_000_000)),
+            ..PriceData::new(dex, pair.clone(), Decimal::new(bid, 2), Decimal::new(ask, 2))
+        };
+        let raydium = MockDexProvider::new(DexType::Raydium)
+            .with_price(quoted(DexType::Raydium, 199, 200));
+        let orca = MockDexProvider::new(DexType::Orca).with_price(quoted(DexType::Orca, 204, 205));
+        let providers: Vec<Arc<dyn DexProvider>> = vec![Arc::new(raydium), Arc::new(orca)];
+        // 1 USDC atom buys 1/2 RAY atom
+        let quotes =
+            Arc::new(MockExecutor::new().with_rate(USDC_MINT, RAY_MINT, Decimal::new(5, 1)));
+        let mut executor = Executor::new();
+        executor.set_quote_source(quotes.clone());
+        let state = scenario_bot(providers, executor, &history);
+
+        trading_tick(&state, std::slice::from_ref(&pair))
+            .await
+            .unwrap()
+            .expect("the price feed is live");
+        assert!(state.read().await.detector.usd_price("SOL").is_none());
+        assert!(quotes.requests().is_empty());
+        assert_eq!(state.read().await.paper_portfolio.as_ref().unwrap().trades(), 0);
+        let _ = std::fs::remove_file(&history);
+    }
+
+    #[tokio::test]
+    async fn test_pair_without_quote_usd_price_is_not_traded() {
+        let history =
+            std::env::temp_dir().join(format!("arb_unvalued_test_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&history);
+        // Only RAY/SOL is monitored, so its SOL profit can't be valued in USD
+        let pair = TokenPair::new("RAY", "SOL");
+        let quoted = |dex, bid: i64, ask: i64| PriceData {
+            liquidity: Some(Decimal::from(10_000_000)),
+            ..PriceData::new(dex, pair.clone(), Decimal::new(bid, 4), Decimal::new(ask, 4))
+        };
+        let raydium = MockDexProvider::new(DexType::Raydium)
+            .with_price(quoted(DexType::Raydium, 199, 200));
+        let orca = MockDexProvider::new(DexType::Orca).with_price(quoted(DexType::Orca, 204, 205));
+        let providers: Vec<Arc<dyn DexProvider>> = vec![Arc::new(raydium), Arc::new(orca)];
+        // 1 lamport buys 0.05 RAY atoms (50 RAY per SOL)
+        let quotes =
+            Arc::new(MockExecutor::new().with_rate(SOL_MINT, RAY_MINT, Decimal::new(5, 2)));
+        let mut executor = Executor::new();
+        executor.set_quote_source(quotes.clone());
+        let state = scenario_bot(providers, executor, &history);
+
+        let opportunities = trading_tick(&state, std::slice::from_ref(&pair))
+            .await
+            .unwrap()
+            .expect("the price feed is live");
+        assert!(!opportunities.is_empty());
+        assert!(quotes.requests().is_empty());
+        assert_eq!(state.read().await.paper_portfolio.as_ref().unwrap().trades(), 0);
+        assert!(!history.exists());
     }
 }
@@ -1,13 +1,30 @@
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 pub fn setup() {
+    // LOG_FORMAT=json for log shippers (Loki, ELK); compact console output otherwise
+    let json = std::env::var("LOG_FORMAT").is_ok_and(|format| format.eq_ignore_ascii_case("json"));
+
     // Console layer for development
-    let console_layer = fmt::layer()
-        .with_target(true)
-        .with_thread_ids(true)
-        .with_level(true)
-        .with_ansi(true)
-        .compact(); // Compact format for cleaner logs
+    let console_layer = (!json).then(|| {
+        fmt::layer()
+            .with_target(true)
+            .with_thread_ids(true)
+            .with_level(true)
+            .with_ansi(true)
+            .compact() // Compact format for cleaner logs
+    });
+
+    // One object per line: event fields (including `message`) at the top level,
+    // and the enclosing span's fields (e.g. a trade's pair and DEXs) under `span`
+    let json_layer = json.then(|| {
+        fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .with_target(true)
+            .with_thread_ids(true)
+    });
 
     // Environment filter (RUST_LOG or default)
     let filter_layer = EnvFilter::try_from_default_env()
@@ -17,5 +34,6 @@ pub fn setup() {
     tracing_subscriber::registry()
         .with(filter_layer)
         .with(console_layer)
+        .with(json_layer)
         .init();
 }
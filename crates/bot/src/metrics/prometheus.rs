@@ -1,4 +1,10 @@
-use prometheus::{Gauge, Histogram, HistogramOpts, IntCounter, IntGauge, Registry};
+use prometheus::{
+    Gauge, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry,
+};
+use rust_decimal::prelude::ToPrimitive;
+use solana_arb_core::pricing::parallel_fetcher::FetchReport;
+use solana_arb_core::ArbitrageOpportunity;
 
 #[allow(dead_code)]
 pub struct MetricsCollector {
@@ -6,19 +12,31 @@ pub struct MetricsCollector {
 
     // Counters
     pub opportunities_detected: IntCounter,
+    pub opportunities_expired: IntCounter,
     pub trades_attempted: IntCounter,
     pub trades_successful: IntCounter,
     pub trades_failed: IntCounter,
 
     // Gauges
     pub current_balance: Gauge,
+    pub opportunities_active: IntGauge,
+    pub best_opportunity_profit_pct: Gauge, // 0 when the tick found none
     pub active_positions: IntGauge,
     pub circuit_breaker_state: IntGauge, // 0=closed, 1=half-open, 2=open
+    pub rpc_requests_in_flight: IntGauge,
+    pub dex_provider_enabled: IntGaugeVec, // per dex: 1=enabled, 0=disabled
+    pub dex_provider_state_changes: IntCounterVec,
+    pub price_fetch_results: IntCounterVec, // per dex and outcome (ok/timeout/error)
+    pub dex_fetch_errors: IntCounterVec,    // per dex: pairs that errored or timed out
+    pub dex_prices_returned: IntGaugeVec,   // per dex: prices in the latest fetch
+    pub price_feed_stalled: IntGauge,       // 1 while the price watchdog halts trading
+    pub last_price_update: IntGauge,        // unix seconds of the last fetch with prices
 
     // Histograms
     pub opportunity_profit: Histogram,
     pub trade_execution_time: Histogram,
     pub price_fetch_latency: Histogram,
+    pub dex_fetch_latency: HistogramVec, // per dex
     pub slippage_distribution: Histogram,
 }
 
@@ -33,6 +51,12 @@ impl MetricsCollector {
         )?;
         registry.register(Box::new(opportunities_detected.clone()))?;
 
+        let opportunities_expired = IntCounter::new(
+            "arb_opportunities_expired_total",
+            "Total number of detected opportunities that went stale",
+        )?;
+        registry.register(Box::new(opportunities_expired.clone()))?;
+
         let trades_attempted = IntCounter::new(
             "arb_trades_attempted_total",
             "Total number of trades attempted",
@@ -54,6 +78,18 @@ impl MetricsCollector {
             Gauge::new("arb_current_balance_usd", "Current account balance in USD")?;
         registry.register(Box::new(current_balance.clone()))?;
 
+        let opportunities_active = IntGauge::new(
+            "arb_opportunities_active",
+            "Opportunities found in the latest tick",
+        )?;
+        registry.register(Box::new(opportunities_active.clone()))?;
+
+        let best_opportunity_profit_pct = Gauge::new(
+            "arb_best_opportunity_profit_pct",
+            "Highest net profit percentage among the latest tick's opportunities",
+        )?;
+        registry.register(Box::new(best_opportunity_profit_pct.clone()))?;
+
         let active_positions = IntGauge::new(
             "arb_active_positions",
             "Number of currently active positions",
@@ -66,6 +102,69 @@ impl MetricsCollector {
         )?;
         registry.register(Box::new(circuit_breaker_state.clone()))?;
 
+        let rpc_requests_in_flight = IntGauge::new(
+            "arb_rpc_requests_in_flight",
+            "Number of RPC requests currently in flight",
+        )?;
+        registry.register(Box::new(rpc_requests_in_flight.clone()))?;
+
+        let dex_provider_enabled = IntGaugeVec::new(
+            Opts::new(
+                "arb_dex_provider_enabled",
+                "Whether a DEX provider is being polled (1=enabled, 0=disabled)",
+            ),
+            &["dex"],
+        )?;
+        registry.register(Box::new(dex_provider_enabled.clone()))?;
+
+        let dex_provider_state_changes = IntCounterVec::new(
+            Opts::new(
+                "arb_dex_provider_state_changes_total",
+                "DEX provider disable/re-enable transitions",
+            ),
+            &["dex", "state"],
+        )?;
+        registry.register(Box::new(dex_provider_state_changes.clone()))?;
+
+        let price_fetch_results = IntCounterVec::new(
+            Opts::new(
+                "arb_price_fetch_results_total",
+                "Pairs fetched per DEX by outcome (ok, timeout, error)",
+            ),
+            &["dex", "outcome"],
+        )?;
+        registry.register(Box::new(price_fetch_results.clone()))?;
+
+        let dex_fetch_errors = IntCounterVec::new(
+            Opts::new(
+                "arb_dex_fetch_errors_total",
+                "Pairs a DEX failed to price, by error or timeout",
+            ),
+            &["dex"],
+        )?;
+        registry.register(Box::new(dex_fetch_errors.clone()))?;
+
+        let dex_prices_returned = IntGaugeVec::new(
+            Opts::new(
+                "arb_dex_prices_returned",
+                "Prices a DEX returned in the latest fetch",
+            ),
+            &["dex"],
+        )?;
+        registry.register(Box::new(dex_prices_returned.clone()))?;
+
+        let price_feed_stalled = IntGauge::new(
+            "arb_price_feed_stalled",
+            "Whether trading is halted because no prices have arrived (1=stalled)",
+        )?;
+        registry.register(Box::new(price_feed_stalled.clone()))?;
+
+        let last_price_update = IntGauge::new(
+            "arb_last_price_update_timestamp_seconds",
+            "Unix time of the last price fetch that returned prices",
+        )?;
+        registry.register(Box::new(last_price_update.clone()))?;
+
         // Initialize histograms
         let opportunity_profit = Histogram::with_opts(
             HistogramOpts::new(
@@ -94,6 +193,16 @@ impl MetricsCollector {
         )?;
         registry.register(Box::new(price_fetch_latency.clone()))?;
 
+        let dex_fetch_latency = HistogramVec::new(
+            HistogramOpts::new(
+                "arb_dex_fetch_latency_seconds",
+                "Time each DEX took to answer a price fetch, in seconds",
+            )
+            .buckets(vec![0.01, 0.05, 0.1, 0.2, 0.5, 1.0, 2.0]),
+            &["dex"],
+        )?;
+        registry.register(Box::new(dex_fetch_latency.clone()))?;
+
         let slippage_distribution = Histogram::with_opts(
             HistogramOpts::new("arb_slippage_bps", "Slippage distribution in basis points")
                 .buckets(vec![5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0]),
@@ -103,19 +212,68 @@ impl MetricsCollector {
         Ok(Self {
             registry,
             opportunities_detected,
+            opportunities_expired,
             trades_attempted,
             trades_successful,
             trades_failed,
             current_balance,
+            opportunities_active,
+            best_opportunity_profit_pct,
             active_positions,
             circuit_breaker_state,
+            rpc_requests_in_flight,
+            dex_provider_enabled,
+            dex_provider_state_changes,
+            price_fetch_results,
+            dex_fetch_errors,
+            dex_prices_returned,
+            price_feed_stalled,
+            last_price_update,
             opportunity_profit,
             trade_execution_time,
             price_fetch_latency,
+            dex_fetch_latency,
             slippage_distribution,
         })
     }
 
+    /// Set the opportunity gauges from the opportunities one tick found
+    pub fn record_tick_opportunities(&self, opportunities: &[ArbitrageOpportunity]) {
+        self.opportunities_active.set(opportunities.len() as i64);
+        let best = opportunities
+            .iter()
+            .map(|opp| opp.net_profit_pct)
+            .max()
+            .and_then(|pct| pct.to_f64())
+            .unwrap_or(0.0);
+        self.best_opportunity_profit_pct.set(best);
+    }
+
+    /// Record each DEX's outcome counts and latency from one price fetch
+    pub fn record_fetch_report(&self, report: &FetchReport) {
+        for (dex, stats) in &report.per_dex {
+            let dex = dex.display_name();
+            for (outcome, count) in [
+                ("ok", stats.ok),
+                ("timeout", stats.timeout),
+                ("error", stats.error),
+            ] {
+                self.price_fetch_results
+                    .with_label_values(&[dex, outcome])
+                    .inc_by(count as u64);
+            }
+            self.dex_fetch_errors
+                .with_label_values(&[dex])
+                .inc_by((stats.error + stats.timeout) as u64);
+            self.dex_prices_returned
+                .with_label_values(&[dex])
+                .set(stats.ok as i64);
+            self.dex_fetch_latency
+                .with_label_values(&[dex])
+                .observe(stats.latency.as_secs_f64());
+        }
+    }
+
     pub fn registry(&self) -> &Registry {
         &self.registry
     }
@@ -0,0 +1,198 @@
+//! Simulated wallet for dry runs
+//!
+//! Dry runs quote trades without sending them, so the real wallet never moves.
+//! [`PaperPortfolio`] keeps token balances that do: each paper trade spends
+//! from them and credits what the quote says it would have returned, giving
+//! dry runs a balance (and an equity curve) to show for their PnL.
+
+use crate::execution::QuoteSummary;
+use anyhow::{anyhow, Result};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use solana_arb_core::token_registry::token_info;
+use solana_arb_core::ArbitrageOpportunity;
+use std::collections::HashMap;
+
+/// A simulated round trip: buy `bought` of `base` with `spent` of `quote`,
+/// then sell it back for `returned` of `quote`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperTrade {
+    pub quote: String,
+    pub base: String,
+    pub spent: Decimal,
+    pub bought: Decimal,
+    pub returned: Decimal,
+}
+
+impl PaperTrade {
+    /// Paper trade of `opp` as quoted by `summary` (the buy leg).
+    ///
+    /// The buy leg uses the quote's amounts as they are. The sell leg isn't
+    /// quoted, so what was bought is sold at `opp`'s sell price, less the fees
+    /// the detector charged the trade (its gross minus net profit).
+    pub fn from_quote(opp: &ArbitrageOpportunity, summary: &QuoteSummary) -> Result<Self> {
+        let amount = |symbol: &str, atoms: u64| {
+            let decimals = token_info(symbol)
+                .map(|t| t.decimals)
+                .ok_or_else(|| anyhow!("Unknown token: {}", symbol))?;
+            Ok::<_, anyhow::Error>(Decimal::from_i128_with_scale(atoms as i128, decimals))
+        };
+        let spent = amount(&opp.pair.quote, summary.in_amount)?;
+        let bought = amount(&opp.pair.base, summary.out_amount)?;
+
+        let fee_pct = opp.gross_profit_pct - opp.net_profit_pct;
+        let returned = bought
+            .checked_mul(opp.sell_price)
+            .and_then(|proceeds| {
+                proceeds.checked_sub(spent.checked_mul(fee_pct)? / Decimal::ONE_HUNDRED)
+            })
+            .ok_or_else(|| anyhow!("Paper trade of {} overflows", opp.pair))?
+            .max(Decimal::ZERO);
+
+        Ok(Self {
+            quote: opp.pair.quote.clone(),
+            base: opp.pair.base.clone(),
+            spent,
+            bought,
+            returned,
+        })
+    }
+
+    /// What the round trip made (negative for a loss), in the quote token
+    pub fn pnl(&self) -> Decimal {
+        self.returned - self.spent
+    }
+}
+
+/// Token balances of the simulated wallet dry runs trade from
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PaperPortfolio {
+    balances: HashMap<String, Decimal>,
+    /// Paper trades applied
+    trades: u64,
+    /// Paper trades turned away for lack of balance
+    rejected: u64,
+}
+
+impl PaperPortfolio {
+    pub fn new(balances: impl IntoIterator<Item = (String, Decimal)>) -> Self {
+        Self {
+            balances: balances.into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Simulated balance of `token`
+    pub fn balance(&self, token: &str) -> Decimal {
+        self.balances.get(token).copied().unwrap_or_default()
+    }
+
+    /// Every token balance, zero ones included
+    pub fn balances(&self) -> &HashMap<String, Decimal> {
+        &self.balances
+    }
+
+    /// Paper trades applied so far
+    pub fn trades(&self) -> u64 {
+        self.trades
+    }
+
+    /// Paper trades rejected so far for lack of balance
+    pub fn rejected(&self) -> u64 {
+        self.rejected
+    }
+
+    /// Applies `trade` to the balances, returning its PnL.
+    ///
+    /// A trade spending more of its quote token than the portfolio holds is
+    /// rejected and leaves the balances untouched.
+    pub fn execute(&mut self, trade: &PaperTrade) -> Result<Decimal> {
+        let available = self.balance(&trade.quote);
+        if trade.spent > available {
+            self.rejected += 1;
+            return Err(anyhow!(
+                "Insufficient paper balance: trade spends {} {} but only {} is held",
+                trade.spent,
+                trade.quote,
+                available
+            ));
+        }
+
+        // Both legs: the base token bought is sold straight back
+        *self.balances.entry(trade.quote.clone()).or_default() += trade.returned - trade.spent;
+        self.balances.entry(trade.base.clone()).or_default();
+        self.trades += 1;
+        Ok(trade.pnl())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use solana_arb_core::{DexType, TokenPair, Uuid};
+
+    fn sol_usdc(sell_price: Decimal) -> ArbitrageOpportunity {
+        ArbitrageOpportunity {
+            id: Uuid::new_v4(),
+            pair: TokenPair::new("SOL", "USDC"),
+            buy_dex: DexType::Raydium,
+            sell_dex: DexType::Orca,
+            buy_price: Decimal::ONE_HUNDRED,
+            sell_price,
+            gross_profit_pct: Decimal::ONE,
+            net_profit_pct: Decimal::new(5, 1),
+            estimated_profit_usd: None,
+            recommended_size: None,
+            detected_at: Utc::now(),
+            expired_at: None,
+            path: None,
+        }
+    }
+
+    /// Quote spending `usdc` for `sol`, both in whole tokens
+    fn quote(usdc: u64, sol: u64) -> QuoteSummary {
+        QuoteSummary {
+            input_mint: "USDC".to_string(),
+            output_mint: "SOL".to_string(),
+            in_amount: usdc * 1_000_000,
+            out_amount: sol * 1_000_000_000,
+            price_impact_pct: Decimal::ZERO,
+            route: vec![],
+        }
+    }
+
+    #[test]
+    fn test_paper_trades_move_balances() {
+        let mut portfolio = PaperPortfolio::new([("USDC".to_string(), Decimal::from(1_000))]);
+
+        // 500 USDC buys 5 SOL, sold at 101 less 0.5% fees on 500: +2.5
+        let trade = PaperTrade::from_quote(&sol_usdc(Decimal::from(101)), &quote(500, 5)).unwrap();
+        assert_eq!(trade.returned, Decimal::new(5025, 1));
+        assert_eq!(portfolio.execute(&trade).unwrap(), Decimal::new(25, 1));
+        assert_eq!(portfolio.balance("USDC"), Decimal::new(10025, 1));
+        assert_eq!(portfolio.balance("SOL"), Decimal::ZERO);
+
+        // The price fell away before the sell: 5 SOL at 99 loses 7.5
+        let losing = PaperTrade::from_quote(&sol_usdc(Decimal::from(99)), &quote(500, 5)).unwrap();
+        assert_eq!(portfolio.execute(&losing).unwrap(), Decimal::new(-75, 1));
+        assert_eq!(portfolio.balance("USDC"), Decimal::from(995));
+
+        // The quote's out-amount is what counts: a worse fill earns less
+        let worse_fill =
+            PaperTrade::from_quote(&sol_usdc(Decimal::from(101)), &quote(400, 4)).unwrap();
+        portfolio.execute(&worse_fill).unwrap();
+        assert_eq!(portfolio.balance("USDC"), Decimal::from(997));
+        assert_eq!(portfolio.trades(), 3);
+    }
+
+    #[test]
+    fn test_paper_trade_rejected_without_balance() {
+        let mut portfolio = PaperPortfolio::new([("USDC".to_string(), Decimal::from(100))]);
+        let trade = PaperTrade::from_quote(&sol_usdc(Decimal::from(101)), &quote(500, 5)).unwrap();
+
+        assert!(portfolio.execute(&trade).is_err());
+        assert_eq!(portfolio.balance("USDC"), Decimal::from(100));
+        assert_eq!((portfolio.trades(), portfolio.rejected()), (0, 1));
+    }
+}
@@ -0,0 +1,108 @@
+//! Dead-man's switch on the price feeds
+//!
+//! When every provider fails, a fetch just comes back empty: the detector
+//! runs on cached prices until they age out and then quietly finds nothing.
+//! [`PriceWatchdog`] notices the silence instead, so the trading loop can
+//! halt and alarm until prices arrive again.
+
+use chrono::{DateTime, Duration, Utc};
+
+/// Silence tolerated, in multiples of the maximum price age, before the feed
+/// is declared stalled
+pub const STALL_AGE_MULTIPLIER: i64 = 6;
+
+/// A change in the feed's state reported by [`PriceWatchdog::check`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedTransition {
+    /// No price has arrived for `silent_for`
+    Stalled { silent_for: Duration },
+    /// Prices are arriving again after a stall
+    Resumed,
+}
+
+/// Tracks when prices last arrived and whether the feed counts as stalled
+#[derive(Debug, Clone)]
+pub struct PriceWatchdog {
+    max_silence: Duration,
+    /// Stands in for the last update until the first one, so a feed that
+    /// never delivers is caught too
+    started_at: DateTime<Utc>,
+    last_update: Option<DateTime<Utc>>,
+    stalled: bool,
+}
+
+impl PriceWatchdog {
+    pub fn new(max_silence: Duration) -> Self {
+        Self {
+            max_silence,
+            started_at: Utc::now(),
+            last_update: None,
+            stalled: false,
+        }
+    }
+
+    /// Watchdog for prices that go stale after `max_price_age_seconds`
+    pub fn for_max_price_age(max_price_age_seconds: i64) -> Self {
+        Self::new(Duration::seconds(
+            max_price_age_seconds.max(1) * STALL_AGE_MULTIPLIER,
+        ))
+    }
+
+    /// Note that a fetch returned prices at `at`
+    pub fn record_update(&mut self, at: DateTime<Utc>) {
+        self.last_update = Some(at);
+    }
+
+    /// When a fetch last returned prices, if one has
+    pub fn last_update(&self) -> Option<DateTime<Utc>> {
+        self.last_update
+    }
+
+    /// Whether trading should stay halted for want of prices
+    pub fn is_stalled(&self) -> bool {
+        self.stalled
+    }
+
+    /// Re-evaluate the feed at `now`, reporting a stall or recovery once
+    pub fn check(&mut self, now: DateTime<Utc>) -> Option<FeedTransition> {
+        let silent_for = now - self.last_update.unwrap_or(self.started_at);
+        let stalled = silent_for > self.max_silence;
+        if stalled == self.stalled {
+            return None;
+        }
+        self.stalled = stalled;
+        Some(if stalled {
+            FeedTransition::Stalled { silent_for }
+        } else {
+            FeedTransition::Resumed
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stall_is_reported_once_and_clears_on_update() {
+        let mut watchdog = PriceWatchdog::new(Duration::seconds(30));
+        let start = watchdog.started_at;
+
+        // Never delivering counts from start-up
+        assert_eq!(watchdog.check(start + Duration::seconds(10)), None);
+        assert_eq!(
+            watchdog.check(start + Duration::seconds(31)),
+            Some(FeedTransition::Stalled {
+                silent_for: Duration::seconds(31)
+            })
+        );
+        assert_eq!(watchdog.check(start + Duration::seconds(40)), None);
+        assert!(watchdog.is_stalled());
+
+        let update = start + Duration::seconds(45);
+        watchdog.record_update(update);
+        assert_eq!(watchdog.check(update), Some(FeedTransition::Resumed));
+        assert_eq!(watchdog.last_update(), Some(update));
+        assert!(!watchdog.is_stalled());
+    }
+}
@@ -0,0 +1,106 @@
+//! Graceful shutdown of the trading loop
+//!
+//! Every trade runs inside a [`TradeGate`] guard, from the risk commit until
+//! its outcome is recorded. Stopping the gate refuses new trades and waits for
+//! the guard of the trade in flight, so a shutdown never cuts a trade off
+//! between execution and bookkeeping.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::warn;
+
+/// Lets trades through while running; drains them when stopped
+#[derive(Debug)]
+pub struct TradeGate {
+    running: AtomicBool,
+    in_flight: Mutex<()>,
+}
+
+impl Default for TradeGate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TradeGate {
+    pub fn new() -> Self {
+        Self {
+            running: AtomicBool::new(true),
+            in_flight: Mutex::new(()),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Start a trade, or `None` once stopping. Hold the guard until the trade
+    /// is fully recorded.
+    pub async fn enter(&self) -> Option<MutexGuard<'_, ()>> {
+        let guard = self.in_flight.lock().await;
+        // Checked under the lock so a trade can't slip in after `stop` drained
+        self.is_running().then_some(guard)
+    }
+
+    /// Refuse new trades and wait for the one in flight to finish
+    pub async fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        drop(self.in_flight.lock().await);
+    }
+}
+
+/// Resolves on Ctrl-C (SIGINT) or SIGTERM
+pub async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+                return;
+            }
+            Err(e) => warn!(
+                "Cannot listen for SIGTERM, only Ctrl-C stops cleanly: {}",
+                e
+            ),
+        }
+    }
+
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        warn!("Cannot listen for Ctrl-C: {}", e);
+        std::future::pending::<()>().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_stop_waits_for_trade_in_flight() {
+        let gate = Arc::new(TradeGate::new());
+        let trade = gate.enter().await.expect("gate starts open");
+
+        let stopping = tokio::spawn({
+            let gate = gate.clone();
+            async move { gate.stop().await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!gate.is_running());
+        assert!(!stopping.is_finished(), "stop returned mid-trade");
+
+        drop(trade);
+        tokio::time::timeout(Duration::from_secs(1), stopping)
+            .await
+            .expect("stop finishes once the trade is done")
+            .unwrap();
+
+        // Nothing new starts afterwards
+        assert!(gate.enter().await.is_none());
+    }
+}
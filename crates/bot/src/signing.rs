@@ -0,0 +1,215 @@
+//! Transaction signing backends
+//!
+//! The executor never touches key material directly: it hands serialized
+//! messages to a [`Signer`] and gets signatures back. Keys can then live in
+//! this process ([`LocalSigner`]), or behind a remote signing service or
+//! hardware wallet ([`RemoteSigner`]) that never exposes them.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64_ENGINE;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer as _};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Something that can sign transaction messages for one public key
+#[async_trait]
+pub trait Signer: Send + Sync {
+    /// The key signatures are made with
+    fn pubkey(&self) -> Pubkey;
+
+    /// Sign a serialized transaction message
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature>;
+}
+
+/// Signs in-process with a keypair held in memory
+pub struct LocalSigner {
+    keypair: Keypair,
+}
+
+impl LocalSigner {
+    pub fn new(keypair: Keypair) -> Self {
+        Self { keypair }
+    }
+}
+
+#[async_trait]
+impl Signer for LocalSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature> {
+        Ok(self.keypair.sign_message(message))
+    }
+}
+
+/// Time a remote signer gets to answer, including any approval on its side
+const REMOTE_SIGN_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize)]
+struct SignRequest {
+    pubkey: String,
+    /// Base64-encoded serialized message
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct SignResponse {
+    /// Base58-encoded signature
+    signature: String,
+}
+
+/// Delegates signing to an HTTP service (e.g. a signer fronting an HSM or
+/// hardware wallet) that holds the key for `pubkey`.
+///
+/// Each message is POSTed to `url` as `{"pubkey", "message"}` (message in
+/// base64) and the service answers `{"signature"}` in base58. Signatures are
+/// verified before use, so a misbehaving service can't slip in a bad one.
+pub struct RemoteSigner {
+    client: reqwest::Client,
+    url: String,
+    pubkey: Pubkey,
+}
+
+impl RemoteSigner {
+    pub fn new(url: impl Into<String>, pubkey: Pubkey) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.into(),
+            pubkey,
+        }
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature> {
+        let request = SignRequest {
+            pubkey: self.pubkey.to_string(),
+            message: BASE64_ENGINE.encode(message),
+        };
+        let response: SignResponse = self
+            .client
+            .post(&self.url)
+            .timeout(REMOTE_SIGN_TIMEOUT)
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let signature = Signature::from_str(&response.signature)
+            .map_err(|e| anyhow!("Remote signer returned a malformed signature: {}", e))?;
+        if !signature.verify(self.pubkey.as_ref(), message) {
+            return Err(anyhow!(
+                "Remote signer returned a signature that doesn't verify for {}",
+                self.pubkey
+            ));
+        }
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::Wallet;
+    use axum::{routing::post, Json, Router};
+    use solana_sdk::hash::Hash;
+    use solana_sdk::message::{v0, VersionedMessage};
+    use solana_sdk::system_instruction;
+    use std::sync::{Arc, Mutex};
+
+    /// Test double: signs with an in-memory key and records what it was asked to sign
+    struct RecordingSigner {
+        keypair: Keypair,
+        signed: Arc<Mutex<Vec<Vec<u8>>>>,
+    }
+
+    #[async_trait]
+    impl Signer for RecordingSigner {
+        fn pubkey(&self) -> Pubkey {
+            self.keypair.pubkey()
+        }
+
+        async fn sign_message(&self, message: &[u8]) -> Result<Signature> {
+            self.signed.lock().unwrap().push(message.to_vec());
+            Ok(self.keypair.sign_message(message))
+        }
+    }
+
+    fn transfer_from(payer: &Pubkey) -> VersionedMessage {
+        let transfer = system_instruction::transfer(payer, &Pubkey::new_unique(), 1_000);
+        VersionedMessage::V0(
+            v0::Message::try_compile(payer, &[transfer], &[], Hash::default()).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_wallet_signs_through_its_signer() {
+        let signed = Arc::new(Mutex::new(Vec::new()));
+        let wallet = Wallet::with_signer(RecordingSigner {
+            keypair: Keypair::new(),
+            signed: signed.clone(),
+        });
+        let payer = Pubkey::from_str(&wallet.pubkey()).unwrap();
+        let message = transfer_from(&payer);
+
+        let tx = wallet.sign(message.clone()).await.unwrap();
+        assert!(tx.verify_with_results().into_iter().all(|ok| ok));
+        assert_eq!(*signed.lock().unwrap(), vec![message.serialize()]);
+
+        // Someone else's transaction is refused before reaching the signer
+        let other = transfer_from(&Pubkey::new_unique());
+        assert!(wallet.sign(other).await.is_err());
+        assert_eq!(signed.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_verifies_returned_signature() {
+        let keypair = Arc::new(Keypair::new());
+        let pubkey = keypair.pubkey();
+        let service_key = keypair.clone();
+        let app = Router::new()
+            .route(
+                "/sign",
+                post(move |Json(request): Json<serde_json::Value>| async move {
+                    let message = BASE64_ENGINE
+                        .decode(request["message"].as_str().unwrap())
+                        .unwrap();
+                    let signature = service_key.sign_message(&message);
+                    Json(serde_json::json!({ "signature": signature.to_string() }))
+                }),
+            )
+            .route(
+                "/wrong-key",
+                post(|Json(request): Json<serde_json::Value>| async move {
+                    let message = BASE64_ENGINE
+                        .decode(request["message"].as_str().unwrap())
+                        .unwrap();
+                    let signature = Keypair::new().sign_message(&message);
+                    Json(serde_json::json!({ "signature": signature.to_string() }))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let wallet = Wallet::with_signer(RemoteSigner::new(format!("{}/sign", base), pubkey));
+        let tx = wallet.sign(transfer_from(&pubkey)).await.unwrap();
+        assert!(tx.verify_with_results().into_iter().all(|ok| ok));
+
+        let rogue = RemoteSigner::new(format!("{}/wrong-key", base), pubkey);
+        let err = rogue.sign_message(b"message").await.unwrap_err();
+        assert!(err.to_string().contains("doesn't verify"));
+    }
+}
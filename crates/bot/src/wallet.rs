@@ -3,28 +3,60 @@
 //! Handles wallet configuration for simulated trading environment.
 //! Supports SDK Keypairs for live signing while retaining simulated defaults.
 
+use crate::signing::{LocalSigner, RemoteSigner, Signer};
 use anyhow::{anyhow, Result};
-use solana_sdk::signature::{Keypair, Signer};
+use async_trait::async_trait;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer as _};
+use solana_sdk::transaction::VersionedTransaction;
 use std::env;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use tracing::{info, warn};
 
 /// Wallet wrapper for simulation and live trading.
 ///
-/// Handles keypair loading from environment variables or creates a simulated
-/// wallet for dry-run modes.
+/// Handles keypair loading from a keypair file or environment variables, or
+/// creates a simulated wallet for dry-run modes. Signing goes through a
+/// [`Signer`], so the key itself may live in a remote service.
 pub struct Wallet {
     /// Public key string representation.
     pub pubkey: String,
-    /// Optional keypair for signing (None in simulation or if key is missing).
-    keypair: Option<Keypair>,
+    /// Signing backend (None in simulation or if key is missing).
+    signer: Option<Box<dyn Signer>>,
 }
 
 impl Wallet {
     /// Creates a new Wallet instance.
     ///
-    /// Loads `PRIVATE_KEY` from environment. If missing or invalid, falls back
-    /// to a simulated wallet.
+    /// Signs through the remote signer at `REMOTE_SIGNER_URL` if set (for the key
+    /// `REMOTE_SIGNER_PUBKEY`). Otherwise loads the keypair file at `KEYPAIR_PATH`
+    /// if set, failing if it can't be read, or else `PRIVATE_KEY` from environment;
+    /// if that is missing or invalid, falls back to a simulated wallet.
     pub fn new() -> Result<Self> {
+        if let Some(url) = env::var("REMOTE_SIGNER_URL")
+            .ok()
+            .filter(|u| !u.trim().is_empty())
+        {
+            let pubkey = env::var("REMOTE_SIGNER_PUBKEY")
+                .map_err(|_| anyhow!("REMOTE_SIGNER_URL is set but REMOTE_SIGNER_PUBKEY is not"))?;
+            let pubkey = Pubkey::from_str(pubkey.trim())
+                .map_err(|e| anyhow!("Invalid REMOTE_SIGNER_PUBKEY: {}", e))?;
+            info!("Wallet signs remotely via {}: {}", url.trim(), pubkey);
+            return Ok(Self::with_signer(RemoteSigner::new(url.trim(), pubkey)));
+        }
+
+        if let Some(path) = env::var("KEYPAIR_PATH")
+            .ok()
+            .filter(|p| !p.trim().is_empty())
+        {
+            if env::var("PRIVATE_KEY").is_ok_and(|pk| !pk.is_empty()) {
+                info!("KEYPAIR_PATH is set; ignoring PRIVATE_KEY");
+            }
+            return Self::from_file(expand_home(path.trim()));
+        }
+
         let pk_str = env::var("PRIVATE_KEY").ok();
 
         let (pubkey, keypair) = if let Some(pk) = pk_str {
@@ -57,7 +89,42 @@ impl Wallet {
         };
 
         info!("Wallet loaded: {}", pubkey);
-        Ok(Self { pubkey, keypair })
+        Ok(Self {
+            pubkey,
+            signer: keypair.map(|kp| Box::new(LocalSigner::new(kp)) as Box<dyn Signer>),
+        })
+    }
+
+    /// Wallet that signs with `signer`, e.g. a remote or hardware signer
+    pub fn with_signer(signer: impl Signer + 'static) -> Self {
+        Self {
+            pubkey: signer.pubkey().to_string(),
+            signer: Some(Box::new(signer)),
+        }
+    }
+
+    /// Loads a wallet from a Solana CLI keypair file (a JSON array of 64 bytes,
+    /// as written by `solana-keygen`)
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read keypair file {}: {}", path.display(), e))?;
+        let bytes: Vec<u8> = serde_json::from_str(contents.trim()).map_err(|e| {
+            anyhow!(
+                "Keypair file {} is not a JSON byte array: {}",
+                path.display(),
+                e
+            )
+        })?;
+        let keypair = Keypair::from_bytes(&bytes)
+            .map_err(|e| anyhow!("Keypair file {} is invalid: {}", path.display(), e))?;
+
+        info!(
+            "Wallet loaded from {}: {}",
+            path.display(),
+            keypair.pubkey()
+        );
+        Ok(Self::with_signer(LocalSigner::new(keypair)))
     }
 
     /// Returns the public key as a string.
@@ -65,9 +132,33 @@ impl Wallet {
         self.pubkey.clone()
     }
 
-    /// Returns the keypair for signing, if available.
-    pub fn signer(&self) -> Option<&Keypair> {
-        self.keypair.as_ref()
+    /// Whether the wallet can sign (false for a simulated wallet).
+    pub fn can_sign(&self) -> bool {
+        self.signer.is_some()
+    }
+
+    /// Signs `message` as its fee payer, returning the transaction ready to send.
+    ///
+    /// Fails for a simulated wallet, or if the message needs signatures from
+    /// other keys too.
+    pub async fn sign(&self, message: VersionedMessage) -> Result<VersionedTransaction> {
+        let pubkey = Signer::pubkey(self);
+        let required = usize::from(message.header().num_required_signatures);
+        let keys = message.static_account_keys();
+        let signers = keys.get(..required).unwrap_or(keys);
+        if signers != [pubkey] {
+            return Err(anyhow!(
+                "Message must be signed by {} alone, but requires {:?}",
+                pubkey,
+                signers
+            ));
+        }
+
+        let signature = self.sign_message(&message.serialize()).await?;
+        Ok(VersionedTransaction {
+            signatures: vec![signature],
+            message,
+        })
     }
 
     fn parse_keypair(value: &str) -> Result<Keypair> {
@@ -81,3 +172,72 @@ impl Wallet {
         Keypair::from_bytes(&decoded).map_err(|e| anyhow!("Invalid base58 keypair: {}", e))
     }
 }
+
+#[async_trait]
+impl Signer for Wallet {
+    fn pubkey(&self) -> Pubkey {
+        self.signer
+            .as_ref()
+            .map(|signer| signer.pubkey())
+            .unwrap_or_default()
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature> {
+        let signer = self
+            .signer
+            .as_ref()
+            .ok_or_else(|| anyhow!("No keypair available for signing"))?;
+        signer.sign_message(message).await
+    }
+}
+
+/// Resolve a leading `~/` against `HOME`, as shells do for `~/.config/solana/id.json`
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path() -> PathBuf {
+        env::temp_dir().join(format!("keypair-{}.json", solana_arb_core::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_from_file_reads_json_byte_array() {
+        let keypair = Keypair::new();
+        let path = temp_path();
+        std::fs::write(
+            &path,
+            serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap(),
+        )
+        .unwrap();
+
+        let wallet = Wallet::from_file(&path).unwrap();
+        assert_eq!(wallet.pubkey(), keypair.pubkey().to_string());
+        assert!(wallet.can_sign());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_from_file_reports_missing_and_malformed_files() {
+        let path = temp_path();
+        let missing = Wallet::from_file(&path).err().unwrap().to_string();
+        assert!(missing.contains("Cannot read keypair file"));
+
+        std::fs::write(&path, "not json").unwrap();
+        let malformed = Wallet::from_file(&path).err().unwrap().to_string();
+        assert!(malformed.contains("not a JSON byte array"));
+
+        std::fs::write(&path, "[1, 2, 3]").unwrap();
+        let short = Wallet::from_file(&path).err().unwrap().to_string();
+        assert!(short.contains("is invalid"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -12,7 +12,9 @@ use tracing_subscriber::FmtSubscriber;
 use solana_arb_core::{
     arbitrage::ArbitrageDetector,
     config::Config,
+    database::opportunity_store::OpportunityStore,
     dex::{jupiter::JupiterProvider, orca::OrcaProvider, raydium::RaydiumProvider, DexProvider},
+    http::pool::{HostRateLimiter, HttpTimeouts},
     ArbitrageConfig, TokenPair,
 };
 
@@ -44,15 +46,17 @@ async fn main() -> Result<()> {
     info!("Starting Solana Arbitrage Collector");
 
     // Load configuration
-    let config = Config::from_env().unwrap_or_default();
+    let config = Config::from_env()?;
     info!("Configuration loaded");
     info!("  RPC URL: {}", config.solana_rpc_url);
     info!("  Min profit threshold: {}%", config.min_profit_threshold);
 
-    // Initialize DEX providers
-    let jupiter = JupiterProvider::new();
-    let raydium = RaydiumProvider::new();
-    let orca = OrcaProvider::new();
+    // Initialize DEX providers, sharing one per-host rate limiter
+    let timeouts = HttpTimeouts::from_config(&config);
+    let rate_limiter = Arc::new(HostRateLimiter::from_config(&config));
+    let jupiter = JupiterProvider::with_http(timeouts, rate_limiter.clone());
+    let raydium = RaydiumProvider::with_http(timeouts, rate_limiter.clone());
+    let orca = OrcaProvider::with_http(timeouts, rate_limiter);
 
     info!("DEX providers initialized");
 
@@ -73,20 +77,41 @@ async fn main() -> Result<()> {
     let arb_config = ArbitrageConfig {
         min_profit_threshold: rust_decimal::Decimal::try_from(config.min_profit_threshold)
             .unwrap_or_default(),
+        jupiter_price_source: config.jupiter_price_source,
         ..Default::default()
     };
     let detector = Arc::new(RwLock::new(ArbitrageDetector::new(arb_config)));
 
+    // Optional database output for analytics
+    let store = if config.collector_persist_opportunities {
+        match OpportunityStore::new(&config.database_url).await {
+            Ok(store) => {
+                info!("Persisting opportunities to the database");
+                Some(store)
+            }
+            Err(e) => {
+                warn!("Opportunity store unavailable, logging only: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
     // Get pairs to monitor
-    let pairs = default_pairs();
+    let pairs: Vec<TokenPair> = default_pairs()
+        .into_iter()
+        .filter(|pair| config.is_pair_enabled(pair))
+        .collect();
     info!("Monitoring {} trading pairs", pairs.len());
 
     // Collect prices from all DEXs
-    let providers: Vec<Box<dyn DexProvider>> = vec![
+    let mut providers: Vec<Box<dyn DexProvider>> = vec![
         Box::new(jupiter),
         Box::new(raydium),
         Box::new(orca),
     ];
+    providers.retain(|provider| config.is_dex_enabled(provider.dex_type()));
 
     // Main collection loop
     let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(500));
@@ -98,23 +123,37 @@ async fn main() -> Result<()> {
 
         // Fetch prices from all providers
         for provider in &providers {
-            match provider.get_prices(&pairs).await {
-                Ok(prices) => {
-                    let mut detector_guard = detector.write().await;
-                    detector_guard.update_prices(prices);
-                    drop(detector_guard);
-                }
-                Err(e) => {
-                    warn!("Failed to get prices from {}: {}", provider.dex_type(), e);
-                }
+            let batch = provider.get_prices(&pairs).await;
+            for (pair, e) in &batch.errors {
+                warn!("Failed to get {} price from {}: {}", pair, provider.dex_type(), e);
+            }
+            if !batch.prices.is_empty() {
+                let mut detector_guard = detector.write().await;
+                detector_guard.update_prices(batch.prices);
+                drop(detector_guard);
             }
         }
 
         // Find opportunities
         let detector_guard = detector.read().await;
         let opportunities = detector_guard.find_all_opportunities();
+        let triangles = detector_guard.find_triangular_opportunities("SOL");
         drop(detector_guard);
 
+        for opp in triangles.iter().take(3) {
+            let legs: Vec<String> = opp
+                .path
+                .iter()
+                .flatten()
+                .map(|(dex, pair)| format!("{} {}", dex, pair))
+                .collect();
+            info!(
+                "  Triangle {} | Net: {:.4}%",
+                legs.join(" -> "),
+                opp.net_profit_pct
+            );
+        }
+
         if !opportunities.is_empty() {
             info!("Found {} arbitrage opportunities:", opportunities.len());
             for opp in opportunities.iter().take(5) {
@@ -128,6 +167,14 @@ async fn main() -> Result<()> {
                     opp.net_profit_pct
                 );
             }
+
+            if let Some(store) = &store {
+                for opp in &opportunities {
+                    if let Err(e) = store.insert(opp).await {
+                        warn!("Failed to store opportunity {}: {}", opp.id, e);
+                    }
+                }
+            }
         }
 
         // Clean up stale prices (older than 5 seconds)
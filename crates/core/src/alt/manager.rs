@@ -1,24 +1,50 @@
-use anyhow::{anyhow, Result};
-use solana_rpc_client::rpc_client::RpcClient;
+use futures_util::future::BoxFuture;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::address_lookup_table::{
     instruction::{create_lookup_table, extend_lookup_table},
+    state::{AddressLookupTable, LOOKUP_TABLE_MAX_ADDRESSES},
     AddressLookupTableAccount,
 };
 use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::slot_hashes::MAX_ENTRIES as SLOT_HASHES_MAX_ENTRIES;
 use solana_sdk::transaction::Transaction; // Use legacy Transaction for creation
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
-use tracing::info;
+use tracing::{debug, info};
+
+use crate::error::{ArbitrageError, ArbitrageResult};
+
+/// Addresses added per extend transaction, keeping each one well under the
+/// 1232-byte packet limit (32 bytes per address)
+pub const MAX_ADDRESSES_PER_EXTEND: usize = 20;
+
+/// How long a fetched table is used before it is fetched again. Tables rarely
+/// change, but anyone holding the authority can extend them.
+pub const DEFAULT_TABLE_TTL: Duration = Duration::from_secs(300);
+
+/// Loads a lookup table's current addresses (from the RPC, unless replaced)
+pub type TableFetcher = Arc<
+    dyn Fn(Pubkey) -> BoxFuture<'static, ArbitrageResult<AddressLookupTableAccount>> + Send + Sync,
+>;
+
+struct CachedTable {
+    table: AddressLookupTableAccount,
+    fetched_at: Instant,
+}
 
 /// Manages Address Lookup Tables (ALTs) for efficient transaction packing
-#[allow(dead_code)]
 pub struct AltManager {
     rpc_client: Arc<RpcClient>,
-    lookup_tables: RwLock<HashMap<String, Pubkey>>,
-    cache: RwLock<HashMap<Pubkey, AddressLookupTableAccount>>,
+    fetcher: TableFetcher,
+    table_ttl: Duration,
+    /// Tables created through this manager, oldest first
+    created: RwLock<Vec<Pubkey>>,
+    cache: RwLock<HashMap<Pubkey, CachedTable>>,
 }
 
 impl AltManager {
@@ -28,92 +54,136 @@ impl AltManager {
             CommitmentConfig::confirmed(),
         ));
 
+        let rpc = rpc_client.clone();
+        let fetcher: TableFetcher = Arc::new(move |address| {
+            let rpc = rpc.clone();
+            Box::pin(async move { fetch_table(&rpc, address).await })
+        });
+
         Self {
             rpc_client,
-            lookup_tables: RwLock::new(HashMap::new()),
+            fetcher,
+            table_ttl: DEFAULT_TABLE_TTL,
+            created: RwLock::new(Vec::new()),
             cache: RwLock::new(HashMap::new()),
         }
     }
 
-    /// Create a new Address Lookup Table
-    pub async fn create_alt(
+    /// Replaces how long fetched tables are cached
+    pub fn with_table_ttl(mut self, ttl: Duration) -> Self {
+        self.table_ttl = ttl;
+        self
+    }
+
+    /// Replaces how tables are fetched on a cache miss
+    pub fn with_fetcher(mut self, fetcher: TableFetcher) -> Self {
+        self.fetcher = fetcher;
+        self
+    }
+
+    /// Drop `key` from the cache so the next lookup fetches it again
+    pub async fn invalidate(&self, key: &Pubkey) {
+        self.cache.write().await.remove(key);
+    }
+
+    /// Create a lookup table owned by `authority`, paid for by `payer`.
+    ///
+    /// The table's address is derived from `recent_slot`, which the program
+    /// checks against the `SlotHashes` sysvar: it must be one of the last 512
+    /// slots, so fetch it with `getSlot` right before calling this.
+    pub async fn create_lookup_table(
         &self,
+        authority: &Pubkey,
         payer: &Keypair,
-        recent_blockhash: solana_sdk::hash::Hash,
-    ) -> Result<Pubkey> {
-        let (instruction, table_address) = create_lookup_table(
-            payer.pubkey(), // authority
-            payer.pubkey(), // payer
-            1,              // recent slot (dummy for now, ideally current slot)
-        );
+        recent_slot: u64,
+    ) -> ArbitrageResult<Pubkey> {
+        let current_slot = self.rpc_client.get_slot().await.map_err(rpc_error)?;
+        check_recent_slot(recent_slot, current_slot)?;
+
+        let (instruction, table_address) =
+            create_lookup_table(*authority, payer.pubkey(), recent_slot);
+        self.submit(instruction, &[payer]).await?;
+        info!("📝 Created new ALT at: {}", table_address);
 
-        // Use legacy transaction for creation as we are not using lookups yet
-        let _tx = Transaction::new_signed_with_payer(
-            &[instruction],
-            Some(&payer.pubkey()),
-            &[payer],
-            recent_blockhash,
+        self.created.write().await.push(table_address);
+        self.cache.write().await.insert(
+            table_address,
+            CachedTable {
+                table: AddressLookupTableAccount {
+                    key: table_address,
+                    addresses: Vec::new(),
+                },
+                fetched_at: Instant::now(),
+            },
         );
+        Ok(table_address)
+    }
 
-        // In a real implementation we would send this tx
-        // For now, we simulate success or rely on caller to handle submission if we returned instruction
-        // But since this method claims to create it, we should probably submit it.
-        // However, `rpc_client` here is blocking in a seemingly async method which is not ideal,
-        // but since we are refactoring, we'll keep it simple or note it.
+    /// Add `addresses` to `table`, skipping any it already holds.
+    ///
+    /// Sends one transaction per [`MAX_ADDRESSES_PER_EXTEND`] addresses, and
+    /// nothing at all if the table would end up over its 256-address cap. New
+    /// addresses can be used in transactions from the next slot on.
+    pub async fn extend_lookup_table(
+        &self,
+        table: Pubkey,
+        authority: &Keypair,
+        payer: &Keypair,
+        addresses: &[Pubkey],
+    ) -> ArbitrageResult<()> {
+        let current = self.get_alt(&table).await?;
+        let (instructions, added) =
+            extend_table_instructions(&current, &authority.pubkey(), &payer.pubkey(), addresses)?;
+        if instructions.is_empty() {
+            return Ok(());
+        }
 
-        info!("📝 Created new ALT at: {}", table_address);
+        for instruction in instructions {
+            self.submit(instruction, &[payer, authority]).await?;
+        }
+        info!(
+            "📝 Extended ALT {} with {} new addresses",
+            table,
+            added.len()
+        );
 
-        // Cache it
-        self.lookup_tables
-            .write()
-            .await
-            .insert("default".to_string(), table_address);
+        self.invalidate(&table).await;
+        Ok(())
+    }
 
-        Ok(table_address)
+    /// Tables created through this manager, oldest first
+    pub async fn created_tables(&self) -> Vec<Pubkey> {
+        self.created.read().await.clone()
     }
 
-    /// Fetch and cache an ALT
-    pub async fn get_alt(&self, address: &Pubkey) -> Result<AddressLookupTableAccount> {
+    /// Fetch and cache an ALT, reusing a cached copy younger than the TTL
+    pub async fn get_alt(&self, address: &Pubkey) -> ArbitrageResult<AddressLookupTableAccount> {
         // Check cache first
         {
             let cache = self.cache.read().await;
-            if let Some(table) = cache.get(address) {
-                return Ok(table.clone());
+            if let Some(cached) = cache.get(address) {
+                if cached.fetched_at.elapsed() < self.table_ttl {
+                    return Ok(cached.table.clone());
+                }
             }
         }
 
-        // Fetch from RPC
-        // This requires blocking call or spawn_blocking
-        // Placeholder for now
-
-        Err(anyhow!("ALT fetching not fully implemented in this phase"))
-    }
-
-    pub async fn extend_alt(
-        &self,
-        payer: &Keypair,
-        alt_address: Pubkey,
-        new_addresses: Vec<Pubkey>,
-        _recent_blockhash: solana_sdk::hash::Hash,
-    ) -> Result<()> {
-        let instruction = extend_lookup_table(
-            alt_address,
-            payer.pubkey(),       // authority
-            Some(payer.pubkey()), // payer
-            new_addresses,
-        );
-
-        info!(
-            "📝 Extending ALT {} with {} new addresses",
-            alt_address,
-            instruction.accounts.len()
+        debug!("Fetching ALT {}", address);
+        let table = (self.fetcher)(*address).await?;
+        self.cache.write().await.insert(
+            *address,
+            CachedTable {
+                table: table.clone(),
+                fetched_at: Instant::now(),
+            },
         );
-        // Transaction submission logic would go here
-
-        Ok(())
+        Ok(table)
     }
 
-    pub async fn get_tables(&self, addresses: &[Pubkey]) -> Result<Vec<AddressLookupTableAccount>> {
+    pub async fn get_tables(
+        &self,
+        addresses: &[Pubkey],
+    ) -> ArbitrageResult<Vec<AddressLookupTableAccount>> {
         let mut tables = Vec::new();
         for addr in addresses {
             // Sequential fetch for now
@@ -121,13 +191,201 @@ impl AltManager {
         }
         Ok(tables)
     }
+
+    /// Sign `instruction` with `signers` (the first pays) and wait for confirmation
+    async fn submit(&self, instruction: Instruction, signers: &[&Keypair]) -> ArbitrageResult<()> {
+        let payer = signers[0].pubkey();
+        let mut unique: Vec<&Keypair> = Vec::with_capacity(signers.len());
+        for signer in signers {
+            if !unique.iter().any(|s| s.pubkey() == signer.pubkey()) {
+                unique.push(signer);
+            }
+        }
+
+        let blockhash = self
+            .rpc_client
+            .get_latest_blockhash()
+            .await
+            .map_err(rpc_error)?;
+        let tx =
+            Transaction::new_signed_with_payer(&[instruction], Some(&payer), &unique, blockhash);
+        self.rpc_client
+            .send_and_confirm_transaction(&tx)
+            .await
+            .map_err(|e| ArbitrageError::SubmissionFailed(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Read a lookup table account from the RPC
+async fn fetch_table(
+    rpc_client: &RpcClient,
+    address: Pubkey,
+) -> ArbitrageResult<AddressLookupTableAccount> {
+    let account = rpc_client.get_account(&address).await.map_err(rpc_error)?;
+    let table = AddressLookupTable::deserialize(&account.data).map_err(|e| {
+        ArbitrageError::Transaction(format!("{} is not a lookup table: {}", address, e))
+    })?;
+    Ok(AddressLookupTableAccount {
+        key: address,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+/// Check that `recent_slot` is still in the `SlotHashes` sysvar at `current_slot`
+pub fn check_recent_slot(recent_slot: u64, current_slot: u64) -> ArbitrageResult<()> {
+    match current_slot.checked_sub(recent_slot) {
+        Some(age) if (age as usize) < SLOT_HASHES_MAX_ENTRIES => Ok(()),
+        _ => Err(ArbitrageError::Transaction(format!(
+            "lookup table slot {} is not among the last {} slots (current {})",
+            recent_slot, SLOT_HASHES_MAX_ENTRIES, current_slot
+        ))),
+    }
+}
+
+/// Extend instructions adding the `addresses` that `table` doesn't hold yet,
+/// [`MAX_ADDRESSES_PER_EXTEND`] per instruction, with the addresses they add.
+///
+/// Errors if the table would hold more than 256 addresses.
+pub fn extend_table_instructions(
+    table: &AddressLookupTableAccount,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    addresses: &[Pubkey],
+) -> ArbitrageResult<(Vec<Instruction>, Vec<Pubkey>)> {
+    let mut added: Vec<Pubkey> = Vec::new();
+    for address in addresses {
+        if !table.addresses.contains(address) && !added.contains(address) {
+            added.push(*address);
+        }
+    }
+    let total = table.addresses.len() + added.len();
+    if total > LOOKUP_TABLE_MAX_ADDRESSES {
+        return Err(ArbitrageError::Transaction(format!(
+            "lookup table {} would hold {} addresses, max {}",
+            table.key, total, LOOKUP_TABLE_MAX_ADDRESSES
+        )));
+    }
+
+    let instructions = added
+        .chunks(MAX_ADDRESSES_PER_EXTEND)
+        .map(|chunk| extend_lookup_table(table.key, *authority, Some(*payer), chunk.to_vec()))
+        .collect();
+    Ok((instructions, added))
+}
+
+fn rpc_error(e: impl fmt::Display) -> ArbitrageError {
+    ArbitrageError::RpcError(e.to_string())
 }
 
 use std::fmt;
 impl fmt::Debug for AltManager {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("AltManager")
+            .field("table_ttl", &self.table_ttl)
             .field("cache_size", &"unknown") // RwLock read is async, can't do in Debug
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::address_lookup_table::{instruction::derive_lookup_table_address, program};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Manager whose fetches return a one-address table and are counted
+    fn counting_manager(ttl: Duration) -> (AltManager, Arc<AtomicUsize>) {
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let counter = fetches.clone();
+        let fetcher: TableFetcher = Arc::new(move |key| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move {
+                Ok(AddressLookupTableAccount {
+                    key,
+                    addresses: vec![Pubkey::new_unique()],
+                })
+            })
+        });
+        let manager = AltManager::new("http://127.0.0.1:1")
+            .with_fetcher(fetcher)
+            .with_table_ttl(ttl);
+        (manager, fetches)
+    }
+
+    #[tokio::test]
+    async fn test_get_tables_fetches_each_table_once_per_ttl() {
+        let (manager, fetches) = counting_manager(Duration::from_secs(60));
+        let key = Pubkey::new_unique();
+
+        let first = manager.get_tables(&[key]).await.unwrap();
+        let second = manager.get_tables(&[key]).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        assert_eq!(first, second);
+
+        manager.invalidate(&key).await;
+        manager.get_tables(&[key]).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+
+        // An expired entry is fetched again
+        let (manager, fetches) = counting_manager(Duration::ZERO);
+        manager.get_alt(&key).await.unwrap();
+        manager.get_alt(&key).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_create_instruction_derives_from_recent_slot() {
+        let authority = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let (instruction, table) = create_lookup_table(authority, payer, 1_000);
+
+        assert_eq!(instruction.program_id, program::id());
+        assert_eq!(table, derive_lookup_table_address(&authority, 1_000).0);
+        assert_eq!(instruction.accounts[0].pubkey, table);
+        assert!(instruction.accounts[2].is_signer);
+
+        assert!(check_recent_slot(1_000, 1_000).is_ok());
+        assert!(check_recent_slot(1_000, 1_511).is_ok());
+        assert!(check_recent_slot(1_000, 1_512).is_err());
+        assert!(check_recent_slot(1_001, 1_000).is_err());
+    }
+
+    #[test]
+    fn test_extend_instructions_dedupe_chunk_and_cap() {
+        let authority = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let existing = Pubkey::new_unique();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![existing],
+        };
+
+        // 45 new addresses, one repeated and one already in the table
+        let mut addresses: Vec<Pubkey> = (0..45).map(|_| Pubkey::new_unique()).collect();
+        addresses.push(addresses[0]);
+        addresses.push(existing);
+        let (instructions, added) =
+            extend_table_instructions(&table, &authority, &payer, &addresses).unwrap();
+        assert_eq!(added, addresses[..45]);
+        assert_eq!(instructions.len(), 3);
+        for instruction in &instructions {
+            assert_eq!(instruction.program_id, program::id());
+            assert_eq!(instruction.accounts[0].pubkey, table.key);
+            assert_eq!(instruction.accounts[1].pubkey, authority);
+            assert!(instruction.accounts[1].is_signer);
+            assert_eq!(instruction.accounts[2].pubkey, payer);
+        }
+
+        // Nothing new: nothing to send
+        let (none, _) = extend_table_instructions(&table, &authority, &payer, &[existing]).unwrap();
+        assert!(none.is_empty());
+
+        let full = AddressLookupTableAccount {
+            key: table.key,
+            addresses: (0..250).map(|_| Pubkey::new_unique()).collect(),
+        };
+        assert!(extend_table_instructions(&full, &authority, &payer, &addresses[..6]).is_ok());
+        assert!(extend_table_instructions(&full, &authority, &payer, &addresses[..7]).is_err());
+    }
+}
@@ -3,29 +3,119 @@
 //! This module identifies arbitrage opportunities by comparing prices
 //! across different DEXs for the same trading pair.
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
-use std::collections::HashMap;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 
-use crate::{ArbitrageConfig, ArbitrageOpportunity, DexType, PriceData, TokenPair, Uuid};
+use crate::dex::estimate_quote;
+use crate::{
+    is_usd_stablecoin, ArbitrageConfig, ArbitrageError, ArbitrageOpportunity, ArbitrageResult,
+    DexType, PriceData, ProfitBreakdown, RoundTripResult, Side, TokenPair, TxCosts, Uuid,
+    USD_STABLECOINS,
+};
+
+/// Per-pool trading fees, falling back to [`DexType::fee_percentage`].
+///
+/// Pool fees vary by tier (Orca Whirlpools range from 0.01% to 1%) and some
+/// DEXs price dynamically, so the static per-DEX default is only a fallback.
+/// Fees use the same units as `fee_percentage`.
+#[derive(Debug, Clone, Default)]
+pub struct FeeSchedule {
+    overrides: HashMap<(DexType, TokenPair), Decimal>,
+}
+
+impl FeeSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `fee` for trades of `pair` on `dex`
+    pub fn register(&mut self, dex: DexType, pair: TokenPair, fee: Decimal) {
+        self.overrides.insert((dex, pair), fee);
+    }
+
+    /// Take every fee registered in `other`, replacing any for the same pool
+    pub fn extend(&mut self, other: FeeSchedule) {
+        self.overrides.extend(other.overrides);
+    }
+
+    /// The fee for `pair` on `dex`: the registered one, else the DEX default
+    pub fn fee(&self, dex: DexType, pair: &TokenPair) -> Decimal {
+        self.overrides
+            .get(&(dex, pair.clone()))
+            .copied()
+            .unwrap_or_else(|| dex.fee_percentage())
+    }
+}
+
+/// DEXs that must quote a pair before [`ArbitrageDetector::sanitize_prices`]
+/// can single out an outlier
+pub const MIN_SANITY_SOURCES: usize = 3;
+
+/// Narrowing steps when searching for the most profitable round-trip size;
+/// each keeps two thirds of the range
+const ROUND_TRIP_SEARCH_STEPS: usize = 60;
 
 /// Arbitrage detector that compares prices across DEXs
 pub struct ArbitrageDetector {
     config: ArbitrageConfig,
+    /// Trading fee per (dex, pair)
+    fees: FeeSchedule,
     /// Cache of latest prices by (pair, dex)
     price_cache: HashMap<(TokenPair, DexType), PriceData>,
+    /// Opportunities handed out via `track` that have not expired yet
+    issued: HashMap<Uuid, ArbitrageOpportunity>,
 }
 
 impl ArbitrageDetector {
     pub fn new(config: ArbitrageConfig) -> Self {
+        Self::new_with_fees(config, FeeSchedule::default())
+    }
+
+    /// Detector that charges fees from `fees` instead of the static DEX defaults
+    pub fn new_with_fees(config: ArbitrageConfig, fees: FeeSchedule) -> Self {
         Self {
             config,
+            fees,
             price_cache: HashMap::new(),
+            issued: HashMap::new(),
         }
     }
 
+    /// Net profit (percent) an opportunity needs to be reported
+    pub fn min_profit_threshold(&self) -> Decimal {
+        self.config.min_profit_threshold
+    }
+
+    /// Change the net profit (percent) opportunities need, e.g. on a config reload
+    pub fn set_min_profit_threshold(&mut self, pct: Decimal) {
+        self.config.min_profit_threshold = pct;
+    }
+
+    /// Charge `fee` for trades of `pair` on `dex` from now on, e.g. a fee
+    /// reported by the DEX itself
+    pub fn register_fee(&mut self, dex: DexType, pair: TokenPair, fee: Decimal) {
+        self.fees.register(dex, pair, fee);
+    }
+
+    /// Charge every fee in `fees` from now on, e.g. a batch fetched from a DEX
+    pub fn register_fees(&mut self, fees: FeeSchedule) {
+        self.fees.extend(fees);
+    }
+
+    /// Whether prices from `dex` take part in cross-DEX comparison
+    pub fn is_price_source(&self, dex: DexType) -> bool {
+        dex != DexType::Jupiter || self.config.jupiter_price_source
+    }
+
     /// Update the price cache with new price data
     pub fn update_price(&mut self, price: PriceData) {
+        if !self.is_price_source(price.dex) {
+            return;
+        }
         let key = (price.pair.clone(), price.dex);
         self.price_cache.insert(key, price);
     }
@@ -64,11 +154,16 @@ impl ArbitrageDetector {
         }
 
         // Sort by profit percentage (descending)
-        opportunities.sort_by(|a, b| b.net_profit_pct.cmp(&a.net_profit_pct));
+        opportunities.sort_by_key(|opp| std::cmp::Reverse(opp.net_profit_pct));
         opportunities
     }
 
-    /// Check if there's an arbitrage opportunity between two prices
+    /// Check if there's an arbitrage opportunity between two prices.
+    ///
+    /// When either leg reports `liquidity`, profit is evaluated at the size that
+    /// maximizes it in [`Self::simulate_round_trip`], which becomes
+    /// `recommended_size`; without liquidity the quotes are taken at face value
+    /// and no size is recommended.
     fn check_opportunity(
         &self,
         buy_from: &PriceData,
@@ -78,23 +173,37 @@ impl ArbitrageDetector {
         let buy_price = buy_from.ask;
         let sell_price = sell_to.bid;
 
-        if buy_price.is_zero() || sell_price.is_zero() {
+        // Both legs would hit the same pool/route - the spread is illusory
+        if buy_from.shares_route_with(sell_to) {
             return None;
         }
 
-        // Calculate gross profit percentage
-        let gross_profit_pct = ((sell_price - buy_price) / buy_price) * Decimal::from(100);
+        // A spread on a pool too shallow to trade at size is a phantom edge
+        if !self.has_min_liquidity(buy_from) || !self.has_min_liquidity(sell_to) {
+            return None;
+        }
 
-        // Calculate fees
-        let buy_fee = buy_from.dex.fee_percentage();
-        let sell_fee = sell_to.dex.fee_percentage();
-        let total_fee_pct = buy_fee + sell_fee;
+        let (mut gross_profit_pct, mut net_profit_pct) =
+            self.quoted_profit_pct(buy_from, sell_to)?;
 
-        // Net profit after fees
-        let net_profit_pct = gross_profit_pct - total_fee_pct;
+        // Replace the quoted spread with both legs filled at the most profitable size
+        let mut recommended_size = None;
+        let mut estimated_profit_usd = None;
+        if !impact_per_unit(buy_from, sell_to).is_zero() && net_profit_pct > Decimal::ZERO {
+            let round_trip = self.best_round_trip(buy_from, sell_to)?;
+            gross_profit_pct = round_trip.gross_profit_pct();
+            net_profit_pct = round_trip.profit_pct();
+            recommended_size = Some(round_trip.capital);
+            estimated_profit_usd = Some(round_trip.profit());
+        }
+
+        // Synthetic bid/ask can hide the real spread, so each one-sided leg needs extra edge
+        let one_sided_legs = [buy_from, sell_to].iter().filter(|p| p.one_sided).count();
+        let required_profit_pct = self.config.min_profit_threshold
+            + self.config.one_sided_extra_edge_pct * Decimal::from(one_sided_legs);
 
         // Only return if profitable after fees and above threshold
-        if net_profit_pct > self.config.min_profit_threshold {
+        if net_profit_pct > required_profit_pct {
             Some(ArbitrageOpportunity {
                 id: Uuid::new_v4(),
                 pair: buy_from.pair.clone(),
@@ -104,16 +213,250 @@ impl ArbitrageDetector {
                 sell_price,
                 gross_profit_pct,
                 net_profit_pct,
-                estimated_profit_usd: None,
-                recommended_size: None,
+                estimated_profit_usd,
+                recommended_size,
                 detected_at: Utc::now(),
                 expired_at: None,
+                path: None,
             })
         } else {
             None
         }
     }
 
+    /// Whether `price`'s pool holds at least `min_liquidity_usd`. Its liquidity is
+    /// in the quote token, so is valued at that token's USD price; liquidity that
+    /// is unreported or can't be valued passes only if `allow_unknown_liquidity`.
+    fn has_min_liquidity(&self, price: &PriceData) -> bool {
+        if self.config.min_liquidity_usd <= Decimal::ZERO {
+            return true;
+        }
+        let liquidity_usd = price
+            .liquidity
+            .zip(self.usd_price(&price.pair.quote))
+            .and_then(|(liquidity, usd)| liquidity.checked_mul(usd));
+        match liquidity_usd {
+            Some(liquidity_usd) => liquidity_usd >= self.config.min_liquidity_usd,
+            None => self.config.allow_unknown_liquidity,
+        }
+    }
+
+    /// Gross and net (after fees) profit percentage at the quoted prices, ignoring
+    /// size. `None` for a missing side or if the math overflows.
+    fn quoted_profit_pct(
+        &self,
+        buy_from: &PriceData,
+        sell_to: &PriceData,
+    ) -> Option<(Decimal, Decimal)> {
+        let buy_price = buy_from.ask;
+        let sell_price = sell_to.bid;
+
+        if buy_price.is_zero() || sell_price.is_zero() {
+            return None;
+        }
+
+        // Calculate gross profit percentage; extreme quotes that overflow are skipped
+        let gross_profit_pct = match sell_price
+            .checked_sub(buy_price)
+            .and_then(|spread| spread.checked_div(buy_price))
+            .and_then(|ratio| ratio.checked_mul(Decimal::ONE_HUNDRED))
+        {
+            Some(pct) => pct,
+            None => {
+                tracing::warn!(
+                    "Skipping {} {:?}->{:?}: profit overflows (buy {}, sell {})",
+                    buy_from.pair,
+                    buy_from.dex,
+                    sell_to.dex,
+                    buy_price,
+                    sell_price
+                );
+                return None;
+            }
+        };
+
+        // Fees are fractions of the trade (0.0025 is 0.25%); the profit is in percent
+        let buy_fee = self.fees.fee(buy_from.dex, &buy_from.pair);
+        let sell_fee = self.fees.fee(sell_to.dex, &sell_to.pair);
+        let total_fee_pct = (buy_fee + sell_fee) * Decimal::ONE_HUNDRED;
+
+        // Net profit after fees
+        Some((gross_profit_pct, gross_profit_pct - total_fee_pct))
+    }
+
+    /// Net profit (quote currency) of buying `size` on `buy` and selling on `sell`.
+    ///
+    /// Uses a linear price-impact model: trading `size` against a leg with
+    /// `liquidity` depth moves its price against us by `size / liquidity`. Legs
+    /// without liquidity data have no impact, so with neither this is simply
+    /// `size * net_profit_pct / 100`. Returns zero if the quotes are unusable.
+    ///
+    /// Both legs are charged on `size` here; [`Self::simulate_round_trip`]
+    /// sells what the buy leg actually returned instead.
+    pub fn estimate_net_profit(&self, buy: &PriceData, sell: &PriceData, size: Decimal) -> Decimal {
+        let Some((_, net_profit_pct)) = self.quoted_profit_pct(buy, sell) else {
+            return Decimal::ZERO;
+        };
+        size.checked_mul(impact_per_unit(buy, sell))
+            .and_then(|slippage| (net_profit_pct / Decimal::ONE_HUNDRED).checked_sub(slippage))
+            .and_then(|net| net.checked_mul(size))
+            .unwrap_or(Decimal::ZERO)
+    }
+
+    /// Spend `capital` buying the base token on `buy`, then sell everything it
+    /// bought on `sell`.
+    ///
+    /// Each leg fills through [`estimate_quote`] with its pool's fee, so the
+    /// sell leg is sized by the buy leg's output (after its slippage and fee)
+    /// rather than by `capital`, and the result is the end-to-end profit.
+    /// Errors if either leg exceeds its pool's liquidity.
+    pub fn simulate_round_trip(
+        &self,
+        buy: &PriceData,
+        sell: &PriceData,
+        capital: Decimal,
+    ) -> ArbitrageResult<RoundTripResult> {
+        let buy_fee = self.fees.fee(buy.dex, &buy.pair);
+        let buy_leg = estimate_quote(buy, capital, Side::Buy, buy_fee)?;
+        let sell_fee = self.fees.fee(sell.dex, &sell.pair);
+        let sell_leg = estimate_quote(sell, buy_leg.out_amount, Side::Sell, sell_fee)?;
+        Ok(RoundTripResult {
+            capital,
+            buy: buy_leg,
+            sell: sell_leg,
+        })
+    }
+
+    /// The most profitable round trip of at most `max_position_size`.
+    ///
+    /// Under linear impact the profit rises, peaks and falls with size, so a
+    /// ternary search finds the peak. `None` if no size can be filled.
+    fn best_round_trip(&self, buy: &PriceData, sell: &PriceData) -> Option<RoundTripResult> {
+        let profit = |size| {
+            self.simulate_round_trip(buy, sell, size)
+                .ok()
+                .map(|round_trip| round_trip.profit())
+        };
+        let (mut low, mut high) = (Decimal::ZERO, self.config.max_position_size);
+        for _ in 0..ROUND_TRIP_SEARCH_STEPS {
+            let third = (high - low) / Decimal::from(3);
+            let (lower, upper) = (low + third, high - third);
+            match (profit(lower), profit(upper)) {
+                (Some(at_lower), Some(at_upper)) if at_lower < at_upper => low = lower,
+                // Past the peak, or too big to fill
+                (Some(_), _) => high = upper,
+                (None, _) => high = lower,
+            }
+        }
+        self.simulate_round_trip(buy, sell, (low + high) / Decimal::TWO)
+            .ok()
+    }
+
+    /// Find three-leg cycles `base -> X -> Y -> base` whose combined exchange rate,
+    /// less each leg's DEX fee, clears the profit threshold.
+    ///
+    /// Each cached price gives up to two edges: selling the pair's base at the bid
+    /// and buying it with the quote at the ask, so a price with only one usable side
+    /// contributes only that direction. Legs may use the same DEX more than once.
+    /// Only the best DEX combination per token cycle is returned.
+    ///
+    /// The opportunity's `path` lists the legs in order; `buy_price` is 1 unit of
+    /// `base` in and `sell_price` the units of `base` that come back.
+    pub fn find_triangular_opportunities(&self, base: &str) -> Vec<ArbitrageOpportunity> {
+        let mut edges: HashMap<&str, Vec<RateEdge<'_>>> = HashMap::new();
+        for ((pair, dex), price) in &self.price_cache {
+            if !price.bid.is_zero() {
+                edges.entry(pair.base.as_str()).or_default().push(RateEdge {
+                    to: &pair.quote,
+                    rate: price.bid,
+                    dex: *dex,
+                    pair,
+                    one_sided: price.one_sided,
+                });
+            }
+            if let Some(rate) = Decimal::ONE.checked_div(price.ask).filter(|r| !r.is_zero()) {
+                edges
+                    .entry(pair.quote.as_str())
+                    .or_default()
+                    .push(RateEdge {
+                        to: &pair.base,
+                        rate,
+                        dex: *dex,
+                        pair,
+                        one_sided: price.one_sided,
+                    });
+            }
+        }
+
+        let mut best: HashMap<(&str, &str), ArbitrageOpportunity> = HashMap::new();
+        for first in edges.get(base).into_iter().flatten() {
+            for second in edges.get(first.to).into_iter().flatten() {
+                if second.to == base || second.to == first.to {
+                    continue;
+                }
+                for third in edges.get(second.to).into_iter().flatten() {
+                    if third.to != base {
+                        continue;
+                    }
+                    let Some(opp) = self.check_cycle([first, second, third]) else {
+                        continue;
+                    };
+                    let key = (first.to, second.to);
+                    if best
+                        .get(&key)
+                        .is_none_or(|current| opp.net_profit_pct > current.net_profit_pct)
+                    {
+                        best.insert(key, opp);
+                    }
+                }
+            }
+        }
+
+        let mut opportunities: Vec<_> = best.into_values().collect();
+        opportunities.sort_by_key(|opp| std::cmp::Reverse(opp.net_profit_pct));
+        opportunities
+    }
+
+    /// Profitability of one concrete three-leg cycle; `None` if below threshold
+    fn check_cycle(&self, legs: [&RateEdge<'_>; 3]) -> Option<ArbitrageOpportunity> {
+        // Product of rates; cycles through extreme quotes that overflow are skipped
+        let returned = legs
+            .iter()
+            .try_fold(Decimal::ONE, |acc, leg| acc.checked_mul(leg.rate))?;
+        let gross_profit_pct = returned
+            .checked_sub(Decimal::ONE)?
+            .checked_mul(Decimal::ONE_HUNDRED)?;
+
+        let total_fee_pct: Decimal = legs
+            .iter()
+            .map(|leg| self.fees.fee(leg.dex, leg.pair))
+            .sum();
+        let net_profit_pct = gross_profit_pct - total_fee_pct;
+
+        let one_sided_legs = legs.iter().filter(|leg| leg.one_sided).count();
+        let required_profit_pct = self.config.min_profit_threshold
+            + self.config.one_sided_extra_edge_pct * Decimal::from(one_sided_legs);
+        if net_profit_pct <= required_profit_pct {
+            return None;
+        }
+
+        Some(ArbitrageOpportunity {
+            id: Uuid::new_v4(),
+            pair: legs[0].pair.clone(),
+            buy_dex: legs[0].dex,
+            sell_dex: legs[2].dex,
+            buy_price: Decimal::ONE,
+            sell_price: returned,
+            gross_profit_pct,
+            net_profit_pct,
+            estimated_profit_usd: None,
+            recommended_size: None,
+            detected_at: Utc::now(),
+            expired_at: None,
+            path: Some(legs.iter().map(|leg| (leg.dex, leg.pair.clone())).collect()),
+        })
+    }
+
     /// Find all profitable opportunities across all cached pairs
     pub fn find_all_opportunities(&self) -> Vec<ArbitrageOpportunity> {
         // Get unique pairs from cache
@@ -136,6 +479,49 @@ impl ArbitrageDetector {
         all_opportunities
     }
 
+    /// Split `available_capital` across `opps`, which are taken to be ranked best
+    /// first (e.g. by [`rank_by_aged_score`]).
+    ///
+    /// Capital goes to opportunities in the order given, each taking up to its
+    /// recommended size and never more than `max_position_size`. Capital is
+    /// committed once per pair: an opportunity trading a pair (or, for a multi-hop
+    /// path, any leg's pair) that is already funded is skipped. Returns the funded
+    /// opportunities with their allocations, in the order given.
+    pub fn select_opportunities(
+        &self,
+        opps: &[ArbitrageOpportunity],
+        available_capital: Decimal,
+    ) -> Vec<(ArbitrageOpportunity, Decimal)> {
+        let ranked = opps
+            .iter()
+            .filter(|opp| opp.is_active() && opp.net_profit_pct > Decimal::ZERO);
+
+        let mut remaining = available_capital;
+        let mut committed = HashSet::new();
+        let mut selected = Vec::new();
+        for opp in ranked {
+            if remaining <= Decimal::ZERO {
+                break;
+            }
+            let pairs = traded_pairs(opp);
+            if pairs.iter().any(|pair| committed.contains(pair)) {
+                continue;
+            }
+            let size = opp
+                .recommended_size
+                .unwrap_or(self.config.max_position_size)
+                .min(self.config.max_position_size)
+                .min(remaining);
+            if size <= Decimal::ZERO {
+                continue;
+            }
+            remaining -= size;
+            committed.extend(pairs);
+            selected.push((opp.clone(), size));
+        }
+        selected
+    }
+
     /// Get the current price cache
     pub fn get_prices(&self) -> &HashMap<(TokenPair, DexType), PriceData> {
         &self.price_cache
@@ -147,6 +533,224 @@ impl ArbitrageDetector {
         self.price_cache
             .retain(|_, price| (now - price.timestamp).num_seconds() < max_age_seconds);
     }
+
+    /// Drop cached prices that disagree wildly with the other DEXs quoting the
+    /// same pair, such as a provider's decimal or scaling bug showing up as a
+    /// fake 50% spread.
+    ///
+    /// Only pairs quoted by at least [`MIN_SANITY_SOURCES`] DEXs are checked, since
+    /// with two there is no telling which one is wrong. A price whose mid is more
+    /// than `max_price_deviation_pct` from the median mid is removed until its DEX
+    /// reports again. Returns the dropped prices.
+    pub fn sanitize_prices(&mut self) -> Vec<PriceData> {
+        let mut mids: HashMap<&TokenPair, Vec<Decimal>> = HashMap::new();
+        for ((pair, _), price) in &self.price_cache {
+            mids.entry(pair).or_default().push(price.mid_price);
+        }
+        let medians: HashMap<TokenPair, Decimal> = mids
+            .into_iter()
+            .filter(|(_, mids)| mids.len() >= MIN_SANITY_SOURCES)
+            .filter_map(|(pair, mids)| Some((pair.clone(), median(mids)?)))
+            .collect();
+
+        let max_deviation = self.config.max_price_deviation_pct;
+        let outliers: Vec<(TokenPair, DexType)> = self
+            .price_cache
+            .iter()
+            .filter(|((pair, _), price)| {
+                medians.get(pair).is_some_and(|median| {
+                    (price.mid_price - median)
+                        .abs()
+                        .checked_div(*median)
+                        .is_none_or(|d| d * Decimal::ONE_HUNDRED > max_deviation)
+                })
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        outliers
+            .into_iter()
+            .filter_map(|key| self.price_cache.remove(&key))
+            .inspect(|price| {
+                tracing::warn!(
+                    "Dropping {:?} price for {}: mid {} is more than {}% from the median {}",
+                    price.dex,
+                    price.pair,
+                    price.mid_price,
+                    max_deviation,
+                    medians[&price.pair]
+                );
+            })
+            .collect()
+    }
+
+    /// Median mid price of SOL in `quote` across the cached DEXs, from SOL/`quote`
+    /// prices or else inverted `quote`/SOL ones
+    pub fn sol_price_in(&self, quote: &str) -> Option<Decimal> {
+        if quote == "SOL" {
+            return Some(Decimal::ONE);
+        }
+        self.cached_price("SOL", quote)
+    }
+
+    /// USD value of one `token`: 1 for a USD stablecoin, else its cached price
+    /// in one. Used to report profit of pairs quoted in other tokens in USD.
+    pub fn usd_price(&self, token: &str) -> Option<Decimal> {
+        if is_usd_stablecoin(token) {
+            return Some(Decimal::ONE);
+        }
+        USD_STABLECOINS
+            .iter()
+            .find_map(|usd| self.cached_price(token, usd))
+    }
+
+    /// Median mid price of `base` in `quote` across the cached DEXs, from
+    /// `base`/`quote` prices or else inverted `quote`/`base` ones
+    fn cached_price(&self, base: &str, quote: &str) -> Option<Decimal> {
+        let mids = |base: &str, quote: &str| {
+            let mids: Vec<Decimal> = self
+                .price_cache
+                .iter()
+                .filter(|((pair, _), _)| pair.base == base && pair.quote == quote)
+                .map(|(_, price)| price.mid_price)
+                .filter(|mid| *mid > Decimal::ZERO)
+                .collect();
+            median(mids)
+        };
+        mids(base, quote)
+            .or_else(|| mids(quote, base).and_then(|inverse| Decimal::ONE.checked_div(inverse)))
+    }
+
+    /// Profit of trading `opp` at `size` once the SOL-denominated transaction
+    /// `costs` and the configured base fee are paid, converted to the pair's
+    /// quote currency at the cached SOL price.
+    ///
+    /// Errors if no SOL price in that currency is cached, or on overflow.
+    pub fn profit_breakdown(
+        &self,
+        opp: &ArbitrageOpportunity,
+        size: Decimal,
+        costs: &TxCosts,
+    ) -> ArbitrageResult<ProfitBreakdown> {
+        let sol_price = self
+            .sol_price_in(&opp.pair.quote)
+            .ok_or_else(|| ArbitrageError::PriceNotAvailable(format!("SOL/{}", opp.pair.quote)))?;
+        let overflow =
+            || ArbitrageError::ArithmeticOverflow(format!("costs of {} at {}", opp.id, size));
+        let lamports_in_quote = |lamports: u64| {
+            Decimal::from(lamports)
+                .checked_div(Decimal::from(LAMPORTS_PER_SOL))
+                .and_then(|sol| sol.checked_mul(sol_price))
+                .ok_or_else(overflow)
+        };
+
+        let gross_profit = opp.gross_profit_usd(size)?;
+        let dex_profit = opp.net_profit_usd(size)?;
+        let base_fee = self
+            .config
+            .solana_tx_fee
+            .checked_mul(sol_price)
+            .ok_or_else(overflow)?;
+        let priority_fee = lamports_in_quote(costs.priority_fee_lamports())?;
+        let jito_tip = lamports_in_quote(costs.jito_tip_lamports)?;
+        let net_profit = dex_profit
+            .checked_sub(base_fee + priority_fee + jito_tip)
+            .ok_or_else(overflow)?;
+
+        Ok(ProfitBreakdown {
+            size,
+            gross_profit,
+            dex_fees: gross_profit - dex_profit,
+            base_fee,
+            priority_fee,
+            jito_tip,
+            net_profit,
+        })
+    }
+
+    /// Remember opportunities that were issued (shown or acted on) so
+    /// [`expire_stale`](Self::expire_stale) can later mark them expired
+    pub fn track(&mut self, opportunities: &[ArbitrageOpportunity]) {
+        for opp in opportunities.iter().filter(|o| o.is_active()) {
+            self.issued.insert(opp.id, opp.clone());
+        }
+    }
+
+    /// Tracked opportunities that have not expired yet
+    pub fn active_opportunities(&self) -> impl Iterator<Item = &ArbitrageOpportunity> {
+        self.issued.values()
+    }
+
+    /// Stamp `expired_at` on tracked opportunities detected `max_age` or more
+    /// ago, or whose prices are no longer cached (e.g. after
+    /// [`clear_stale_prices`](Self::clear_stale_prices)), and stop tracking them.
+    /// Returns the newly expired opportunities.
+    pub fn expire_stale(&mut self, max_age: Duration) -> Vec<ArbitrageOpportunity> {
+        let now = Utc::now();
+        let max_age = chrono::Duration::from_std(max_age).unwrap_or(chrono::Duration::MAX);
+
+        let stale: Vec<Uuid> = self
+            .issued
+            .values()
+            .filter(|opp| now - opp.detected_at >= max_age || !self.has_prices_for(opp))
+            .map(|opp| opp.id)
+            .collect();
+
+        stale
+            .into_iter()
+            .filter_map(|id| self.issued.remove(&id))
+            .map(|mut opp| {
+                opp.expired_at = Some(now);
+                opp
+            })
+            .collect()
+    }
+
+    /// Whether every leg of `opp` still has a cached price
+    fn has_prices_for(&self, opp: &ArbitrageOpportunity) -> bool {
+        match &opp.path {
+            Some(path) => path
+                .iter()
+                .all(|(dex, pair)| self.price_cache.contains_key(&(pair.clone(), *dex))),
+            None => [opp.buy_dex, opp.sell_dex]
+                .iter()
+                .all(|dex| self.price_cache.contains_key(&(opp.pair.clone(), *dex))),
+        }
+    }
+}
+
+/// Pairs whose capital an opportunity ties up, in either orientation
+fn traded_pairs(opp: &ArbitrageOpportunity) -> Vec<(String, String)> {
+    let key = |pair: &TokenPair| {
+        if pair.base <= pair.quote {
+            (pair.base.clone(), pair.quote.clone())
+        } else {
+            (pair.quote.clone(), pair.base.clone())
+        }
+    };
+    match &opp.path {
+        Some(legs) => legs.iter().map(|(_, pair)| key(pair)).collect(),
+        None => vec![key(&opp.pair)],
+    }
+}
+
+/// Combined price impact of both legs per unit of size (`1 / liquidity` each);
+/// legs without positive liquidity contribute nothing
+fn impact_per_unit(buy: &PriceData, sell: &PriceData) -> Decimal {
+    [buy, sell]
+        .iter()
+        .filter_map(|price| price.liquidity.filter(|l| *l > Decimal::ZERO))
+        .filter_map(|liquidity| Decimal::ONE.checked_div(liquidity))
+        .sum()
+}
+
+/// One direction of a cached price: 1 unit of the source token buys `rate` of `to`
+struct RateEdge<'a> {
+    to: &'a str,
+    rate: Decimal,
+    dex: DexType,
+    pair: &'a TokenPair,
+    one_sided: bool,
 }
 
 impl Default for ArbitrageDetector {
@@ -155,10 +759,127 @@ impl Default for ArbitrageDetector {
     }
 }
 
+/// Middle value of `values` (the mean of the two middle ones for an even count)
+fn median(mut values: Vec<Decimal>) -> Option<Decimal> {
+    values.sort();
+    let mid = values.len() / 2;
+    match values.len() {
+        0 => None,
+        n if n % 2 == 0 => Some((values[mid - 1] + values[mid]) / Decimal::TWO),
+        _ => Some(values[mid]),
+    }
+}
+
+/// Net profit discounted by age: `net_profit_pct * 0.5^(age / half_life)`.
+/// A zero `half_life` disables decay.
+pub fn aged_score(opp: &ArbitrageOpportunity, now: DateTime<Utc>, half_life: Duration) -> f64 {
+    let profit = opp.net_profit_pct.to_f64().unwrap_or_default();
+    if half_life.is_zero() {
+        return profit;
+    }
+    let age = (now - opp.detected_at).to_std().unwrap_or_default();
+    profit * 0.5f64.powf(age.as_secs_f64() / half_life.as_secs_f64())
+}
+
+/// Sort opportunities best-first by [`aged_score`], so a fresh opportunity can
+/// outrank a staler one with a higher headline profit
+pub fn rank_by_aged_score(opportunities: &mut [ArbitrageOpportunity], half_life: Duration) {
+    let now = Utc::now();
+    opportunities
+        .sort_by(|a, b| aged_score(b, now, half_life).total_cmp(&aged_score(a, now, half_life)));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_triangular_cycle_detection() {
+        let mut detector = ArbitrageDetector::default();
+        let sol_usdc = TokenPair::new("SOL", "USDC");
+        let ray_usdc = TokenPair::new("RAY", "USDC");
+        let ray_sol = TokenPair::new("RAY", "SOL");
+
+        // 1 SOL -> 100 USDC -> 50 RAY -> 1.05 SOL: 5% gross before fees
+        detector.update_price(create_test_price(
+            DexType::Raydium,
+            sol_usdc.clone(),
+            100.0,
+            100.2,
+        ));
+        detector.update_price(create_test_price(
+            DexType::Raydium,
+            ray_usdc.clone(),
+            1.99,
+            2.0,
+        ));
+        detector.update_price(create_test_price(
+            DexType::Orca,
+            ray_sol.clone(),
+            0.021,
+            0.0211,
+        ));
+
+        let opps = detector.find_triangular_opportunities("SOL");
+        assert_eq!(opps.len(), 1);
+        let opp = &opps[0];
+        assert_eq!(
+            opp.path,
+            Some(vec![
+                (DexType::Raydium, sol_usdc.clone()),
+                (DexType::Raydium, ray_usdc.clone()),
+                (DexType::Orca, ray_sol.clone()),
+            ])
+        );
+        assert_eq!(opp.sell_price, Decimal::new(105, 2));
+        // Raydium is used twice, so its fee counts twice
+        let fees =
+            DexType::Raydium.fee_percentage() * Decimal::TWO + DexType::Orca.fee_percentage();
+        assert_eq!(opp.net_profit_pct, Decimal::new(5, 0) - fees);
+
+        // Without a bid, RAY can't be sold for SOL, so the cycle can't close
+        detector.update_price(create_test_price(DexType::Orca, ray_sol, 0.0, 0.0211));
+        assert!(detector.find_triangular_opportunities("SOL").is_empty());
+    }
+
+    #[test]
+    fn test_aged_ranking_prefers_fresh_opportunity() {
+        let mut detector = ArbitrageDetector::default();
+        let pair = TokenPair::new("SOL", "USDC");
+        detector.update_price(create_test_price(
+            DexType::Raydium,
+            pair.clone(),
+            100.0,
+            100.0,
+        ));
+        detector.update_price(create_test_price(DexType::Orca, pair.clone(), 102.0, 102.0));
+        let template = detector.find_opportunities(&pair).remove(0);
+
+        let half_life = Duration::from_secs(1);
+        let now = Utc::now();
+        let stale = ArbitrageOpportunity {
+            net_profit_pct: Decimal::from(2),
+            detected_at: now - chrono::Duration::seconds(3),
+            ..template.clone()
+        };
+        let fresh = ArbitrageOpportunity {
+            net_profit_pct: Decimal::ONE,
+            detected_at: now,
+            ..template
+        };
+
+        // Three half-lives: 2% decays to 0.25%
+        assert!((aged_score(&stale, now, half_life) - 0.25).abs() < 1e-9);
+        assert_eq!(aged_score(&stale, now, Duration::ZERO), 2.0);
+
+        let mut ranked = vec![stale.clone(), fresh.clone()];
+        rank_by_aged_score(&mut ranked, half_life);
+        assert_eq!(ranked[0].id, fresh.id);
+
+        rank_by_aged_score(&mut ranked, Duration::ZERO);
+        assert_eq!(ranked[0].id, stale.id);
+    }
+
     fn create_test_price(dex: DexType, pair: TokenPair, bid: f64, ask: f64) -> PriceData {
         PriceData::new(
             dex,
@@ -249,4 +970,380 @@ mod tests {
         assert_eq!(best.sell_dex, DexType::Orca);
         assert!(best.net_profit_pct > Decimal::ZERO);
     }
+
+    #[test]
+    fn test_slippage_on_shallow_pool() {
+        let detector = ArbitrageDetector::default();
+        let pair = TokenPair::new("SOL", "USDC");
+        let mut buy = create_test_price(DexType::Raydium, pair.clone(), 99.90, 100.00);
+        let mut sell = create_test_price(DexType::Orca, pair.clone(), 102.545, 102.645);
+
+        // Without depth data, profit scales with size
+        let flat = detector.estimate_net_profit(&buy, &sell, Decimal::from(100));
+        assert_eq!(
+            detector.estimate_net_profit(&buy, &sell, Decimal::from(200)),
+            flat * Decimal::TWO
+        );
+
+        // A $40k pool on each side: past the optimum, bigger trades earn less
+        buy.liquidity = Some(Decimal::from(40_000));
+        sell.liquidity = Some(Decimal::from(40_000));
+        let profit = |size: i64| detector.estimate_net_profit(&buy, &sell, Decimal::from(size));
+        assert!(profit(100) < flat);
+        assert!(profit(100) < profit(200));
+        assert!(profit(400) < profit(200));
+        assert!(profit(800) < profit(400));
+        assert!(profit(800) < Decimal::ZERO);
+
+        // The opportunity is sized where the simulated round trip peaks, with
+        // slippage in its net profit
+        let mut detector = ArbitrageDetector::default();
+        detector.update_prices(vec![buy.clone(), sell.clone()]);
+        let opp = &detector.find_opportunities(&pair)[0];
+        let size = opp.recommended_size.unwrap();
+        let round_trip = |size| detector.simulate_round_trip(&buy, &sell, size).unwrap();
+        let peak = round_trip(size).profit();
+        assert!(peak > round_trip(size - Decimal::TEN).profit());
+        assert!(peak > round_trip(size + Decimal::TEN).profit());
+        assert!(opp.net_profit_pct < Decimal::ONE);
+        assert_eq!(opp.estimated_profit_usd, Some(peak));
+        assert_eq!(opp.net_profit_pct, round_trip(size).profit_pct());
+    }
+
+    #[test]
+    fn test_round_trip_sells_what_the_buy_leg_returned() {
+        let pair = TokenPair::new("SOL", "USDC");
+        let mut fees = FeeSchedule::new();
+        fees.register(DexType::Raydium, pair.clone(), Decimal::ZERO);
+        fees.register(DexType::Orca, pair.clone(), Decimal::ZERO);
+        let detector = ArbitrageDetector::new_with_fees(ArbitrageConfig::default(), fees);
+
+        // A deep pool to buy from and a shallow one to sell into
+        let mut buy = create_test_price(DexType::Raydium, pair.clone(), 99.90, 100.00);
+        let mut sell = create_test_price(DexType::Orca, pair.clone(), 102.00, 102.10);
+        buy.liquidity = Some(Decimal::from(1_000_000));
+        sell.liquidity = Some(Decimal::from(20_000));
+
+        // 200 USDC buys 2 SOL less 0.02% impact, and all of it is sold at 102
+        let capital = Decimal::from(200);
+        let round_trip = detector.simulate_round_trip(&buy, &sell, capital).unwrap();
+        assert_eq!(round_trip.buy.out_amount, Decimal::new(19996, 4));
+        let sold = round_trip.buy.out_amount * Decimal::from(102);
+        assert_eq!(
+            round_trip.sell.price_impact_pct,
+            sold / Decimal::from(20_000) * Decimal::ONE_HUNDRED
+        );
+
+        // The naive model charges the shallow leg's impact on the 200 spent
+        // rather than the ~204 sold, so it overstates the profit
+        let naive = detector.estimate_net_profit(&buy, &sell, capital);
+        assert!(round_trip.profit() > Decimal::ZERO);
+        assert!(round_trip.profit() < naive);
+
+        // Each leg's pool fee comes out of what that leg returns
+        let charged = ArbitrageDetector::default()
+            .simulate_round_trip(&buy, &sell, capital)
+            .unwrap();
+        assert!(charged.fees() > Decimal::ZERO);
+        assert!(charged.profit() < round_trip.profit());
+        assert!(charged.profit_pct() < charged.gross_profit_pct());
+    }
+
+    #[test]
+    fn test_pools_below_liquidity_floor_are_skipped() {
+        let config = ArbitrageConfig {
+            min_liquidity_usd: Decimal::from(50_000),
+            allow_unknown_liquidity: false,
+            ..Default::default()
+        };
+        let sol = TokenPair::new("SOL", "USDC");
+        let ray = TokenPair::new("RAY", "SOL");
+        let pool = |dex, pair: &TokenPair, bid: f64, ask: f64, liquidity: Option<i64>| PriceData {
+            liquidity: liquidity.map(Decimal::from),
+            ..create_test_price(dex, pair.clone(), bid, ask)
+        };
+        let found = |prices: Vec<PriceData>, pair: &TokenPair, allow_unknown: bool| {
+            let mut detector = ArbitrageDetector::new(ArbitrageConfig {
+                allow_unknown_liquidity: allow_unknown,
+                ..config.clone()
+            });
+            detector.update_prices(prices);
+            !detector.find_opportunities(pair).is_empty()
+        };
+
+        let deep = pool(DexType::Raydium, &sol, 149.90, 150.00, Some(1_000_000));
+        let spread = |liquidity| pool(DexType::Orca, &sol, 153.00, 153.10, liquidity);
+        assert!(found(
+            vec![deep.clone(), spread(Some(1_000_000))],
+            &sol,
+            false
+        ));
+        assert!(!found(
+            vec![deep.clone(), spread(Some(20_000))],
+            &sol,
+            false
+        ));
+
+        // Unreported depth passes only when allowed
+        assert!(!found(vec![deep.clone(), spread(None)], &sol, false));
+        assert!(found(vec![deep.clone(), spread(None)], &sol, true));
+
+        // Depth in SOL is valued at SOL's price: 400 SOL clears $50k, 300 doesn't
+        let ray_pools = |liquidity| {
+            vec![
+                deep.clone(),
+                pool(DexType::Raydium, &ray, 0.0099, 0.0100, Some(1_000)),
+                pool(DexType::Orca, &ray, 0.0103, 0.0104, Some(liquidity)),
+            ]
+        };
+        assert!(found(ray_pools(400), &ray, false));
+        assert!(!found(ray_pools(300), &ray, false));
+    }
+
+    #[test]
+    fn test_registered_fee_overrides_default() {
+        let config = ArbitrageConfig {
+            min_profit_threshold: Decimal::ZERO,
+            ..Default::default()
+        };
+        let pair = TokenPair::new("SOL", "USDC");
+        let prices = [
+            create_test_price(DexType::Raydium, pair.clone(), 99.990, 100.000),
+            create_test_price(DexType::Orca, pair.clone(), 100.400, 100.410),
+        ];
+
+        // 0.4% gross doesn't cover the default 0.25% Raydium + 0.30% Orca fees
+        let mut detector = ArbitrageDetector::new(config.clone());
+        detector.update_prices(prices.to_vec());
+        assert!(detector.find_opportunities(&pair).is_empty());
+
+        // With a 0.01% Orca pool the fees come to 0.26%, which it does
+        let mut fees = FeeSchedule::new();
+        fees.register(DexType::Orca, pair.clone(), Decimal::new(1, 4));
+        let mut detector = ArbitrageDetector::new_with_fees(config.clone(), fees.clone());
+        detector.update_prices(prices.to_vec());
+        let opps = detector.find_opportunities(&pair);
+        assert_eq!(opps.len(), 1);
+        assert_eq!(opps[0].sell_dex, DexType::Orca);
+
+        // Fees fetched later apply to a running detector too
+        let mut detector = ArbitrageDetector::new(config);
+        detector.update_prices(prices.to_vec());
+        detector.register_fees(fees);
+        assert_eq!(detector.find_opportunities(&pair).len(), 1);
+    }
+
+    #[test]
+    fn test_same_route_suppressed() {
+        let config = ArbitrageConfig {
+            min_profit_threshold: Decimal::new(1, 2),
+            ..Default::default()
+        };
+        let mut detector = ArbitrageDetector::new(config);
+        let pair = TokenPair::new("SOL", "USDC");
+
+        // Jupiter quoting through the very same Raydium pool
+        detector.update_price(
+            create_test_price(DexType::Raydium, pair.clone(), 99.90, 100.00)
+                .with_route("58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2"),
+        );
+        detector.update_price(
+            create_test_price(DexType::Jupiter, pair.clone(), 101.50, 101.60)
+                .with_route("58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2"),
+        );
+
+        assert!(detector.find_opportunities(&pair).is_empty());
+
+        // Distinct routes are still compared normally
+        detector.update_price(
+            create_test_price(DexType::Jupiter, pair.clone(), 101.50, 101.60)
+                .with_route("other-route"),
+        );
+        assert!(!detector.find_opportunities(&pair).is_empty());
+    }
+
+    #[test]
+    fn test_jupiter_execution_only() {
+        let config = ArbitrageConfig {
+            min_profit_threshold: Decimal::new(1, 2),
+            jupiter_price_source: false,
+            ..Default::default()
+        };
+        let mut detector = ArbitrageDetector::new(config);
+        let pair = TokenPair::new("SOL", "USDC");
+
+        detector.update_price(create_test_price(
+            DexType::Raydium,
+            pair.clone(),
+            99.90,
+            100.00,
+        ));
+        detector.update_price(create_test_price(
+            DexType::Jupiter,
+            pair.clone(),
+            101.50,
+            101.60,
+        ));
+
+        assert!(!detector.is_price_source(DexType::Jupiter));
+        assert!(detector.find_opportunities(&pair).is_empty());
+
+        // Real venues are still compared against each other
+        detector.update_price(create_test_price(
+            DexType::Orca,
+            pair.clone(),
+            101.50,
+            101.60,
+        ));
+        let opportunities = detector.find_opportunities(&pair);
+        assert_eq!(opportunities.len(), 1);
+        assert_eq!(opportunities[0].sell_dex, DexType::Orca);
+    }
+
+    #[test]
+    fn test_sanitize_prices_drops_outlier() {
+        let mut detector = ArbitrageDetector::default();
+        let pair = TokenPair::new("SOL", "USDC");
+        let ray = TokenPair::new("RAY", "USDC");
+
+        // Orca reports SOL 50% high, as if its decimals were off
+        detector.update_prices(vec![
+            create_test_price(DexType::Raydium, pair.clone(), 99.9, 100.1),
+            create_test_price(DexType::Orca, pair.clone(), 149.9, 150.1),
+            create_test_price(DexType::Jupiter, pair.clone(), 100.0, 100.2),
+            create_test_price(DexType::Meteora, pair.clone(), 99.8, 100.0),
+            // Two sources can't outvote each other, so RAY is left alone
+            create_test_price(DexType::Raydium, ray.clone(), 1.0, 1.01),
+            create_test_price(DexType::Orca, ray.clone(), 2.0, 2.01),
+        ]);
+        assert!(!detector.find_opportunities(&pair).is_empty());
+
+        let dropped = detector.sanitize_prices();
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].dex, DexType::Orca);
+        assert_eq!(dropped[0].pair, pair);
+        assert_eq!(detector.get_prices().len(), 5);
+        assert!(detector.find_opportunities(&pair).is_empty());
+
+        // Nothing left to drop
+        assert!(detector.sanitize_prices().is_empty());
+    }
+
+    #[test]
+    fn test_profit_breakdown_charges_gas_and_tip() {
+        let mut detector = ArbitrageDetector::default();
+        let sol_usdc = TokenPair::new("SOL", "USDC");
+        detector.update_price(create_test_price(
+            DexType::Raydium,
+            sol_usdc.clone(),
+            149.9,
+            150.1,
+        ));
+
+        // 0.6% net of DEX fees on RAY/USDC
+        let opp = ArbitrageOpportunity {
+            id: Uuid::new_v4(),
+            pair: TokenPair::new("RAY", "USDC"),
+            buy_dex: DexType::Raydium,
+            sell_dex: DexType::Orca,
+            buy_price: Decimal::ONE,
+            sell_price: Decimal::new(1009, 3),
+            gross_profit_pct: Decimal::new(9, 1),
+            net_profit_pct: Decimal::new(6, 1),
+            estimated_profit_usd: None,
+            recommended_size: None,
+            detected_at: Utc::now(),
+            expired_at: None,
+            path: None,
+        };
+        // 0.0002 SOL of priority fee and a 0.001 SOL tip: $0.18 with the base fee
+        let costs = TxCosts {
+            priority_fee_micro_lamports: 1_000_000,
+            compute_unit_limit: 200_000,
+            jito_tip_lamports: 1_000_000,
+        };
+        assert_eq!(costs.priority_fee_lamports(), 200_000);
+
+        // A $20 trade clears DEX fees ($0.12) but not gas and tip
+        let small = detector
+            .profit_breakdown(&opp, Decimal::from(20), &costs)
+            .unwrap();
+        assert_eq!(small.gross_profit, Decimal::new(18, 2));
+        assert_eq!(small.dex_fees, Decimal::new(6, 2));
+        assert_eq!(small.priority_fee, Decimal::new(3, 2));
+        assert_eq!(small.jito_tip, Decimal::new(15, 2));
+        assert_eq!(small.base_fee, Decimal::new(75, 5));
+        assert_eq!(small.net_profit, Decimal::new(12, 2) - small.tx_costs());
+        assert!(small.net_profit < Decimal::ZERO);
+
+        // At $1,000 the same costs barely dent it
+        let large = detector
+            .profit_breakdown(&opp, Decimal::from(1000), &costs)
+            .unwrap();
+        assert!(large.net_profit > Decimal::new(58, 1));
+        assert!(large.net_profit_pct() > Decimal::new(58, 2));
+
+        // Without a SOL price the costs can't be valued
+        let mut orphan = opp.clone();
+        orphan.pair = TokenPair::new("RAY", "USDT");
+        assert!(matches!(
+            detector.profit_breakdown(&orphan, Decimal::from(20), &costs),
+            Err(ArbitrageError::PriceNotAvailable(_))
+        ));
+    }
+
+    #[test]
+    fn test_select_opportunities_allocates_limited_capital() {
+        // $1,000 per trade, $1,500 to spread
+        let detector = ArbitrageDetector::default();
+        let opp = |pair: TokenPair, net_pct: i64, recommended: Option<i64>| ArbitrageOpportunity {
+            id: Uuid::new_v4(),
+            pair,
+            buy_dex: DexType::Raydium,
+            sell_dex: DexType::Orca,
+            buy_price: Decimal::ONE,
+            sell_price: Decimal::ONE,
+            gross_profit_pct: Decimal::new(net_pct, 1),
+            net_profit_pct: Decimal::new(net_pct, 1),
+            estimated_profit_usd: None,
+            recommended_size: recommended.map(Decimal::from),
+            detected_at: Utc::now(),
+            expired_at: None,
+            path: None,
+        };
+        let ray = opp(TokenPair::new("RAY", "USDC"), 12, Some(400));
+        let sol = opp(TokenPair::new("SOL", "USDC"), 10, None);
+        // Same pair as `sol`, the other way round: can't share its capital
+        let sol_reversed = opp(TokenPair::new("USDC", "SOL"), 8, None);
+        let bonk = opp(TokenPair::new("BONK", "USDC"), 5, None);
+
+        let selected = detector.select_opportunities(
+            &[ray.clone(), sol.clone(), sol_reversed.clone(), bonk.clone()],
+            Decimal::from(1500),
+        );
+        let allocation: Vec<_> = selected.iter().map(|(o, size)| (o.id, *size)).collect();
+        assert_eq!(
+            allocation,
+            vec![
+                // Capped by its recommended size
+                (ray.id, Decimal::from(400)),
+                // Capped by max_position_size
+                (sol.id, Decimal::from(1000)),
+                // What's left
+                (bonk.id, Decimal::from(100)),
+            ]
+        );
+
+        let total: Decimal = selected.iter().map(|(_, size)| *size).sum();
+        assert_eq!(total, Decimal::from(1500));
+
+        // The caller's ranking wins over raw profit, e.g. an older, more
+        // profitable opportunity ranked below a fresh one
+        let selected = detector.select_opportunities(&[sol_reversed.clone(), sol], Decimal::ONE);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].0.id, sol_reversed.id);
+        assert!(detector
+            .select_opportunities(&[ray], Decimal::ZERO)
+            .is_empty());
+    }
 }
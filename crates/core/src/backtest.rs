@@ -0,0 +1,220 @@
+//! Backtesting by replaying recorded prices
+//!
+//! A recording is a JSONL file where each line is one snapshot: the
+//! `Vec<PriceData>` fetched in one polling cycle. [`Backtester`] feeds the
+//! snapshots through an [`ArbitrageDetector`] in order and prices every
+//! opportunity at a fixed notional trade size. Opportunities from other sources
+//! (e.g. strategies) can be added per snapshot with [`Backtester::record`].
+//!
+//! An opportunity that stays open over consecutive snapshots is counted once,
+//! when it first appears, since it could only have been traded once.
+
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashSet};
+use std::io::BufRead;
+
+use crate::arbitrage::ArbitrageDetector;
+use crate::{ArbitrageConfig, ArbitrageError, ArbitrageOpportunity, DexType, PriceData, TokenPair};
+
+/// Hypothetical results for one pair
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PairBacktest {
+    pub opportunities: usize,
+    pub gross_profit_usd: Decimal,
+    pub net_profit_usd: Decimal,
+}
+
+/// Hypothetical results of a replay
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BacktestSummary {
+    /// Snapshots replayed
+    pub snapshots: usize,
+    /// Opportunities counted (each open window once)
+    pub total_opportunities: usize,
+    /// Profit before DEX fees
+    pub gross_profit_usd: Decimal,
+    /// Profit after `DexType::fee_percentage` on both legs
+    pub net_profit_usd: Decimal,
+    /// Breakdown by pair symbol
+    pub by_pair: BTreeMap<String, PairBacktest>,
+}
+
+/// Replays price snapshots through the detector and tallies what it finds
+pub struct Backtester {
+    detector: ArbitrageDetector,
+    min_profit_threshold: Decimal,
+    trade_size_usd: Decimal,
+    /// Routes open in the previous snapshot, and in the current one
+    previous_routes: HashSet<(TokenPair, DexType, DexType)>,
+    current_routes: HashSet<(TokenPair, DexType, DexType)>,
+    detected: Vec<ArbitrageOpportunity>,
+    summary: BacktestSummary,
+}
+
+impl Backtester {
+    /// `min_profit_threshold` is a net percentage; every counted opportunity
+    /// is assumed to trade `trade_size_usd`
+    pub fn new(min_profit_threshold: Decimal, trade_size_usd: Decimal) -> Self {
+        let config = ArbitrageConfig {
+            min_profit_threshold,
+            ..Default::default()
+        };
+        Self {
+            detector: ArbitrageDetector::new(config),
+            min_profit_threshold,
+            trade_size_usd,
+            previous_routes: HashSet::new(),
+            current_routes: HashSet::new(),
+            detected: Vec::new(),
+            summary: BacktestSummary::default(),
+        }
+    }
+
+    /// Load a recording; lines that don't parse are skipped with a warning
+    pub fn load_snapshots(file_path: &str) -> Result<Vec<Vec<PriceData>>, std::io::Error> {
+        let reader = std::io::BufReader::new(std::fs::File::open(file_path)?);
+        let mut snapshots = Vec::new();
+        for (idx, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<Vec<PriceData>>(&line) {
+                Ok(snapshot) => snapshots.push(snapshot),
+                Err(e) => tracing::warn!("Skipping snapshot on line {}: {}", idx + 1, e),
+            }
+        }
+        Ok(snapshots)
+    }
+
+    /// Start the next snapshot: apply its prices, record the detector's
+    /// opportunities and return them
+    pub fn step(&mut self, snapshot: Vec<PriceData>) -> Vec<ArbitrageOpportunity> {
+        self.previous_routes = std::mem::take(&mut self.current_routes);
+        self.summary.snapshots += 1;
+
+        self.detector.update_prices(snapshot);
+        let opportunities = self.detector.find_all_opportunities();
+        self.record(opportunities.iter().cloned());
+        opportunities
+    }
+
+    /// Count opportunities found for the current snapshot by another source.
+    /// Ones below the threshold or already open are ignored.
+    pub fn record(&mut self, opportunities: impl IntoIterator<Item = ArbitrageOpportunity>) {
+        for opp in opportunities {
+            if opp.net_profit_pct < self.min_profit_threshold {
+                continue;
+            }
+            let route = (opp.pair.clone(), opp.buy_dex, opp.sell_dex);
+            if !self.current_routes.insert(route.clone()) || self.previous_routes.contains(&route) {
+                continue;
+            }
+
+            // DEX fees are fractions of the trade (0.0025 is 0.25%)
+            let fee_rate = opp.buy_dex.fee_percentage() + opp.sell_dex.fee_percentage();
+            let fees = self.trade_size_usd.checked_mul(fee_rate).ok_or_else(|| {
+                ArbitrageError::ArithmeticOverflow(format!(
+                    "{} * {}",
+                    self.trade_size_usd, fee_rate
+                ))
+            });
+            let (gross, fees) = match (opp.gross_profit_usd(self.trade_size_usd), fees) {
+                (Ok(gross), Ok(fees)) => (gross, fees),
+                (Err(e), _) | (_, Err(e)) => {
+                    tracing::warn!("Skipping {} in backtest: {}", opp.pair, e);
+                    continue;
+                }
+            };
+            let net = gross - fees;
+
+            let summary = &mut self.summary;
+            summary.total_opportunities += 1;
+            summary.gross_profit_usd += gross;
+            summary.net_profit_usd += net;
+            let pair = summary.by_pair.entry(opp.pair.symbol()).or_default();
+            pair.opportunities += 1;
+            pair.gross_profit_usd += gross;
+            pair.net_profit_usd += net;
+
+            self.detected.push(opp);
+        }
+    }
+
+    /// Replay `snapshots` through the detector alone
+    pub fn run(&mut self, snapshots: impl IntoIterator<Item = Vec<PriceData>>) -> &BacktestSummary {
+        for snapshot in snapshots {
+            self.step(snapshot);
+        }
+        &self.summary
+    }
+
+    /// Every counted opportunity, in the order found
+    pub fn detected(&self) -> &[ArbitrageOpportunity] {
+        &self.detected
+    }
+
+    pub fn summary(&self) -> &BacktestSummary {
+        &self.summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(dex: DexType, base: &str, bid: i64, ask: i64) -> PriceData {
+        PriceData::new(
+            dex,
+            TokenPair::new(base, "USDC"),
+            Decimal::new(bid, 2),
+            Decimal::new(ask, 2),
+        )
+    }
+
+    #[test]
+    fn test_replay_counts_each_window_once() {
+        let open = vec![
+            price(DexType::Raydium, "SOL", 9990, 10000),
+            price(DexType::Orca, "SOL", 10100, 10110),
+        ];
+        let closed = vec![price(DexType::Orca, "SOL", 10000, 10010)];
+        let ray = vec![
+            price(DexType::Raydium, "RAY", 199, 200),
+            price(DexType::Orca, "RAY", 204, 205),
+        ];
+
+        // Recording round trip, with one corrupt line
+        let path = std::env::temp_dir().join(format!("ticks-{}.jsonl", uuid::Uuid::new_v4()));
+        let mut lines: Vec<String> = [&open, &open, &closed, &open]
+            .iter()
+            .map(|s| serde_json::to_string(s).unwrap())
+            .collect();
+        lines.push("[{\"dex\":".to_string());
+        lines.push(serde_json::to_string(&ray).unwrap());
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        let snapshots = Backtester::load_snapshots(path.to_str().unwrap()).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(snapshots.len(), 5);
+
+        let mut backtester = Backtester::new(Decimal::new(1, 1), Decimal::new(1000, 0));
+        let summary = backtester.run(snapshots).clone();
+
+        // SOL opens twice (the repeat snapshot is the same window), RAY once
+        assert_eq!(summary.snapshots, 5);
+        assert_eq!(summary.total_opportunities, 3);
+        let sol = &summary.by_pair["SOL/USDC"];
+        assert_eq!(sol.opportunities, 2);
+        // 1% gross on $1000 twice, less 0.25% Raydium + 0.30% Orca fees each:
+        // $20 - $1000 * 0.0055 * 2
+        assert_eq!(sol.gross_profit_usd, Decimal::new(20, 0));
+        assert_eq!(sol.net_profit_usd, Decimal::new(9, 0));
+        assert_eq!(
+            summary.by_pair["RAY/USDC"].gross_profit_usd,
+            Decimal::new(20, 0)
+        );
+        // $40 gross less $5.50 of fees on each of the three trades
+        assert_eq!(summary.net_profit_usd, Decimal::new(235, 1));
+        assert_eq!(backtester.detected().len(), 3);
+    }
+}
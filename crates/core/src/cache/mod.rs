@@ -1 +1,3 @@
 pub mod mmap_cache;
+#[cfg(feature = "cache")]
+pub mod redis_cache;
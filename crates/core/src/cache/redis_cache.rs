@@ -0,0 +1,109 @@
+//! Price state shared across processes through Redis
+//!
+//! Each `PriceData` is stored as JSON under `dex:pair` (e.g. `Raydium:SOL/USDC`)
+//! and expires after the configured maximum price age, so a reader never sees
+//! a quote the detector would already have dropped as stale.
+
+use redis::aio::MultiplexedConnection;
+use redis::AsyncCommands;
+
+use crate::error::{ArbitrageError, ArbitrageResult};
+use crate::types::{DexType, PriceData, TokenPair};
+
+pub struct PriceCache {
+    conn: MultiplexedConnection,
+    ttl_seconds: u64,
+}
+
+impl PriceCache {
+    /// Connect to `redis_url`; stored prices live for `max_price_age_seconds`
+    pub async fn connect(redis_url: &str, max_price_age_seconds: i64) -> ArbitrageResult<Self> {
+        let client = redis::Client::open(redis_url)?;
+        let conn = client.get_multiplexed_tokio_connection().await?;
+        Ok(Self {
+            conn,
+            // Redis rejects a zero expiry
+            ttl_seconds: max_price_age_seconds.max(1) as u64,
+        })
+    }
+
+    /// Store `price`, replacing the previous one for its DEX and pair
+    pub async fn put(&self, price: &PriceData) -> ArbitrageResult<()> {
+        let key = price_key(price.dex, &price.pair);
+        let value = encode(price)?;
+        let mut conn = self.conn.clone();
+        conn.set_ex::<_, _, ()>(key, value, self.ttl_seconds)
+            .await?;
+        Ok(())
+    }
+
+    /// The most recent unexpired price for `pair` on any DEX
+    pub async fn get_latest(&self, pair: &TokenPair) -> ArbitrageResult<Option<PriceData>> {
+        let keys: Vec<String> = DexType::all()
+            .iter()
+            .map(|dex| price_key(*dex, pair))
+            .collect();
+        let mut conn = self.conn.clone();
+        let values: Vec<Option<String>> =
+            redis::cmd("MGET").arg(&keys).query_async(&mut conn).await?;
+
+        let mut latest: Option<PriceData> = None;
+        for value in values.into_iter().flatten() {
+            match decode(&value) {
+                Ok(price) => {
+                    if latest
+                        .as_ref()
+                        .is_none_or(|l| price.timestamp > l.timestamp)
+                    {
+                        latest = Some(price);
+                    }
+                }
+                Err(e) => tracing::warn!("Ignoring unreadable cached price for {}: {}", pair, e),
+            }
+        }
+        Ok(latest)
+    }
+}
+
+fn price_key(dex: DexType, pair: &TokenPair) -> String {
+    format!("{}:{}", dex, pair.symbol())
+}
+
+fn encode(price: &PriceData) -> ArbitrageResult<String> {
+    serde_json::to_string(price).map_err(|e| ArbitrageError::Redis(e.to_string()))
+}
+
+fn decode(value: &str) -> ArbitrageResult<PriceData> {
+    serde_json::from_str(value).map_err(|e| ArbitrageError::Redis(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_stored_price_round_trip() {
+        let pair = TokenPair::new("SOL", "USDC");
+        let mut price =
+            PriceData::from_mid(DexType::Orca, pair.clone(), Decimal::new(15025, 2), 10)
+                .with_route("whirlpool");
+        price.volume_24h = Some(Decimal::new(1_250_000, 0));
+        price.liquidity = Some(Decimal::new(50_000, 0));
+
+        let decoded = decode(&encode(&price).unwrap()).unwrap();
+        assert_eq!(decoded.dex, price.dex);
+        assert_eq!(decoded.pair, pair);
+        assert_eq!(decoded.bid, price.bid);
+        assert_eq!(decoded.ask, price.ask);
+        assert_eq!(decoded.mid_price, price.mid_price);
+        assert_eq!(decoded.volume_24h, price.volume_24h);
+        assert_eq!(decoded.liquidity, price.liquidity);
+        assert_eq!(decoded.timestamp, price.timestamp);
+        assert_eq!(decoded.route_id.as_deref(), Some("whirlpool"));
+        assert!(decoded.one_sided);
+
+        assert_eq!(price_key(DexType::Orca, &pair), "Orca:SOL/USDC");
+        assert!(matches!(decode("{\"dex\":"), Err(ArbitrageError::Redis(_))));
+    }
+}
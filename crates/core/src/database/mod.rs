@@ -1,2 +1,6 @@
 #[cfg(feature = "db")]
+pub mod opportunity_store;
+#[cfg(feature = "db")]
 pub mod timescale;
+#[cfg(feature = "db")]
+pub mod trade_history;
@@ -0,0 +1,149 @@
+use crate::types::{ArbitrageOpportunity, DexType, TokenPair};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{ManagerConfig, Pool, RecyclingMethod, Runtime};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use tokio_postgres::{NoTls, Row};
+
+/// Persists detected opportunities to the `arbitrage_opportunities` table
+/// (see `migrations/002_create_arbitrage_opportunities.sql`).
+///
+/// Decimals travel as text and are cast to `NUMERIC` in SQL so no precision is
+/// lost on the way in or out.
+pub struct OpportunityStore {
+    pool: Pool,
+}
+
+impl OpportunityStore {
+    pub async fn new(database_url: &str) -> Result<Self> {
+        let pg_config: tokio_postgres::Config = database_url.parse()?;
+
+        let mgr_config = ManagerConfig {
+            recycling_method: RecyclingMethod::Fast,
+        };
+
+        let mgr = deadpool_postgres::Manager::from_config(pg_config, NoTls, mgr_config);
+
+        let pool = Pool::builder(mgr)
+            .max_size(8)
+            .runtime(Runtime::Tokio1)
+            .build()?;
+
+        Ok(Self { pool })
+    }
+
+    pub async fn insert(&self, opp: &ArbitrageOpportunity) -> Result<()> {
+        let client = self.pool.get().await?;
+
+        let stmt = client
+            .prepare_cached(
+                "INSERT INTO arbitrage_opportunities
+            (id, base_token, quote_token, buy_dex, sell_dex, buy_price, sell_price,
+             gross_profit_pct, net_profit_pct, estimated_profit_usd, recommended_size,
+             detected_at, expired_at)
+            VALUES ($1, $2, $3, $4, $5, $6::text::numeric, $7::text::numeric,
+                    $8::text::numeric, $9::text::numeric, $10::text::numeric,
+                    $11::text::numeric, $12, $13)
+            ON CONFLICT DO NOTHING",
+            )
+            .await?;
+
+        let optional = |v: Option<Decimal>| v.map(|d| d.to_string());
+
+        client
+            .execute(
+                &stmt,
+                &[
+                    &opp.id,
+                    &opp.pair.base,
+                    &opp.pair.quote,
+                    &opp.buy_dex.display_name(),
+                    &opp.sell_dex.display_name(),
+                    &opp.buy_price.to_string(),
+                    &opp.sell_price.to_string(),
+                    &opp.gross_profit_pct.to_string(),
+                    &opp.net_profit_pct.to_string(),
+                    &optional(opp.estimated_profit_usd),
+                    &optional(opp.recommended_size),
+                    &opp.detected_at,
+                    &opp.expired_at,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Opportunities detected in `[from, to)`, oldest first
+    pub async fn query_range(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<ArbitrageOpportunity>> {
+        let client = self.pool.get().await?;
+
+        let rows = client
+            .query(
+                "SELECT id, base_token, quote_token, buy_dex, sell_dex,
+                    buy_price::text, sell_price::text, gross_profit_pct::text,
+                    net_profit_pct::text, estimated_profit_usd::text, recommended_size::text,
+                    detected_at, expired_at
+             FROM arbitrage_opportunities
+             WHERE detected_at >= $1 AND detected_at < $2
+             ORDER BY detected_at",
+                &[&from, &to],
+            )
+            .await?;
+
+        rows.iter().map(opportunity_from_row).collect()
+    }
+}
+
+fn opportunity_from_row(row: &Row) -> Result<ArbitrageOpportunity> {
+    let decimal = |idx: usize| -> Result<Decimal> { Ok(Decimal::from_str(row.try_get(idx)?)?) };
+    let optional = |idx: usize| -> Result<Option<Decimal>> {
+        row.try_get::<_, Option<&str>>(idx)?
+            .map(Decimal::from_str)
+            .transpose()
+            .map_err(Into::into)
+    };
+
+    Ok(ArbitrageOpportunity {
+        id: row.try_get(0)?,
+        pair: TokenPair::new(row.try_get::<_, String>(1)?, row.try_get::<_, String>(2)?),
+        buy_dex: parse_dex(row.try_get(3)?)?,
+        sell_dex: parse_dex(row.try_get(4)?)?,
+        buy_price: decimal(5)?,
+        sell_price: decimal(6)?,
+        gross_profit_pct: decimal(7)?,
+        net_profit_pct: decimal(8)?,
+        estimated_profit_usd: optional(9)?,
+        recommended_size: optional(10)?,
+        detected_at: row.try_get(11)?,
+        expired_at: row.try_get(12)?,
+        path: None,
+    })
+}
+
+/// Inverse of `DexType::display_name`, which is how DEXs are stored
+fn parse_dex(name: &str) -> Result<DexType> {
+    DexType::all()
+        .iter()
+        .copied()
+        .find(|dex| dex.display_name() == name)
+        .ok_or_else(|| anyhow!("Unknown DEX in opportunity row: {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dex_round_trips_display_name() {
+        for dex in DexType::all() {
+            assert_eq!(parse_dex(dex.display_name()).unwrap(), *dex);
+        }
+        assert!(parse_dex("Uniswap").is_err());
+    }
+}
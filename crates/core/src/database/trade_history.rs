@@ -0,0 +1,259 @@
+use crate::history::{HistoryRecorder, TradeRecord};
+use crate::types::{ArbitrageOpportunity, LatencyBreakdown};
+use anyhow::Result;
+use deadpool_postgres::{ManagerConfig, Pool, RecyclingMethod, Runtime};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+use tokio_postgres::NoTls;
+
+/// Schema for the `trade_records` table, applied in order on startup
+const MIGRATIONS: &[&str] = &[
+    include_str!("../../../../migrations/003_create_trade_records.sql"),
+    include_str!("../../../../migrations/004_trade_records_quote_profit.sql"),
+];
+
+/// Records waiting for the database before new ones are left file-only
+const BUFFER_SIZE: usize = 1024;
+
+/// Work for the writer task, handled in order
+enum Command {
+    Insert(Box<TradeRecord>),
+    /// Acknowledged once everything queued before it is written
+    Flush(oneshot::Sender<()>),
+}
+
+/// Writes trade history to the `trade_records` table
+/// (see `migrations/003_create_trade_records.sql`).
+///
+/// Every record is appended to the JSONL file of the wrapped `HistoryRecorder`,
+/// which stays the complete history `HistoryAnalyzer` reads. `record_trade` then
+/// queues it for the database; a background task does the insert, and records
+/// that can't be inserted, or that arrive while the queue is full, stay file-only.
+/// Call [`PgHistoryRecorder::flush`] before exiting so queued records aren't lost.
+pub struct PgHistoryRecorder {
+    file: Arc<HistoryRecorder>,
+    tx: mpsc::Sender<Command>,
+}
+
+impl PgHistoryRecorder {
+    /// Connect, create the table if missing and start the writer task
+    pub async fn new(database_url: &str, file: HistoryRecorder) -> Result<Self> {
+        let pg_config: tokio_postgres::Config = database_url.parse()?;
+
+        let mgr_config = ManagerConfig {
+            recycling_method: RecyclingMethod::Fast,
+        };
+
+        let mgr = deadpool_postgres::Manager::from_config(pg_config, NoTls, mgr_config);
+
+        let pool = Pool::builder(mgr)
+            .max_size(4)
+            .runtime(Runtime::Tokio1)
+            .build()?;
+
+        let client = pool.get().await?;
+        for migration in MIGRATIONS {
+            client.batch_execute(migration).await?;
+        }
+
+        Ok(Self::with_pool(pool, file))
+    }
+
+    fn with_pool(pool: Pool, file: HistoryRecorder) -> Self {
+        let (tx, mut rx) = mpsc::channel::<Command>(BUFFER_SIZE);
+
+        tokio::spawn(async move {
+            while let Some(command) = rx.recv().await {
+                match command {
+                    Command::Insert(record) => {
+                        if let Err(e) = insert(&pool, &record).await {
+                            tracing::warn!("Trade history insert failed, kept in file only: {}", e);
+                        }
+                    }
+                    Command::Flush(done) => {
+                        let _ = done.send(());
+                    }
+                }
+            }
+        });
+
+        Self {
+            file: Arc::new(file),
+            tx,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn record_trade(
+        &self,
+        opp: &ArbitrageOpportunity,
+        size_usd: Decimal,
+        profit: Decimal,
+        success: bool,
+        tx_sig: Option<String>,
+        error: Option<String>,
+        is_dry_run: bool,
+    ) {
+        self.record_trade_with_latency(
+            opp, size_usd, profit, success, tx_sig, error, is_dry_run, None,
+        )
+        .await;
+    }
+
+    /// Same as `record_trade`, additionally recording a per-stage latency breakdown
+    #[allow(clippy::too_many_arguments)]
+    pub async fn record_trade_with_latency(
+        &self,
+        opp: &ArbitrageOpportunity,
+        size_usd: Decimal,
+        profit: Decimal,
+        success: bool,
+        tx_sig: Option<String>,
+        error: Option<String>,
+        is_dry_run: bool,
+        latency: Option<&LatencyBreakdown>,
+    ) {
+        let record = self.file.build_record(
+            opp, size_usd, profit, None, success, tx_sig, error, is_dry_run, latency,
+        );
+        self.record(record).await;
+    }
+
+    /// Append an already-built record to the file and queue it for insertion
+    pub async fn record(&self, record: TradeRecord) {
+        append_to_file(&self.file, record.clone()).await;
+        // Never wait on the database from the trading loop
+        if self.tx.try_send(Command::Insert(Box::new(record))).is_err() {
+            tracing::warn!("Trade history queue unavailable, kept in file only");
+        }
+    }
+
+    /// Wait until every record queued so far has been inserted or given up on
+    pub async fn flush(&self) {
+        let (done_tx, done_rx) = oneshot::channel();
+        if self.tx.send(Command::Flush(done_tx)).await.is_ok() {
+            let _ = done_rx.await;
+        }
+    }
+}
+
+/// Append to the JSONL file on the blocking pool
+async fn append_to_file(file: &Arc<HistoryRecorder>, record: TradeRecord) {
+    let file = file.clone();
+    if let Err(e) = tokio::task::spawn_blocking(move || file.append(&record)).await {
+        tracing::error!("Trade history file write panicked: {}", e);
+    }
+}
+
+async fn insert(pool: &Pool, record: &TradeRecord) -> Result<()> {
+    let client = pool.get().await?;
+
+    let stmt = client
+        .prepare_cached(
+            "INSERT INTO trade_records
+            (recorded_at, session_id, trade_type, pair, buy_dex, sell_dex, size_usd,
+             profit_usd, profit_pct, tx_signature, success, error, detected_at,
+             quote_ms, build_ms, submit_ms, confirm_ms, profit_quote)
+            VALUES ($1::text::timestamptz, $2, $3, $4, $5, $6, $7::text::numeric,
+                    $8::text::numeric, $9::text::numeric, $10, $11, $12,
+                    $13::text::timestamptz, $14, $15, $16, $17, $18::text::numeric)",
+        )
+        .await?;
+
+    let millis = |v: Option<u64>| v.map(|ms| ms as i64);
+
+    client
+        .execute(
+            &stmt,
+            &[
+                &record.timestamp,
+                &record.session_id,
+                &record.trade_type,
+                &record.pair,
+                &record.buy_dex,
+                &record.sell_dex,
+                &record.size_usd,
+                &record.profit_usd,
+                &record.profit_pct,
+                &record.tx_signature,
+                &record.success,
+                &record.error,
+                &record.detected_at,
+                &millis(record.quote_ms),
+                &millis(record.build_ms),
+                &millis(record.submit_ms),
+                &millis(record.confirm_ms),
+                &record.profit_quote,
+            ],
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DexType, TokenPair};
+    use chrono::Utc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_records_reach_file_even_when_insert_fails() {
+        let path = std::env::temp_dir().join(format!("pg-history-{}.jsonl", uuid::Uuid::new_v4()));
+        let path_str = path.to_str().unwrap();
+
+        // Nothing listens on port 1, so every insert fails
+        let pg_config: tokio_postgres::Config =
+            "postgres://postgres@127.0.0.1:1/none".parse().unwrap();
+        let mgr = deadpool_postgres::Manager::from_config(
+            pg_config,
+            NoTls,
+            ManagerConfig {
+                recycling_method: RecyclingMethod::Fast,
+            },
+        );
+        let pool = Pool::builder(mgr).runtime(Runtime::Tokio1).build().unwrap();
+        let recorder =
+            PgHistoryRecorder::with_pool(pool, HistoryRecorder::new(path_str, "TEST-SESSION"));
+
+        let opp = ArbitrageOpportunity {
+            id: uuid::Uuid::new_v4(),
+            pair: TokenPair::new("SOL", "USDC"),
+            buy_dex: DexType::Raydium,
+            sell_dex: DexType::Orca,
+            buy_price: Decimal::new(100, 0),
+            sell_price: Decimal::new(101, 0),
+            gross_profit_pct: Decimal::ONE,
+            net_profit_pct: Decimal::new(9, 1),
+            estimated_profit_usd: None,
+            recommended_size: None,
+            detected_at: Utc::now(),
+            expired_at: None,
+            path: None,
+        };
+        recorder
+            .record_trade(
+                &opp,
+                Decimal::new(100, 0),
+                Decimal::ONE,
+                true,
+                None,
+                None,
+                true,
+            )
+            .await;
+
+        // The record is in the file once, whatever happens to the insert
+        tokio::time::timeout(Duration::from_secs(10), recorder.flush())
+            .await
+            .unwrap();
+        let written = std::fs::read_to_string(&path).unwrap_or_default();
+        let _ = std::fs::remove_file(&path);
+
+        let record: TradeRecord = serde_json::from_str(written.trim()).unwrap();
+        assert_eq!(record.session_id, "TEST-SESSION");
+        assert_eq!(record.pair, "SOL/USDC");
+    }
+}
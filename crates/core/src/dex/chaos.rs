@@ -0,0 +1,138 @@
+//! Fault injection for resilience testing
+//!
+//! `ChaosProvider` wraps any `DexProvider` and, before each price request or
+//! health check, sleeps for a random delay drawn uniformly from
+//! `[min_delay, max_delay]` and then fails with probability `failure_rate`.
+//! This exercises the timeout, provider-disable and partial-result paths
+//! against otherwise healthy providers.
+//!
+//! Only compiled in debug builds (`CHAOS_MODE` is ignored in release).
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rand::Rng;
+
+use super::{DexProvider, PriceStream};
+use crate::{ArbitrageError, ArbitrageResult, DexType, PriceData, TokenPair};
+
+/// How much latency and how many failures to inject
+#[derive(Debug, Clone)]
+pub struct ChaosConfig {
+    /// Probability (0.0-1.0) that a request fails after its delay
+    pub failure_rate: f64,
+    /// Shortest injected delay
+    pub min_delay: Duration,
+    /// Longest injected delay
+    pub max_delay: Duration,
+}
+
+impl Default for ChaosConfig {
+    /// 10% failures, up to 500ms of added latency
+    fn default() -> Self {
+        Self {
+            failure_rate: 0.1,
+            min_delay: Duration::ZERO,
+            max_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+/// A provider that delays and randomly fails requests to the one it wraps
+pub struct ChaosProvider {
+    inner: Arc<dyn DexProvider>,
+    config: ChaosConfig,
+}
+
+impl ChaosProvider {
+    pub fn new(inner: Arc<dyn DexProvider>, config: ChaosConfig) -> Self {
+        Self { inner, config }
+    }
+
+    /// Sleep for a random delay, then fail with the configured probability
+    async fn disturb(&self) -> ArbitrageResult<()> {
+        let (delay, fail) = {
+            let mut rng = rand::thread_rng();
+            let min = self.config.min_delay.as_millis() as u64;
+            let max = (self.config.max_delay.as_millis() as u64).max(min);
+            (
+                Duration::from_millis(rng.gen_range(min..=max)),
+                rng.gen_bool(self.config.failure_rate.clamp(0.0, 1.0)),
+            )
+        };
+        tokio::time::sleep(delay).await;
+        if fail {
+            return Err(ArbitrageError::PriceFetch(format!(
+                "chaos: injected failure for {:?} after {:?}",
+                self.inner.dex_type(),
+                delay
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DexProvider for ChaosProvider {
+    fn dex_type(&self) -> DexType {
+        self.inner.dex_type()
+    }
+
+    fn fee_percentage(&self) -> rust_decimal::Decimal {
+        self.inner.fee_percentage()
+    }
+
+    async fn get_price(&self, pair: &TokenPair) -> ArbitrageResult<PriceData> {
+        self.disturb().await?;
+        self.inner.get_price(pair).await
+    }
+
+    async fn subscribe(&self, pairs: Vec<TokenPair>) -> ArbitrageResult<PriceStream> {
+        self.inner.subscribe(pairs).await
+    }
+
+    async fn health_check(&self) -> ArbitrageResult<bool> {
+        self.disturb().await?;
+        self.inner.health_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::mock::MockDexProvider;
+    use rust_decimal::Decimal;
+    use std::time::Instant;
+
+    fn chaos(failure_rate: f64, delay_ms: u64) -> ChaosProvider {
+        let price = PriceData::new(
+            DexType::Orca,
+            TokenPair::new("SOL", "USDC"),
+            Decimal::ONE,
+            Decimal::TWO,
+        );
+        ChaosProvider::new(
+            Arc::new(MockDexProvider::new(DexType::Orca).with_price(price)),
+            ChaosConfig {
+                failure_rate,
+                min_delay: Duration::from_millis(delay_ms),
+                max_delay: Duration::from_millis(delay_ms),
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_injects_delay_and_failures() {
+        let pair = TokenPair::new("SOL", "USDC");
+
+        let start = Instant::now();
+        assert!(chaos(0.0, 20).get_price(&pair).await.is_ok());
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        let always_fail = chaos(1.0, 0);
+        assert!(always_fail.get_price(&pair).await.is_err());
+        assert!(always_fail.health_check().await.is_err());
+        assert_eq!(always_fail.dex_type(), DexType::Orca);
+    }
+}
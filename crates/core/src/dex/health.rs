@@ -0,0 +1,261 @@
+//! Provider health tracking
+//!
+//! Counts consecutive fetch / health-check failures per DEX. After
+//! `failure_threshold` failures in a row a provider is disabled and no longer
+//! polled; it is re-probed with `health_check` every `reprobe_interval` and
+//! re-enabled as soon as a probe succeeds.
+//!
+//! Optionally, enabled providers are also health-checked every
+//! `check_interval`, so a DEX that goes down is noticed from its health check
+//! rather than only from timed-out price fetches.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::DexProvider;
+use crate::DexType;
+
+/// Callback invoked with `(dex, enabled)` whenever a provider is disabled or re-enabled
+pub type ProviderStateObserver = Arc<dyn Fn(DexType, bool) + Send + Sync>;
+
+#[derive(Debug, Default)]
+struct ProviderState {
+    consecutive_failures: u32,
+    /// Time of the last probe while disabled; `None` while the provider is enabled
+    disabled_probe_at: Option<Instant>,
+    /// Time of the last health check, whatever its outcome
+    checked_at: Option<Instant>,
+}
+
+/// Shared enable/disable state for a set of DEX providers
+pub struct ProviderHealth {
+    failure_threshold: u32,
+    reprobe_interval: Duration,
+    check_interval: Option<Duration>,
+    states: Mutex<HashMap<DexType, ProviderState>>,
+    observer: Option<ProviderStateObserver>,
+}
+
+impl std::fmt::Debug for ProviderHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProviderHealth")
+            .field("failure_threshold", &self.failure_threshold)
+            .field("reprobe_interval", &self.reprobe_interval)
+            .field("check_interval", &self.check_interval)
+            .field("disabled", &self.disabled())
+            .finish()
+    }
+}
+
+impl ProviderHealth {
+    /// Disable a provider after `failure_threshold` consecutive failures (minimum 1)
+    /// and re-probe it every `reprobe_interval`
+    pub fn new(failure_threshold: u32, reprobe_interval: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            reprobe_interval,
+            check_interval: None,
+            states: Mutex::new(HashMap::new()),
+            observer: None,
+        }
+    }
+
+    /// Also health-check enabled providers every `interval`
+    pub fn with_check_interval(mut self, interval: Duration) -> Self {
+        self.check_interval = Some(interval);
+        self
+    }
+
+    /// Report enable/disable transitions to `observer` (used to drive metrics)
+    pub fn with_observer(
+        mut self,
+        observer: impl Fn(DexType, bool) + Send + Sync + 'static,
+    ) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Whether the provider should be polled for prices
+    pub fn is_enabled(&self, dex: DexType) -> bool {
+        self.states
+            .lock()
+            .unwrap()
+            .get(&dex)
+            .is_none_or(|s| s.disabled_probe_at.is_none())
+    }
+
+    /// Whether a disabled provider is due for a recovery probe
+    pub fn due_for_probe(&self, dex: DexType) -> bool {
+        self.states
+            .lock()
+            .unwrap()
+            .get(&dex)
+            .and_then(|s| s.disabled_probe_at)
+            .is_some_and(|at| at.elapsed() >= self.reprobe_interval)
+    }
+
+    /// Whether an enabled provider's cached health check has expired
+    pub fn due_for_check(&self, dex: DexType) -> bool {
+        let Some(interval) = self.check_interval else {
+            return false;
+        };
+        self.states.lock().unwrap().get(&dex).is_none_or(|s| {
+            s.disabled_probe_at.is_none() && s.checked_at.is_none_or(|at| at.elapsed() >= interval)
+        })
+    }
+
+    /// Providers currently disabled
+    pub fn disabled(&self) -> Vec<DexType> {
+        self.states
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, s)| s.disabled_probe_at.is_some())
+            .map(|(dex, _)| *dex)
+            .collect()
+    }
+
+    /// Record a successful fetch or probe, re-enabling the provider if it was disabled
+    pub fn record_success(&self, dex: DexType) {
+        let was_disabled = {
+            let mut states = self.states.lock().unwrap();
+            let state = states.entry(dex).or_default();
+            state.consecutive_failures = 0;
+            state.disabled_probe_at.take().is_some()
+        };
+        if was_disabled {
+            tracing::info!("✅ DEX {:?} recovered - re-enabling price polling", dex);
+            self.notify(dex, true);
+        }
+    }
+
+    /// Record a failed fetch or probe, disabling the provider once the threshold is hit
+    pub fn record_failure(&self, dex: DexType) {
+        let newly_disabled = {
+            let mut states = self.states.lock().unwrap();
+            let state = states.entry(dex).or_default();
+            state.consecutive_failures += 1;
+            match state.disabled_probe_at {
+                // Still down: wait another interval before the next probe
+                Some(_) => {
+                    state.disabled_probe_at = Some(Instant::now());
+                    false
+                }
+                None if state.consecutive_failures >= self.failure_threshold => {
+                    state.disabled_probe_at = Some(Instant::now());
+                    true
+                }
+                None => false,
+            }
+        };
+        if newly_disabled {
+            tracing::warn!(
+                "⛔ DEX {:?} disabled after {} consecutive failures - re-probing every {:?}",
+                dex,
+                self.failure_threshold,
+                self.reprobe_interval
+            );
+            self.notify(dex, false);
+        }
+    }
+
+    /// Run the provider's health check and record the result.
+    /// Returns whether the provider is enabled afterwards.
+    pub async fn probe(&self, provider: &dyn DexProvider) -> bool {
+        let dex = provider.dex_type();
+        let result = provider.health_check().await;
+        self.states
+            .lock()
+            .unwrap()
+            .entry(dex)
+            .or_default()
+            .checked_at = Some(Instant::now());
+        match result {
+            Ok(true) => self.record_success(dex),
+            Ok(false) => self.record_failure(dex),
+            Err(e) => {
+                tracing::debug!("DEX {:?} health probe failed: {}", dex, e);
+                self.record_failure(dex);
+            }
+        }
+        self.is_enabled(dex)
+    }
+
+    /// Whether the provider needs a health check before it is polled: a
+    /// disabled one due for a re-probe, or an enabled one due for a routine check.
+    /// `None` means skip it this round.
+    pub fn needs_probe(&self, dex: DexType) -> Option<bool> {
+        if self.is_enabled(dex) {
+            Some(self.due_for_check(dex))
+        } else if self.due_for_probe(dex) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    /// Whether to poll the provider now: it is enabled (after a health check if
+    /// one is due), or a due re-probe just recovered it
+    pub async fn should_poll(&self, provider: &dyn DexProvider) -> bool {
+        match self.needs_probe(provider.dex_type()) {
+            Some(true) => self.probe(provider).await,
+            Some(false) => true,
+            None => false,
+        }
+    }
+
+    fn notify(&self, dex: DexType, enabled: bool) {
+        if let Some(observer) = &self.observer {
+            observer(dex, enabled);
+        }
+    }
+}
+
+impl Default for ProviderHealth {
+    /// 5 consecutive failures, re-probe every minute
+    fn default() -> Self {
+        Self::new(5, Duration::from_secs(60))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_disable_after_threshold_and_recover() {
+        let transitions = Arc::new(AtomicUsize::new(0));
+        let seen = transitions.clone();
+        let health = ProviderHealth::new(3, Duration::ZERO).with_observer(move |_, _| {
+            seen.fetch_add(1, Ordering::SeqCst);
+        });
+
+        health.record_failure(DexType::Orca);
+        health.record_failure(DexType::Orca);
+        health.record_success(DexType::Orca); // streak broken
+        health.record_failure(DexType::Orca);
+        health.record_failure(DexType::Orca);
+        assert!(health.is_enabled(DexType::Orca));
+
+        health.record_failure(DexType::Orca);
+        assert!(!health.is_enabled(DexType::Orca));
+        assert_eq!(health.disabled(), vec![DexType::Orca]);
+        assert!(health.due_for_probe(DexType::Orca));
+        assert!(!health.due_for_probe(DexType::Raydium));
+
+        health.record_success(DexType::Orca);
+        assert!(health.is_enabled(DexType::Orca));
+        assert!(health.disabled().is_empty());
+        assert_eq!(transitions.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_reprobe_waits_for_interval() {
+        let health = ProviderHealth::new(1, Duration::from_secs(3600));
+        health.record_failure(DexType::Jupiter);
+        assert!(!health.is_enabled(DexType::Jupiter));
+        assert!(!health.due_for_probe(DexType::Jupiter));
+    }
+}
@@ -1,24 +1,42 @@
 //! Jupiter DEX Provider
 //!
 //! Jupiter is a DEX aggregator that routes trades through multiple DEXs
-//! to find the best prices. We use their Price API for price data.
+//! to find the best prices. We use their Price API for price data, and the
+//! Quote API to price specific trade sizes.
 
 use async_trait::async_trait;
 use rust_decimal::Decimal;
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 
 use super::{DexProvider, PriceStream};
-use crate::{ArbitrageError, ArbitrageResult, DexType, PriceData, TokenPair};
+use crate::http::pool::{create_client, HostRateLimiter, HttpTimeouts};
+use crate::token_registry::TokenRegistry;
+use crate::{ArbitrageError, ArbitrageResult, DexType, PriceData, Quote, Side, TokenPair};
 
 const JUPITER_PRICE_API: &str = "https://price.jup.ag/v6/price";
+const JUPITER_QUOTE_API: &str = "https://quote-api.jup.ag/v6/quote";
+
+/// Synthetic spread around the single reported price (0.01% each side)
+const SYNTHETIC_SPREAD_BPS: u32 = 2;
 
 /// Jupiter DEX provider implementation
 pub struct JupiterProvider {
     client: reqwest::Client,
-    /// Token symbol to mint address mapping
-    token_mints: HashMap<String, String>,
+    /// Per-host budget shared with the other providers
+    rate_limiter: Arc<HostRateLimiter>,
+    /// Per-request timeouts and retries
+    timeouts: HttpTimeouts,
+    /// Mint and SPL decimals of each token symbol, the decimals needed to
+    /// quote in UI units
+    tokens: TokenRegistry,
+    /// Fee rate of the latest quoted route per pair, in
+    /// `DexType::fee_percentage` units
+    route_fees: Mutex<HashMap<TokenPair, Decimal>>,
+    /// AMMs of the latest quoted route per pair (see [`JupiterQuoteResponse::route_id`])
+    routes: Mutex<HashMap<TokenPair, String>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -35,57 +53,164 @@ struct JupiterTokenPrice {
     price: f64,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JupiterQuoteResponse {
+    input_mint: String,
+    in_amount: String,
+    output_mint: String,
+    out_amount: String,
+    price_impact_pct: String,
+    #[serde(default)]
+    route_plan: Vec<JupiterRouteStep>,
+    platform_fee: Option<JupiterPlatformFee>,
+}
+
+/// Integrator fee taken from the output of an exact-in swap
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JupiterPlatformFee {
+    amount: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JupiterRouteStep {
+    swap_info: JupiterSwapInfo,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JupiterSwapInfo {
+    amm_key: Option<String>,
+    fee_amount: Option<String>,
+    fee_mint: Option<String>,
+}
+
+impl JupiterQuoteResponse {
+    /// The route's pool identity: the AMM's address for a single hop, which
+    /// matches the pool id the DEX's own provider reports, else every hop's AMM
+    /// in order. `None` if any hop's AMM is missing.
+    fn route_id(&self) -> Option<String> {
+        let amms = self
+            .route_plan
+            .iter()
+            .map(|step| step.swap_info.amm_key.as_deref())
+            .collect::<Option<Vec<&str>>>()?;
+        (!amms.is_empty()).then(|| amms.join(","))
+    }
+
+    /// The quote in UI units, given the decimals of the tokens spent and received.
+    /// The fee is the hops' fees plus any platform fee. Fees a multi-hop route
+    /// charges in an intermediate token aren't counted.
+    fn to_quote(&self, in_decimals: u32, out_decimals: u32) -> ArbitrageResult<Quote> {
+        let in_amount = ui_amount(&self.in_amount, in_decimals)?;
+        let out_amount = ui_amount(&self.out_amount, out_decimals)?;
+        let price_impact_pct = Decimal::from_str_exact(&self.price_impact_pct).map_err(|e| {
+            ArbitrageError::JupiterApiError(format!("invalid priceImpactPct: {}", e))
+        })? * Decimal::ONE_HUNDRED;
+
+        let mut fee = Decimal::ZERO;
+        for step in &self.route_plan {
+            let (Some(amount), Some(mint)) = (&step.swap_info.fee_amount, &step.swap_info.fee_mint)
+            else {
+                continue;
+            };
+            if *mint == self.output_mint {
+                fee += ui_amount(amount, out_decimals)?;
+            } else if *mint == self.input_mint && !in_amount.is_zero() {
+                // Valued at the quote's own rate
+                fee += ui_amount(amount, in_decimals)? * out_amount / in_amount;
+            }
+        }
+        if let Some(platform_fee) = &self.platform_fee {
+            fee += ui_amount(&platform_fee.amount, out_decimals)?;
+        }
+
+        Ok(Quote {
+            out_amount,
+            price_impact_pct,
+            fee,
+        })
+    }
+}
+
+/// Atomic amount string to UI units
+fn ui_amount(atoms: &str, decimals: u32) -> ArbitrageResult<Decimal> {
+    let atoms: u64 = atoms
+        .parse()
+        .map_err(|_| ArbitrageError::JupiterApiError(format!("invalid amount {:?}", atoms)))?;
+    Ok(Decimal::from_i128_with_scale(atoms as i128, decimals))
+}
+
 impl JupiterProvider {
+    /// Provider with the default timeouts and a rate limiter of its own
     pub fn new() -> Self {
-        let mut token_mints = HashMap::new();
-        // Common Solana tokens
-        token_mints.insert(
-            "SOL".to_string(),
-            "So11111111111111111111111111111111111111112".to_string(),
-        );
-        token_mints.insert(
-            "USDC".to_string(),
-            "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(),
-        );
-        token_mints.insert(
-            "USDT".to_string(),
-            "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB".to_string(),
-        );
-        token_mints.insert(
-            "RAY".to_string(),
-            "4k3Dyjzvzp8eMZWUXbBCjEvwSkkk59S5iCNLY3QrkX6R".to_string(),
-        );
-        token_mints.insert(
-            "SRM".to_string(),
-            "SRMuApVNdxXokk5GT7XD5cUUgXMBCoAz2LHeuAoKWRt".to_string(),
-        );
-        token_mints.insert(
-            "BONK".to_string(),
-            "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263".to_string(),
-        );
-        token_mints.insert(
-            "JUP".to_string(),
-            "JUPyiwrYJFskUPiHa7hkeR8VUtAe6poCFFRLnWo6h7rL".to_string(),
-        );
-        token_mints.insert(
-            "ORCA".to_string(),
-            "orcaEKTdK7LKz57vaAYr9QeNsVEPfiu6QeMU1kektZE".to_string(),
-        );
+        Self::with_http(HttpTimeouts::default(), Arc::default())
+    }
 
+    /// Provider using `timeouts` and sharing `rate_limiter`, both usually
+    /// built from the caller's `Config`
+    pub fn with_http(timeouts: HttpTimeouts, rate_limiter: Arc<HostRateLimiter>) -> Self {
         Self {
-            client: crate::http::pool::create_optimized_client(),
-            token_mints,
+            client: create_client(&timeouts),
+            rate_limiter,
+            timeouts,
+            tokens: TokenRegistry::mainnet(),
+            route_fees: Mutex::new(HashMap::new()),
+            routes: Mutex::new(HashMap::new()),
         }
     }
 
     /// Get the mint address for a token symbol
-    fn get_mint(&self, symbol: &str) -> Option<&String> {
-        self.token_mints.get(symbol)
+    fn get_mint(&self, symbol: &str) -> Option<&str> {
+        self.tokens.get(symbol).map(|t| t.mint.as_str())
+    }
+
+    /// Add a custom token
+    pub fn add_token(&mut self, symbol: String, mint: String, decimals: u32) {
+        self.tokens.insert(symbol, mint, decimals);
+    }
+
+    /// Fee rate of the route Jupiter last quoted for `pair`, in
+    /// `DexType::fee_percentage` units. `DexType::Jupiter` defaults to no fee,
+    /// but routes pay their pools' fees and possibly a platform fee.
+    pub fn route_fee(&self, pair: &TokenPair) -> Option<Decimal> {
+        self.route_fees.lock().unwrap().get(pair).copied()
+    }
+
+    /// Pool identity of the route Jupiter last quoted for `pair`, so a Jupiter
+    /// price routed through another DEX's pool isn't compared with that pool
+    pub fn route_id(&self, pair: &TokenPair) -> Option<String> {
+        self.routes.lock().unwrap().get(pair).cloned()
     }
 
-    /// Add a custom token mapping
-    pub fn add_token(&mut self, symbol: String, mint: String) {
-        self.token_mints.insert(symbol, mint);
+    /// Quote buying each pair with the given amount of its quote token, so
+    /// [`Self::route_fee`] and [`Self::route_id`] reflect current routes. Pairs
+    /// that can't be quoted keep the last route seen.
+    pub async fn refresh_route_fees(&self, pairs: &[(TokenPair, Decimal)]) {
+        for (pair, amount_in) in pairs {
+            if let Err(e) = self.get_quote(pair, *amount_in, Side::Buy).await {
+                tracing::debug!("No Jupiter route fee for {}: {}", pair, e);
+            }
+        }
+    }
+
+    /// `price` tagged with its pair's last quoted route, if one is known
+    fn with_last_route(&self, price: PriceData) -> PriceData {
+        match self.route_id(&price.pair) {
+            Some(route) => price.with_route(route),
+            None => price,
+        }
+    }
+
+    /// Mint and decimals of a token symbol
+    fn token(&self, symbol: &str) -> ArbitrageResult<(&str, u32)> {
+        let token = self
+            .tokens
+            .get(symbol)
+            .ok_or_else(|| ArbitrageError::Config(format!("Unknown token: {}", symbol)))?;
+        Ok((&token.mint, token.decimals))
     }
 }
 
@@ -115,7 +240,13 @@ impl DexProvider for JupiterProvider {
             JUPITER_PRICE_API, base_mint, quote_mint
         );
 
-        let response: JupiterPriceResponse = self.client.get(&url).send().await?.json().await?;
+        let request = self.client.get(&url).timeout(self.timeouts.price_fetch);
+        let response: JupiterPriceResponse = self
+            .rate_limiter
+            .send(request, self.timeouts.retries)
+            .await?
+            .json()
+            .await?;
 
         let token_price = response
             .data
@@ -125,28 +256,75 @@ impl DexProvider for JupiterProvider {
         let price = Decimal::try_from(token_price.price)
             .map_err(|e| ArbitrageError::PriceFetch(format!("Invalid price: {}", e)))?;
 
-        // Jupiter provides a single price, we estimate bid/ask with a small spread
-        let spread = price * Decimal::new(1, 4); // 0.01% spread estimate
-        let bid = price - spread;
-        let ask = price + spread;
+        // Jupiter provides a single price, so bid/ask are synthesized around it.
+        // The Price API doesn't say how it routes; the last quote does.
+        Ok(self.with_last_route(PriceData::from_mid(
+            DexType::Jupiter,
+            pair.clone(),
+            price,
+            SYNTHETIC_SPREAD_BPS,
+        )))
+    }
+
+    /// Asks Jupiter's Quote API for the best route at exactly this size
+    async fn get_quote(
+        &self,
+        pair: &TokenPair,
+        amount_in: Decimal,
+        side: Side,
+    ) -> ArbitrageResult<Quote> {
+        let (base_mint, base_decimals) = self.token(&pair.base)?;
+        let (quote_mint, quote_decimals) = self.token(&pair.quote)?;
+        let ((in_mint, in_decimals), (out_mint, out_decimals)) = match side {
+            Side::Buy => ((quote_mint, quote_decimals), (base_mint, base_decimals)),
+            Side::Sell => ((base_mint, base_decimals), (quote_mint, quote_decimals)),
+        };
+        let amount = crate::math::to_atoms(amount_in, in_decimals)?;
 
-        Ok(PriceData::new(DexType::Jupiter, pair.clone(), bid, ask))
+        let url = format!(
+            "{}?inputMint={}&outputMint={}&amount={}",
+            JUPITER_QUOTE_API, in_mint, out_mint, amount
+        );
+        let request = self.client.get(&url).timeout(self.timeouts.quote);
+        let response = self
+            .rate_limiter
+            .send(request, self.timeouts.retries)
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ArbitrageError::JupiterQuoteFailed(format!(
+                "{} for {} {}: {}",
+                status, amount_in, pair, body
+            )));
+        }
+
+        let response: JupiterQuoteResponse = response.json().await?;
+        let quote = response.to_quote(in_decimals, out_decimals)?;
+        if let Some(fee) = quote.fee_rate() {
+            self.route_fees.lock().unwrap().insert(pair.clone(), fee);
+        }
+        if let Some(route) = response.route_id() {
+            self.routes.lock().unwrap().insert(pair.clone(), route);
+        }
+        Ok(quote)
     }
 
     async fn subscribe(&self, pairs: Vec<TokenPair>) -> ArbitrageResult<PriceStream> {
         let (tx, rx) = mpsc::channel(100);
         let client = self.client.clone();
-        let token_mints = self.token_mints.clone();
+        let request_timeout = self.timeouts.price_fetch;
+        let tokens = self.tokens.clone();
 
         tokio::spawn(async move {
             loop {
                 for pair in &pairs {
-                    let base_mint = match token_mints.get(&pair.base) {
-                        Some(m) => m,
+                    let base_mint = match tokens.get(&pair.base) {
+                        Some(t) => &t.mint,
                         None => continue,
                     };
-                    let quote_mint = match token_mints.get(&pair.quote) {
-                        Some(m) => m,
+                    let quote_mint = match tokens.get(&pair.quote) {
+                        Some(t) => &t.mint,
                         None => continue,
                     };
 
@@ -155,16 +333,16 @@ impl DexProvider for JupiterProvider {
                         JUPITER_PRICE_API, base_mint, quote_mint
                     );
 
-                    if let Ok(response) = client.get(&url).send().await {
+                    if let Ok(response) = client.get(&url).timeout(request_timeout).send().await {
                         if let Ok(data) = response.json::<JupiterPriceResponse>().await {
                             if let Some(token_price) = data.data.get(base_mint) {
                                 if let Ok(price) = Decimal::try_from(token_price.price) {
-                                    let spread = price * Decimal::new(1, 4);
-                                    let bid = price - spread;
-                                    let ask = price + spread;
-
-                                    let price_data =
-                                        PriceData::new(DexType::Jupiter, pair.clone(), bid, ask);
+                                    let price_data = PriceData::from_mid(
+                                        DexType::Jupiter,
+                                        pair.clone(),
+                                        price,
+                                        SYNTHETIC_SPREAD_BPS,
+                                    );
 
                                     if tx.send(price_data).await.is_err() {
                                         return; // Channel closed
@@ -188,7 +366,12 @@ impl DexProvider for JupiterProvider {
             "{}?ids=So11111111111111111111111111111111111111112",
             JUPITER_PRICE_API
         );
-        let response = self.client.get(&url).send().await?;
+        let response = self
+            .client
+            .get(&url)
+            .timeout(self.timeouts.price_fetch)
+            .send()
+            .await?;
         Ok(response.status().is_success())
     }
 }
@@ -197,6 +380,115 @@ impl DexProvider for JupiterProvider {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_quote_response_to_ui_units() {
+        // 100 USDC -> 0.665 SOL over two hops, fees in both tokens
+        let response: JupiterQuoteResponse = serde_json::from_str(
+            r#"{
+                "inputMint": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+                "inAmount": "100000000",
+                "outputMint": "So11111111111111111111111111111111111111112",
+                "outAmount": "665000000",
+                "priceImpactPct": "0.0012",
+                "routePlan": [
+                    { "swapInfo": {
+                        "feeAmount": "250000",
+                        "feeMint": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"
+                    } },
+                    { "swapInfo": {
+                        "feeAmount": "1000000",
+                        "feeMint": "So11111111111111111111111111111111111111112"
+                    } }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let quote = response.to_quote(6, 9).unwrap();
+        assert_eq!(quote.out_amount, Decimal::new(665, 3));
+        assert_eq!(quote.price_impact_pct, Decimal::new(12, 2));
+        // 0.25 USDC at 0.00665 SOL/USDC, plus 0.001 SOL
+        assert_eq!(quote.fee, Decimal::new(2_662_500, 9));
+    }
+
+    #[test]
+    fn test_platform_fee_counts_toward_route_fee() {
+        // 100 USDC -> 0.994 SOL: 0.003 SOL in pool fees plus a 0.3% platform fee
+        let response: JupiterQuoteResponse = serde_json::from_str(
+            r#"{
+                "inputMint": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+                "inAmount": "100000000",
+                "outputMint": "So11111111111111111111111111111111111111112",
+                "outAmount": "994000000",
+                "priceImpactPct": "0",
+                "routePlan": [
+                    { "swapInfo": {
+                        "feeAmount": "3000000",
+                        "feeMint": "So11111111111111111111111111111111111111112"
+                    } }
+                ],
+                "platformFee": { "amount": "3000000", "feeBps": 30 }
+            }"#,
+        )
+        .unwrap();
+
+        let quote = response.to_quote(6, 9).unwrap();
+        assert_eq!(quote.fee, Decimal::new(6, 3));
+        // 0.006 of the 1 SOL the route would give without fees: 0.6%, not
+        // the zero `DexType::Jupiter` defaults to
+        assert_eq!(quote.fee_rate(), Some(Decimal::new(6, 3)));
+        assert!(quote.fee_rate().unwrap() > DexType::Jupiter.fee_percentage());
+    }
+
+    #[test]
+    fn test_price_carries_the_quoted_route() {
+        let quote = |route_plan: &str| -> JupiterQuoteResponse {
+            serde_json::from_str(&format!(
+                r#"{{
+                    "inputMint": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+                    "inAmount": "100000000",
+                    "outputMint": "So11111111111111111111111111111111111111112",
+                    "outAmount": "665000000",
+                    "priceImpactPct": "0",
+                    "routePlan": {}
+                }}"#,
+                route_plan
+            ))
+            .unwrap()
+        };
+        let raydium_amm = "58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2";
+        let single = quote(&format!(
+            r#"[{{ "swapInfo": {{ "ammKey": "{}" }} }}]"#,
+            raydium_amm
+        ));
+        assert_eq!(single.route_id().as_deref(), Some(raydium_amm));
+        let two_hops =
+            quote(r#"[{ "swapInfo": { "ammKey": "A" } }, { "swapInfo": { "ammKey": "B" } }]"#);
+        assert_eq!(two_hops.route_id().as_deref(), Some("A,B"));
+        assert_eq!(quote("[]").route_id(), None);
+
+        // Jupiter mirroring Raydium's pool shares its route, so the pair isn't
+        // reported as an opportunity against itself
+        let provider = JupiterProvider::new();
+        let pair = TokenPair::new("SOL", "USDC");
+        let mid = PriceData::from_mid(DexType::Jupiter, pair.clone(), Decimal::from(150), 2);
+        assert_eq!(provider.with_last_route(mid.clone()).route_id, None);
+        provider
+            .routes
+            .lock()
+            .unwrap()
+            .insert(pair.clone(), single.route_id().unwrap());
+        let jupiter = provider.with_last_route(mid);
+        let raydium = PriceData::new(
+            DexType::Raydium,
+            pair,
+            Decimal::from(149),
+            Decimal::from(151),
+        )
+        .with_route(raydium_amm);
+        assert!(jupiter.shares_route_with(&raydium));
+    }
+
     #[tokio::test]
     #[ignore] // Requires network access - run with: cargo test -- --ignored
     async fn test_jupiter_health_check() {
@@ -0,0 +1,176 @@
+//! Scripted DEX provider for tests and simulations
+//!
+//! [`MockDexProvider`] serves prices queued up front instead of fetching them,
+//! so the detection and trading paths can be driven deterministically: queue
+//! the prices that open a spread, run a tick, and assert on what was traded.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use rust_decimal::Decimal;
+
+use super::{estimate_quote, DexProvider, PriceStream};
+use crate::{ArbitrageError, ArbitrageResult, DexType, PriceData, Quote, Side, TokenPair};
+
+/// A provider answering from scripted prices and quotes
+pub struct MockDexProvider {
+    dex: DexType,
+    /// Prices still to be served per pair; the last one repeats once the rest
+    /// are used up
+    prices: Mutex<HashMap<TokenPair, VecDeque<PriceData>>>,
+    /// Fixed quotes by pair and side, overriding the estimate from the price
+    quotes: Mutex<HashMap<(TokenPair, Side), Quote>>,
+    /// Whether every request fails, as if the DEX were unreachable
+    down: AtomicBool,
+}
+
+impl MockDexProvider {
+    pub fn new(dex: DexType) -> Self {
+        Self {
+            dex,
+            prices: Mutex::new(HashMap::new()),
+            quotes: Mutex::new(HashMap::new()),
+            down: AtomicBool::new(false),
+        }
+    }
+
+    /// Queue `price` for its pair (its DEX is taken to be this provider's)
+    pub fn with_price(self, price: PriceData) -> Self {
+        self.push_price(price);
+        self
+    }
+
+    /// Quote every `side` trade on `pair` as `quote`, whatever its size
+    pub fn with_quote(self, pair: TokenPair, side: Side, quote: Quote) -> Self {
+        self.quotes.lock().unwrap().insert((pair, side), quote);
+        self
+    }
+
+    /// Fail every price and quote request, and report unhealthy, until
+    /// brought back up
+    pub fn set_down(&self, down: bool) {
+        self.down.store(down, Ordering::SeqCst);
+    }
+
+    /// Queue `price` to be served after those already queued for its pair
+    pub fn push_price(&self, price: PriceData) {
+        let price = PriceData {
+            dex: self.dex,
+            ..price
+        };
+        self.prices
+            .lock()
+            .unwrap()
+            .entry(price.pair.clone())
+            .or_default()
+            .push_back(price);
+    }
+
+    /// Fails while the provider is down
+    fn check_up(&self) -> ArbitrageResult<()> {
+        if self.down.load(Ordering::SeqCst) {
+            return Err(ArbitrageError::PriceFetch(format!("{} is down", self.dex)));
+        }
+        Ok(())
+    }
+
+    /// The next scripted price for `pair`, stamped as just observed
+    fn next_price(&self, pair: &TokenPair, advance: bool) -> ArbitrageResult<PriceData> {
+        self.check_up()?;
+        let mut prices = self.prices.lock().unwrap();
+        let queue = prices
+            .get_mut(pair)
+            .filter(|queue| !queue.is_empty())
+            .ok_or_else(|| ArbitrageError::PriceNotAvailable(pair.to_string()))?;
+        let price = if advance && queue.len() > 1 {
+            queue.pop_front()
+        } else {
+            queue.front().cloned()
+        };
+        Ok(PriceData {
+            timestamp: Utc::now(),
+            ..price.expect("queue is not empty")
+        })
+    }
+}
+
+#[async_trait]
+impl DexProvider for MockDexProvider {
+    fn dex_type(&self) -> DexType {
+        self.dex
+    }
+
+    async fn get_price(&self, pair: &TokenPair) -> ArbitrageResult<PriceData> {
+        self.next_price(pair, true)
+    }
+
+    /// The scripted quote, else an estimate from the current price without
+    /// advancing the script
+    async fn get_quote(
+        &self,
+        pair: &TokenPair,
+        amount_in: Decimal,
+        side: Side,
+    ) -> ArbitrageResult<Quote> {
+        self.check_up()?;
+        if let Some(quote) = self.quotes.lock().unwrap().get(&(pair.clone(), side)) {
+            return Ok(*quote);
+        }
+        let price = self.next_price(pair, false)?;
+        estimate_quote(&price, amount_in, side, self.fee_percentage())
+    }
+
+    async fn subscribe(&self, _pairs: Vec<TokenPair>) -> ArbitrageResult<PriceStream> {
+        Err(ArbitrageError::WebSocket(
+            "mock provider has no stream".to_string(),
+        ))
+    }
+
+    async fn health_check(&self) -> ArbitrageResult<bool> {
+        Ok(!self.down.load(Ordering::SeqCst))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scripted_prices_play_in_order_then_hold() {
+        let pair = TokenPair::new("SOL", "USDC");
+        let at = |mid: i64| PriceData::from_mid(DexType::Orca, pair.clone(), Decimal::from(mid), 0);
+        let provider = MockDexProvider::new(DexType::Raydium)
+            .with_price(at(150))
+            .with_price(at(152));
+
+        let first = provider.get_price(&pair).await.unwrap();
+        assert_eq!(
+            (first.dex, first.mid_price),
+            (DexType::Raydium, Decimal::from(150))
+        );
+        // Quoting reads the current price without consuming it
+        let quote = provider
+            .get_quote(&pair, Decimal::from(152), Side::Buy)
+            .await
+            .unwrap();
+        assert_eq!(quote.out_amount + quote.fee, Decimal::ONE);
+        for _ in 0..2 {
+            let price = provider.get_price(&pair).await.unwrap();
+            assert_eq!(price.mid_price, Decimal::from(152));
+        }
+        assert!(provider
+            .get_price(&TokenPair::new("RAY", "USDC"))
+            .await
+            .is_err());
+
+        // Down: every request fails until it comes back
+        provider.set_down(true);
+        assert!(provider.get_price(&pair).await.is_err());
+        assert!(!provider.health_check().await.unwrap());
+        provider.set_down(false);
+        assert!(provider.get_price(&pair).await.is_ok());
+    }
+}
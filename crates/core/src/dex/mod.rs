@@ -3,21 +3,45 @@
 //! This module contains the trait definition and implementations for
 //! connecting to various Solana DEXs and fetching price data.
 
+#[cfg(debug_assertions)]
+pub mod chaos;
+pub mod health;
 #[cfg(feature = "http")]
 pub mod jupiter;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod mock;
 #[cfg(feature = "http")]
 pub mod orca;
 #[cfg(feature = "http")]
 pub mod raydium;
 
 use async_trait::async_trait;
+use rust_decimal::Decimal;
 use tokio::sync::mpsc;
 
-use crate::{ArbitrageResult, DexType, PriceData, TokenPair};
+use crate::{ArbitrageError, ArbitrageResult, DexType, PriceData, Quote, Side, TokenPair};
+
+pub use health::ProviderHealth;
 
 /// Stream of price updates from a DEX
 pub type PriceStream = mpsc::Receiver<PriceData>;
 
+/// Outcome of a batch price fetch: one bad pair doesn't discard the rest
+#[derive(Debug, Default)]
+pub struct PriceBatch {
+    /// Prices for the pairs that succeeded
+    pub prices: Vec<PriceData>,
+    /// Pairs that failed, with the reason
+    pub errors: Vec<(TokenPair, ArbitrageError)>,
+}
+
+impl PriceBatch {
+    /// Whether every requested pair failed (an empty request is not a failure)
+    pub fn is_total_failure(&self) -> bool {
+        self.prices.is_empty() && !self.errors.is_empty()
+    }
+}
+
 /// Trait for DEX price data providers
 #[async_trait]
 pub trait DexProvider: Send + Sync {
@@ -25,25 +49,46 @@ pub trait DexProvider: Send + Sync {
     fn dex_type(&self) -> DexType;
 
     /// Returns the trading fee percentage for this DEX
-    fn fee_percentage(&self) -> rust_decimal::Decimal {
+    fn fee_percentage(&self) -> Decimal {
         self.dex_type().fee_percentage()
     }
 
     /// Get the current price for a specific trading pair
     async fn get_price(&self, pair: &TokenPair) -> ArbitrageResult<PriceData>;
 
-    /// Get prices for multiple trading pairs
-    async fn get_prices(&self, pairs: &[TokenPair]) -> ArbitrageResult<Vec<PriceData>> {
-        let mut prices = Vec::with_capacity(pairs.len());
+    /// Get prices for multiple trading pairs, reporting failures per pair
+    async fn get_prices(&self, pairs: &[TokenPair]) -> PriceBatch {
+        let mut batch = PriceBatch {
+            prices: Vec::with_capacity(pairs.len()),
+            errors: Vec::new(),
+        };
         for pair in pairs {
             match self.get_price(pair).await {
-                Ok(price) => prices.push(price),
-                Err(e) => {
-                    tracing::warn!("Failed to get price for {}: {}", pair, e);
-                }
+                Ok(price) => batch.prices.push(price),
+                Err(e) => batch.errors.push((pair.clone(), e)),
             }
         }
-        Ok(prices)
+        batch
+    }
+
+    /// Quote trading `amount_in` of the token spent on `side`: the quote token
+    /// when buying, the base token when selling (UI units).
+    ///
+    /// The default is [`estimate_quote`] over the current top of book, which
+    /// assumes impact grows linearly with size; providers that can quote a size
+    /// directly should override it.
+    async fn get_quote(
+        &self,
+        pair: &TokenPair,
+        amount_in: Decimal,
+        side: Side,
+    ) -> ArbitrageResult<Quote> {
+        let mut batch = self.get_prices(std::slice::from_ref(pair)).await;
+        match (batch.prices.pop(), batch.errors.pop()) {
+            (Some(price), _) => estimate_quote(&price, amount_in, side, self.fee_percentage()),
+            (None, Some((_, e))) => Err(e),
+            (None, None) => Err(ArbitrageError::PriceNotAvailable(pair.to_string())),
+        }
     }
 
     /// Subscribe to real-time price updates for the given pairs
@@ -53,12 +98,91 @@ pub trait DexProvider: Send + Sync {
     async fn health_check(&self) -> ArbitrageResult<bool>;
 }
 
+/// Linear estimate of a quote from a top-of-book price.
+///
+/// The trade fills at the bid or ask, moved against it by the trade's share of
+/// the reported liquidity (a trade worth 1% of the liquidity moves the price 1%),
+/// then pays `fee_rate` (a fraction) out of what it receives. Without a liquidity
+/// figure there is no way to size the impact, so it is taken as zero.
+pub fn estimate_quote(
+    price: &PriceData,
+    amount_in: Decimal,
+    side: Side,
+    fee_rate: Decimal,
+) -> ArbitrageResult<Quote> {
+    if amount_in.is_sign_negative() {
+        return Err(ArbitrageError::InvalidOpportunity(format!(
+            "negative trade size {}",
+            amount_in
+        )));
+    }
+    let overflow = || {
+        ArbitrageError::ArithmeticOverflow(format!(
+            "quoting {} on {} {}",
+            amount_in, price.dex, price.pair
+        ))
+    };
+
+    let (top, notional) = match side {
+        Side::Buy => (price.ask, amount_in),
+        Side::Sell => (
+            price.bid,
+            amount_in.checked_mul(price.bid).ok_or_else(overflow)?,
+        ),
+    };
+    if top <= Decimal::ZERO {
+        return Err(ArbitrageError::PriceNotAvailable(price.pair.to_string()));
+    }
+    let price_impact_pct = match price.liquidity.filter(|l| *l > Decimal::ZERO) {
+        Some(liquidity) => notional
+            .checked_div(liquidity)
+            .and_then(|share| share.checked_mul(Decimal::ONE_HUNDRED))
+            .ok_or_else(overflow)?,
+        None => Decimal::ZERO,
+    };
+    if price_impact_pct >= Decimal::ONE_HUNDRED {
+        return Err(ArbitrageError::InsufficientLiquidity(format!(
+            "{} of {} exceeds {} liquidity",
+            amount_in, price.pair, price.dex
+        )));
+    }
+
+    let slipped = Decimal::ONE_HUNDRED - price_impact_pct;
+    let gross = match side {
+        // Paying more per base token than the ask
+        Side::Buy => amount_in
+            .checked_mul(slipped)
+            .and_then(|v| v.checked_div(top.checked_mul(Decimal::ONE_HUNDRED)?)),
+        // Receiving less per base token than the bid
+        Side::Sell => notional
+            .checked_mul(slipped)
+            .and_then(|v| v.checked_div(Decimal::ONE_HUNDRED)),
+    }
+    .ok_or_else(overflow)?;
+    let fee = gross.checked_mul(fee_rate).ok_or_else(overflow)?;
+
+    Ok(Quote {
+        out_amount: gross - fee,
+        price_impact_pct,
+        fee,
+    })
+}
+
+/// A pool's USD TVL in units of its pair's quote token, which is what
+/// [`PriceData::liquidity`] holds, given `quote_usd`, the USD value of one quote
+/// token. `None` (unknown liquidity) without a usable price.
+pub fn tvl_in_quote(tvl_usd: Decimal, quote_usd: Option<Decimal>) -> Option<Decimal> {
+    let quote_usd = quote_usd.filter(|p| *p > Decimal::ZERO)?;
+    tvl_usd.checked_div(quote_usd)
+}
+
 /// Manager for multiple DEX providers.
 ///
 /// Aggregates multiple DEX implementations to allow unified price fetching
 /// and interaction across the Solana ecosystem.
 pub struct DexManager {
     providers: Vec<std::sync::Arc<dyn DexProvider>>,
+    health: std::sync::Arc<ProviderHealth>,
 }
 
 impl DexManager {
@@ -66,25 +190,85 @@ impl DexManager {
     pub fn new() -> Self {
         Self {
             providers: Vec::new(),
+            health: std::sync::Arc::new(ProviderHealth::default()),
         }
     }
 
+    /// Replaces the provider health tracker (failure threshold, re-probe interval).
+    pub fn with_health(mut self, health: std::sync::Arc<ProviderHealth>) -> Self {
+        self.health = health;
+        self
+    }
+
+    /// Shared health tracker, so other fetchers can honour the same disabled set.
+    pub fn health(&self) -> std::sync::Arc<ProviderHealth> {
+        self.health.clone()
+    }
+
+    /// DEXs currently disabled after repeated failures.
+    pub fn disabled_providers(&self) -> Vec<DexType> {
+        self.health.disabled()
+    }
+
     /// Registers a new DEX provider.
     pub fn add_provider(&mut self, provider: std::sync::Arc<dyn DexProvider>) {
         self.providers.push(provider);
     }
 
+    /// Wraps every registered provider in a [`chaos::ChaosProvider`] that injects
+    /// latency and random failures. Debug builds only.
+    #[cfg(debug_assertions)]
+    pub fn enable_chaos(&mut self, config: chaos::ChaosConfig) {
+        tracing::warn!(
+            "🐒 CHAOS MODE: injecting {:?}-{:?} latency and {:.0}% failures into {} providers",
+            config.min_delay,
+            config.max_delay,
+            config.failure_rate * 100.0,
+            self.providers.len()
+        );
+        self.providers = self
+            .providers
+            .drain(..)
+            .map(|provider| {
+                std::sync::Arc::new(chaos::ChaosProvider::new(provider, config.clone()))
+                    as std::sync::Arc<dyn DexProvider>
+            })
+            .collect();
+    }
+
+    /// Providers that are currently healthy, running any health checks that
+    /// are due (concurrently). Disabled providers are included again once a
+    /// re-probe finds them recovered.
+    pub async fn healthy_providers(&self) -> Vec<std::sync::Arc<dyn DexProvider>> {
+        let checks = self.providers.iter().map(|provider| async move {
+            self.health
+                .should_poll(provider.as_ref())
+                .await
+                .then(|| provider.clone())
+        });
+        futures_util::future::join_all(checks)
+            .await
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
     /// Returns a slice of all registered providers.
     pub fn providers(&self) -> &[std::sync::Arc<dyn DexProvider>] {
         &self.providers
     }
 
-    /// Fetches prices for a given pair from all registered providers.
+    /// Fetches prices for a given pair from all enabled providers.
     ///
-    /// Useful for price discovery and cross-exchange comparison.
+    /// Useful for price discovery and cross-exchange comparison. Disabled
+    /// providers are skipped until a health probe brings them back.
     pub async fn get_all_prices(&self, pair: &TokenPair) -> Vec<PriceData> {
         let mut prices = Vec::new();
         for provider in &self.providers {
+            let dex = provider.dex_type();
+            if !self.health.should_poll(provider.as_ref()).await {
+                continue;
+            }
             tracing::info!("➡️ Calling price fetch for DEX: {:?}", provider.dex_type());
             match provider.get_price(pair).await {
                 Ok(price) => {
@@ -93,10 +277,12 @@ impl DexManager {
                         provider.dex_type(),
                         pair
                     );
+                    self.health.record_success(dex);
                     prices.push(price);
                 }
                 Err(e) => {
                     tracing::warn!("❌ DEX {:?} fetch error: {}", provider.dex_type(), e);
+                    self.health.record_failure(dex);
                 }
             }
         }
@@ -109,3 +295,130 @@ impl Default for DexManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mock::MockDexProvider;
+    use rust_decimal::Decimal;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Quotes every pair except those with base "BAD"
+    struct PartialProvider;
+
+    #[async_trait]
+    impl DexProvider for PartialProvider {
+        fn dex_type(&self) -> DexType {
+            DexType::Raydium
+        }
+
+        async fn get_price(&self, pair: &TokenPair) -> ArbitrageResult<PriceData> {
+            if pair.base == "BAD" {
+                return Err(ArbitrageError::PriceFetch(format!(
+                    "unsupported pair {}",
+                    pair
+                )));
+            }
+            Ok(PriceData::new(
+                self.dex_type(),
+                pair.clone(),
+                Decimal::ONE,
+                Decimal::TWO,
+            ))
+        }
+
+        async fn subscribe(&self, _pairs: Vec<TokenPair>) -> ArbitrageResult<PriceStream> {
+            Err(ArbitrageError::WebSocket(
+                "test provider has no stream".to_string(),
+            ))
+        }
+
+        async fn health_check(&self) -> ArbitrageResult<bool> {
+            Ok(true)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_prices_keeps_good_pairs_on_partial_failure() {
+        let pairs = vec![
+            TokenPair::new("SOL", "USDC"),
+            TokenPair::new("BAD", "USDC"),
+            TokenPair::new("RAY", "USDC"),
+        ];
+        let batch = PartialProvider.get_prices(&pairs).await;
+        assert_eq!(batch.prices.len(), 2);
+        assert_eq!(batch.errors.len(), 1);
+        assert_eq!(batch.errors[0].0, pairs[1]);
+        assert!(!batch.is_total_failure());
+
+        let all_bad = PartialProvider.get_prices(&pairs[1..2]).await;
+        assert!(all_bad.is_total_failure());
+        assert!(!PartialProvider.get_prices(&[]).await.is_total_failure());
+    }
+
+    #[tokio::test]
+    async fn test_default_get_quote_estimates_from_top_of_book() {
+        // Bid 1, ask 2, no liquidity reported: no impact, Raydium's 0.25% fee
+        let pair = TokenPair::new("SOL", "USDC");
+        let quote = PartialProvider
+            .get_quote(&pair, Decimal::TEN, Side::Buy)
+            .await
+            .unwrap();
+        assert_eq!(quote.price_impact_pct, Decimal::ZERO);
+        assert_eq!(quote.fee, Decimal::new(125, 4));
+        assert_eq!(quote.out_amount, Decimal::new(49875, 4));
+
+        let bad = TokenPair::new("BAD", "USDC");
+        assert!(PartialProvider
+            .get_quote(&bad, Decimal::TEN, Side::Sell)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_estimate_quote_scales_impact_with_liquidity() {
+        let mut price = PriceData::new(
+            DexType::Orca,
+            TokenPair::new("SOL", "USDC"),
+            Decimal::from(99),
+            Decimal::from(101),
+        );
+        price.liquidity = Some(Decimal::from(10_000));
+
+        // Selling 1 SOL: 99 USDC is 0.99% of the liquidity
+        let sell = estimate_quote(&price, Decimal::ONE, Side::Sell, Decimal::ZERO).unwrap();
+        assert_eq!(sell.price_impact_pct, Decimal::new(99, 2));
+        assert_eq!(sell.out_amount, Decimal::new(980199, 4));
+
+        // Spending 1,010 USDC: 10.1% impact, so 8.99 SOL instead of 10
+        let buy = estimate_quote(&price, Decimal::from(1010), Side::Buy, Decimal::ZERO).unwrap();
+        assert_eq!(buy.price_impact_pct, Decimal::new(101, 1));
+        assert_eq!(buy.out_amount, Decimal::new(899, 2));
+
+        assert!(matches!(
+            estimate_quote(&price, Decimal::from(10_000), Side::Buy, Decimal::ZERO),
+            Err(ArbitrageError::InsufficientLiquidity(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_healthy_providers_routes_around_failed_checks() {
+        let flaky = Arc::new(MockDexProvider::new(DexType::Orca));
+        flaky.set_down(true);
+        let health = ProviderHealth::new(1, Duration::ZERO).with_check_interval(Duration::ZERO);
+        let mut manager = DexManager::new().with_health(Arc::new(health));
+        manager.add_provider(Arc::new(PartialProvider));
+        manager.add_provider(flaky.clone());
+
+        let healthy = manager.healthy_providers().await;
+        assert_eq!(healthy.len(), 1);
+        assert_eq!(healthy[0].dex_type(), DexType::Raydium);
+        assert_eq!(manager.disabled_providers(), vec![DexType::Orca]);
+
+        // The re-probe picks the provider back up once it recovers
+        flaky.set_down(false);
+        assert_eq!(manager.healthy_providers().await.len(), 2);
+        assert!(manager.disabled_providers().is_empty());
+    }
+}
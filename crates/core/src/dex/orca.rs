@@ -1,20 +1,41 @@
 //! Orca DEX Provider
 //!
 //! Orca is a popular AMM DEX on Solana with Whirlpools for concentrated liquidity.
+//! Pools are found through Orca's whirlpool list; given an RPC endpoint, each is
+//! then priced from its on-chain account (see [`crate::parsers::whirlpool`])
+//! rather than the list's cached price.
 
 use async_trait::async_trait;
 use rust_decimal::Decimal;
 use serde::Deserialize;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
 use tokio::sync::mpsc;
+use tracing::warn;
 
-use super::{DexProvider, PriceStream};
-use crate::{ArbitrageError, ArbitrageResult, DexType, PriceData, TokenPair};
+use super::{estimate_quote, tvl_in_quote, DexProvider, PriceStream};
+use crate::http::pool::{create_client, HostRateLimiter, HttpTimeouts};
+use crate::parsers::whirlpool::{parse_whirlpool, WhirlpoolState};
+use crate::types::{is_usd_stablecoin, USD_STABLECOINS};
+use crate::{ArbitrageError, ArbitrageResult, DexType, PriceData, Quote, Side, TokenPair};
 
 const ORCA_WHIRLPOOL_API: &str = "https://api.mainnet.orca.so/v1/whirlpool/list";
 
+/// Synthetic spread around the single reported price (~0.03% each side)
+const SYNTHETIC_SPREAD_BPS: u32 = 6;
+
 /// Orca DEX provider implementation
 pub struct OrcaProvider {
     client: reqwest::Client,
+    /// Per-host budget shared with the other providers
+    rate_limiter: Arc<HostRateLimiter>,
+    /// Per-request timeouts and retries
+    timeouts: HttpTimeouts,
+    /// Reads whirlpool accounts, when set
+    rpc_client: Option<Arc<RpcClient>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -42,12 +63,118 @@ struct OrcaToken {
     decimals: u8,
 }
 
+impl OrcaWhirlpool {
+    fn matches(&self, pair: &TokenPair) -> bool {
+        (self.token_a.symbol == pair.base && self.token_b.symbol == pair.quote)
+            || (self.token_a.symbol == pair.quote && self.token_b.symbol == pair.base)
+    }
+
+    /// Whether the pool's token A is the pair's quote token
+    fn is_inverted(&self, pair: &TokenPair) -> bool {
+        self.token_a.symbol == pair.quote
+    }
+
+    /// Price from the list's cached price, with its USD TVL as liquidity in the
+    /// quote token at `quote_usd` (unknown without it)
+    fn listed_price(
+        &self,
+        pair: &TokenPair,
+        quote_usd: Option<Decimal>,
+    ) -> ArbitrageResult<PriceData> {
+        let mut price = Decimal::try_from(self.price)
+            .map_err(|e| ArbitrageError::PriceFetch(format!("Invalid price: {}", e)))?;
+
+        // Invert if tokens are reversed
+        if self.is_inverted(pair) {
+            price = Decimal::ONE / price;
+        }
+
+        // Whirlpool listings carry a single price; synthesize bid/ask around it
+        let mut price_data =
+            PriceData::from_mid(DexType::Orca, pair.clone(), price, SYNTHETIC_SPREAD_BPS)
+                .with_route(self.address.clone());
+
+        if let Some(vol) = self.volume_24h {
+            price_data.volume_24h = Decimal::try_from(vol).ok();
+        }
+        price_data.liquidity = self
+            .tvl
+            .and_then(|tvl| Decimal::try_from(tvl).ok())
+            .and_then(|tvl| tvl_in_quote(tvl, quote_usd));
+
+        Ok(price_data)
+    }
+
+    /// Price from the pool's account: bid and ask are the current sqrt price
+    /// less and plus the pool's fee, and liquidity is what the active range
+    /// holds, in the quote token. Errors if nothing is active at this price.
+    fn on_chain_price(
+        &self,
+        pair: &TokenPair,
+        state: &WhirlpoolState,
+    ) -> ArbitrageResult<PriceData> {
+        let (decimals_a, decimals_b) = (self.token_a.decimals, self.token_b.decimals);
+        let price_a = state.price(decimals_a, decimals_b)?;
+        let depth_b = state.range_liquidity(decimals_a, decimals_b)?;
+
+        let invalid = || ArbitrageError::PriceFetch(format!("Invalid price for {}", pair));
+        let (mid, liquidity) = if self.is_inverted(pair) {
+            let mid = Decimal::ONE.checked_div(price_a).ok_or_else(invalid)?;
+            (mid, depth_b.checked_div(price_a).ok_or_else(invalid)?)
+        } else {
+            (price_a, depth_b)
+        };
+        let keep = Decimal::ONE - state.fee_fraction();
+        let ask = mid.checked_div(keep).ok_or_else(invalid)?;
+
+        let mut price_data = PriceData::new(DexType::Orca, pair.clone(), mid * keep, ask)
+            .with_route(self.address.clone());
+        price_data.liquidity = Some(liquidity);
+        if let Some(vol) = self.volume_24h {
+            price_data.volume_24h = Decimal::try_from(vol).ok();
+        }
+        Ok(price_data)
+    }
+}
+
 impl OrcaProvider {
+    /// Provider with the default timeouts and a rate limiter of its own
     pub fn new() -> Self {
+        Self::with_http(HttpTimeouts::default(), Arc::default())
+    }
+
+    /// Provider using `timeouts` and sharing `rate_limiter`, both usually
+    /// built from the caller's `Config`
+    pub fn with_http(timeouts: HttpTimeouts, rate_limiter: Arc<HostRateLimiter>) -> Self {
         Self {
-            client: crate::http::pool::create_optimized_client(),
+            client: create_client(&timeouts),
+            rate_limiter,
+            timeouts,
+            rpc_client: None,
         }
     }
+
+    /// Price whirlpools from their on-chain accounts, read from `rpc_url`
+    pub fn with_rpc(mut self, rpc_url: &str) -> Self {
+        self.rpc_client = Some(Arc::new(RpcClient::new_with_commitment(
+            rpc_url.to_string(),
+            CommitmentConfig::confirmed(),
+        )));
+        self
+    }
+
+    async fn fetch_whirlpools(&self) -> ArbitrageResult<OrcaWhirlpoolList> {
+        let request = self
+            .client
+            .get(ORCA_WHIRLPOOL_API)
+            .timeout(self.timeouts.price_fetch);
+        Ok(self
+            .rate_limiter
+            .send(request, self.timeouts.retries)
+            .await?
+            .json()
+            .await?)
+    }
 }
 
 impl Default for OrcaProvider {
@@ -56,6 +183,57 @@ impl Default for OrcaProvider {
     }
 }
 
+impl OrcaWhirlpoolList {
+    /// USD value of one `symbol`, from its deepest pool against a USD
+    /// stablecoin in the list
+    fn usd_price(&self, symbol: &str) -> Option<Decimal> {
+        if is_usd_stablecoin(symbol) {
+            return Some(Decimal::ONE);
+        }
+        self.whirlpools
+            .iter()
+            .filter(|w| {
+                USD_STABLECOINS
+                    .iter()
+                    .any(|usd| w.matches(&TokenPair::new(symbol, *usd)))
+            })
+            .max_by(|a, b| a.tvl.unwrap_or(0.0).total_cmp(&b.tvl.unwrap_or(0.0)))
+            .and_then(|w| {
+                let price = Decimal::try_from(w.price).ok()?;
+                if w.token_a.symbol == symbol {
+                    Some(price)
+                } else {
+                    Decimal::ONE.checked_div(price)
+                }
+            })
+    }
+}
+
+fn find_whirlpool<'a>(
+    list: &'a OrcaWhirlpoolList,
+    pair: &TokenPair,
+) -> ArbitrageResult<&'a OrcaWhirlpool> {
+    list.whirlpools
+        .iter()
+        .find(|w| w.matches(pair))
+        .ok_or_else(|| ArbitrageError::PriceFetch(format!("Pair {} not found on Orca", pair)))
+}
+
+/// Read and decode a whirlpool's account
+async fn fetch_state(
+    rpc_client: &RpcClient,
+    whirlpool: &OrcaWhirlpool,
+) -> ArbitrageResult<WhirlpoolState> {
+    let address = Pubkey::from_str(&whirlpool.address).map_err(|e| {
+        ArbitrageError::PriceFetch(format!("Invalid whirlpool {}: {}", whirlpool.address, e))
+    })?;
+    let data = rpc_client
+        .get_account_data(&address)
+        .await
+        .map_err(|e| ArbitrageError::RpcError(e.to_string()))?;
+    parse_whirlpool(&data)
+}
+
 #[async_trait]
 impl DexProvider for OrcaProvider {
     fn dex_type(&self) -> DexType {
@@ -63,86 +241,73 @@ impl DexProvider for OrcaProvider {
     }
 
     async fn get_price(&self, pair: &TokenPair) -> ArbitrageResult<PriceData> {
-        let response: OrcaWhirlpoolList = self
-            .client
-            .get(ORCA_WHIRLPOOL_API)
-            .send()
-            .await?
-            .json()
-            .await?;
-
-        let whirlpool = response
-            .whirlpools
-            .iter()
-            .find(|w| {
-                (w.token_a.symbol == pair.base && w.token_b.symbol == pair.quote)
-                    || (w.token_a.symbol == pair.quote && w.token_b.symbol == pair.base)
-            })
-            .ok_or_else(|| {
-                ArbitrageError::PriceFetch(format!("Pair {} not found on Orca", pair))
-            })?;
-
-        let mut price = Decimal::try_from(whirlpool.price)
-            .map_err(|e| ArbitrageError::PriceFetch(format!("Invalid price: {}", e)))?;
+        let response = self.fetch_whirlpools().await?;
+        let whirlpool = find_whirlpool(&response, pair)?;
 
-        // Invert if tokens are reversed
-        if whirlpool.token_a.symbol == pair.quote {
-            price = Decimal::ONE / price;
+        let Some(rpc_client) = &self.rpc_client else {
+            return whirlpool.listed_price(pair, response.usd_price(&pair.quote));
+        };
+        match fetch_state(rpc_client, whirlpool).await {
+            Ok(state) => whirlpool.on_chain_price(pair, &state),
+            Err(e @ ArbitrageError::RpcError(_)) => {
+                warn!(
+                    "Whirlpool {} unavailable over RPC, using listed price: {}",
+                    whirlpool.address, e
+                );
+                whirlpool.listed_price(pair, response.usd_price(&pair.quote))
+            }
+            Err(e) => Err(e),
         }
+    }
 
-        // Orca Whirlpools have variable spreads, estimate ~0.3%
-        let spread = price * Decimal::new(30, 5); // 0.03% each side
-        let bid = price - spread;
-        let ask = price + spread;
-
-        let mut price_data = PriceData::new(DexType::Orca, pair.clone(), bid, ask);
-
-        if let Some(vol) = whirlpool.volume_24h {
-            price_data.volume_24h = Some(Decimal::try_from(vol).unwrap_or_default());
-        }
-        if let Some(tvl) = whirlpool.tvl {
-            price_data.liquidity = Some(Decimal::try_from(tvl).unwrap_or_default());
-        }
+    /// Quotes along the whirlpool's active liquidity when reading accounts over
+    /// RPC; otherwise a linear estimate from the listed price and TVL.
+    async fn get_quote(
+        &self,
+        pair: &TokenPair,
+        amount_in: Decimal,
+        side: Side,
+    ) -> ArbitrageResult<Quote> {
+        let Some(rpc_client) = &self.rpc_client else {
+            let price = self.get_price(pair).await?;
+            return estimate_quote(&price, amount_in, side, self.fee_percentage());
+        };
+        let response = self.fetch_whirlpools().await?;
+        let whirlpool = find_whirlpool(&response, pair)?;
+        let state = fetch_state(rpc_client, whirlpool).await?;
 
-        Ok(price_data)
+        // Selling spends the base token, which is token A unless the pool is inverted
+        let a_to_b = (side == Side::Sell) != whirlpool.is_inverted(pair);
+        state.quote(
+            amount_in,
+            a_to_b,
+            whirlpool.token_a.decimals,
+            whirlpool.token_b.decimals,
+        )
     }
 
     async fn subscribe(&self, pairs: Vec<TokenPair>) -> ArbitrageResult<PriceStream> {
         let (tx, rx) = mpsc::channel(100);
         let client = self.client.clone();
+        let request_timeout = self.timeouts.price_fetch;
 
         tokio::spawn(async move {
             loop {
-                if let Ok(response) = client.get(ORCA_WHIRLPOOL_API).send().await {
+                if let Ok(response) = client
+                    .get(ORCA_WHIRLPOOL_API)
+                    .timeout(request_timeout)
+                    .send()
+                    .await
+                {
                     if let Ok(data) = response.json::<OrcaWhirlpoolList>().await {
                         for pair in &pairs {
-                            if let Some(whirlpool) = data.whirlpools.iter().find(|w| {
-                                (w.token_a.symbol == pair.base && w.token_b.symbol == pair.quote)
-                                    || (w.token_a.symbol == pair.quote
-                                        && w.token_b.symbol == pair.base)
-                            }) {
-                                if let Ok(mut price) = Decimal::try_from(whirlpool.price) {
-                                    if whirlpool.token_a.symbol == pair.quote {
-                                        price = Decimal::ONE / price;
-                                    }
-
-                                    let spread = price * Decimal::new(30, 5);
-                                    let bid = price - spread;
-                                    let ask = price + spread;
-
-                                    let mut price_data =
-                                        PriceData::new(DexType::Orca, pair.clone(), bid, ask);
-
-                                    if let Some(vol) = whirlpool.volume_24h {
-                                        price_data.volume_24h = Decimal::try_from(vol).ok();
-                                    }
-                                    if let Some(tvl) = whirlpool.tvl {
-                                        price_data.liquidity = Decimal::try_from(tvl).ok();
-                                    }
-
-                                    if tx.send(price_data).await.is_err() {
-                                        return;
-                                    }
+                            let Ok(whirlpool) = find_whirlpool(&data, pair) else {
+                                continue;
+                            };
+                            let quote_usd = data.usd_price(&pair.quote);
+                            if let Ok(price_data) = whirlpool.listed_price(pair, quote_usd) {
+                                if tx.send(price_data).await.is_err() {
+                                    return;
                                 }
                             }
                         }
@@ -158,7 +323,46 @@ impl DexProvider for OrcaProvider {
     }
 
     async fn health_check(&self) -> ArbitrageResult<bool> {
-        let response = self.client.get(ORCA_WHIRLPOOL_API).send().await?;
+        let response = self
+            .client
+            .get(ORCA_WHIRLPOOL_API)
+            .timeout(self.timeouts.price_fetch)
+            .send()
+            .await?;
         Ok(response.status().is_success())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listed_tvl_is_converted_to_the_quote_token() {
+        let token =
+            |symbol: &str| serde_json::json!({"mint": symbol, "symbol": symbol, "decimals": 6});
+        let list: OrcaWhirlpoolList = serde_json::from_value(serde_json::json!({
+            "whirlpools": [
+                {"address": "A", "tokenA": token("SOL"), "tokenB": token("USDC"),
+                 "price": 150.0, "tvl": 3_000_000.0},
+                {"address": "B", "tokenA": token("ORCA"), "tokenB": token("SOL"),
+                 "price": 0.02, "tvl": 300_000.0},
+            ]
+        }))
+        .unwrap();
+
+        // $300k in an ORCA/SOL pool is 2,000 SOL of depth, not 300,000
+        let pair = TokenPair::new("ORCA", "SOL");
+        let pool = find_whirlpool(&list, &pair).unwrap();
+        let price = pool.listed_price(&pair, list.usd_price("SOL")).unwrap();
+        assert_eq!(price.liquidity, Some(Decimal::from(2_000)));
+        // Unknown rather than overstated without a USD price for SOL
+        assert_eq!(pool.listed_price(&pair, None).unwrap().liquidity, None);
+
+        // USD TVL is already in USDC
+        let pair = TokenPair::new("SOL", "USDC");
+        let pool = find_whirlpool(&list, &pair).unwrap();
+        let price = pool.listed_price(&pair, list.usd_price("USDC")).unwrap();
+        assert_eq!(price.liquidity, Some(Decimal::from(3_000_000)));
+    }
+}
@@ -6,16 +6,26 @@
 use async_trait::async_trait;
 use rust_decimal::Decimal;
 use serde::Deserialize;
+use std::sync::Arc;
 use tokio::sync::mpsc;
 
-use super::{DexProvider, PriceStream};
+use super::{tvl_in_quote, DexProvider, PriceStream};
+use crate::http::pool::{create_client, HostRateLimiter, HttpTimeouts};
+use crate::types::{is_usd_stablecoin, USD_STABLECOINS};
 use crate::{ArbitrageError, ArbitrageResult, DexType, PriceData, TokenPair};
 
 const RAYDIUM_API: &str = "https://api.raydium.io/v2/main/pairs";
 
+/// Synthetic spread around the single reported price (~0.025% each side)
+const SYNTHETIC_SPREAD_BPS: u32 = 5;
+
 /// Raydium DEX provider implementation
 pub struct RaydiumProvider {
     client: reqwest::Client,
+    /// Per-host budget shared with the other providers
+    rate_limiter: Arc<HostRateLimiter>,
+    /// Per-request timeouts and retries
+    timeouts: HttpTimeouts,
 }
 
 #[allow(dead_code)]
@@ -33,9 +43,18 @@ struct RaydiumPair {
 }
 
 impl RaydiumProvider {
+    /// Provider with the default timeouts and a rate limiter of its own
     pub fn new() -> Self {
+        Self::with_http(HttpTimeouts::default(), Arc::default())
+    }
+
+    /// Provider using `timeouts` and sharing `rate_limiter`, both usually
+    /// built from the caller's `Config`
+    pub fn with_http(timeouts: HttpTimeouts, rate_limiter: Arc<HostRateLimiter>) -> Self {
         Self {
-            client: crate::http::pool::create_optimized_client(),
+            client: create_client(&timeouts),
+            rate_limiter,
+            timeouts,
         }
     }
 
@@ -51,6 +70,32 @@ impl RaydiumProvider {
     }
 }
 
+impl RaydiumPair {
+    /// The pair's USD TVL in its quote token, at `quote_usd` per quote token
+    fn liquidity_in_quote(&self, quote_usd: Option<Decimal>) -> Option<Decimal> {
+        tvl_in_quote(Decimal::try_from(self.liquidity).ok()?, quote_usd)
+    }
+}
+
+/// USD value of one `symbol`, from its pool against a USD stablecoin in `pairs`
+fn usd_price(pairs: &[RaydiumPair], symbol: &str) -> Option<Decimal> {
+    if is_usd_stablecoin(symbol) {
+        return Some(Decimal::ONE);
+    }
+    USD_STABLECOINS.iter().find_map(|usd| {
+        pairs.iter().find_map(|p| {
+            let price = Decimal::try_from(p.price).ok()?;
+            if p.name == format!("{}-{}", symbol, usd) {
+                Some(price)
+            } else if p.name == format!("{}-{}", usd, symbol) {
+                Decimal::ONE.checked_div(price)
+            } else {
+                None
+            }
+        })
+    })
+}
+
 impl Default for RaydiumProvider {
     fn default() -> Self {
         Self::new()
@@ -64,7 +109,16 @@ impl DexProvider for RaydiumProvider {
     }
 
     async fn get_price(&self, pair: &TokenPair) -> ArbitrageResult<PriceData> {
-        let pairs: Vec<RaydiumPair> = self.client.get(RAYDIUM_API).send().await?.json().await?;
+        let request = self
+            .client
+            .get(RAYDIUM_API)
+            .timeout(self.timeouts.price_fetch);
+        let pairs: Vec<RaydiumPair> = self
+            .rate_limiter
+            .send(request, self.timeouts.retries)
+            .await?
+            .json()
+            .await?;
 
         let target_name = format!("{}-{}", pair.base, pair.quote);
         let reverse_name = format!("{}-{}", pair.quote, pair.base);
@@ -84,15 +138,14 @@ impl DexProvider for RaydiumProvider {
             price = Decimal::ONE / price;
         }
 
-        // Raydium AMM typically has ~0.25% spread
-        let spread = price * Decimal::new(25, 5); // 0.025% each side
-        let bid = price - spread;
-        let ask = price + spread;
-
-        let mut price_data = PriceData::new(DexType::Raydium, pair.clone(), bid, ask);
+        // Raydium's API reports a single pool price; synthesize bid/ask around it
+        let mut price_data =
+            PriceData::from_mid(DexType::Raydium, pair.clone(), price, SYNTHETIC_SPREAD_BPS)
+                .with_route(raydium_pair.amm_id.clone());
         price_data.volume_24h =
             Some(Decimal::try_from(raydium_pair.volume_24h).unwrap_or_default());
-        price_data.liquidity = Some(Decimal::try_from(raydium_pair.liquidity).unwrap_or_default());
+        // The API reports USD TVL; liquidity is kept in the quote token
+        price_data.liquidity = raydium_pair.liquidity_in_quote(usd_price(&pairs, &pair.quote));
 
         Ok(price_data)
     }
@@ -100,10 +153,16 @@ impl DexProvider for RaydiumProvider {
     async fn subscribe(&self, pairs: Vec<TokenPair>) -> ArbitrageResult<PriceStream> {
         let (tx, rx) = mpsc::channel(100);
         let client = self.client.clone();
+        let request_timeout = self.timeouts.price_fetch;
 
         tokio::spawn(async move {
             loop {
-                if let Ok(response) = client.get(RAYDIUM_API).send().await {
+                if let Ok(response) = client
+                    .get(RAYDIUM_API)
+                    .timeout(request_timeout)
+                    .send()
+                    .await
+                {
                     if let Ok(all_pairs) = response.json::<Vec<RaydiumPair>>().await {
                         for pair in &pairs {
                             let target_name = format!("{}-{}", pair.base, pair.quote);
@@ -118,16 +177,17 @@ impl DexProvider for RaydiumProvider {
                                         price = Decimal::ONE / price;
                                     }
 
-                                    let spread = price * Decimal::new(25, 5);
-                                    let bid = price - spread;
-                                    let ask = price + spread;
-
-                                    let mut price_data =
-                                        PriceData::new(DexType::Raydium, pair.clone(), bid, ask);
+                                    let mut price_data = PriceData::from_mid(
+                                        DexType::Raydium,
+                                        pair.clone(),
+                                        price,
+                                        SYNTHETIC_SPREAD_BPS,
+                                    )
+                                    .with_route(raydium_pair.amm_id.clone());
                                     price_data.volume_24h =
                                         Decimal::try_from(raydium_pair.volume_24h).ok();
-                                    price_data.liquidity =
-                                        Decimal::try_from(raydium_pair.liquidity).ok();
+                                    price_data.liquidity = raydium_pair
+                                        .liquidity_in_quote(usd_price(&all_pairs, &pair.quote));
 
                                     if tx.send(price_data).await.is_err() {
                                         return;
@@ -147,7 +207,12 @@ impl DexProvider for RaydiumProvider {
     }
 
     async fn health_check(&self) -> ArbitrageResult<bool> {
-        let response = self.client.get(RAYDIUM_API).send().await?;
+        let response = self
+            .client
+            .get(RAYDIUM_API)
+            .timeout(self.timeouts.price_fetch)
+            .send()
+            .await?;
         Ok(response.status().is_success())
     }
 }
@@ -42,6 +42,9 @@ pub enum ArbitrageError {
     #[error("Opportunity validation failed: {0}")]
     InvalidOpportunity(String),
 
+    #[error("Arithmetic overflow: {0}")]
+    ArithmeticOverflow(String),
+
     // ── Execution Errors ────────────────────────────────────────────
     #[error("Transaction simulation failed: {0}")]
     SimulationFailed(String),
@@ -98,6 +101,9 @@ pub enum ArbitrageError {
     #[error("Jupiter quote failed: {0}")]
     JupiterQuoteFailed(String),
 
+    #[error("Jupiter request failed: {0}")]
+    Jupiter(#[from] JupiterError),
+
     // ── WebSocket Errors ────────────────────────────────────────────
     #[error("WebSocket error: {0}")]
     WebSocket(String),
@@ -142,6 +148,73 @@ pub enum ArbitrageError {
     Unknown(String),
 }
 
+/// Why a Jupiter API request was refused, parsed from its error response
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum JupiterError {
+    #[error("no route found: {0}")]
+    NoRoute(String),
+
+    #[error("rate limited")]
+    RateLimited,
+
+    #[error("invalid or untradable mint: {0}")]
+    InvalidMint(String),
+
+    #[error("server error (HTTP {0})")]
+    Server(u16),
+
+    #[error("{0}")]
+    Other(String),
+}
+
+impl JupiterError {
+    /// Classifies a failed response from its HTTP status and body.
+    ///
+    /// Jupiter answers with `{"error": "...", "errorCode": "..."}`; the code is
+    /// matched when present, the message otherwise. Bodies that aren't JSON
+    /// (proxies, load balancers) are classified by status alone.
+    pub fn from_response(status: u16, body: &str) -> Self {
+        if status == 429 {
+            return JupiterError::RateLimited;
+        }
+        if status >= 500 {
+            return JupiterError::Server(status);
+        }
+
+        let json: Option<serde_json::Value> = serde_json::from_str(body).ok();
+        let field = |name: &str| {
+            json.as_ref()
+                .and_then(|v| v.get(name))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+        let code = field("errorCode").unwrap_or_default();
+        let message = field("error")
+            .or_else(|| field("message"))
+            .unwrap_or_else(|| body.trim().to_string());
+        let lower = message.to_lowercase();
+
+        match code.as_str() {
+            "COULD_NOT_FIND_ANY_ROUTE" | "NO_ROUTES_FOUND" => JupiterError::NoRoute(message),
+            "TOKEN_NOT_TRADABLE" | "INVALID_MINT" => JupiterError::InvalidMint(message),
+            "RATE_LIMITED" => JupiterError::RateLimited,
+            _ if lower.contains("route") => JupiterError::NoRoute(message),
+            _ if lower.contains("mint") || lower.contains("not tradable") => {
+                JupiterError::InvalidMint(message)
+            }
+            _ if lower.contains("rate limit") || lower.contains("too many requests") => {
+                JupiterError::RateLimited
+            }
+            _ => JupiterError::Other(message),
+        }
+    }
+
+    /// Whether the same request may succeed later
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, JupiterError::RateLimited | JupiterError::Server(_))
+    }
+}
+
 // ── Severity & Classification ───────────────────────────────────────
 
 /// Error severity level for logging and alerting
@@ -167,6 +240,7 @@ impl ArbitrageError {
                 | ArbitrageError::ConfirmationTimeout { .. }
                 | ArbitrageError::RateLimited(_)
                 | ArbitrageError::PriceFetch(_)
+                | ArbitrageError::Jupiter(JupiterError::RateLimited | JupiterError::Server(_))
         )
     }
 
@@ -350,6 +424,50 @@ mod tests {
         assert!(!ArbitrageError::Config("missing".into()).is_retryable());
     }
 
+    #[test]
+    fn test_jupiter_error_responses() {
+        let parse = JupiterError::from_response;
+
+        assert_eq!(
+            parse(
+                400,
+                r#"{"error":"Could not find any route","errorCode":"COULD_NOT_FIND_ANY_ROUTE"}"#
+            ),
+            JupiterError::NoRoute("Could not find any route".into())
+        );
+        assert!(matches!(
+            parse(
+                400,
+                r#"{"error":"The token 7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU is not tradable","errorCode":"TOKEN_NOT_TRADABLE"}"#
+            ),
+            JupiterError::InvalidMint(_)
+        ));
+        assert!(matches!(
+            parse(
+                400,
+                r#"{"error":"Query parameter inputMint cannot be parsed: Invalid"}"#
+            ),
+            JupiterError::InvalidMint(_)
+        ));
+        assert_eq!(
+            parse(429, r#"{"message":"Too many requests"}"#),
+            JupiterError::RateLimited
+        );
+        assert_eq!(
+            parse(502, "<html>Bad Gateway</html>"),
+            JupiterError::Server(502)
+        );
+        assert_eq!(
+            parse(400, r#"{"error":"amount must be positive"}"#),
+            JupiterError::Other("amount must be positive".into())
+        );
+
+        // Only the transient ones are worth retrying
+        assert!(ArbitrageError::from(JupiterError::RateLimited).is_retryable());
+        assert!(ArbitrageError::from(JupiterError::Server(503)).is_retryable());
+        assert!(!ArbitrageError::from(JupiterError::NoRoute("none".into())).is_retryable());
+    }
+
     #[test]
     fn test_critical_errors() {
         assert!(ArbitrageError::DailyLossLimitReached {
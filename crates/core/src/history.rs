@@ -1,4 +1,5 @@
-use crate::types::ArbitrageOpportunity;
+use crate::math::DisplayRounding;
+use crate::types::{ArbitrageOpportunity, LatencyBreakdown};
 use chrono::Utc;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
@@ -6,7 +7,11 @@ use std::fs::{self, OpenOptions};
 use std::io::Write;
 use std::path::Path;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Decimal places kept for amounts in a quote token: a lamport, the finest
+/// unit of the tokens traded
+const QUOTE_AMOUNT_DP: u32 = 9;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TradeRecord {
     pub timestamp: String,
     pub session_id: String,
@@ -15,16 +20,36 @@ pub struct TradeRecord {
     pub buy_dex: String,
     pub sell_dex: String,
     pub size_usd: String,
-    pub profit_usd: String,
+    /// Profit in USD; absent when the pair isn't quoted in a USD stablecoin and
+    /// no reference price for its quote token was available
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profit_usd: Option<String>,
+    /// Profit in the pair's quote token (absent in older records, whose
+    /// `profit_usd` holds it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profit_quote: Option<String>,
     pub profit_pct: String,
     pub tx_signature: Option<String>,
     pub success: bool,
     pub error: Option<String>,
+    // Optional latency breakdown (absent in older records)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detected_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submit_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirm_ms: Option<u64>,
 }
 
+#[derive(Clone)]
 pub struct HistoryRecorder {
     file_path: String,
     session_id: String,
+    rounding: DisplayRounding,
 }
 
 impl HistoryRecorder {
@@ -37,21 +62,73 @@ impl HistoryRecorder {
         Self {
             file_path: file_path.to_string(),
             session_id: session_id.to_string(),
+            rounding: DisplayRounding::default(),
         }
     }
 
+    /// Override how amounts are rounded when written to the history file
+    pub fn with_rounding(mut self, rounding: DisplayRounding) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Record a trade whose `profit` is in the pair's quote token. Its USD
+    /// value is only recorded for stablecoin-quoted pairs; use `build_record`
+    /// to convert other quotes at a reference price.
     #[allow(clippy::too_many_arguments)]
     pub fn record_trade(
         &self,
         opp: &ArbitrageOpportunity,
         size_usd: Decimal,
-        profit_usd: Decimal,
+        profit: Decimal,
         success: bool,
         tx_sig: Option<String>,
         error: Option<String>,
         is_dry_run: bool,
     ) {
-        let record = TradeRecord {
+        self.record_trade_with_latency(
+            opp, size_usd, profit, success, tx_sig, error, is_dry_run, None,
+        );
+    }
+
+    /// Same as `record_trade`, additionally recording a per-stage latency breakdown
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_trade_with_latency(
+        &self,
+        opp: &ArbitrageOpportunity,
+        size_usd: Decimal,
+        profit: Decimal,
+        success: bool,
+        tx_sig: Option<String>,
+        error: Option<String>,
+        is_dry_run: bool,
+        latency: Option<&LatencyBreakdown>,
+    ) {
+        let record = self.build_record(
+            opp, size_usd, profit, None, success, tx_sig, error, is_dry_run, latency,
+        );
+        self.append(&record);
+    }
+
+    /// The record `record_trade_with_latency` would write, without writing it.
+    ///
+    /// `profit` is in the pair's quote token; `quote_usd_price` (the USD value
+    /// of one quote token) converts it to USD for pairs not quoted in USD.
+    #[allow(clippy::too_many_arguments)]
+    pub fn build_record(
+        &self,
+        opp: &ArbitrageOpportunity,
+        size_usd: Decimal,
+        profit: Decimal,
+        quote_usd_price: Option<Decimal>,
+        success: bool,
+        tx_sig: Option<String>,
+        error: Option<String>,
+        is_dry_run: bool,
+        latency: Option<&LatencyBreakdown>,
+    ) -> TradeRecord {
+        let latency = latency.cloned().unwrap_or_default();
+        TradeRecord {
             timestamp: Utc::now().to_rfc3339(),
             session_id: self.session_id.clone(),
             trade_type: if is_dry_run {
@@ -62,15 +139,26 @@ impl HistoryRecorder {
             pair: opp.pair.symbol(),
             buy_dex: opp.buy_dex.display_name().to_string(),
             sell_dex: opp.sell_dex.display_name().to_string(),
-            size_usd: size_usd.round_dp(2).to_string(),
-            profit_usd: profit_usd.round_dp(4).to_string(),
-            profit_pct: opp.net_profit_pct.round_dp(2).to_string(),
+            size_usd: self.rounding.usd(size_usd).to_string(),
+            profit_usd: opp
+                .quote_to_usd(profit, quote_usd_price)
+                .map(|usd| self.rounding.profit_usd(usd).to_string()),
+            profit_quote: Some(profit.round_dp(QUOTE_AMOUNT_DP).normalize().to_string()),
+            profit_pct: self.rounding.pct(opp.net_profit_pct).to_string(),
             tx_signature: tx_sig,
             success,
             error,
-        };
+            detected_at: latency.detected_at.map(|t| t.to_rfc3339()),
+            quote_ms: latency.quote_ms,
+            build_ms: latency.build_ms,
+            submit_ms: latency.submit_ms,
+            confirm_ms: latency.confirm_ms,
+        }
+    }
 
-        match serde_json::to_string(&record) {
+    /// Append `record` to the history file as one JSON line
+    pub fn append(&self, record: &TradeRecord) {
+        match serde_json::to_string(record) {
             Ok(json) => {
                 let open_result = OpenOptions::new()
                     .create(true)
@@ -91,6 +179,126 @@ impl HistoryRecorder {
     }
 }
 
+/// A history line with its amount fields parsed back into decimals
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub record: TradeRecord,
+    pub size_usd: Decimal,
+    /// Profit in the pair's quote token
+    pub profit_quote: Decimal,
+    /// Profit in USD, when the record has it
+    pub profit_usd: Option<Decimal>,
+    pub profit_pct: Decimal,
+}
+
+impl HistoryEntry {
+    fn parse(record: TradeRecord) -> Option<Self> {
+        use std::str::FromStr;
+        let profit_usd = match &record.profit_usd {
+            Some(usd) => Some(Decimal::from_str(usd).ok()?),
+            None => None,
+        };
+        let profit_quote = record
+            .profit_quote
+            .as_deref()
+            .or(record.profit_usd.as_deref())?;
+        Some(Self {
+            size_usd: Decimal::from_str(&record.size_usd).ok()?,
+            profit_quote: Decimal::from_str(profit_quote).ok()?,
+            profit_usd,
+            profit_pct: Decimal::from_str(&record.profit_pct).ok()?,
+            record,
+        })
+    }
+}
+
+/// Queries over a JSONL history file written by `HistoryRecorder`.
+///
+/// Lines that aren't valid records, or whose amounts don't parse as decimals,
+/// are skipped and counted in `skipped_lines`; blank lines are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryReader {
+    entries: Vec<HistoryEntry>,
+    skipped_lines: usize,
+}
+
+impl HistoryReader {
+    /// Load every record in `file_path`
+    pub fn load(file_path: &str) -> Result<Self, std::io::Error> {
+        use std::io::BufRead;
+        let reader = std::io::BufReader::new(fs::File::open(file_path)?);
+
+        let mut history = Self::default();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<TradeRecord>(&line)
+                .ok()
+                .and_then(HistoryEntry::parse)
+            {
+                Some(entry) => history.entries.push(entry),
+                None => history.skipped_lines += 1,
+            }
+        }
+        Ok(history)
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Lines that could not be parsed (corrupt JSON or amounts)
+    pub fn skipped_lines(&self) -> usize {
+        self.skipped_lines
+    }
+
+    /// Trades recorded in session `id`
+    pub fn filter_by_session(&self, id: &str) -> Self {
+        self.filtered(|record| record.session_id == id)
+    }
+
+    /// Trades on `pair` (e.g. "SOL/USDC")
+    pub fn filter_by_pair(&self, pair: &str) -> Self {
+        self.filtered(|record| record.pair == pair)
+    }
+
+    fn filtered(&self, keep: impl Fn(&TradeRecord) -> bool) -> Self {
+        Self {
+            entries: self
+                .entries
+                .iter()
+                .filter(|entry| keep(&entry.record))
+                .cloned()
+                .collect(),
+            skipped_lines: self.skipped_lines,
+        }
+    }
+
+    /// Sum of recorded USD profit (failed trades are recorded with zero profit).
+    /// Trades recorded without a USD value are left out.
+    pub fn total_pnl(&self) -> Decimal {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.profit_usd)
+            .sum()
+    }
+
+    /// Fraction (0.0-1.0) of trades that succeeded with a positive profit
+    pub fn win_rate(&self) -> f64 {
+        if self.entries.is_empty() {
+            return 0.0;
+        }
+        let wins = self
+            .entries
+            .iter()
+            .filter(|entry| entry.record.success && entry.profit_quote > Decimal::ZERO)
+            .count();
+        wins as f64 / self.entries.len() as f64
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AnalysisReport {
     pub total_trades: usize,
@@ -108,6 +316,14 @@ pub struct HistoryAnalyzer;
 
 impl HistoryAnalyzer {
     pub fn analyze(file_path: &str) -> Result<AnalysisReport, std::io::Error> {
+        Self::analyze_with_rounding(file_path, DisplayRounding::default())
+    }
+
+    /// Same as `analyze`, rounding report totals with the given policy
+    pub fn analyze_with_rounding(
+        file_path: &str,
+        rounding: DisplayRounding,
+    ) -> Result<AnalysisReport, std::io::Error> {
         let path = Path::new(file_path);
         if !path.exists() {
             return Ok(AnalysisReport {
@@ -165,7 +381,9 @@ impl HistoryAnalyzer {
 
         use std::str::FromStr;
         for trade in &trades {
-            if let Ok(profit) = Decimal::from_str(&trade.profit_usd) {
+            // Trades without a USD value can't be summed with the rest
+            let profit_usd = trade.profit_usd.as_deref().map(Decimal::from_str);
+            if let Some(Ok(profit)) = profit_usd {
                 total_profit += profit;
                 *pair_profit.entry(trade.pair.clone()).or_default() += profit;
 
@@ -202,12 +420,12 @@ impl HistoryAnalyzer {
             total_trades,
             successful_trades,
             success_rate,
-            total_profit_usd: total_profit.round_dp(2).to_string(),
-            avg_profit_usd: avg_profit.round_dp(4).to_string(),
+            total_profit_usd: rounding.usd(total_profit).to_string(),
+            avg_profit_usd: rounding.profit_usd(avg_profit).to_string(),
             best_pair,
             best_route,
             worst_route,
-            total_volume_usd: total_volume.round_dp(2).to_string(),
+            total_volume_usd: rounding.usd(total_volume).to_string(),
         })
     }
 }
@@ -1,13 +1,319 @@
-use reqwest::{Client, ClientBuilder};
-use std::time::Duration;
+use rand::Rng;
+use reqwest::{Client, ClientBuilder, Request, RequestBuilder, Response, StatusCode, Url};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-pub fn create_optimized_client() -> Client {
+use crate::config::Config;
+use crate::rate_limiter::RateLimiter;
+use crate::{ArbitrageError, ArbitrageResult};
+
+/// How long a host that answered 429 is left alone before it is called again
+const RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(1);
+/// Delay before the first retry; it doubles with each attempt after
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(50);
+
+/// Per-operation HTTP timeouts, applied at the request level, plus the
+/// connection settings shared by a provider's client.
+///
+/// Price polls should fail fast, while Jupiter quote and swap building
+/// legitimately take longer, so a single client-wide timeout fits neither.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpTimeouts {
+    /// Timeout for DEX price fetches and health checks
+    pub price_fetch: Duration,
+    /// Timeout for aggregator quote requests
+    pub quote: Duration,
+    /// Timeout for swap / swap-instructions building
+    pub swap_build: Duration,
+    /// Time allowed to establish a connection
+    pub connect: Duration,
+    /// Extra attempts for idempotent GETs that fail in transit or with a 5xx
+    pub retries: u32,
+}
+
+impl HttpTimeouts {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            price_fetch: Duration::from_millis(config.price_fetch_timeout_ms),
+            quote: Duration::from_millis(config.quote_timeout_ms),
+            swap_build: Duration::from_millis(config.swap_build_timeout_ms),
+            connect: Duration::from_millis(config.http_connect_timeout_ms),
+            retries: config.http_retries,
+        }
+    }
+}
+
+impl Default for HttpTimeouts {
+    fn default() -> Self {
+        Self::from_config(&Config::default())
+    }
+}
+
+/// Pooled client using `timeouts`' connect timeout. It sets no overall
+/// timeout: each request sets its own from `timeouts`.
+pub fn create_client(timeouts: &HttpTimeouts) -> Client {
     ClientBuilder::new()
         .pool_max_idle_per_host(50) // Keep connections alive
         .pool_idle_timeout(Duration::from_secs(90))
         .tcp_keepalive(Duration::from_secs(60))
         .tcp_nodelay(true) // Disable Nagle's algorithm
-        .timeout(Duration::from_millis(500))
+        .connect_timeout(timeouts.connect)
         .build()
         .expect("Failed to create HTTP client")
 }
+
+/// Send an idempotent request, retrying up to `retries` times when it fails in
+/// transit or the server answers 5xx. Retries back off exponentially with
+/// jitter so providers failing together don't retry in lockstep.
+pub async fn send_with_retry(request: RequestBuilder, retries: u32) -> reqwest::Result<Response> {
+    let (client, request) = request.build_split();
+    execute_with_retry(&client, request?, retries).await
+}
+
+async fn execute_with_retry(
+    client: &Client,
+    request: Request,
+    retries: u32,
+) -> reqwest::Result<Response> {
+    let mut attempt = 0;
+    loop {
+        // Streaming bodies can't be replayed, so those get a single attempt
+        let Some(retry) = request.try_clone().filter(|_| attempt < retries) else {
+            return client.execute(request).await;
+        };
+        let failure = match client.execute(retry).await {
+            Ok(response) if !response.status().is_server_error() => return Ok(response),
+            Ok(response) => response.status().to_string(),
+            Err(e) => e.to_string(),
+        };
+        tracing::debug!(url = %request.url(), attempt, failure, "Retrying request");
+        tokio::time::sleep(retry_delay(attempt)).await;
+        attempt += 1;
+    }
+}
+
+/// Exponential backoff from [`RETRY_BASE_DELAY`] plus up to 50% jitter
+fn retry_delay(attempt: u32) -> Duration {
+    let delay = RETRY_BASE_DELAY * 2u32.saturating_pow(attempt.min(6));
+    delay + delay.mul_f64(rand::thread_rng().gen_range(0.0..0.5))
+}
+
+/// Per-host request budgets for outbound provider calls.
+///
+/// Price fetches fan out to every provider each tick, so public endpoints see
+/// bursts well beyond their limits. Each host gets its own requests-per-second
+/// budget; a call over budget is skipped for the tick rather than queued, and a
+/// host that answers 429 is left alone for a short cooldown. Both surface as
+/// [`ArbitrageError::RateLimited`].
+#[derive(Debug)]
+pub struct HostRateLimiter {
+    /// Budget for hosts without their own limit (0 = unlimited)
+    requests_per_second: usize,
+    host_limits: HashMap<String, usize>,
+    hosts: Mutex<HashMap<String, HostState>>,
+}
+
+#[derive(Debug)]
+struct HostState {
+    limiter: Arc<RateLimiter>,
+    cooldown_until: Option<Instant>,
+}
+
+impl HostRateLimiter {
+    /// Allow every host `requests_per_second` (0 = unlimited)
+    pub fn new(requests_per_second: usize) -> Self {
+        Self {
+            requests_per_second,
+            host_limits: HashMap::new(),
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.provider_requests_per_second)
+    }
+
+    /// Give `host` its own budget instead of the default
+    pub fn with_host_limit(mut self, host: impl Into<String>, requests_per_second: usize) -> Self {
+        self.host_limits.insert(host.into(), requests_per_second);
+        self
+    }
+
+    /// Take a request slot for `url`'s host, or fail if the host is over budget
+    /// or cooling down after a 429
+    pub async fn check(&self, url: &Url) -> ArbitrageResult<()> {
+        let host = url.host_str().unwrap_or_default();
+        let limiter = {
+            let mut hosts = self.hosts.lock().unwrap();
+            let state = hosts.entry(host.to_string()).or_insert_with(|| HostState {
+                limiter: Arc::new(RateLimiter::per_second(self.limit_for(host))),
+                cooldown_until: None,
+            });
+            if state
+                .cooldown_until
+                .is_some_and(|until| Instant::now() < until)
+            {
+                tracing::debug!(host, "Skipping request: host is cooling down after a 429");
+                return Err(ArbitrageError::RateLimited(host.to_string()));
+            }
+            state.limiter.clone()
+        };
+
+        if self.limit_for(host) > 0 && !limiter.try_acquire().await {
+            tracing::debug!(
+                host,
+                "Skipping request: per-host rate limit reached this tick"
+            );
+            return Err(ArbitrageError::RateLimited(host.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Send `request` within its host's budget. A 429 response starts the
+    /// host's cooldown and is returned as [`ArbitrageError::RateLimited`].
+    /// Failures in transit and 5xx answers are retried up to `retries` times
+    /// (see [`send_with_retry`]) within the one budget slot.
+    pub async fn send(&self, request: RequestBuilder, retries: u32) -> ArbitrageResult<Response> {
+        let (client, request) = request.build_split();
+        let request = request?;
+        self.check(request.url()).await?;
+
+        let host = request.url().host_str().unwrap_or_default().to_string();
+        let response = execute_with_retry(&client, request, retries).await?;
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            tracing::warn!(host, "Rate limited (429); backing off");
+            self.cool_down(&host);
+            return Err(ArbitrageError::RateLimited(host));
+        }
+        Ok(response)
+    }
+
+    /// Hold off requests to `host` for the cooldown
+    fn cool_down(&self, host: &str) {
+        if let Some(state) = self.hosts.lock().unwrap().get_mut(host) {
+            state.cooldown_until = Some(Instant::now() + RATE_LIMIT_COOLDOWN);
+        }
+    }
+
+    fn limit_for(&self, host: &str) -> usize {
+        self.host_limits
+            .get(host)
+            .copied()
+            .unwrap_or(self.requests_per_second)
+    }
+}
+
+impl Default for HostRateLimiter {
+    fn default() -> Self {
+        Self::from_config(&Config::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Local server that accepts connections and never answers, counting them
+    async fn silent_server() -> (Url, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                held.push(socket);
+            }
+        });
+        (url, connections)
+    }
+
+    #[tokio::test]
+    async fn test_client_applies_timeouts_and_retries() {
+        let timeouts = HttpTimeouts {
+            price_fetch: Duration::from_millis(100),
+            connect: Duration::from_millis(100),
+            retries: 2,
+            ..HttpTimeouts::default()
+        };
+        let client = create_client(&timeouts);
+
+        // Connected but never answered: the request's own timeout ends it
+        let (url, connections) = silent_server().await;
+        let started = Instant::now();
+        assert!(client
+            .get(url.clone())
+            .timeout(timeouts.price_fetch)
+            .send()
+            .await
+            .unwrap_err()
+            .is_timeout());
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // Each retry is a fresh attempt
+        let request = client.get(url).timeout(timeouts.price_fetch);
+        let err = send_with_retry(request, timeouts.retries)
+            .await
+            .unwrap_err();
+        assert!(err.is_timeout());
+        assert_eq!(connections.load(Ordering::SeqCst), 4);
+
+        // A listener whose backlog is full never completes the handshake, so
+        // the connect timeout ends it well before the request timeout
+        let socket = tokio::net::TcpSocket::new_v4().unwrap();
+        socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let listener = socket.listen(0).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut backlog = Vec::new();
+        for _ in 0..4 {
+            if let Ok(Ok(stream)) = tokio::time::timeout(
+                Duration::from_millis(50),
+                tokio::net::TcpStream::connect(addr),
+            )
+            .await
+            {
+                backlog.push(stream);
+            }
+        }
+        let started = Instant::now();
+        let err = client
+            .get(format!("http://{}/", addr))
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(err.is_connect() || err.is_timeout(), "{:?}", err);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_host_limiter_caps_requests_per_window() {
+        let limiter = HostRateLimiter::new(3).with_host_limit("quote-api.jup.ag", 1);
+        let raydium = Url::parse("https://api.raydium.io/v2/main/pairs").unwrap();
+        let jupiter = Url::parse("https://quote-api.jup.ag/v6/quote").unwrap();
+
+        let mut allowed = 0;
+        for _ in 0..5 {
+            match limiter.check(&raydium).await {
+                Ok(()) => allowed += 1,
+                Err(e) => {
+                    assert!(matches!(e, ArbitrageError::RateLimited(h) if h == "api.raydium.io"))
+                }
+            }
+        }
+        assert_eq!(allowed, 3);
+
+        // Other hosts keep their own budget
+        assert!(limiter.check(&jupiter).await.is_ok());
+        assert!(limiter.check(&jupiter).await.is_err());
+
+        // A 429 holds the host off even with budget left
+        let orca = Url::parse("https://api.mainnet.orca.so/v1/whirlpool/list").unwrap();
+        assert!(limiter.check(&orca).await.is_ok());
+        limiter.cool_down("api.mainnet.orca.so");
+        assert!(limiter.check(&orca).await.is_err());
+    }
+}
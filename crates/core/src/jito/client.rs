@@ -1,15 +1,39 @@
 use anyhow::{anyhow, Result};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use serde_json::{json, Value};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{system_instruction, system_program};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 use tracing::{debug, info, warn};
 
+use crate::error::{ArbitrageError, ArbitrageResult};
+
+/// How often `wait_for_landed` re-checks a bundle (about one slot)
+const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Jito's mainnet tip accounts; a bundle must pay one of them
+pub const JITO_TIP_ACCOUNTS: [&str; 8] = [
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+
 /// Jito block engine client for bundle submission
 #[derive(Debug, Clone)]
 pub struct JitoClient {
     client: Client,
     block_engine_url: String,
     tip_lamports: u64,
+    tip_accounts: Vec<Pubkey>,
 }
 
 #[derive(Debug, Serialize)]
@@ -31,6 +55,26 @@ struct BundleError {
     message: String,
 }
 
+/// Where a submitted bundle stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleStatus {
+    /// Accepted by the block engine, not landed yet
+    Pending,
+    /// Included on chain at `slot`
+    Landed { slot: u64 },
+    /// Rejected by the block engine, or landed with a failed transaction
+    Failed,
+    /// The block engine has no record of the bundle (unknown ID, or expired)
+    Unknown,
+}
+
+impl BundleStatus {
+    /// Whether the status can no longer change
+    pub fn is_final(&self) -> bool {
+        matches!(self, BundleStatus::Landed { .. } | BundleStatus::Failed)
+    }
+}
+
 impl JitoClient {
     pub fn new(block_engine_url: &str, tip_lamports: u64) -> Self {
         Self {
@@ -40,7 +84,39 @@ impl JitoClient {
                 .unwrap_or_default(),
             block_engine_url: block_engine_url.to_string(),
             tip_lamports,
+            tip_accounts: JITO_TIP_ACCOUNTS
+                .iter()
+                .map(|account| Pubkey::from_str(account).expect("valid tip account"))
+                .collect(),
+        }
+    }
+
+    /// Pay tips to `accounts` instead of [`JITO_TIP_ACCOUNTS`], e.g. for a
+    /// non-mainnet block engine. An empty list keeps the defaults.
+    pub fn with_tip_accounts(mut self, accounts: &[String]) -> ArbitrageResult<Self> {
+        if !accounts.is_empty() {
+            self.tip_accounts = accounts
+                .iter()
+                .map(|account| {
+                    Pubkey::from_str(account.trim())
+                        .map_err(|e| ArbitrageError::InvalidPubkey(format!("{}: {}", account, e)))
+                })
+                .collect::<ArbitrageResult<_>>()?;
         }
+        Ok(self)
+    }
+
+    /// Check that bundles from this client will carry a tip
+    pub fn validate(&self) -> ArbitrageResult<()> {
+        if self.tip_lamports == 0 {
+            return Err(ArbitrageError::Config(
+                "Jito bundles need a tip: JITO_TIP_LAMPORTS is 0".to_string(),
+            ));
+        }
+        if self.tip_accounts.is_empty() {
+            return Err(ArbitrageError::Config("no Jito tip accounts".to_string()));
+        }
+        Ok(())
     }
 
     /// Submit a transaction as a Jito bundle
@@ -88,6 +164,83 @@ impl JitoClient {
         }
     }
 
+    /// Look up a bundle returned by [`JitoClient::send_bundle`].
+    ///
+    /// `getBundleStatuses` only knows bundles that landed, so anything it doesn't
+    /// report is looked up again with `getInflightBundleStatuses`, which covers
+    /// the last five minutes of pending and failed bundles.
+    pub async fn get_bundle_status(&self, bundle_id: &str) -> ArbitrageResult<BundleStatus> {
+        let landed = self.bundle_rpc("getBundleStatuses", bundle_id).await?;
+        if let Some(status) = parse_bundle_statuses(&landed)? {
+            return Ok(status);
+        }
+        let inflight = self
+            .bundle_rpc("getInflightBundleStatuses", bundle_id)
+            .await?;
+        parse_inflight_bundle_statuses(&inflight)
+    }
+
+    /// Poll [`JitoClient::get_bundle_status`] until the bundle lands or fails.
+    ///
+    /// Returns the last status seen once `timeout` passes, i.e. `Pending` or
+    /// `Unknown` if the bundle never settled. Lookups that error are retried.
+    pub async fn wait_for_landed(
+        &self,
+        bundle_id: &str,
+        timeout: Duration,
+    ) -> ArbitrageResult<BundleStatus> {
+        let deadline = Instant::now() + timeout;
+        let mut status = BundleStatus::Unknown;
+        loop {
+            match self.get_bundle_status(bundle_id).await {
+                Ok(latest) if latest.is_final() => return Ok(latest),
+                Ok(latest) => status = latest,
+                Err(e) => debug!("Jito bundle status lookup failed: {}", e),
+            }
+            if Instant::now() + STATUS_POLL_INTERVAL > deadline {
+                warn!(
+                    "Jito bundle {} not settled after {:?}: {:?}",
+                    bundle_id, timeout, status
+                );
+                return Ok(status);
+            }
+            tokio::time::sleep(STATUS_POLL_INTERVAL).await;
+        }
+    }
+
+    /// JSON-RPC `method` on the bundles endpoint for a single bundle ID
+    async fn bundle_rpc(&self, method: &str, bundle_id: &str) -> ArbitrageResult<Value> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": [[bundle_id]],
+        });
+        let url = format!("{}/api/v1/bundles", self.block_engine_url);
+        let response = self.client.post(&url).json(&request).send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(ArbitrageError::RpcError(format!(
+                "Jito {} failed ({}): {}",
+                method, status, error_text
+            )));
+        }
+
+        let body: Value = response.json().await?;
+        if let Some(error) = body.get("error") {
+            let message = error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown");
+            return Err(ArbitrageError::RpcError(format!(
+                "Jito {} error: {}",
+                method, message
+            )));
+        }
+        Ok(body)
+    }
+
     /// Check if the Jito block engine is reachable
     pub async fn health_check(&self) -> Result<bool> {
         let url = format!("{}/api/v1/bundles", self.block_engine_url);
@@ -100,29 +253,208 @@ impl JitoClient {
         }
     }
 
-    /// Get random tip account (Placeholder - normally fetched from Jito API)
+    /// Get random tip account
     pub async fn get_tip_account(&self) -> Result<String> {
-        // List of common Jito tip accounts
-        let tip_accounts = ["96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
-            "HFqU5x63VTqvQss8hp11i4wVV8bD44Puy60pxTKAW4PH",
-            "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
-            "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
-            "DfXygSm4jCyNCyb3qzK6966vGgy5tQSZHarris11tc66",
-            "ADuUkR4ykG49cvq5RTu3TRLpVIUwDiIHjYyC1E1AtDyV",
-            "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
-            "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnIzKZ6jJ"];
+        self.tip_account()
+            .map(|account| account.to_string())
+            .ok_or_else(|| anyhow!("No Jito tip accounts configured"))
+    }
 
+    /// A random tip account, spreading tips (and write locks) across them
+    pub fn tip_account(&self) -> Option<Pubkey> {
         use rand::seq::SliceRandom;
-        let mut rng = rand::thread_rng();
-        // Safety: tip_accounts is a non-empty compile-time constant
-        Ok(tip_accounts
-            .choose(&mut rng)
-            .expect("tip_accounts is non-empty")
-            .to_string())
+        self.tip_accounts.choose(&mut rand::thread_rng()).copied()
+    }
+
+    /// The accounts tips are paid to
+    pub fn tip_accounts(&self) -> &[Pubkey] {
+        &self.tip_accounts
     }
 
     /// Get the tip amount in lamports
     pub fn tip_lamports(&self) -> u64 {
         self.tip_lamports
     }
+
+    /// Transfer of the tip from `payer` to a random tip account
+    pub fn tip_instruction(&self, payer: &Pubkey) -> ArbitrageResult<Instruction> {
+        let tip_account = self
+            .tip_account()
+            .ok_or_else(|| ArbitrageError::Config("no Jito tip accounts".to_string()))?;
+        Ok(system_instruction::transfer(
+            payer,
+            &tip_account,
+            self.tip_lamports,
+        ))
+    }
+
+    /// Whether `message` pays at least the configured tip to a tip account
+    pub fn has_tip(&self, message: &VersionedMessage) -> bool {
+        let keys = message.static_account_keys();
+        message.instructions().iter().any(|ix| {
+            let key = |index: u8| keys.get(index as usize);
+            // SystemInstruction::Transfer: u32 tag 2, then u64 lamports
+            key(ix.program_id_index) == Some(&system_program::id())
+                && ix.data.len() == 12
+                && ix.data[..4] == 2u32.to_le_bytes()
+                && u64::from_le_bytes(ix.data[4..].try_into().unwrap()) >= self.tip_lamports
+                && ix
+                    .accounts
+                    .get(1)
+                    .and_then(|index| key(*index))
+                    .is_some_and(|to| self.tip_accounts.contains(to))
+        })
+    }
+}
+
+/// Status from a `getBundleStatuses` response, or `None` if the bundle isn't in it
+fn parse_bundle_statuses(response: &Value) -> ArbitrageResult<Option<BundleStatus>> {
+    let Some(entry) = first_status(response)? else {
+        return Ok(None);
+    };
+    // A landed bundle's `err` is `{"Ok": null}` unless its transaction failed
+    let succeeded = entry
+        .get("err")
+        .is_none_or(|err| err.is_null() || err.get("Ok").is_some());
+    if !succeeded {
+        return Ok(Some(BundleStatus::Failed));
+    }
+    let slot = entry
+        .get("slot")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| malformed("getBundleStatuses entry has no slot"))?;
+    Ok(Some(BundleStatus::Landed { slot }))
+}
+
+/// Status from a `getInflightBundleStatuses` response
+fn parse_inflight_bundle_statuses(response: &Value) -> ArbitrageResult<BundleStatus> {
+    let Some(entry) = first_status(response)? else {
+        return Ok(BundleStatus::Unknown);
+    };
+    match entry.get("status").and_then(Value::as_str) {
+        Some("Pending") => Ok(BundleStatus::Pending),
+        Some("Failed") => Ok(BundleStatus::Failed),
+        Some("Landed") => entry
+            .get("landed_slot")
+            .and_then(Value::as_u64)
+            .map(|slot| BundleStatus::Landed { slot })
+            .ok_or_else(|| malformed("landed bundle has no landed_slot")),
+        Some("Invalid") => Ok(BundleStatus::Unknown),
+        other => Err(malformed(&format!("unexpected bundle status {:?}", other))),
+    }
+}
+
+/// The single entry of `result.value`, if the bundle was found
+fn first_status(response: &Value) -> ArbitrageResult<Option<&Value>> {
+    let value = response
+        .get("result")
+        .and_then(|result| result.get("value"))
+        .ok_or_else(|| malformed("bundle status response has no result.value"))?;
+    match value {
+        Value::Null => Ok(None),
+        Value::Array(entries) => Ok(entries.first().filter(|entry| !entry.is_null())),
+        _ => Err(malformed("bundle status result.value is not an array")),
+    }
+}
+
+fn malformed(what: &str) -> ArbitrageError {
+    use serde::de::Error as _;
+    ArbitrageError::Serialization(serde_json::Error::custom(what))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::message::Message;
+
+    #[test]
+    fn test_tip_instruction_pays_a_tip_account() {
+        let client = JitoClient::new("http://localhost", 10_000);
+        assert_eq!(client.tip_accounts().len(), JITO_TIP_ACCOUNTS.len());
+        assert!(client.validate().is_ok());
+
+        let payer = Pubkey::new_unique();
+        let tip = client.tip_instruction(&payer).unwrap();
+        let message = VersionedMessage::Legacy(Message::new(&[tip], Some(&payer)));
+        assert!(client.has_tip(&message));
+        assert!(!JitoClient::new("http://localhost", 20_000).has_tip(&message));
+
+        // Only accounts from the configured list count
+        let custom = JitoClient::new("http://localhost", 10_000)
+            .with_tip_accounts(&[Pubkey::new_unique().to_string()])
+            .unwrap();
+        assert!(!custom.has_tip(&message));
+        assert!(client
+            .clone()
+            .with_tip_accounts(&["not-a-key".to_string()])
+            .is_err());
+        assert!(JitoClient::new("http://localhost", 0).validate().is_err());
+    }
+
+    #[test]
+    fn test_parse_landed_bundle() {
+        let landed = json!({
+            "jsonrpc": "2.0",
+            "result": {
+                "context": { "slot": 242806119 },
+                "value": [{
+                    "bundle_id": "892b79ed49138bfb",
+                    "transactions": ["3bC2M9fiACSjkTXZ"],
+                    "slot": 242804011,
+                    "confirmation_status": "finalized",
+                    "err": { "Ok": null }
+                }]
+            },
+            "id": 1
+        });
+        assert_eq!(
+            parse_bundle_statuses(&landed).unwrap(),
+            Some(BundleStatus::Landed { slot: 242804011 })
+        );
+
+        let reverted = json!({
+            "result": { "value": [{ "slot": 242804011, "err": { "Err": "InstructionError" } }] }
+        });
+        assert_eq!(
+            parse_bundle_statuses(&reverted).unwrap(),
+            Some(BundleStatus::Failed)
+        );
+
+        // Not landed (yet): getBundleStatuses has nothing to say
+        let missing = json!({ "result": { "context": { "slot": 1 }, "value": [null] } });
+        assert_eq!(parse_bundle_statuses(&missing).unwrap(), None);
+        assert!(parse_bundle_statuses(&json!({ "id": 1 })).is_err());
+    }
+
+    #[test]
+    fn test_parse_inflight_bundle() {
+        let inflight = |status: &str, landed_slot: Value| {
+            json!({
+                "result": {
+                    "context": { "slot": 280999028 },
+                    "value": [{ "bundle_id": "b1", "status": status, "landed_slot": landed_slot }]
+                }
+            })
+        };
+        let parse = |response: Value| parse_inflight_bundle_statuses(&response).unwrap();
+
+        assert_eq!(
+            parse(inflight("Pending", Value::Null)),
+            BundleStatus::Pending
+        );
+        assert_eq!(parse(inflight("Failed", Value::Null)), BundleStatus::Failed);
+        assert_eq!(
+            parse(inflight("Invalid", Value::Null)),
+            BundleStatus::Unknown
+        );
+        assert_eq!(
+            parse(inflight("Landed", json!(280999020))),
+            BundleStatus::Landed { slot: 280999020 }
+        );
+        assert_eq!(
+            parse(json!({ "result": { "value": [] } })),
+            BundleStatus::Unknown
+        );
+        assert!(parse_inflight_bundle_statuses(&inflight("Landed", Value::Null)).is_err());
+    }
 }
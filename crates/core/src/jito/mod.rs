@@ -1,3 +1,3 @@
 pub mod client;
 
-pub use client::JitoClient;
+pub use client::{BundleStatus, JitoClient};
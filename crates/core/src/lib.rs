@@ -5,6 +5,7 @@
 
 pub mod arbitrage;
 pub mod audit_log;
+pub mod backtest;
 pub mod cache;
 pub mod config;
 pub mod database;
@@ -14,12 +15,16 @@ pub mod events;
 pub mod flash_loan;
 pub mod history;
 pub mod http;
+pub mod math;
 pub mod parsers;
 pub mod pathfinding;
 pub mod pricing;
+pub mod priority_fee;
 pub mod rate_limiter;
 pub mod risk;
+pub mod rpc;
 pub mod streaming;
+pub mod token_registry;
 pub mod types;
 pub mod secrets;
 
@@ -0,0 +1,199 @@
+//! Decimal Math Utilities
+//!
+//! Numeric helpers that stay in `Decimal` instead of round-tripping through `f64`.
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+use crate::{ArbitrageError, ArbitrageResult};
+
+/// Upper bound on Newton-Raphson iterations; convergence is quadratic so this is never hit
+/// for realistic inputs.
+const MAX_SQRT_ITERATIONS: usize = 100;
+
+/// Square root of a `Decimal` using Newton-Raphson iteration.
+///
+/// The result is accurate to within a few units of the last of `Decimal`'s 28 significant
+/// digits. Negative inputs have no real root and return zero.
+pub fn decimal_sqrt(value: Decimal) -> Decimal {
+    if value <= Decimal::ZERO {
+        return Decimal::ZERO;
+    }
+
+    // (x + 1) / 2 >= sqrt(x) for all x > 0, so iterates decrease monotonically to the root
+    let mut guess = (value + Decimal::ONE) / Decimal::TWO;
+
+    for _ in 0..MAX_SQRT_ITERATIONS {
+        let next = (guess + value / guess) / Decimal::TWO;
+        // Stops once rounding prevents further progress
+        if next >= guess {
+            break;
+        }
+        guess = next;
+    }
+
+    guess
+}
+
+/// `amount * pct / 100` (e.g. USD profit of a trade from its net profit percentage).
+///
+/// Returns [`ArbitrageError::ArithmeticOverflow`] instead of panicking when the
+/// product does not fit in a `Decimal`.
+pub fn checked_pct_of(amount: Decimal, pct: Decimal) -> ArbitrageResult<Decimal> {
+    amount
+        .checked_mul(pct)
+        .and_then(|v| v.checked_div(Decimal::ONE_HUNDRED))
+        .ok_or_else(|| ArbitrageError::ArithmeticOverflow(format!("{} * {}%", amount, pct)))
+}
+
+/// Convert a UI amount to integer base units (`amount * 10^decimals`), truncating dust.
+///
+/// Errors if the scale factor or the result does not fit in a `u64`, or if the
+/// amount is negative.
+pub fn to_atoms(amount: Decimal, decimals: u32) -> ArbitrageResult<u64> {
+    let overflow = || {
+        ArbitrageError::ArithmeticOverflow(format!(
+            "{} with {} decimals does not fit in u64 atoms",
+            amount, decimals
+        ))
+    };
+    if amount.is_sign_negative() {
+        return Err(ArbitrageError::InvalidOpportunity(format!(
+            "negative amount {}",
+            amount
+        )));
+    }
+
+    let scale = 10u64.checked_pow(decimals).ok_or_else(overflow)?;
+    amount
+        .checked_mul(Decimal::from(scale))
+        .and_then(|atoms| atoms.trunc().to_u64())
+        .ok_or_else(overflow)
+}
+
+/// Decimal places applied when values leave the engine (history files, reports, logs).
+///
+/// All comparisons and sizing use full-precision `Decimal`s; rounding happens only
+/// through this policy at serialization/display boundaries, so a borderline
+/// opportunity can never be accepted or rejected because of how it is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayRounding {
+    /// Decimal places for USD amounts such as trade size and volume
+    pub usd_dp: u32,
+    /// Decimal places for USD profit figures
+    pub profit_usd_dp: u32,
+    /// Decimal places for percentages
+    pub pct_dp: u32,
+}
+
+impl Default for DisplayRounding {
+    fn default() -> Self {
+        Self {
+            usd_dp: 2,
+            profit_usd_dp: 4,
+            pct_dp: 2,
+        }
+    }
+}
+
+impl DisplayRounding {
+    pub fn usd(&self, value: Decimal) -> Decimal {
+        value.round_dp(self.usd_dp)
+    }
+
+    pub fn profit_usd(&self, value: Decimal) -> Decimal {
+        value.round_dp(self.profit_usd_dp)
+    }
+
+    pub fn pct(&self, value: Decimal) -> Decimal {
+        value.round_dp(self.pct_dp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn assert_close(actual: Decimal, expected: &str) {
+        let expected = Decimal::from_str(expected).unwrap();
+        let diff = (actual - expected).abs();
+        assert!(
+            diff < Decimal::new(1, 20),
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_perfect_squares() {
+        assert_eq!(decimal_sqrt(Decimal::from(4)), Decimal::from(2));
+        assert_eq!(decimal_sqrt(Decimal::from(144)), Decimal::from(12));
+        assert_eq!(decimal_sqrt(Decimal::ONE), Decimal::ONE);
+        assert_close(decimal_sqrt(Decimal::new(1, 4)), "0.01");
+        assert_close(
+            decimal_sqrt(Decimal::from_str("100000000000000000000").unwrap()),
+            "10000000000",
+        );
+    }
+
+    #[test]
+    fn test_irrational_roots() {
+        assert_close(decimal_sqrt(Decimal::TWO), "1.4142135623730950488016887242");
+        assert_close(
+            decimal_sqrt(Decimal::from(3)),
+            "1.7320508075688772935274463415",
+        );
+        assert_close(
+            decimal_sqrt(Decimal::new(5, 1)),
+            "0.7071067811865475244008443621",
+        );
+    }
+
+    #[test]
+    fn test_checked_pct_of() {
+        assert_eq!(
+            checked_pct_of(Decimal::from(1000), Decimal::new(5, 1)).unwrap(),
+            Decimal::from(5)
+        );
+        assert!(matches!(
+            checked_pct_of(Decimal::MAX, Decimal::from(200)),
+            Err(ArbitrageError::ArithmeticOverflow(_))
+        ));
+        assert!(checked_pct_of(Decimal::MIN, Decimal::MAX).is_err());
+    }
+
+    #[test]
+    fn test_to_atoms() {
+        // 9-decimal token (SOL) at a large but representable size
+        assert_eq!(
+            to_atoms(Decimal::from(1_000_000), 9).unwrap(),
+            1_000_000_000_000_000
+        );
+        // Dust below one atom is truncated
+        assert_eq!(to_atoms(Decimal::new(15, 10), 9).unwrap(), 1);
+        // 10^12 tokens * 10^9 exceeds u64::MAX
+        assert!(matches!(
+            to_atoms(Decimal::from(1_000_000_000_000u64), 9),
+            Err(ArbitrageError::ArithmeticOverflow(_))
+        ));
+        // Scale factor itself overflows u64
+        assert!(to_atoms(Decimal::ONE, 20).is_err());
+        // Product overflows Decimal
+        assert!(to_atoms(Decimal::MAX, 9).is_err());
+        assert!(to_atoms(Decimal::from(-1), 6).is_err());
+    }
+
+    #[test]
+    fn test_zero_and_negative() {
+        assert_eq!(decimal_sqrt(Decimal::ZERO), Decimal::ZERO);
+        assert_eq!(decimal_sqrt(Decimal::from(-9)), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_tiny_variance() {
+        // Typical squared return magnitudes seen by the volatility tracker
+        assert_close(decimal_sqrt(Decimal::new(1, 10)), "0.00001");
+    }
+}
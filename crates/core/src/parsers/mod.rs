@@ -1 +1,4 @@
 pub mod fast_json;
+pub mod raydium;
+pub mod swap_events;
+pub mod whirlpool;
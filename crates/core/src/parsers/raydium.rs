@@ -0,0 +1,288 @@
+//! Decoding of Raydium AMM v4 pool accounts
+//!
+//! Lets a provider price a pool from `getAccountInfo` data instead of an HTTP
+//! API. The pool (`AmmInfo`) account holds the token decimals, fee settings and
+//! vault addresses, but not the reserves themselves: those are the balances of
+//! the two vault token accounts, less the PnL the pool owes its owner. So a
+//! price needs three accounts: the pool, then both vaults.
+
+use rust_decimal::Decimal;
+use serde::de::Error as _;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::{ArbitrageError, ArbitrageResult};
+
+/// Size of an `AmmInfo` account
+pub const AMM_INFO_LEN: usize = 752;
+/// Size of an SPL Token account
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+
+// Field offsets within `AmmInfo` (all integers little-endian)
+const STATUS: usize = 0;
+const COIN_DECIMALS: usize = 32;
+const PC_DECIMALS: usize = 40;
+const SWAP_FEE_NUMERATOR: usize = 176;
+const SWAP_FEE_DENOMINATOR: usize = 184;
+const NEED_TAKE_PNL_COIN: usize = 192;
+const NEED_TAKE_PNL_PC: usize = 200;
+const COIN_VAULT: usize = 336;
+const PC_VAULT: usize = 368;
+const COIN_MINT: usize = 400;
+const PC_MINT: usize = 432;
+const LP_MINT: usize = 464;
+const LP_AMOUNT: usize = 720;
+
+/// Offset of `amount` within an SPL Token account
+const TOKEN_ACCOUNT_AMOUNT: usize = 64;
+
+/// `AmmStatus` values that accept swaps
+const STATUS_INITIALIZED: u64 = 1;
+const STATUS_SWAP_ONLY: u64 = 6;
+const STATUS_WAITING_TRADE: u64 = 7;
+
+/// The fields of a Raydium AMM v4 pool needed to price it.
+/// "Coin" is the pool's base token and "pc" its quote token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmmPoolState {
+    pub status: u64,
+    pub coin_decimals: u8,
+    pub pc_decimals: u8,
+    pub swap_fee_numerator: u64,
+    pub swap_fee_denominator: u64,
+    /// PnL owed to the pool owner, still sitting in the vaults
+    pub need_take_pnl_coin: u64,
+    pub need_take_pnl_pc: u64,
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+    pub coin_mint: Pubkey,
+    pub pc_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub lp_amount: u64,
+}
+
+/// Pool reserves in raw token units
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmmReserves {
+    pub base: u64,
+    pub quote: u64,
+}
+
+impl AmmPoolState {
+    /// Whether the pool currently accepts swaps
+    pub fn is_swappable(&self) -> bool {
+        matches!(
+            self.status,
+            STATUS_INITIALIZED | STATUS_SWAP_ONLY | STATUS_WAITING_TRADE
+        )
+    }
+
+    /// Swap fee as a fraction (0.25% = 0.0025), or `None` if unset
+    pub fn fee_rate(&self) -> Option<Decimal> {
+        Decimal::from(self.swap_fee_numerator).checked_div(Decimal::from(self.swap_fee_denominator))
+    }
+
+    /// Tradable reserves given the balances of the coin and pc vaults
+    pub fn reserves(&self, coin_vault_amount: u64, pc_vault_amount: u64) -> AmmReserves {
+        AmmReserves {
+            base: coin_vault_amount.saturating_sub(self.need_take_pnl_coin),
+            quote: pc_vault_amount.saturating_sub(self.need_take_pnl_pc),
+        }
+    }
+
+    /// Best bid and ask for the base token in quote terms, from the vault balances
+    pub fn bid_ask(
+        &self,
+        coin_vault_amount: u64,
+        pc_vault_amount: u64,
+    ) -> ArbitrageResult<(Decimal, Decimal)> {
+        let fee = self
+            .fee_rate()
+            .ok_or_else(|| malformed("pool has a zero swap fee denominator"))?;
+        bid_ask_from_reserves(
+            self.reserves(coin_vault_amount, pc_vault_amount),
+            self.coin_decimals,
+            self.pc_decimals,
+            fee,
+        )
+    }
+}
+
+/// Decode a Raydium AMM v4 pool (`AmmInfo`) account
+pub fn parse_amm_pool(data: &[u8]) -> ArbitrageResult<AmmPoolState> {
+    if data.len() != AMM_INFO_LEN {
+        return Err(malformed(&format!(
+            "Raydium AMM account is {} bytes, expected {}",
+            data.len(),
+            AMM_INFO_LEN
+        )));
+    }
+    let decimals = |offset: usize| {
+        u8::try_from(read_u64(data, offset))
+            .map_err(|_| malformed("Raydium AMM account has out-of-range decimals"))
+    };
+
+    Ok(AmmPoolState {
+        status: read_u64(data, STATUS),
+        coin_decimals: decimals(COIN_DECIMALS)?,
+        pc_decimals: decimals(PC_DECIMALS)?,
+        swap_fee_numerator: read_u64(data, SWAP_FEE_NUMERATOR),
+        swap_fee_denominator: read_u64(data, SWAP_FEE_DENOMINATOR),
+        need_take_pnl_coin: read_u64(data, NEED_TAKE_PNL_COIN),
+        need_take_pnl_pc: read_u64(data, NEED_TAKE_PNL_PC),
+        coin_vault: read_pubkey(data, COIN_VAULT),
+        pc_vault: read_pubkey(data, PC_VAULT),
+        coin_mint: read_pubkey(data, COIN_MINT),
+        pc_mint: read_pubkey(data, PC_MINT),
+        lp_mint: read_pubkey(data, LP_MINT),
+        lp_amount: read_u64(data, LP_AMOUNT),
+    })
+}
+
+/// Balance of an SPL Token account, e.g. a pool vault
+pub fn parse_token_account_amount(data: &[u8]) -> ArbitrageResult<u64> {
+    if data.len() < TOKEN_ACCOUNT_LEN {
+        return Err(malformed(&format!(
+            "token account is {} bytes, expected {}",
+            data.len(),
+            TOKEN_ACCOUNT_LEN
+        )));
+    }
+    Ok(read_u64(data, TOKEN_ACCOUNT_AMOUNT))
+}
+
+/// Bid and ask for the base token in quote terms: the spot price of a
+/// constant-product pool, less (bid) or plus (ask) the swap fee
+pub fn bid_ask_from_reserves(
+    reserves: AmmReserves,
+    base_decimals: u8,
+    quote_decimals: u8,
+    fee_rate: Decimal,
+) -> ArbitrageResult<(Decimal, Decimal)> {
+    let ui_amount = |amount: u64, decimals: u8| {
+        Decimal::from(amount)
+            .checked_div(Decimal::from(10u64.checked_pow(decimals.into())?))
+            .filter(|v| !v.is_zero())
+    };
+    let (Some(base), Some(quote)) = (
+        ui_amount(reserves.base, base_decimals),
+        ui_amount(reserves.quote, quote_decimals),
+    ) else {
+        return Err(ArbitrageError::InsufficientLiquidity(
+            "pool has an empty reserve".to_string(),
+        ));
+    };
+
+    let overflow = || ArbitrageError::PriceFetch("pool price out of range".to_string());
+    let mid = quote.checked_div(base).ok_or_else(overflow)?;
+    let bid = mid
+        .checked_mul(Decimal::ONE - fee_rate)
+        .ok_or_else(overflow)?;
+    let ask = mid
+        .checked_div(Decimal::ONE - fee_rate)
+        .ok_or_else(overflow)?;
+    Ok((bid, ask))
+}
+
+fn malformed(what: &str) -> ArbitrageError {
+    ArbitrageError::Serialization(serde_json::Error::custom(what))
+}
+
+/// Callers check the length up front
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    Pubkey::new_from_array(data[offset..offset + 32].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+    const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    /// Account of a SOL/USDC pool: 9/6 decimals, 0.25% swap fee, some PnL owed
+    fn sol_usdc_pool() -> (Vec<u8>, Pubkey, Pubkey) {
+        let coin_vault = Pubkey::new_unique();
+        let pc_vault = Pubkey::new_unique();
+        let mut data = vec![0u8; AMM_INFO_LEN];
+        let mut put_u64 = |offset: usize, v: u64| {
+            data[offset..offset + 8].copy_from_slice(&v.to_le_bytes());
+        };
+        put_u64(STATUS, STATUS_INITIALIZED);
+        put_u64(COIN_DECIMALS, 9);
+        put_u64(PC_DECIMALS, 6);
+        put_u64(SWAP_FEE_NUMERATOR, 25);
+        put_u64(SWAP_FEE_DENOMINATOR, 10_000);
+        put_u64(NEED_TAKE_PNL_COIN, 1_000_000_000);
+        put_u64(NEED_TAKE_PNL_PC, 150_000_000);
+        put_u64(LP_AMOUNT, 42);
+        let mut put_key = |offset: usize, key: Pubkey| {
+            data[offset..offset + 32].copy_from_slice(key.as_ref());
+        };
+        put_key(COIN_VAULT, coin_vault);
+        put_key(PC_VAULT, pc_vault);
+        put_key(COIN_MINT, SOL_MINT.parse().unwrap());
+        put_key(PC_MINT, USDC_MINT.parse().unwrap());
+        (data, coin_vault, pc_vault)
+    }
+
+    fn token_account(amount: u64) -> Vec<u8> {
+        let mut data = vec![0u8; TOKEN_ACCOUNT_LEN];
+        data[TOKEN_ACCOUNT_AMOUNT..TOKEN_ACCOUNT_AMOUNT + 8].copy_from_slice(&amount.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_parse_amm_pool_and_price() {
+        let (data, coin_vault, pc_vault) = sol_usdc_pool();
+        let pool = parse_amm_pool(&data).unwrap();
+        assert!(pool.is_swappable());
+        assert_eq!((pool.coin_decimals, pool.pc_decimals), (9, 6));
+        assert_eq!(pool.coin_vault, coin_vault);
+        assert_eq!(pool.pc_vault, pc_vault);
+        assert_eq!(pool.coin_mint.to_string(), SOL_MINT);
+        assert_eq!(pool.pc_mint.to_string(), USDC_MINT);
+        assert_eq!(pool.lp_amount, 42);
+        assert_eq!(pool.fee_rate(), Some(Decimal::new(25, 4)));
+
+        // 1,001 SOL and 150,150 USDC in the vaults, 1 SOL / 150 USDC of it owed as PnL
+        let coin = parse_token_account_amount(&token_account(1_001_000_000_000)).unwrap();
+        let pc = parse_token_account_amount(&token_account(150_150_000_000)).unwrap();
+        assert_eq!(
+            pool.reserves(coin, pc),
+            AmmReserves {
+                base: 1_000_000_000_000,
+                quote: 150_000_000_000
+            }
+        );
+
+        let (bid, ask) = pool.bid_ask(coin, pc).unwrap();
+        assert_eq!(bid, Decimal::new(1_496_250, 4));
+        assert!(ask > Decimal::from(150) && ask < Decimal::new(1504, 1));
+    }
+
+    #[test]
+    fn test_malformed_accounts_are_rejected() {
+        assert!(matches!(
+            parse_amm_pool(&[0u8; 100]),
+            Err(ArbitrageError::Serialization(_))
+        ));
+        assert!(matches!(
+            parse_token_account_amount(&[0u8; 64]),
+            Err(ArbitrageError::Serialization(_))
+        ));
+
+        let (mut data, _, _) = sol_usdc_pool();
+        data[COIN_DECIMALS + 1] = 1; // decimals = 265
+        assert!(matches!(
+            parse_amm_pool(&data),
+            Err(ArbitrageError::Serialization(_))
+        ));
+
+        let empty = AmmReserves { base: 0, quote: 1 };
+        assert!(bid_ask_from_reserves(empty, 9, 6, Decimal::ZERO).is_err());
+    }
+}
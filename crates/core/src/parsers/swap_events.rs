@@ -0,0 +1,523 @@
+//! Decoding of on-chain swap events
+//!
+//! Extracts the actual input/output amounts of each Raydium AMM v4 and Orca
+//! Whirlpool swap in a confirmed transaction, as returned by `getTransaction`
+//! with `"encoding": "json"`. Swaps are found among both top-level and inner
+//! instructions (so swaps routed through an aggregator are included), and their
+//! amounts are taken from the SPL Token transfers each swap makes. For Raydium,
+//! the `ray_log` line is used as a fallback when inner instructions are missing.
+
+use base64::engine::general_purpose::STANDARD as BASE64_ENGINE;
+use base64::Engine;
+use serde_json::Value;
+
+use crate::error::{ArbitrageError, ArbitrageResult};
+use crate::types::DexType;
+
+/// Raydium AMM v4 program
+pub const RAYDIUM_AMM_V4_PROGRAM_ID: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+/// Orca Whirlpool program
+pub const ORCA_WHIRLPOOL_PROGRAM_ID: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+/// SPL Token program, which both AMMs use to move the swapped tokens
+pub const SPL_TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+
+/// Anchor discriminator of Whirlpool's `swap` instruction (`sha256("global:swap")[..8]`)
+const WHIRLPOOL_SWAP_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+const RAYDIUM_SWAP_BASE_IN: u8 = 9;
+const RAYDIUM_SWAP_BASE_OUT: u8 = 11;
+const RAYDIUM_LOG_SWAP_BASE_IN: u8 = 3;
+const RAYDIUM_LOG_SWAP_BASE_OUT: u8 = 4;
+const SPL_TRANSFER: u8 = 3;
+const SPL_TRANSFER_CHECKED: u8 = 12;
+const RAY_LOG_PREFIX: &str = "Program log: ray_log: ";
+
+/// Arguments of a Raydium AMM v4 swap instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaydiumSwapInstruction {
+    /// Exact input: `amount_in` for at least `minimum_amount_out`
+    BaseIn {
+        amount_in: u64,
+        minimum_amount_out: u64,
+    },
+    /// Exact output: `amount_out` for at most `max_amount_in`
+    BaseOut { max_amount_in: u64, amount_out: u64 },
+}
+
+/// Arguments of an Orca Whirlpool `swap` instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WhirlpoolSwapInstruction {
+    pub amount: u64,
+    pub other_amount_threshold: u64,
+    pub sqrt_price_limit: u128,
+    pub amount_specified_is_input: bool,
+    /// Swapping token A for token B
+    pub a_to_b: bool,
+}
+
+/// Amounts reported by a Raydium `ray_log` swap line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RaydiumSwapLog {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// Pool coin / pc vault balances before the swap
+    pub pool_coin: u64,
+    pub pool_pc: u64,
+}
+
+/// A swap executed in a confirmed transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapEvent {
+    pub dex: DexType,
+    /// Index of the top-level instruction the swap ran under
+    pub instruction_index: usize,
+    /// Program id of the top-level instruction (the AMM itself, or e.g. an aggregator)
+    pub outer_program: String,
+    /// Token amount actually sent into the pool
+    pub amount_in: u64,
+    /// Token amount actually received from the pool
+    pub amount_out: u64,
+}
+
+/// Decode Raydium AMM v4 swap instruction data
+pub fn decode_raydium_swap(data: &[u8]) -> Option<RaydiumSwapInstruction> {
+    let (&tag, rest) = data.split_first()?;
+    let first = read_u64(rest, 0)?;
+    let second = read_u64(rest, 8)?;
+    match tag {
+        RAYDIUM_SWAP_BASE_IN => Some(RaydiumSwapInstruction::BaseIn {
+            amount_in: first,
+            minimum_amount_out: second,
+        }),
+        RAYDIUM_SWAP_BASE_OUT => Some(RaydiumSwapInstruction::BaseOut {
+            max_amount_in: first,
+            amount_out: second,
+        }),
+        _ => None,
+    }
+}
+
+/// Decode Orca Whirlpool `swap` instruction data
+pub fn decode_whirlpool_swap(data: &[u8]) -> Option<WhirlpoolSwapInstruction> {
+    let args = data.strip_prefix(&WHIRLPOOL_SWAP_DISCRIMINATOR)?;
+    let flag = |offset: usize| args.get(offset).map(|b| *b != 0);
+    Some(WhirlpoolSwapInstruction {
+        amount: read_u64(args, 0)?,
+        other_amount_threshold: read_u64(args, 8)?,
+        sqrt_price_limit: u128::from_le_bytes(args.get(16..32)?.try_into().ok()?),
+        amount_specified_is_input: flag(32)?,
+        a_to_b: flag(33)?,
+    })
+}
+
+/// Decode a `Program log: ray_log: <base64>` line emitted by a Raydium swap
+pub fn parse_ray_log(line: &str) -> Option<RaydiumSwapLog> {
+    let bytes = BASE64_ENGINE
+        .decode(line.strip_prefix(RAY_LOG_PREFIX)?.trim())
+        .ok()?;
+    let (&log_type, fields) = bytes.split_first()?;
+    // SwapBaseIn:  amount_in, minimum_out, direction, user_source, pool_coin, pool_pc, out_amount
+    // SwapBaseOut: max_in, amount_out, direction, user_source, pool_coin, pool_pc, deduct_in
+    let field = |i: usize| read_u64(fields, i * 8);
+    let (amount_in, amount_out) = match log_type {
+        RAYDIUM_LOG_SWAP_BASE_IN => (field(0)?, field(6)?),
+        RAYDIUM_LOG_SWAP_BASE_OUT => (field(6)?, field(1)?),
+        _ => return None,
+    };
+    Some(RaydiumSwapLog {
+        amount_in,
+        amount_out,
+        pool_coin: field(4)?,
+        pool_pc: field(5)?,
+    })
+}
+
+/// Amount moved by an SPL Token `Transfer` / `TransferChecked` instruction
+pub fn decode_token_transfer(data: &[u8]) -> Option<u64> {
+    match data.first()? {
+        &SPL_TRANSFER | &SPL_TRANSFER_CHECKED => read_u64(data, 1),
+        _ => None,
+    }
+}
+
+/// All Raydium AMM v4 and Orca Whirlpool swaps in a `getTransaction` (json encoding)
+/// result, in execution order
+pub fn parse_swaps(tx: &Value) -> ArbitrageResult<Vec<SwapEvent>> {
+    let malformed =
+        |what: &str| ArbitrageError::Transaction(format!("malformed transaction: {}", what));
+
+    let message = tx
+        .pointer("/transaction/message")
+        .ok_or_else(|| malformed("missing transaction.message"))?;
+    let meta = tx.get("meta");
+    // v0 transactions index the addresses loaded from lookup tables after the
+    // static keys: writable ones first, then read-only
+    let loaded = |kind: &str| {
+        meta.and_then(|m| m.get("loadedAddresses"))
+            .and_then(|l| l.get(kind))
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    };
+    let account_keys: Vec<&str> = message
+        .get("accountKeys")
+        .and_then(Value::as_array)
+        .ok_or_else(|| malformed("missing accountKeys"))?
+        .iter()
+        .chain(loaded("writable"))
+        .chain(loaded("readonly"))
+        .filter_map(Value::as_str)
+        .collect();
+    let top_level = message
+        .get("instructions")
+        .and_then(Value::as_array)
+        .ok_or_else(|| malformed("missing instructions"))?;
+
+    let inner_sets = meta
+        .and_then(|m| m.get("innerInstructions"))
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let mut ray_logs = meta
+        .and_then(|m| m.get("logMessages"))
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .filter_map(parse_ray_log);
+
+    let mut events = Vec::new();
+    for (index, outer) in top_level.iter().enumerate() {
+        let outer = decode_instruction(outer, &account_keys, Some(1))?;
+        let inner = inner_sets
+            .iter()
+            .find(|set| set.get("index").and_then(Value::as_u64) == Some(index as u64))
+            .and_then(|set| set.get("instructions"))
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        // The top-level instruction followed by its CPIs, as one execution trace
+        let mut trace = vec![outer.clone()];
+        for ix in inner {
+            trace.push(decode_instruction(ix, &account_keys, None)?);
+        }
+
+        for (pos, ix) in trace.iter().enumerate() {
+            let transfers = child_transfers(&trace, pos);
+            let event = match ix.program {
+                RAYDIUM_AMM_V4_PROGRAM_ID if decode_raydium_swap(&ix.data).is_some() => {
+                    // Logs are consumed in order, one per Raydium swap
+                    let log = ray_logs.next();
+                    let amounts = match transfers.as_slice() {
+                        [amount_in, amount_out, ..] => Some((*amount_in, *amount_out)),
+                        _ => log.map(|l| (l.amount_in, l.amount_out)),
+                    };
+                    amounts.map(|amounts| (DexType::Raydium, amounts))
+                }
+                ORCA_WHIRLPOOL_PROGRAM_ID => {
+                    decode_whirlpool_swap(&ix.data).and_then(|swap| match transfers.as_slice() {
+                        // Whirlpool always moves token A before token B
+                        [a, b, ..] if swap.a_to_b => Some((DexType::Orca, (*a, *b))),
+                        [a, b, ..] => Some((DexType::Orca, (*b, *a))),
+                        _ => None,
+                    })
+                }
+                _ => None,
+            };
+            if let Some((dex, (amount_in, amount_out))) = event {
+                events.push(SwapEvent {
+                    dex,
+                    instruction_index: index,
+                    outer_program: outer.program.to_string(),
+                    amount_in,
+                    amount_out,
+                });
+            }
+        }
+    }
+
+    Ok(events)
+}
+
+/// An instruction with its program id resolved and data decoded
+#[derive(Debug, Clone)]
+struct DecodedInstruction<'a> {
+    program: &'a str,
+    data: Vec<u8>,
+    /// CPI depth (1 = top level); `None` for transactions recorded without it
+    stack_height: Option<u64>,
+}
+
+fn decode_instruction<'a>(
+    ix: &Value,
+    account_keys: &[&'a str],
+    default_height: Option<u64>,
+) -> ArbitrageResult<DecodedInstruction<'a>> {
+    let program = ix
+        .get("programIdIndex")
+        .and_then(Value::as_u64)
+        .and_then(|i| account_keys.get(i as usize))
+        .ok_or_else(|| ArbitrageError::Transaction("instruction program id out of range".into()))?;
+    let data = ix
+        .get("data")
+        .and_then(Value::as_str)
+        .and_then(|d| bs58::decode(d).into_vec().ok())
+        .ok_or_else(|| ArbitrageError::Transaction("instruction data is not base58".into()))?;
+    Ok(DecodedInstruction {
+        program,
+        data,
+        stack_height: ix
+            .get("stackHeight")
+            .and_then(Value::as_u64)
+            .or(default_height),
+    })
+}
+
+/// Amounts of the SPL Token transfers made directly by `trace[pos]`
+fn child_transfers(trace: &[DecodedInstruction<'_>], pos: usize) -> Vec<u64> {
+    let parent_height = trace[pos].stack_height;
+    trace[pos + 1..]
+        .iter()
+        .take_while(|ix| match (parent_height, ix.stack_height) {
+            (Some(parent), Some(height)) => height > parent,
+            // Without stack heights, assume the swap's transfers immediately follow it
+            _ => ix.program == SPL_TOKEN_PROGRAM_ID,
+        })
+        .filter(|ix| match (parent_height, ix.stack_height) {
+            (Some(parent), Some(height)) => height == parent + 1,
+            _ => true,
+        })
+        .filter(|ix| ix.program == SPL_TOKEN_PROGRAM_ID)
+        .filter_map(|ix| decode_token_transfer(&ix.data))
+        .collect()
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        bytes.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const JUPITER: &str = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+    const USER: &str = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+
+    fn b58(bytes: &[u8]) -> String {
+        bs58::encode(bytes).into_string()
+    }
+
+    fn raydium_base_in(amount_in: u64, min_out: u64) -> Vec<u8> {
+        let mut data = vec![RAYDIUM_SWAP_BASE_IN];
+        data.extend(amount_in.to_le_bytes());
+        data.extend(min_out.to_le_bytes());
+        data
+    }
+
+    fn whirlpool_swap(amount: u64, threshold: u64, a_to_b: bool) -> Vec<u8> {
+        let mut data = WHIRLPOOL_SWAP_DISCRIMINATOR.to_vec();
+        data.extend(amount.to_le_bytes());
+        data.extend(threshold.to_le_bytes());
+        data.extend(0u128.to_le_bytes());
+        data.extend([1, a_to_b as u8]);
+        data
+    }
+
+    fn transfer(amount: u64) -> Vec<u8> {
+        let mut data = vec![SPL_TRANSFER];
+        data.extend(amount.to_le_bytes());
+        data
+    }
+
+    fn ray_log(amount_in: u64, out_amount: u64) -> String {
+        let mut log = vec![RAYDIUM_LOG_SWAP_BASE_IN];
+        for field in [amount_in, 1, 1, amount_in, 5_000_000, 7_000_000, out_amount] {
+            log.extend(field.to_le_bytes());
+        }
+        format!("{}{}", RAY_LOG_PREFIX, BASE64_ENGINE.encode(log))
+    }
+
+    /// A compiled instruction; `stack_height` is only recorded for inner instructions
+    fn ix(program_index: u8, data: &[u8], stack_height: Option<u64>) -> Value {
+        let mut ix = json!({
+            "programIdIndex": program_index,
+            "accounts": [0],
+            "data": b58(data),
+        });
+        if let Some(height) = stack_height {
+            ix["stackHeight"] = json!(height);
+        }
+        ix
+    }
+
+    /// Transaction in the `getTransaction` json layout. Account keys:
+    /// 0 user, 1 Jupiter, 2 Raydium AMM, 3 Whirlpool, 4 SPL Token.
+    fn fixture(instructions: Value, inner: Value, logs: Vec<String>) -> Value {
+        json!({
+            "slot": 250_000_000u64,
+            "transaction": {
+                "message": {
+                    "accountKeys": [
+                        USER,
+                        JUPITER,
+                        RAYDIUM_AMM_V4_PROGRAM_ID,
+                        ORCA_WHIRLPOOL_PROGRAM_ID,
+                        SPL_TOKEN_PROGRAM_ID
+                    ],
+                    "instructions": instructions,
+                }
+            },
+            "meta": { "err": null, "innerInstructions": inner, "logMessages": logs }
+        })
+    }
+
+    #[test]
+    fn test_decode_instruction_layouts() {
+        assert_eq!(
+            decode_raydium_swap(&raydium_base_in(1_000_000, 990)),
+            Some(RaydiumSwapInstruction::BaseIn {
+                amount_in: 1_000_000,
+                minimum_amount_out: 990
+            })
+        );
+        assert_eq!(decode_raydium_swap(&[RAYDIUM_SWAP_BASE_IN, 1, 2]), None);
+
+        let swap = decode_whirlpool_swap(&whirlpool_swap(500, 400, false)).unwrap();
+        assert_eq!((swap.amount, swap.other_amount_threshold), (500, 400));
+        assert!(swap.amount_specified_is_input && !swap.a_to_b);
+        assert_eq!(decode_whirlpool_swap(&raydium_base_in(1, 1)), None);
+
+        let log = parse_ray_log(&ray_log(1_000_000, 6_543)).unwrap();
+        assert_eq!((log.amount_in, log.amount_out), (1_000_000, 6_543));
+        assert_eq!((log.pool_coin, log.pool_pc), (5_000_000, 7_000_000));
+        assert_eq!(parse_ray_log("Program log: Instruction: Swap"), None);
+    }
+
+    #[test]
+    fn test_parse_direct_raydium_swap() {
+        let tx = fixture(
+            json!([ix(2, &raydium_base_in(1_000_000, 6_400), None)]),
+            json!([{ "index": 0, "instructions": [
+                ix(4, &transfer(1_000_000), Some(2)),
+                ix(4, &transfer(6_500), Some(2))
+            ]}]),
+            vec![ray_log(1_000_000, 6_500)],
+        );
+
+        let swaps = parse_swaps(&tx).unwrap();
+        assert_eq!(
+            swaps,
+            vec![SwapEvent {
+                dex: DexType::Raydium,
+                instruction_index: 0,
+                outer_program: RAYDIUM_AMM_V4_PROGRAM_ID.to_string(),
+                amount_in: 1_000_000,
+                amount_out: 6_500,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_routed_swaps_through_aggregator() {
+        // Jupiter route: USDC -> SOL on Raydium, then SOL -> USDC on a B-to-A Whirlpool
+        let tx = fixture(
+            json!([ix(4, &transfer(1), None), ix(1, &[229, 23, 203, 151], None)]),
+            json!([{ "index": 1, "instructions": [
+                ix(2, &raydium_base_in(2_000_000, 1), Some(2)),
+                ix(4, &transfer(2_000_000), Some(3)),
+                ix(4, &transfer(13_100_000), Some(3)),
+                ix(3, &whirlpool_swap(13_100_000, 1, false), Some(2)),
+                ix(4, &transfer(2_004_000), Some(3)),
+                ix(4, &transfer(13_100_000), Some(3)),
+                ix(4, &transfer(42), Some(2))
+            ]}]),
+            vec![ray_log(2_000_000, 13_100_000)],
+        );
+
+        let swaps = parse_swaps(&tx).unwrap();
+        assert_eq!(swaps.len(), 2);
+        assert!(swaps
+            .iter()
+            .all(|s| s.instruction_index == 1 && s.outer_program == JUPITER));
+        assert_eq!(swaps[0].dex, DexType::Raydium);
+        assert_eq!(
+            (swaps[0].amount_in, swaps[0].amount_out),
+            (2_000_000, 13_100_000)
+        );
+        // B-to-A: token A (USDC) leaves the pool first, then token B (SOL) goes in
+        assert_eq!(swaps[1].dex, DexType::Orca);
+        assert_eq!(
+            (swaps[1].amount_in, swaps[1].amount_out),
+            (13_100_000, 2_004_000)
+        );
+    }
+
+    #[test]
+    fn test_raydium_falls_back_to_ray_log_and_rejects_malformed() {
+        let tx = fixture(
+            json!([ix(2, &raydium_base_in(750, 1), None)]),
+            json!([]),
+            vec![
+                "Program log: Instruction: Swap".to_string(),
+                ray_log(750, 5),
+            ],
+        );
+        let swaps = parse_swaps(&tx).unwrap();
+        assert_eq!((swaps[0].amount_in, swaps[0].amount_out), (750, 5));
+
+        assert!(parse_swaps(&json!({ "meta": {} })).is_err());
+        let bad_program = fixture(
+            json!([{ "programIdIndex": 9, "data": "" }]),
+            json!([]),
+            vec![],
+        );
+        assert!(parse_swaps(&bad_program).is_err());
+    }
+
+    #[test]
+    fn test_v0_program_ids_resolve_through_loaded_addresses() {
+        // A v0 Jupiter route whose Whirlpool and SPL Token program ids come from a
+        // lookup table: static keys 0 user, 1 Jupiter; loaded writable 2 pool,
+        // loaded read-only 3 Whirlpool, 4 SPL Token
+        let pool = "HJPjoWUrhoZzkNfRpHuieeFk9WcZWjwy6PBjZ81ngndJ";
+        let mut tx = fixture(
+            json!([ix(1, &[229, 23, 203, 151], None)]),
+            json!([{ "index": 0, "instructions": [
+                ix(3, &whirlpool_swap(1_000_000, 1, true), Some(2)),
+                ix(4, &transfer(1_000_000), Some(3)),
+                ix(4, &transfer(6_480), Some(3))
+            ]}]),
+            vec![],
+        );
+        tx["version"] = json!(0);
+        tx["transaction"]["message"]["accountKeys"] = json!([USER, JUPITER]);
+        tx["transaction"]["message"]["addressTableLookups"] = json!([{
+            "accountKey": "2immgwYNHBbyVQKVGCEkgWpi53bLwWNRMB5G2nbgYV17",
+            "writableIndexes": [7],
+            "readonlyIndexes": [1, 2]
+        }]);
+        tx["meta"]["loadedAddresses"] = json!({
+            "writable": [pool],
+            "readonly": [ORCA_WHIRLPOOL_PROGRAM_ID, SPL_TOKEN_PROGRAM_ID]
+        });
+
+        let swaps = parse_swaps(&tx).unwrap();
+        assert_eq!(
+            swaps,
+            vec![SwapEvent {
+                dex: DexType::Orca,
+                instruction_index: 0,
+                outer_program: JUPITER.to_string(),
+                amount_in: 1_000_000,
+                amount_out: 6_480,
+            }]
+        );
+
+        // Without its loaded addresses the transaction can't be resolved
+        tx["meta"]["loadedAddresses"] = json!({ "writable": [], "readonly": [] });
+        assert!(parse_swaps(&tx).is_err());
+    }
+}
@@ -0,0 +1,381 @@
+//! Decoding and pricing of Orca Whirlpool accounts
+//!
+//! Whirlpools are concentrated-liquidity pools: rather than reserves, the pool
+//! account stores the square root of the current price (a Q64.64 fixed-point
+//! number of raw token B per raw token A) and the liquidity active at the
+//! current tick. Token decimals live on the mints, so callers pass them in.
+//!
+//! Liquidity only stays constant until the price crosses an initialized tick,
+//! and those live in separate tick array accounts. Quotes here therefore assume
+//! the active liquidity holds across the tick array around the current price and
+//! refuse trades that would leave it, rather than guessing what lies beyond.
+
+use rust_decimal::Decimal;
+use serde::de::Error as _;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::{ArbitrageError, ArbitrageResult};
+use crate::Quote;
+
+/// Size of a `Whirlpool` account
+pub const WHIRLPOOL_LEN: usize = 653;
+/// Ticks covered by one tick array account
+pub const TICK_ARRAY_SIZE: i32 = 88;
+
+// Field offsets within `Whirlpool`, after the 8-byte Anchor discriminator
+// (all integers little-endian)
+const TICK_SPACING: usize = 41;
+const FEE_RATE: usize = 45;
+const PROTOCOL_FEE_RATE: usize = 47;
+const LIQUIDITY: usize = 49;
+const SQRT_PRICE: usize = 65;
+const TICK_CURRENT_INDEX: usize = 81;
+const TOKEN_MINT_A: usize = 101;
+const TOKEN_VAULT_A: usize = 133;
+const TOKEN_MINT_B: usize = 181;
+const TOKEN_VAULT_B: usize = 213;
+
+/// `fee_rate` is in hundredths of a basis point
+const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+/// The fields of a Whirlpool needed to price it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhirlpoolState {
+    pub tick_spacing: u16,
+    /// Swap fee in hundredths of a basis point (3000 = 0.3%)
+    pub fee_rate: u16,
+    pub protocol_fee_rate: u16,
+    /// Liquidity active at the current tick
+    pub liquidity: u128,
+    /// Square root of the raw B-per-A price, Q64.64
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub token_mint_a: Pubkey,
+    pub token_vault_a: Pubkey,
+    pub token_mint_b: Pubkey,
+    pub token_vault_b: Pubkey,
+}
+
+impl WhirlpoolState {
+    /// Swap fee as a fraction (0.3% = 0.003)
+    pub fn fee_fraction(&self) -> Decimal {
+        Decimal::from(self.fee_rate) / Decimal::from(FEE_RATE_DENOMINATOR)
+    }
+
+    /// Current price of token A in token B, in UI units
+    pub fn price(&self, decimals_a: u8, decimals_b: u8) -> ArbitrageResult<Decimal> {
+        let sqrt_price = self.sqrt_price_decimal()?;
+        sqrt_price
+            .checked_mul(sqrt_price)
+            .and_then(|raw| scale_decimals(raw, decimals_a, decimals_b))
+            .ok_or_else(out_of_range)
+    }
+
+    /// Bounds of the tick array holding the current tick: the range over which
+    /// quotes assume the active liquidity holds
+    pub fn tick_array_range(&self) -> (i32, i32) {
+        let span = TICK_ARRAY_SIZE * i32::from(self.tick_spacing.max(1));
+        let start = self.tick_current_index.div_euclid(span) * span;
+        (start, start + span)
+    }
+
+    /// Raw amounts of token A and token B the active liquidity can give up
+    /// before the price leaves `[lower_tick, upper_tick]`: A as the price rises
+    /// to the upper bound, B as it falls to the lower one.
+    ///
+    /// Errors if no liquidity is active at the current price.
+    pub fn range_amounts(
+        &self,
+        lower_tick: i32,
+        upper_tick: i32,
+    ) -> ArbitrageResult<(Decimal, Decimal)> {
+        let liquidity = self.active_liquidity()?;
+        let sqrt_price = self.sqrt_price_decimal()?;
+        let lower = sqrt_price_at_tick(lower_tick)?.min(sqrt_price);
+        let upper = sqrt_price_at_tick(upper_tick)?.max(sqrt_price);
+
+        let amount_a = Decimal::ONE
+            .checked_div(sqrt_price)
+            .zip(Decimal::ONE.checked_div(upper))
+            .and_then(|(inv, inv_upper)| liquidity.checked_mul(inv - inv_upper));
+        let amount_b = liquidity.checked_mul(sqrt_price - lower);
+        amount_a.zip(amount_b).ok_or_else(out_of_range)
+    }
+
+    /// Value, in UI units of token B, of what the active liquidity holds across
+    /// the current tick array
+    pub fn range_liquidity(&self, decimals_a: u8, decimals_b: u8) -> ArbitrageResult<Decimal> {
+        let (lower, upper) = self.tick_array_range();
+        let (amount_a, amount_b) = self.range_amounts(lower, upper)?;
+        let price = self.price(decimals_a, decimals_b)?;
+        ui_amount(amount_a, decimals_a)
+            .and_then(|a| a.checked_mul(price))
+            .zip(ui_amount(amount_b, decimals_b))
+            .and_then(|(a, b)| a.checked_add(b))
+            .ok_or_else(out_of_range)
+    }
+
+    /// Quote swapping `amount_in` (UI units) of token A for B (`a_to_b`) or of B
+    /// for A, along the curve of the active liquidity. The fee is taken from the
+    /// input, as the pool does, and reported in units of the token received.
+    ///
+    /// Errors if no liquidity is active, or the trade would push the price out
+    /// of the current tick array.
+    pub fn quote(
+        &self,
+        amount_in: Decimal,
+        a_to_b: bool,
+        decimals_a: u8,
+        decimals_b: u8,
+    ) -> ArbitrageResult<Quote> {
+        if amount_in.is_sign_negative() {
+            return Err(ArbitrageError::InvalidOpportunity(format!(
+                "negative trade size {}",
+                amount_in
+            )));
+        }
+        let (decimals_in, decimals_out) = if a_to_b {
+            (decimals_a, decimals_b)
+        } else {
+            (decimals_b, decimals_a)
+        };
+        let raw_in = raw_amount(amount_in, decimals_in).ok_or_else(out_of_range)?;
+        let raw_in_after_fee = raw_in
+            .checked_mul(Decimal::ONE - self.fee_fraction())
+            .ok_or_else(out_of_range)?;
+
+        let (gross, _) = self.swap(raw_in, a_to_b)?;
+        let (out, next_sqrt_price) = self.swap(raw_in_after_fee, a_to_b)?;
+
+        // How far the price of the token sold falls
+        let sqrt_price = self.sqrt_price_decimal()?;
+        let ratio = if a_to_b {
+            next_sqrt_price.checked_div(sqrt_price)
+        } else {
+            sqrt_price.checked_div(next_sqrt_price)
+        }
+        .and_then(|r| r.checked_mul(r))
+        .ok_or_else(out_of_range)?;
+
+        let out_amount = ui_amount(out, decimals_out).ok_or_else(out_of_range)?;
+        let fee = ui_amount(gross - out, decimals_out).ok_or_else(out_of_range)?;
+        Ok(Quote {
+            out_amount,
+            price_impact_pct: (Decimal::ONE - ratio) * Decimal::ONE_HUNDRED,
+            fee,
+        })
+    }
+
+    /// Raw amount out and the resulting sqrt price for `raw_in` in, keeping the
+    /// active liquidity constant
+    fn swap(&self, raw_in: Decimal, a_to_b: bool) -> ArbitrageResult<(Decimal, Decimal)> {
+        let liquidity = self.active_liquidity()?;
+        let sqrt_price = self.sqrt_price_decimal()?;
+        let (lower_tick, upper_tick) = self.tick_array_range();
+
+        if a_to_b {
+            // 1/√P' = 1/√P + Δa/L, so √P' = L·√P / (L + Δa·√P)
+            let next = raw_in
+                .checked_mul(sqrt_price)
+                .and_then(|v| liquidity.checked_add(v))
+                .and_then(|denominator| liquidity.checked_mul(sqrt_price)?.checked_div(denominator))
+                .ok_or_else(out_of_range)?;
+            if next < sqrt_price_at_tick(lower_tick)? {
+                return Err(leaves_range(raw_in));
+            }
+            let out = liquidity
+                .checked_mul(sqrt_price - next)
+                .ok_or_else(out_of_range)?;
+            Ok((out, next))
+        } else {
+            // √P' = √P + Δb/L, and Δa = L·(√P' - √P) / (√P·√P')
+            let next = raw_in
+                .checked_div(liquidity)
+                .and_then(|v| sqrt_price.checked_add(v))
+                .ok_or_else(out_of_range)?;
+            if next > sqrt_price_at_tick(upper_tick)? {
+                return Err(leaves_range(raw_in));
+            }
+            let out = liquidity
+                .checked_mul(next - sqrt_price)
+                .and_then(|v| v.checked_div(sqrt_price.checked_mul(next)?))
+                .ok_or_else(out_of_range)?;
+            Ok((out, next))
+        }
+    }
+
+    fn active_liquidity(&self) -> ArbitrageResult<Decimal> {
+        if self.liquidity == 0 {
+            return Err(ArbitrageError::InsufficientLiquidity(
+                "whirlpool has no liquidity at the current price".to_string(),
+            ));
+        }
+        Decimal::try_from_i128_with_scale(self.liquidity as i128, 0).map_err(|_| out_of_range())
+    }
+
+    fn sqrt_price_decimal(&self) -> ArbitrageResult<Decimal> {
+        Decimal::try_from_i128_with_scale(self.sqrt_price as i128, 0)
+            .ok()
+            .and_then(|v| v.checked_div(Decimal::from(1u128 << 64)))
+            .filter(|v| !v.is_zero())
+            .ok_or_else(out_of_range)
+    }
+}
+
+/// Decode an Orca Whirlpool account
+pub fn parse_whirlpool(data: &[u8]) -> ArbitrageResult<WhirlpoolState> {
+    if data.len() != WHIRLPOOL_LEN {
+        return Err(malformed(&format!(
+            "Whirlpool account is {} bytes, expected {}",
+            data.len(),
+            WHIRLPOOL_LEN
+        )));
+    }
+
+    Ok(WhirlpoolState {
+        tick_spacing: u16::from_le_bytes(read(data, TICK_SPACING)),
+        fee_rate: u16::from_le_bytes(read(data, FEE_RATE)),
+        protocol_fee_rate: u16::from_le_bytes(read(data, PROTOCOL_FEE_RATE)),
+        liquidity: u128::from_le_bytes(read(data, LIQUIDITY)),
+        sqrt_price: u128::from_le_bytes(read(data, SQRT_PRICE)),
+        tick_current_index: i32::from_le_bytes(read(data, TICK_CURRENT_INDEX)),
+        token_mint_a: Pubkey::new_from_array(read(data, TOKEN_MINT_A)),
+        token_vault_a: Pubkey::new_from_array(read(data, TOKEN_VAULT_A)),
+        token_mint_b: Pubkey::new_from_array(read(data, TOKEN_MINT_B)),
+        token_vault_b: Pubkey::new_from_array(read(data, TOKEN_VAULT_B)),
+    })
+}
+
+/// √(1.0001^tick), the sqrt price (not Q64.64) at a tick boundary
+fn sqrt_price_at_tick(tick: i32) -> ArbitrageResult<Decimal> {
+    Decimal::try_from(1.0001f64.powf(f64::from(tick) / 2.0)).map_err(|_| out_of_range())
+}
+
+/// Convert a raw B-per-A price to UI units
+fn scale_decimals(raw: Decimal, decimals_a: u8, decimals_b: u8) -> Option<Decimal> {
+    let shift = i32::from(decimals_a) - i32::from(decimals_b);
+    let factor = Decimal::from(10u64.checked_pow(shift.unsigned_abs())?);
+    if shift >= 0 {
+        raw.checked_mul(factor)
+    } else {
+        raw.checked_div(factor)
+    }
+}
+
+fn ui_amount(raw: Decimal, decimals: u8) -> Option<Decimal> {
+    raw.checked_div(Decimal::from(10u64.checked_pow(decimals.into())?))
+}
+
+fn raw_amount(ui: Decimal, decimals: u8) -> Option<Decimal> {
+    ui.checked_mul(Decimal::from(10u64.checked_pow(decimals.into())?))
+}
+
+fn leaves_range(raw_in: Decimal) -> ArbitrageError {
+    ArbitrageError::InsufficientLiquidity(format!(
+        "swap of {} raw units would move the whirlpool past the current tick array",
+        raw_in.round()
+    ))
+}
+
+fn out_of_range() -> ArbitrageError {
+    ArbitrageError::PriceFetch("whirlpool price out of range".to_string())
+}
+
+fn malformed(what: &str) -> ArbitrageError {
+    ArbitrageError::Serialization(serde_json::Error::custom(what))
+}
+
+/// Callers check the length up front
+fn read<const N: usize>(data: &[u8], offset: usize) -> [u8; N] {
+    data[offset..offset + N].try_into().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+    const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    /// Account of a SOL/USDC whirlpool at ~150 USDC: tick spacing 64, 0.3% fee,
+    /// about 150k USDC worth of liquidity across the current tick array
+    fn sol_usdc_whirlpool(liquidity: u128) -> Vec<u8> {
+        let mut data = vec![0u8; WHIRLPOOL_LEN];
+        let mut put = |offset: usize, bytes: &[u8]| {
+            data[offset..offset + bytes.len()].copy_from_slice(bytes);
+        };
+        put(TICK_SPACING, &64u16.to_le_bytes());
+        put(FEE_RATE, &3000u16.to_le_bytes());
+        put(PROTOCOL_FEE_RATE, &1300u16.to_le_bytes());
+        put(LIQUIDITY, &liquidity.to_le_bytes());
+        put(SQRT_PRICE, &7_144_393_258_922_745_856u128.to_le_bytes());
+        put(TICK_CURRENT_INDEX, &(-18_973i32).to_le_bytes());
+        put(TOKEN_MINT_A, SOL_MINT.parse::<Pubkey>().unwrap().as_ref());
+        put(TOKEN_MINT_B, USDC_MINT.parse::<Pubkey>().unwrap().as_ref());
+        data
+    }
+
+    #[test]
+    fn test_parse_whirlpool_and_price() {
+        let pool = parse_whirlpool(&sol_usdc_whirlpool(1_500_000_000_000)).unwrap();
+        assert_eq!(pool.tick_spacing, 64);
+        assert_eq!(pool.fee_fraction(), Decimal::new(3, 3));
+        assert_eq!(pool.tick_current_index, -18_973);
+        assert_eq!(pool.token_mint_a.to_string(), SOL_MINT);
+        assert_eq!(pool.token_mint_b.to_string(), USDC_MINT);
+
+        let price = pool.price(9, 6).unwrap();
+        assert!((price - Decimal::from(150)).abs() < Decimal::new(1, 2));
+
+        // 88 ticks of 64: the array starting at -22,528 holds the current tick
+        assert_eq!(pool.tick_array_range(), (-22_528, -16_896));
+        let depth = pool.range_liquidity(9, 6).unwrap();
+        assert!(depth > Decimal::from(100_000) && depth < Decimal::from(200_000));
+    }
+
+    #[test]
+    fn test_quote_moves_along_the_curve() {
+        let pool = parse_whirlpool(&sol_usdc_whirlpool(1_500_000_000_000)).unwrap();
+
+        // Selling 1 SOL: a little under 150 USDC less the 0.3% fee
+        let sell = pool.quote(Decimal::ONE, true, 9, 6).unwrap();
+        assert!(sell.out_amount > Decimal::new(1494, 1) && sell.out_amount < Decimal::new(1496, 1));
+        assert!(sell.fee > Decimal::new(44, 2) && sell.fee < Decimal::new(46, 2));
+        assert!(sell.price_impact_pct > Decimal::ZERO);
+
+        // A bigger trade moves the price further
+        let bigger = pool.quote(Decimal::from(100), true, 9, 6).unwrap();
+        assert!(bigger.price_impact_pct > sell.price_impact_pct);
+        assert!(bigger.out_amount < sell.out_amount * Decimal::from(100));
+
+        // Buying with 150 USDC gets a little under 1 SOL
+        let buy = pool.quote(Decimal::from(150), false, 9, 6).unwrap();
+        assert!(buy.out_amount > Decimal::new(99, 2) && buy.out_amount < Decimal::ONE);
+
+        // Beyond the tick array the liquidity is unknown
+        assert!(matches!(
+            pool.quote(Decimal::from(10_000), true, 9, 6),
+            Err(ArbitrageError::InsufficientLiquidity(_))
+        ));
+    }
+
+    #[test]
+    fn test_empty_range_and_malformed_accounts_are_rejected() {
+        let empty = parse_whirlpool(&sol_usdc_whirlpool(0)).unwrap();
+        // The last price is still known, but nothing can trade at it
+        assert!(empty.price(9, 6).is_ok());
+        assert!(matches!(
+            empty.quote(Decimal::ONE, true, 9, 6),
+            Err(ArbitrageError::InsufficientLiquidity(_))
+        ));
+        assert!(matches!(
+            empty.range_liquidity(9, 6),
+            Err(ArbitrageError::InsufficientLiquidity(_))
+        ));
+
+        assert!(matches!(
+            parse_whirlpool(&[0u8; 100]),
+            Err(ArbitrageError::Serialization(_))
+        ));
+    }
+}
@@ -0,0 +1,123 @@
+//! Constant-product (x * y = k) AMM pricing
+//!
+//! Executable prices from pool reserves, as decoded by e.g.
+//! [`crate::parsers::raydium`], for pools that price along a single
+//! constant-product curve (Raydium AMM v4, Orca classic pools). Unlike a quoted
+//! mid price, these account for how far the trade itself moves the pool.
+
+use rust_decimal::Decimal;
+
+use crate::{ArbitrageError, ArbitrageResult};
+
+/// Price of buying `trade_size` base tokens from a pool holding `base_reserve`
+/// and `quote_reserve`, paying a swap fee of `fee_bps` on the quote input.
+///
+/// Returns `(exec_price, price_impact_pct)`: the average quote paid per base
+/// token including the fee, and how far the pool's price moves against the
+/// trade, excluding the fee (1.0 = 1%). Reserves and size are in the same units
+/// (both raw or both UI amounts).
+///
+/// Errors if either reserve is empty, the size is negative, or the trade would
+/// take the pool's entire base reserve or more.
+pub fn price_from_reserves(
+    base_reserve: Decimal,
+    quote_reserve: Decimal,
+    trade_size: Decimal,
+    fee_bps: u32,
+) -> ArbitrageResult<(Decimal, Decimal)> {
+    if base_reserve <= Decimal::ZERO || quote_reserve <= Decimal::ZERO {
+        return Err(ArbitrageError::InsufficientLiquidity(
+            "pool has an empty reserve".to_string(),
+        ));
+    }
+    if trade_size.is_sign_negative() {
+        return Err(ArbitrageError::InvalidOpportunity(format!(
+            "negative trade size {}",
+            trade_size
+        )));
+    }
+    if trade_size >= base_reserve {
+        return Err(ArbitrageError::InsufficientLiquidity(format!(
+            "trade of {} exceeds pool reserve of {}",
+            trade_size, base_reserve
+        )));
+    }
+    let fee_multiplier = Decimal::ONE - Decimal::from(fee_bps) / Decimal::from(10_000);
+    if fee_multiplier <= Decimal::ZERO {
+        return Err(ArbitrageError::InvalidOpportunity(format!(
+            "fee of {} bps leaves nothing to swap",
+            fee_bps
+        )));
+    }
+
+    let overflow = || {
+        ArbitrageError::ArithmeticOverflow(format!(
+            "buying {} from reserves {} / {}",
+            trade_size, base_reserve, quote_reserve
+        ))
+    };
+    // Keeping k constant, taking `size` out of the base side leaves the average
+    // price at quote / (base - size); the fee is charged on top of the input
+    let remaining = base_reserve - trade_size;
+    let price_before_fee = quote_reserve.checked_div(remaining).ok_or_else(overflow)?;
+    let exec_price = price_before_fee
+        .checked_div(fee_multiplier)
+        .ok_or_else(overflow)?;
+    // (quote / (base - size)) / (quote / base) - 1 = size / (base - size)
+    let price_impact_pct = trade_size
+        .checked_div(remaining)
+        .and_then(|ratio| ratio.checked_mul(Decimal::ONE_HUNDRED))
+        .ok_or_else(overflow)?;
+
+    Ok((exec_price, price_impact_pct))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_from_reserves_matches_cpmm() {
+        // 1,000 SOL / 100,000 USDC; buying 10 SOL with a 0.3% fee.
+        // Input before fee = 100,000 * 10 / 990 = 1010.1010...; after fee / 0.997
+        let (exec_price, impact) = price_from_reserves(
+            Decimal::from(1000),
+            Decimal::from(100_000),
+            Decimal::TEN,
+            30,
+        )
+        .unwrap();
+        assert_eq!(exec_price.round_dp(6), Decimal::new(101_314_043, 6));
+        assert_eq!(impact.round_dp(6), Decimal::new(1_010_101, 6));
+
+        // No size and no fee: the spot price
+        let (spot, impact) = price_from_reserves(
+            Decimal::from(1000),
+            Decimal::from(100_000),
+            Decimal::ZERO,
+            0,
+        )
+        .unwrap();
+        assert_eq!(spot, Decimal::ONE_HUNDRED);
+        assert!(impact.is_zero());
+    }
+
+    #[test]
+    fn test_price_from_reserves_rejects_empty_and_oversized() {
+        let reserve = Decimal::from(1000);
+        assert!(matches!(
+            price_from_reserves(Decimal::ZERO, reserve, Decimal::ONE, 30),
+            Err(ArbitrageError::InsufficientLiquidity(_))
+        ));
+        assert!(matches!(
+            price_from_reserves(reserve, Decimal::ZERO, Decimal::ONE, 30),
+            Err(ArbitrageError::InsufficientLiquidity(_))
+        ));
+        assert!(matches!(
+            price_from_reserves(reserve, reserve, reserve, 30),
+            Err(ArbitrageError::InsufficientLiquidity(_))
+        ));
+        assert!(price_from_reserves(reserve, reserve, -Decimal::ONE, 30).is_err());
+        assert!(price_from_reserves(reserve, reserve, Decimal::ONE, 10_000).is_err());
+    }
+}
@@ -1,2 +1,7 @@
+pub mod amm;
 pub mod hybrid_fetcher;
+#[cfg(feature = "http")]
+pub mod oracle;
+pub mod pair_scheduler;
 pub mod parallel_fetcher;
+pub mod smoothing;
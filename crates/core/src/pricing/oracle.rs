@@ -0,0 +1,384 @@
+//! Independent reference prices from Pyth
+//!
+//! DEX prices can agree with each other and still be wrong: when every feed
+//! the detector reads is stale in the same direction, the "spread" between
+//! them is an artifact, not an opportunity. [`PythPriceFeed`] reads Pyth's
+//! Hermes API for a price that doesn't come from any DEX, and
+//! [`check_against_oracle`] refuses trades whose buy and sell prices both sit
+//! on the same side of it, far away.
+
+use crate::error::ArbitrageError;
+use crate::types::TokenPair;
+use crate::ArbitrageResult;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Pyth's public Hermes endpoint for the latest price updates
+pub const PYTH_HERMES_API: &str = "https://hermes.pyth.network/v2/updates/price/latest";
+
+/// Prices published longer ago than this (seconds) are refused by default
+pub const DEFAULT_MAX_ORACLE_AGE_SECONDS: i64 = 60;
+
+/// Time Hermes gets to answer
+const HERMES_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Pyth USD price feeds of the tokens the bot trades
+pub const PYTH_USD_FEEDS: &[(&str, &str)] = &[
+    (
+        "SOL",
+        "ef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d",
+    ),
+    (
+        "USDC",
+        "eaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a",
+    ),
+    (
+        "USDT",
+        "2b89b9dc8fdf9f34709a5b106b472f0f39bb6ca9ce04b0fd7f2e971688e2e53b",
+    ),
+    (
+        "BTC",
+        "e62df6c8b4a85fe1a67db44dc12de5db330f7ac66b72dc658afedf0f4a415b43",
+    ),
+    (
+        "ETH",
+        "ff61491a931112ddf1bd8147cd1b641375f79f5825126d665480874634fd0ace",
+    ),
+];
+
+#[derive(Debug, Deserialize)]
+struct HermesResponse {
+    parsed: Vec<HermesPriceUpdate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HermesPriceUpdate {
+    id: String,
+    price: PythPrice,
+}
+
+/// `price ± conf`, both scaled by `10^expo`, published at `publish_time` (unix seconds)
+#[derive(Debug, Deserialize)]
+struct PythPrice {
+    price: String,
+    conf: String,
+    expo: i32,
+    publish_time: i64,
+}
+
+impl PythPrice {
+    fn scaled(&self, mantissa: &str) -> Option<Decimal> {
+        let mantissa = i64::from_str(mantissa).ok()?;
+        let scale = u32::try_from(-self.expo).ok()?;
+        Decimal::try_new(mantissa, scale).ok()
+    }
+}
+
+/// An oracle price and Pyth's confidence interval around it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub price: Decimal,
+    /// Half-width of the interval the true price is expected in
+    pub confidence: Decimal,
+    pub publish_time: DateTime<Utc>,
+}
+
+impl OraclePrice {
+    /// `base` priced in `quote`, both being USD prices.
+    ///
+    /// Relative uncertainties add, so the result is as unsure as both inputs
+    /// together; it is as old as the older of the two.
+    pub fn ratio(base: &OraclePrice, quote: &OraclePrice) -> Option<OraclePrice> {
+        let price = base.price.checked_div(quote.price)?;
+        let relative =
+            base.confidence.checked_div(base.price)? + quote.confidence.checked_div(quote.price)?;
+        Some(OraclePrice {
+            price,
+            confidence: price * relative,
+            publish_time: base.publish_time.min(quote.publish_time),
+        })
+    }
+}
+
+/// Reads USD prices from Pyth's Hermes HTTP API
+pub struct PythPriceFeed {
+    client: reqwest::Client,
+    url: String,
+    feeds: HashMap<String, String>,
+    max_age_seconds: i64,
+}
+
+impl Default for PythPriceFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PythPriceFeed {
+    /// A feed reading [`PYTH_HERMES_API`], knowing the tokens in [`PYTH_USD_FEEDS`]
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: PYTH_HERMES_API.to_string(),
+            feeds: PYTH_USD_FEEDS
+                .iter()
+                .map(|(symbol, id)| (symbol.to_string(), id.to_string()))
+                .collect(),
+            max_age_seconds: DEFAULT_MAX_ORACLE_AGE_SECONDS,
+        }
+    }
+
+    /// Replaces the Hermes endpoint prices are read from
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.url = url.into();
+        self
+    }
+
+    /// Prices `symbol` (in USD) from the Pyth feed `feed_id`
+    pub fn with_feed(mut self, symbol: &str, feed_id: &str) -> Self {
+        self.feeds.insert(
+            symbol.to_uppercase(),
+            feed_id.trim_start_matches("0x").to_lowercase(),
+        );
+        self
+    }
+
+    /// Refuse prices published longer ago than this
+    pub fn with_max_age_seconds(mut self, seconds: i64) -> Self {
+        self.max_age_seconds = seconds;
+        self
+    }
+
+    /// Whether there is a feed to price `symbol` with
+    pub fn has_feed(&self, symbol: &str) -> bool {
+        self.feeds.contains_key(&symbol.to_uppercase())
+    }
+
+    /// Current USD price of `symbol`
+    pub async fn get_price(&self, symbol: &str) -> ArbitrageResult<Decimal> {
+        Ok(self.get_price_with_confidence(symbol).await?.price)
+    }
+
+    /// Current USD price of `symbol`, with its confidence interval
+    pub async fn get_price_with_confidence(&self, symbol: &str) -> ArbitrageResult<OraclePrice> {
+        let feed = self.feed(symbol)?;
+        Ok(self.fetch(&[feed]).await?.remove(0))
+    }
+
+    /// Price of the pair's base token in its quote token, from the two USD feeds
+    /// (read in one request)
+    pub async fn pair_price(&self, pair: &TokenPair) -> ArbitrageResult<OraclePrice> {
+        let feeds = [self.feed(&pair.base)?, self.feed(&pair.quote)?];
+        let prices = self.fetch(&feeds).await?;
+        OraclePrice::ratio(&prices[0], &prices[1])
+            .ok_or_else(|| ArbitrageError::PriceFetch(format!("Invalid oracle price for {}", pair)))
+    }
+
+    fn feed(&self, symbol: &str) -> ArbitrageResult<&str> {
+        self.feeds
+            .get(&symbol.to_uppercase())
+            .map(String::as_str)
+            .ok_or_else(|| {
+                ArbitrageError::PriceNotAvailable(format!("No Pyth feed for {}", symbol))
+            })
+    }
+
+    /// Latest price of each feed in `feeds`, in the same order
+    async fn fetch(&self, feeds: &[&str]) -> ArbitrageResult<Vec<OraclePrice>> {
+        let query: Vec<(&str, &str)> = feeds.iter().map(|feed| ("ids[]", *feed)).collect();
+        let response: HermesResponse = self
+            .client
+            .get(&self.url)
+            .timeout(HERMES_TIMEOUT)
+            .query(&query)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let now = Utc::now().timestamp();
+        feeds
+            .iter()
+            .map(|feed| {
+                let update = response
+                    .parsed
+                    .iter()
+                    .find(|u| u.id.trim_start_matches("0x") == *feed)
+                    .ok_or_else(|| {
+                        ArbitrageError::PriceFetch(format!("Pyth returned no price for {}", feed))
+                    })?;
+                let age = now - update.price.publish_time;
+                if age > self.max_age_seconds {
+                    return Err(ArbitrageError::StalePriceData {
+                        pair: feed.to_string(),
+                        age_seconds: age as u64,
+                        max_age: self.max_age_seconds as u64,
+                    });
+                }
+                let price = &update.price;
+                match (
+                    price.scaled(&price.price),
+                    price.scaled(&price.conf),
+                    DateTime::from_timestamp(price.publish_time, 0),
+                ) {
+                    (Some(value), Some(confidence), Some(publish_time))
+                        if value > Decimal::ZERO =>
+                    {
+                        Ok(OraclePrice {
+                            price: value,
+                            confidence,
+                            publish_time,
+                        })
+                    }
+                    _ => Err(ArbitrageError::PriceFetch(format!(
+                        "Invalid Pyth price for {}",
+                        feed
+                    ))),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Refuse a trade whose buy and sell prices both sit more than
+/// `max_deviation_pct` (plus the oracle's confidence) on the same side of
+/// `oracle`: the DEXs agree with each other but not with the market, which is
+/// what stale feeds look like. Prices straddling the oracle pass.
+pub fn check_against_oracle(
+    buy_price: Decimal,
+    sell_price: Decimal,
+    oracle: &OraclePrice,
+    max_deviation_pct: Decimal,
+) -> ArbitrageResult<()> {
+    let band = oracle.price * max_deviation_pct / Decimal::ONE_HUNDRED + oracle.confidence;
+    let (low, high) = (oracle.price - band, oracle.price + band);
+    let both_above = buy_price > high && sell_price > high;
+    let both_below = buy_price < low && sell_price < low;
+    if both_above || both_below {
+        return Err(ArbitrageError::InvalidOpportunity(format!(
+            "buy {} and sell {} are both {} oracle price {} (±{})",
+            buy_price,
+            sell_price,
+            if both_above { "above" } else { "below" },
+            oracle.price,
+            band
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    const SOL: &str = "ef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
+    const USDC: &str = "eaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a";
+
+    fn update(feed: &str, price: &str, conf: &str, publish_time: i64) -> serde_json::Value {
+        serde_json::json!({
+            "id": feed,
+            "price": {"price": price, "conf": conf, "expo": -8, "publish_time": publish_time},
+        })
+    }
+
+    /// Serves `body` as JSON to every request, standing in for Hermes
+    async fn mock_hermes(body: serde_json::Value) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "http://{}/v2/updates/price/latest",
+            listener.local_addr().unwrap()
+        );
+        let body = body.to_string();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = [0u8; 4096];
+                let _ = socket.read(&mut request).await;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_pyth_feed_reads_hermes_prices() {
+        let now = Utc::now().timestamp();
+        let url = mock_hermes(serde_json::json!({"parsed": [
+            update(SOL, "15012345678", "7500000", now),
+            update(&format!("0x{}", USDC), "99990000", "10000", now),
+        ]}))
+        .await;
+        let feed = PythPriceFeed::new().with_url(url);
+
+        let sol = feed.get_price_with_confidence("sol").await.unwrap();
+        assert_eq!(sol.price, Decimal::new(15012345678, 8));
+        assert_eq!(sol.confidence, Decimal::new(75, 3));
+        assert_eq!(sol.publish_time.timestamp(), now);
+
+        let pair = feed
+            .pair_price(&TokenPair::new("SOL", "USDC"))
+            .await
+            .unwrap();
+        assert_eq!(pair.price.round_dp(4), Decimal::new(1501385, 4));
+        assert!(pair.confidence > sol.confidence);
+
+        assert!(matches!(
+            feed.get_price("BONK").await,
+            Err(ArbitrageError::PriceNotAvailable(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_pyth_feed_refuses_stale_prices() {
+        let old = Utc::now().timestamp() - 300;
+        let url = mock_hermes(serde_json::json!({"parsed": [
+            update(SOL, "15000000000", "5000000", old),
+        ]}))
+        .await;
+        let feed = PythPriceFeed::new().with_url(url);
+
+        assert!(matches!(
+            feed.get_price("SOL").await,
+            Err(ArbitrageError::StalePriceData { .. })
+        ));
+        assert!(feed
+            .with_max_age_seconds(600)
+            .get_price("SOL")
+            .await
+            .is_ok());
+    }
+
+    #[test]
+    fn test_check_against_oracle() {
+        let oracle = OraclePrice {
+            price: Decimal::from(150),
+            confidence: Decimal::new(1, 1),
+            publish_time: Utc::now(),
+        };
+        let max = Decimal::ONE;
+        let check = |buy: i64, sell: i64| {
+            check_against_oracle(Decimal::new(buy, 1), Decimal::new(sell, 1), &oracle, max)
+        };
+
+        // A real spread around the oracle
+        assert!(check(1495, 1505).is_ok());
+        // One side far away is still a spread the other DEX can close
+        assert!(check(1495, 1600).is_ok());
+        // Both well above (or below): the feeds agree on a price the market doesn't
+        assert!(check(1550, 1560).is_err());
+        assert!(check(1400, 1410).is_err());
+        // Within the band plus confidence (148.4..151.6) either way
+        assert!(check(1515, 1516).is_ok());
+    }
+}
@@ -0,0 +1,131 @@
+use crate::types::TokenPair;
+use std::collections::HashMap;
+
+/// Per-tick decay applied to each pair's opportunity score
+const SCORE_DECAY: f64 = 0.95;
+
+/// Chooses which pairs to poll each tick when more are configured than the cap.
+///
+/// Most slots go to the pairs that produced opportunities most often recently
+/// (an exponentially decayed count); the remaining rotation slots cycle through
+/// the rest so the long tail is still covered over time.
+#[derive(Debug, Clone)]
+pub struct PairScheduler {
+    pairs: Vec<TokenPair>,
+    max_active: usize,
+    rotation_slots: usize,
+    scores: HashMap<TokenPair, f64>,
+    cursor: usize,
+}
+
+impl PairScheduler {
+    /// Poll at most `max_active` pairs per tick (0 = no cap). A quarter of the
+    /// slots (at least one) rotate through the lower-priority pairs.
+    pub fn new(pairs: Vec<TokenPair>, max_active: usize) -> Self {
+        Self {
+            pairs,
+            max_active,
+            rotation_slots: (max_active / 4).max(1),
+            scores: HashMap::new(),
+            cursor: 0,
+        }
+    }
+
+    /// Override how many active slots are reserved for rotation (capped at `max_active`)
+    pub fn with_rotation_slots(mut self, rotation_slots: usize) -> Self {
+        self.rotation_slots = rotation_slots;
+        self
+    }
+
+    /// All configured pairs
+    pub fn pairs(&self) -> &[TokenPair] {
+        &self.pairs
+    }
+
+    /// Whether more pairs are configured than can be polled at once
+    pub fn is_capped(&self) -> bool {
+        self.max_active > 0 && self.pairs.len() > self.max_active
+    }
+
+    /// Credit `pair` with `count` opportunities found this tick
+    pub fn record_opportunities(&mut self, pair: &TokenPair, count: usize) {
+        if count > 0 {
+            *self.scores.entry(pair.clone()).or_default() += count as f64;
+        }
+    }
+
+    /// Pairs to poll this tick. Decays the priority scores, so call once per tick.
+    pub fn next_batch(&mut self) -> Vec<TokenPair> {
+        if !self.is_capped() {
+            return self.pairs.clone();
+        }
+
+        for score in self.scores.values_mut() {
+            *score *= SCORE_DECAY;
+        }
+
+        // Highest score first; ties keep the configured order
+        let mut ranked: Vec<&TokenPair> = self.pairs.iter().collect();
+        ranked.sort_by(|a, b| self.score(b).total_cmp(&self.score(a)));
+
+        let priority_slots = self.max_active - self.rotation_slots.min(self.max_active);
+        let mut batch: Vec<TokenPair> = ranked[..priority_slots]
+            .iter()
+            .map(|p| (*p).clone())
+            .collect();
+
+        // Fill the rotation slots round-robin from the pairs not already chosen
+        let tail: Vec<&TokenPair> = self.pairs.iter().filter(|p| !batch.contains(p)).collect();
+        for i in 0..self.max_active - priority_slots {
+            batch.push(tail[(self.cursor + i) % tail.len()].clone());
+        }
+        self.cursor = (self.cursor + self.max_active - priority_slots) % tail.len();
+
+        batch
+    }
+
+    fn score(&self, pair: &TokenPair) -> f64 {
+        self.scores.get(pair).copied().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn pairs(n: usize) -> Vec<TokenPair> {
+        (0..n)
+            .map(|i| TokenPair::new(format!("T{}", i), "USDC"))
+            .collect()
+    }
+
+    #[test]
+    fn test_uncapped_returns_all_pairs() {
+        let mut scheduler = PairScheduler::new(pairs(3), 5);
+        assert!(!scheduler.is_capped());
+        assert_eq!(scheduler.next_batch().len(), 3);
+
+        let mut unlimited = PairScheduler::new(pairs(30), 0);
+        assert_eq!(unlimited.next_batch().len(), 30);
+    }
+
+    #[test]
+    fn test_prioritizes_productive_pairs_and_rotates_the_rest() {
+        let all = pairs(6);
+        let mut scheduler = PairScheduler::new(all.clone(), 3).with_rotation_slots(1);
+        scheduler.record_opportunities(&all[4], 5);
+        scheduler.record_opportunities(&all[2], 2);
+
+        let mut rotated = HashSet::new();
+        for _ in 0..4 {
+            let batch = scheduler.next_batch();
+            assert_eq!(batch.len(), 3);
+            assert_eq!(batch[0], all[4]);
+            assert_eq!(batch[1], all[2]);
+            rotated.insert(batch[2].clone());
+        }
+        // Every remaining pair got a turn in the rotation slot
+        assert_eq!(rotated.len(), 4);
+    }
+}
@@ -1,50 +1,167 @@
-use crate::dex::DexProvider;
-use crate::types::{PriceData, TokenPair};
+use crate::dex::{DexProvider, PriceBatch, ProviderHealth};
+use crate::types::{DexType, PriceData, TokenPair};
+use std::collections::{hash_map::Entry, HashMap};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tokio::task::JoinSet;
 
+/// Default time a provider gets to answer before its batch is dropped
+pub const DEFAULT_PROVIDER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Per-pair outcome counts for one DEX in one fetch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DexFetchStats {
+    /// Pairs priced successfully
+    pub ok: usize,
+    /// Pairs requested from a provider that missed the timeout
+    pub timeout: usize,
+    /// Pairs the provider reported as failed
+    pub error: usize,
+    /// Time the provider took to answer (the timeout, if it didn't)
+    pub latency: Duration,
+}
+
+/// What happened to each provider during a `fetch_all_prices_with_report` call
+#[derive(Debug, Clone, Default)]
+pub struct FetchReport {
+    pub per_dex: HashMap<DexType, DexFetchStats>,
+}
+
+impl FetchReport {
+    /// DEXs whose batch was dropped for missing the timeout
+    pub fn timed_out(&self) -> Vec<DexType> {
+        self.per_dex
+            .iter()
+            .filter(|(_, stats)| stats.timeout > 0)
+            .map(|(dex, _)| *dex)
+            .collect()
+    }
+}
+
 pub struct ParallelPriceFetcher {
     dex_providers: Vec<Arc<dyn DexProvider>>,
+    health: Arc<ProviderHealth>,
+    per_provider_timeout: Duration,
 }
 
 impl ParallelPriceFetcher {
     pub fn new(providers: Vec<Arc<dyn DexProvider>>) -> Self {
         Self {
             dex_providers: providers,
+            health: Arc::new(ProviderHealth::default()),
+            per_provider_timeout: DEFAULT_PROVIDER_TIMEOUT,
         }
     }
 
+    /// How long each provider may take before its prices are left out of a tick
+    pub fn with_provider_timeout(mut self, timeout: Duration) -> Self {
+        self.per_provider_timeout = timeout;
+        self
+    }
+
+    /// Share a health tracker (e.g. `DexManager::health()`) so disabled providers
+    /// are skipped here too
+    pub fn with_health(mut self, health: Arc<ProviderHealth>) -> Self {
+        self.health = health;
+        self
+    }
+
     pub async fn fetch_all_prices(&self, pairs: &[TokenPair]) -> Vec<PriceData> {
+        self.fetch_all_prices_with_report(pairs).await.0
+    }
+
+    /// Fetch from every healthy provider concurrently. A provider that doesn't
+    /// answer within the per-provider timeout is left out of this tick. If two
+    /// providers return the same (DEX, pair), the newest price wins.
+    pub async fn fetch_all_prices_with_report(
+        &self,
+        pairs: &[TokenPair],
+    ) -> (Vec<PriceData>, FetchReport) {
         let start = Instant::now();
+        let timeout = self.per_provider_timeout;
         let mut join_set = JoinSet::new();
 
         // Iterate over providers
         for provider in &self.dex_providers {
+            let dex = provider.dex_type();
+            // Unhealthy providers are skipped, apart from the occasional recovery probe
+            let Some(probe) = self.health.needs_probe(dex) else {
+                continue;
+            };
+
             let provider = provider.clone();
+            let health = self.health.clone();
             let pairs = pairs.to_vec();
 
             // Spawn concurrent task for each provider
             // We use spawn since we want them to run in parallel
-            join_set.spawn(async move { provider.get_prices(&pairs).await });
+            join_set.spawn(async move {
+                if probe && !health.probe(provider.as_ref()).await {
+                    return (dex, None);
+                }
+                let fetch_start = Instant::now();
+                let Ok(batch) = tokio::time::timeout(timeout, provider.get_prices(&pairs)).await
+                else {
+                    tracing::warn!("{:?} timed out after {}ms", dex, timeout.as_millis());
+                    health.record_failure(dex);
+                    let stats = DexFetchStats {
+                        timeout: pairs.len(),
+                        latency: timeout,
+                        ..Default::default()
+                    };
+                    return (dex, Some((PriceBatch::default(), stats)));
+                };
+                for (pair, e) in &batch.errors {
+                    tracing::warn!("{:?} price fetch failed for {}: {}", dex, pair, e);
+                }
+                // Only a batch where every pair failed counts against the provider
+                if batch.is_total_failure() {
+                    health.record_failure(dex);
+                } else {
+                    health.record_success(dex);
+                }
+                let stats = DexFetchStats {
+                    ok: batch.prices.len(),
+                    error: batch.errors.len(),
+                    latency: fetch_start.elapsed(),
+                    ..Default::default()
+                };
+                (dex, Some((batch, stats)))
+            });
         }
 
-        let mut all_prices = Vec::new();
+        let mut latest: HashMap<(DexType, TokenPair), PriceData> = HashMap::new();
+        let mut report = FetchReport::default();
 
         // Collect results
         while let Some(result) = join_set.join_next().await {
             match result {
-                Ok(Ok(prices)) => {
-                    all_prices.extend(prices);
-                }
-                Ok(Err(e)) => {
-                    tracing::warn!("Task error in price fetch: {}", e);
+                Ok((dex, Some((batch, stats)))) => {
+                    let entry = report.per_dex.entry(dex).or_default();
+                    entry.ok += stats.ok;
+                    entry.timeout += stats.timeout;
+                    entry.error += stats.error;
+                    entry.latency = entry.latency.max(stats.latency);
+                    for price in batch.prices {
+                        match latest.entry((price.dex, price.pair.clone())) {
+                            Entry::Occupied(mut e) => {
+                                if price.timestamp > e.get().timestamp {
+                                    e.insert(price);
+                                }
+                            }
+                            Entry::Vacant(e) => {
+                                e.insert(price);
+                            }
+                        }
+                    }
                 }
+                Ok((_, None)) => {}
                 Err(e) => {
                     tracing::error!("Join error in price fetch: {}", e);
                 }
             }
         }
+        let all_prices: Vec<PriceData> = latest.into_values().collect();
 
         tracing::debug!(
             elapsed_ms = start.elapsed().as_millis(),
@@ -52,6 +169,82 @@ impl ParallelPriceFetcher {
             "Parallel price fetch completed"
         );
 
-        all_prices
+        (all_prices, report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::PriceStream;
+    use crate::{ArbitrageError, ArbitrageResult};
+    use async_trait::async_trait;
+    use chrono::{Duration as ChronoDuration, Utc};
+    use rust_decimal::Decimal;
+
+    /// Answers after `delay`, quoting Orca prices stamped `age_secs` ago
+    struct DelayedProvider {
+        delay: Duration,
+        age_secs: i64,
+        bid: Decimal,
+    }
+
+    #[async_trait]
+    impl DexProvider for DelayedProvider {
+        fn dex_type(&self) -> DexType {
+            DexType::Orca
+        }
+
+        async fn get_price(&self, pair: &TokenPair) -> ArbitrageResult<PriceData> {
+            tokio::time::sleep(self.delay).await;
+            let mut price = PriceData::new(DexType::Orca, pair.clone(), self.bid, self.bid);
+            price.timestamp = Utc::now() - ChronoDuration::seconds(self.age_secs);
+            Ok(price)
+        }
+
+        async fn subscribe(&self, _pairs: Vec<TokenPair>) -> ArbitrageResult<PriceStream> {
+            Err(ArbitrageError::WebSocket(
+                "test provider has no stream".to_string(),
+            ))
+        }
+
+        async fn health_check(&self) -> ArbitrageResult<bool> {
+            Ok(true)
+        }
+    }
+
+    fn provider(delay_ms: u64, age_secs: i64, bid: i64) -> Arc<dyn DexProvider> {
+        Arc::new(DelayedProvider {
+            delay: Duration::from_millis(delay_ms),
+            age_secs,
+            bid: Decimal::from(bid),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_slow_provider_dropped_and_duplicates_keep_newest() {
+        let fetcher = ParallelPriceFetcher::new(vec![
+            provider(0, 10, 99),
+            provider(0, 1, 100),
+            provider(5_000, 0, 101),
+        ])
+        .with_provider_timeout(Duration::from_millis(100));
+        let pair = TokenPair::new("SOL", "USDC");
+
+        let start = Instant::now();
+        let (prices, report) = fetcher.fetch_all_prices_with_report(&[pair]).await;
+        assert!(start.elapsed() < Duration::from_secs(2));
+
+        // Both fast providers quote Orca SOL/USDC; the fresher one is kept
+        assert_eq!(prices.len(), 1);
+        assert_eq!(prices[0].bid, Decimal::from(100));
+
+        let stats = report.per_dex[&DexType::Orca];
+        assert_eq!(stats.ok, 2);
+        assert_eq!(stats.timeout, 1);
+        assert_eq!(stats.error, 0);
+        // The slowest provider's time is reported: here, the one that timed out
+        assert_eq!(stats.latency, Duration::from_millis(100));
+        assert_eq!(report.timed_out(), vec![DexType::Orca]);
     }
 }
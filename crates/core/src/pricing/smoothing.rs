@@ -0,0 +1,133 @@
+use crate::types::{DexType, PriceData, TokenPair};
+use crate::{ArbitrageError, ArbitrageResult};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+/// Exponential moving average of each DEX's price for each pair.
+///
+/// Single-tick spikes in a raw feed show up as opportunities that are gone by
+/// the time a trade lands. Detecting on smoothed prices filters those out; the
+/// raw prices are left untouched for everything that must see the market as
+/// it is, such as execution and volatility tracking.
+#[derive(Debug, Clone)]
+pub struct PriceSmoother {
+    factor: Decimal,
+    averages: HashMap<(DexType, TokenPair), PriceData>,
+}
+
+impl PriceSmoother {
+    /// Smoother weighting each new price by `factor`, in (0, 1]: higher tracks
+    /// the market more closely, 1 passes prices through unchanged.
+    pub fn new(factor: Decimal) -> ArbitrageResult<Self> {
+        if factor <= Decimal::ZERO || factor > Decimal::ONE {
+            return Err(ArbitrageError::Config(format!(
+                "price smoothing factor {} is outside (0, 1]",
+                factor
+            )));
+        }
+        Ok(Self {
+            factor,
+            averages: HashMap::new(),
+        })
+    }
+
+    /// Weight given to each new price
+    pub fn factor(&self) -> Decimal {
+        self.factor
+    }
+
+    /// Fold `price` into its DEX and pair's average, returning the smoothed
+    /// price. The first price for a DEX and pair seeds its average as is.
+    pub fn update(&mut self, price: &PriceData) -> PriceData {
+        let key = (price.dex, price.pair.clone());
+        let smoothed = match self.averages.get(&key) {
+            Some(average) => {
+                let ema = |previous: Decimal, latest: Decimal| {
+                    previous + (latest - previous) * self.factor
+                };
+                PriceData {
+                    bid: ema(average.bid, price.bid),
+                    ask: ema(average.ask, price.ask),
+                    mid_price: ema(average.mid_price, price.mid_price),
+                    ..price.clone()
+                }
+            }
+            None => price.clone(),
+        };
+        self.averages.insert(key, smoothed.clone());
+        smoothed
+    }
+
+    /// Smoothed counterpart of each of `prices`, in order
+    pub fn smooth(&mut self, prices: &[PriceData]) -> Vec<PriceData> {
+        prices.iter().map(|price| self.update(price)).collect()
+    }
+
+    /// Current average for `dex` on `pair`, if it has seen a price
+    pub fn smoothed(&self, dex: DexType, pair: &TokenPair) -> Option<&PriceData> {
+        self.averages.get(&(dex, pair.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(dex: DexType, mid: Decimal) -> PriceData {
+        PriceData::new(
+            dex,
+            TokenPair::new("SOL", "USDC"),
+            mid - Decimal::ONE,
+            mid + Decimal::ONE,
+        )
+    }
+
+    #[test]
+    fn test_average_reacts_to_a_step_and_converges() {
+        let mut smoother = PriceSmoother::new(Decimal::new(5, 1)).unwrap();
+        let hundred = Decimal::from(100);
+        for _ in 0..3 {
+            let steady = smoother.update(&price(DexType::Raydium, hundred));
+            assert_eq!(steady.mid_price, hundred);
+        }
+
+        // A step to 110 moves the average halfway each tick
+        let stepped = Decimal::from(110);
+        let mids: Vec<Decimal> = (0..3)
+            .map(|_| smoother.update(&price(DexType::Raydium, stepped)).mid_price)
+            .collect();
+        assert_eq!(
+            mids,
+            [
+                Decimal::new(105, 0),
+                Decimal::new(1075, 1),
+                Decimal::new(10875, 2)
+            ]
+        );
+        let smoothed = smoother.update(&price(DexType::Raydium, stepped));
+        assert_eq!(smoothed.ask - smoothed.bid, Decimal::TWO);
+
+        for _ in 0..30 {
+            smoother.update(&price(DexType::Raydium, stepped));
+        }
+        let pair = TokenPair::new("SOL", "USDC");
+        let converged = smoother.smoothed(DexType::Raydium, &pair).unwrap();
+        assert!((converged.mid_price - stepped).abs() < Decimal::new(1, 6));
+
+        // Each DEX keeps its own average
+        let orca = smoother.update(&price(DexType::Orca, hundred));
+        assert_eq!(orca.mid_price, hundred);
+    }
+
+    #[test]
+    fn test_factor_must_be_a_fraction() {
+        assert!(PriceSmoother::new(Decimal::ZERO).is_err());
+        assert!(PriceSmoother::new(Decimal::TWO).is_err());
+
+        // A factor of one passes prices through unchanged
+        let mut smoother = PriceSmoother::new(Decimal::ONE).unwrap();
+        smoother.update(&price(DexType::Orca, Decimal::from(100)));
+        let raw = price(DexType::Orca, Decimal::from(120));
+        assert_eq!(smoother.update(&raw).mid_price, raw.mid_price);
+    }
+}
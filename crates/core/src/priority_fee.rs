@@ -0,0 +1,164 @@
+//! Priority-fee estimation from recent network fees
+//!
+//! When enabled, the compute-unit price is derived from
+//! `getRecentPrioritizationFees` for the accounts a route touches: the
+//! configured percentile of the recent fees, clamped to `[min, max]`. Estimates
+//! are cached per account set for a short TTL so back-to-back trades on the same
+//! route don't each cost an RPC round trip. The static fee is used whenever
+//! estimation is disabled, the query fails, or it returns no samples.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{ArbitrageError, ArbitrageResult};
+
+/// Settings for [`PriorityFeeOracle`]
+#[derive(Debug, Clone)]
+pub struct PriorityFeeConfig {
+    /// Estimate from recent fees instead of always using `static_fee`
+    pub dynamic: bool,
+    /// Percentile (0-100) of recent fees to pay
+    pub percentile: u8,
+    /// Lower bound for the estimate (micro-lamports per CU)
+    pub min_fee: u64,
+    /// Upper bound for the estimate (micro-lamports per CU)
+    pub max_fee: u64,
+    /// How long an estimate is reused for the same account set
+    pub cache_ttl: Duration,
+    /// Fee used when estimation is disabled or unavailable
+    pub static_fee: u64,
+}
+
+impl Default for PriorityFeeConfig {
+    fn default() -> Self {
+        Self {
+            dynamic: false,
+            percentile: 75,
+            min_fee: 10_000,
+            max_fee: 1_000_000,
+            cache_ttl: Duration::from_millis(2_000),
+            static_fee: 50_000,
+        }
+    }
+}
+
+/// Turns recent prioritization fees into a compute-unit price
+#[derive(Debug)]
+pub struct PriorityFeeOracle {
+    config: PriorityFeeConfig,
+    cache: Mutex<HashMap<Vec<Pubkey>, (Instant, u64)>>,
+}
+
+impl PriorityFeeOracle {
+    pub fn new(config: PriorityFeeConfig) -> Self {
+        Self {
+            config,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether fees should be estimated from the network at all
+    pub fn is_dynamic(&self) -> bool {
+        self.config.dynamic
+    }
+
+    /// The configured static fee
+    pub fn fallback(&self) -> u64 {
+        self.config.static_fee
+    }
+
+    /// Replace the static fee, e.g. on a config reload
+    pub fn set_static_fee(&mut self, fee: u64) {
+        self.config.static_fee = fee;
+    }
+
+    /// A still-fresh estimate for `accounts`, if one is cached
+    pub fn cached(&self, accounts: &[Pubkey]) -> Option<u64> {
+        self.cache
+            .lock()
+            .unwrap()
+            .get(accounts)
+            .filter(|(at, _)| at.elapsed() < self.config.cache_ttl)
+            .map(|(_, fee)| *fee)
+    }
+
+    /// Query `getRecentPrioritizationFees` for `accounts` and record the estimate
+    /// (see [`PriorityFeeOracle::record`])
+    pub async fn fetch(&self, client: &RpcClient, accounts: &[Pubkey]) -> ArbitrageResult<u64> {
+        let samples = client
+            .get_recent_prioritization_fees(accounts)
+            .await
+            .map_err(|e| ArbitrageError::RpcError(e.to_string()))?;
+        let fees = samples.iter().map(|s| s.prioritization_fee).collect();
+        Ok(self.record(accounts, fees))
+    }
+
+    /// Compute the fee from recent samples for `accounts` and cache it.
+    /// With no samples the static fee is returned and nothing is cached.
+    pub fn record(&self, accounts: &[Pubkey], mut fees: Vec<u64>) -> u64 {
+        let Some(fee) = percentile(&mut fees, self.config.percentile) else {
+            return self.config.static_fee;
+        };
+        let fee = fee.clamp(
+            self.config.min_fee,
+            self.config.max_fee.max(self.config.min_fee),
+        );
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(accounts.to_vec(), (Instant::now(), fee));
+        fee
+    }
+}
+
+/// Nearest-rank percentile of `values` (sorted in place); `None` when empty
+pub fn percentile(values: &mut [u64], pct: u8) -> Option<u64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    let pct = pct.min(100) as usize;
+    let rank = (pct * values.len()).div_ceil(100).max(1);
+    Some(values[rank - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_nearest_rank() {
+        let mut fees = vec![50, 10, 40, 20, 30];
+        assert_eq!(percentile(&mut fees, 0), Some(10));
+        assert_eq!(percentile(&mut fees, 50), Some(30));
+        assert_eq!(percentile(&mut fees, 75), Some(40));
+        assert_eq!(percentile(&mut fees, 100), Some(50));
+        assert_eq!(percentile(&mut [], 75), None);
+    }
+
+    #[test]
+    fn test_record_clamps_caches_and_falls_back() {
+        let oracle = PriorityFeeOracle::new(PriorityFeeConfig {
+            dynamic: true,
+            min_fee: 1_000,
+            max_fee: 100_000,
+            static_fee: 7_777,
+            ..Default::default()
+        });
+        let accounts = vec![Pubkey::new_unique()];
+
+        assert_eq!(oracle.cached(&accounts), None);
+        assert_eq!(oracle.record(&accounts, vec![0, 0, 0, 10]), 1_000);
+        assert_eq!(oracle.cached(&accounts), Some(1_000));
+        assert_eq!(oracle.record(&accounts, vec![5_000_000]), 100_000);
+
+        // No samples: static fee, cache untouched
+        let other = vec![Pubkey::new_unique()];
+        assert_eq!(oracle.record(&other, Vec::new()), 7_777);
+        assert_eq!(oracle.cached(&other), None);
+    }
+}
@@ -1,11 +1,12 @@
 //! Token-bucket rate limiter for RPC and API calls
 //!
 //! Prevents exceeding rate limits on external services like Solana RPC,
-//! Jupiter API, and Jito block engine.
+//! Jupiter API, and Jito block engine. [`ConcurrencyLimiter`] complements it
+//! by capping how many requests may be in flight at the same time.
 
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
 
 /// Rate limiter using a sliding window approach
 #[derive(Debug)]
@@ -110,6 +111,89 @@ impl RateLimiter {
     }
 }
 
+/// Callback invoked with the new in-flight count whenever a permit is taken or released
+pub type InFlightObserver = Arc<dyn Fn(usize) + Send + Sync>;
+
+/// Caps the number of concurrent requests against a shared resource (e.g. the RPC node)
+#[derive(Clone)]
+pub struct ConcurrencyLimiter {
+    semaphore: Arc<Semaphore>,
+    max_concurrent: usize,
+    observer: Option<InFlightObserver>,
+}
+
+impl std::fmt::Debug for ConcurrencyLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConcurrencyLimiter")
+            .field("max_concurrent", &self.max_concurrent)
+            .field("in_flight", &self.in_flight())
+            .finish()
+    }
+}
+
+impl ConcurrencyLimiter {
+    /// Create a limiter allowing at most `max_concurrent` requests in flight (minimum 1)
+    pub fn new(max_concurrent: usize) -> Self {
+        let max_concurrent = max_concurrent.max(1);
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent,
+            observer: None,
+        }
+    }
+
+    /// Report in-flight changes to `observer` (used to drive a metrics gauge)
+    pub fn with_observer(mut self, observer: impl Fn(usize) + Send + Sync + 'static) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Wait for a free slot. The slot is held until the returned permit is dropped.
+    pub async fn acquire(&self) -> ConcurrencyPermit {
+        let permit = self
+            .semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("concurrency limiter semaphore is never closed");
+        self.notify();
+        ConcurrencyPermit {
+            permit: Some(permit),
+            limiter: self.clone(),
+        }
+    }
+
+    /// Number of requests currently holding a permit
+    pub fn in_flight(&self) -> usize {
+        self.max_concurrent - self.semaphore.available_permits()
+    }
+
+    /// Configured maximum number of concurrent requests
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+
+    fn notify(&self) {
+        if let Some(observer) = &self.observer {
+            observer(self.in_flight());
+        }
+    }
+}
+
+/// Slot held in a [`ConcurrencyLimiter`]; released on drop
+pub struct ConcurrencyPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    limiter: ConcurrencyLimiter,
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        // Release first so the observer sees the updated count
+        self.permit.take();
+        self.limiter.notify();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -156,4 +240,28 @@ mod tests {
 
         assert!(limiter.try_acquire().await); // Should work now
     }
+
+    #[tokio::test]
+    async fn test_concurrency_limiter_caps_in_flight() {
+        let seen = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let seen_clone = seen.clone();
+        let limiter = ConcurrencyLimiter::new(2)
+            .with_observer(move |n| seen_clone.store(n, std::sync::atomic::Ordering::SeqCst));
+
+        let first = limiter.acquire().await;
+        let _second = limiter.acquire().await;
+        assert_eq!(limiter.in_flight(), 2);
+        assert_eq!(seen.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        // A third request waits until a slot frees up
+        let blocked = tokio::time::timeout(Duration::from_millis(20), limiter.acquire()).await;
+        assert!(blocked.is_err());
+
+        drop(first);
+        assert_eq!(limiter.in_flight(), 1);
+        assert_eq!(seen.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let _third = limiter.acquire().await;
+        assert_eq!(limiter.in_flight(), 2);
+    }
 }
@@ -3,13 +3,18 @@
 //! Implements position sizing, exposure limits, and circuit breakers
 //! for safe automated trading.
 
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use crate::error::ArbitrageResult;
 use crate::events::{EventBus, TradingEvent};
 
 pub mod circuit_breaker;
+pub mod dedupe;
 pub mod var;
 pub mod volatility;
 
@@ -22,12 +27,35 @@ pub struct RiskConfig {
     pub max_total_exposure: Decimal,
     /// Maximum loss per day before circuit breaker triggers
     pub max_daily_loss: Decimal,
+    /// Maximum fall of the day's P&L from its intraday peak before trading halts
+    /// (zero = no drawdown limit)
+    pub max_drawdown: Decimal,
     /// Minimum profit threshold to execute a trade
     pub min_profit_threshold: Decimal,
     /// Maximum slippage tolerance percentage
     pub max_slippage: Decimal,
     /// Cool-down period after a loss (seconds)
     pub loss_cooldown_seconds: i64,
+    /// Fraction of the requested size allowed while recovering from a circuit-breaker trip
+    pub recovery_size_fraction: Decimal,
+    /// Number of trades after the circuit breaker closes that run at reduced size
+    pub recovery_trades: u32,
+    /// How long executed opportunities and blacklisted routes are remembered (seconds)
+    pub dedupe_window_seconds: i64,
+    /// Absolute ceiling on any single trade in USD, applied after every other limit
+    pub hard_max_trade_usd: Decimal,
+    /// Fraction of the full Kelly bet used by `calculate_kelly_size` (0.5 = half Kelly)
+    pub kelly_fraction: Decimal,
+    /// Confidence level of the reported portfolio VaR (0.95 or 0.99)
+    pub confidence_level: f64,
+    /// Whether consecutive failed trades open the circuit breaker
+    pub circuit_breaker_enabled: bool,
+    /// Consecutive failed trades that open the circuit breaker
+    pub circuit_failure_threshold: usize,
+    /// Consecutive successful test trades that close it again
+    pub circuit_success_threshold: usize,
+    /// How long the breaker stays open before allowing a test trade (seconds)
+    pub circuit_timeout_seconds: u64,
 }
 
 impl Default for RiskConfig {
@@ -36,13 +64,43 @@ impl Default for RiskConfig {
             max_position_size: Decimal::from(1000), // $1,000 max per trade
             max_total_exposure: Decimal::from(5000), // $5,000 total exposure
             max_daily_loss: Decimal::from(100),     // $100 daily loss limit
+            max_drawdown: Decimal::ZERO,            // No drawdown limit
             min_profit_threshold: Decimal::new(5, 3), // 0.5% min profit
             max_slippage: Decimal::new(1, 2),       // 1% max slippage
             loss_cooldown_seconds: 300,             // 5 minute cooldown
+            recovery_size_fraction: Decimal::new(5, 1), // 50% size while recovering
+            recovery_trades: 5,                     // for 5 trades after recovery
+            dedupe_window_seconds: 120,             // 2 minute dedupe window
+            hard_max_trade_usd: Decimal::from(2000), // $2,000 absolute ceiling
+            kelly_fraction: Decimal::new(5, 1),     // half Kelly
+            confidence_level: 0.95,                 // 95% VaR
+            circuit_breaker_enabled: true,
+            circuit_failure_threshold: 3,           // 3 failures open the breaker,
+            circuit_success_threshold: 5,           // 5 successes close it,
+            circuit_timeout_seconds: 300,           // after a 5 minute timeout
         }
     }
 }
 
+impl RiskConfig {
+    /// Clamp `size` to `hard_max_trade_usd`, logging loudly when the cap binds
+    pub fn clamp_to_hard_cap(&self, size: Decimal) -> Decimal {
+        if size > self.hard_max_trade_usd {
+            tracing::warn!(
+                "🚨 HARD TRADE CAP: size ${} clamped to ${} - check sizing inputs",
+                size,
+                self.hard_max_trade_usd
+            );
+            return self.hard_max_trade_usd;
+        }
+        size
+    }
+}
+
+/// Weight, in trades, given to the caller's win probability when blending it
+/// with a pair's recorded win rate in `calculate_kelly_size`
+const KELLY_PRIOR_TRADES: u32 = 10;
+
 /// Trade outcome for tracking
 #[derive(Debug, Clone)]
 pub struct TradeOutcome {
@@ -52,13 +110,45 @@ pub struct TradeOutcome {
     pub was_successful: bool,
 }
 
+/// Daily P&L state persisted across restarts, so a crash can't reset the loss limits
+#[derive(Debug, Serialize, Deserialize)]
+struct DailyRiskState {
+    /// UTC day the figures belong to
+    day: NaiveDate,
+    daily_pnl: Decimal,
+    peak_pnl: Decimal,
+    kill_switch: Option<String>,
+}
+
+/// A trade whose legs haven't all settled, tracked from when it was opened
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OpenPosition {
+    /// Identifies the trade, e.g. its opportunity id
+    pub id: String,
+    pub pair: String,
+    pub size: Decimal,
+    pub opened_at: DateTime<Utc>,
+}
+
 /// Risk manager for controlling trade execution
 pub struct RiskManager {
     config: RiskConfig,
     /// Current open positions by pair
     positions: HashMap<String, Decimal>,
+    /// Trades in flight (or stranded mid-way) by id
+    open_positions: HashMap<String, OpenPosition>,
     /// Trade history for the current day
     daily_trades: Vec<TradeOutcome>,
+    /// UTC day the daily figures belong to
+    trading_day: NaiveDate,
+    /// P&L from trades before a restart, reloaded from the state file
+    carried_pnl: Decimal,
+    /// Highest daily P&L reached today, for the drawdown limit
+    peak_pnl: Decimal,
+    /// Why trading is halted for the rest of the day, once a loss limit is hit
+    kill_switch: Option<String>,
+    /// Where the daily state is persisted, if anywhere
+    state_path: Option<PathBuf>,
     /// Timestamp of last loss
     last_loss_time: Option<DateTime<Utc>>,
     /// Circuit breaker
@@ -67,88 +157,195 @@ pub struct RiskManager {
     pub volatility_tracker: volatility::VolatilityTracker,
     /// VaR calculator
     pub var_calculator: var::VarCalculator,
+    /// Recently executed opportunities and blacklisted routes
+    pub dedupe: dedupe::ExecutionDedupe,
     /// Event bus for publishing risk events
     event_bus: Option<Arc<EventBus>>,
 }
 
 impl RiskManager {
     pub fn new(config: RiskConfig) -> Self {
+        // With the breaker disabled only an explicit trip (daily loss limit) opens it
+        let failure_threshold = if config.circuit_breaker_enabled {
+            config.circuit_failure_threshold
+        } else {
+            usize::MAX
+        };
+        let circuit_breaker = circuit_breaker::CircuitBreaker::new(
+            failure_threshold,
+            config.circuit_success_threshold,
+            config.circuit_timeout_seconds,
+        )
+        .with_recovery_trades(config.recovery_trades as usize);
+        let dedupe = dedupe::ExecutionDedupe::new(config.dedupe_window_seconds);
+        let var_calculator = var::VarCalculator::new(config.confidence_level);
+
         Self {
             config,
             positions: HashMap::new(),
+            open_positions: HashMap::new(),
             daily_trades: Vec::new(),
+            trading_day: Utc::now().date_naive(),
+            carried_pnl: Decimal::ZERO,
+            peak_pnl: Decimal::ZERO,
+            kill_switch: None,
+            state_path: None,
             last_loss_time: None,
-            circuit_breaker: circuit_breaker::CircuitBreaker::new(3, 5, 300), // 3 failures, 5 successes, 5 min timeout
+            circuit_breaker,
             volatility_tracker: volatility::VolatilityTracker::new(20), // 20-period moving average
-            var_calculator: var::VarCalculator::new(0.95),              // 95% confidence
+            var_calculator,
+            dedupe,
             event_bus: None,
         }
     }
 
+    /// Persist the dedupe seen-set and blacklist to `path`, reloading any existing state
+    pub fn with_dedupe_file(mut self, path: impl AsRef<std::path::Path>) -> Self {
+        self.dedupe = dedupe::ExecutionDedupe::load(path, self.config.dedupe_window_seconds);
+        self
+    }
+
+    /// Persist the daily P&L and kill switch to `path` after every trade, reloading
+    /// today's figures if the file exists
+    pub fn with_state_file(mut self, path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Err(e) = self.load_state(&path) {
+            tracing::warn!("Ignoring unreadable risk state {}: {}", path.display(), e);
+        }
+        self.state_path = Some(path);
+        self
+    }
+
+    /// Restore the daily figures saved by [`RiskManager::save_state`]. A missing file
+    /// or one from an earlier UTC day leaves a fresh day.
+    pub fn load_state(&mut self, path: impl AsRef<Path>) -> ArbitrageResult<()> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let state: DailyRiskState = serde_json::from_str(&contents)?;
+        if state.day != Utc::now().date_naive() {
+            return Ok(());
+        }
+
+        self.start_day(state.day);
+        self.carried_pnl = state.daily_pnl;
+        self.peak_pnl = state.peak_pnl;
+        self.kill_switch = state.kill_switch;
+        if let Some(reason) = &self.kill_switch {
+            tracing::warn!("🛑 Trading stays halted until UTC midnight: {}", reason);
+        }
+        Ok(())
+    }
+
+    /// Write today's P&L, its peak and the kill switch to `path`
+    pub fn save_state(&self, path: impl AsRef<Path>) -> ArbitrageResult<()> {
+        let path = path.as_ref();
+        let state = DailyRiskState {
+            day: self.trading_day,
+            daily_pnl: self.daily_pnl(),
+            peak_pnl: self.peak_pnl,
+            kill_switch: self.kill_switch.clone(),
+        };
+        // Write then rename so a crash mid-write never leaves a truncated file
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_string(&state)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    fn persist(&self) {
+        if let Some(path) = &self.state_path {
+            if let Err(e) = self.save_state(path) {
+                tracing::warn!("Failed to persist risk state {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Clear the daily figures and kill switch for a new trading day
+    fn start_day(&mut self, day: NaiveDate) {
+        self.trading_day = day;
+        self.daily_trades.clear();
+        self.carried_pnl = Decimal::ZERO;
+        self.peak_pnl = Decimal::ZERO;
+        self.kill_switch = None;
+    }
+
+    /// Kill switch reason, unless it was set on an earlier UTC day
+    fn active_kill_switch(&self, now: DateTime<Utc>) -> Option<&String> {
+        self.kill_switch
+            .as_ref()
+            .filter(|_| self.trading_day == now.date_naive())
+    }
+
     pub async fn set_event_bus(&mut self, event_bus: Arc<EventBus>) {
         self.event_bus = Some(event_bus.clone());
         self.circuit_breaker.set_event_bus(event_bus).await;
     }
 
     /// Check if a trade is allowed under current risk parameters
-    pub async fn can_trade(&self, _pair: &str, size: Decimal) -> TradeDecision {
-        // Check circuit breaker
-        if !self.circuit_breaker.can_execute().await {
-            let reason = "Circuit breaker OPEN - trading halted".to_string();
-            if let Some(bus) = &self.event_bus {
-                 bus.publish(TradingEvent::TradeRejected {
-                     id: "pre-check".to_string(), // No opp ID here yet
-                     reason: reason.clone(),
-                 });
-            }
-            return TradeDecision::Rejected { reason };
+    pub async fn can_trade(&self, _pair: &str, requested_size: Decimal) -> TradeDecision {
+        let decision = self.snapshot().await.evaluate(requested_size);
+        if let (TradeDecision::Rejected { reason }, Some(bus)) = (&decision, &self.event_bus) {
+            bus.publish(TradingEvent::TradeRejected {
+                id: "pre-check".to_string(), // No opp ID here yet
+                reason: reason.clone(),
+            });
         }
+        decision
+    }
 
-        // Check cooldown after loss
-        if let Some(last_loss) = self.last_loss_time {
-            let cooldown = Duration::seconds(self.config.loss_cooldown_seconds);
-            if Utc::now() - last_loss < cooldown {
-                let remaining = (last_loss + cooldown - Utc::now()).num_seconds();
-                let reason = format!("Cooldown active - {} seconds remaining", remaining);
-                if let Some(bus) = &self.event_bus {
-                     bus.publish(TradingEvent::TradeRejected {
-                         id: "pre-check".to_string(),
-                         reason: reason.clone(),
-                     });
-                }
-                return TradeDecision::Rejected { reason };
-            }
-        }
+    /// Capture exposure, daily P&L and circuit state once so many candidates can be
+    /// evaluated without re-reading (and re-locking) the live risk manager
+    pub async fn snapshot(&self) -> RiskSnapshot {
+        let taken_at = Utc::now();
+        let kill_switch = self.active_kill_switch(taken_at).cloned();
+        // can_execute may move Open -> HalfOpen, so check it before reading the state
+        let trading_halted = !self.circuit_breaker.can_execute().await || kill_switch.is_some();
+        let recovery_trades_remaining = if self.circuit_breaker.in_recovery().await {
+            self.circuit_breaker.recovery_trades_remaining().await
+        } else {
+            0
+        };
 
-        // Check position size limit
-        if size > self.config.max_position_size {
-            return TradeDecision::Reduced {
-                new_size: self.config.max_position_size,
-                reason: "Size reduced to max position limit".to_string(),
-            };
+        RiskSnapshot {
+            taken_at,
+            kill_switch,
+            total_exposure: self.total_exposure(),
+            daily_pnl: self.daily_pnl(),
+            circuit_state: self.circuit_breaker.state().await,
+            trading_halted,
+            circuit_cooldown_remaining: self.circuit_breaker.cooldown_remaining().await,
+            recovery_trades_remaining,
+            cooldown_until: self
+                .last_loss_time
+                .map(|t| t + Duration::seconds(self.config.loss_cooldown_seconds)),
+            config: self.config.clone(),
         }
+    }
 
-        // Check total exposure
-        let current_exposure: Decimal = self.positions.values().sum();
-        if current_exposure + size > self.config.max_total_exposure {
-            let available = self.config.max_total_exposure - current_exposure;
-            if available <= Decimal::ZERO {
-                let reason = "Maximum exposure limit reached".to_string();
-                if let Some(bus) = &self.event_bus {
-                     bus.publish(TradingEvent::TradeRejected {
-                         id: "pre-check".to_string(),
-                         reason: reason.clone(),
-                     });
-                }
-                return TradeDecision::Rejected { reason };
+    /// Re-validate a trade against the live state and, if allowed, reserve its size as
+    /// exposure on `pair`. Call [`RiskManager::release`] once the trade has settled.
+    pub async fn commit(&mut self, pair: &str, requested_size: Decimal) -> TradeDecision {
+        let decision = self.can_trade(pair, requested_size).await;
+        match &decision {
+            TradeDecision::Approved { size } | TradeDecision::Reduced { new_size: size, .. } => {
+                let reserved = self.positions.get(pair).copied().unwrap_or_default() + *size;
+                self.update_position(pair, reserved);
             }
-            return TradeDecision::Reduced {
-                new_size: available,
-                reason: "Size reduced due to exposure limit".to_string(),
-            };
+            TradeDecision::Rejected { .. } => {}
         }
+        decision
+    }
 
-        TradeDecision::Approved { size }
+    /// Release exposure previously reserved by [`RiskManager::commit`]
+    pub fn release(&mut self, pair: &str, size: Decimal) {
+        let remaining = self.positions.get(pair).copied().unwrap_or_default() - size;
+        self.update_position(pair, remaining.max(Decimal::ZERO));
     }
 
     /// Calculate optimal position size based on risk parameters and volatility
@@ -186,32 +383,95 @@ impl RiskManager {
         let calculated = base_size * profit_factor;
 
         // Don't exceed liquidity
-        calculated
+        let size = calculated
             .min(available_liquidity)
-            .min(self.config.max_position_size)
+            .min(self.config.max_position_size);
+
+        // Last line of defence against a sizing bug
+        self.config.clamp_to_hard_cap(size)
+    }
+
+    /// Size a trade with fractional Kelly.
+    ///
+    /// `edge` is the expected profit percentage of a winning trade; a losing
+    /// trade is assumed to cost `max_slippage` of its size. `win_prob` is blended
+    /// with the pair's recorded win rate, the history counting for more as
+    /// trades accumulate. Returns zero when there is no edge.
+    pub fn calculate_kelly_size(&self, pair: &str, edge: Decimal, win_prob: f64) -> Decimal {
+        if edge <= Decimal::ZERO || self.config.max_slippage <= Decimal::ZERO {
+            return Decimal::ZERO;
+        }
+
+        let prior = Decimal::try_from(win_prob.clamp(0.0, 1.0)).unwrap_or_default();
+        let (wins, trades) = self
+            .daily_trades
+            .iter()
+            .filter(|t| t.pair == pair)
+            .fold((0u32, 0u32), |(wins, trades), t| {
+                let won = t.was_successful && t.profit_loss > Decimal::ZERO;
+                (wins + won as u32, trades + 1)
+            });
+        let p = (Decimal::from(wins) + prior * Decimal::from(KELLY_PRIOR_TRADES))
+            / Decimal::from(trades + KELLY_PRIOR_TRADES);
+
+        // f* = p - (1 - p) / b, with b the win/loss payoff ratio
+        let payoff = match (edge / Decimal::ONE_HUNDRED).checked_div(self.config.max_slippage) {
+            Some(b) if !b.is_zero() => b,
+            _ => return Decimal::ZERO,
+        };
+        let kelly = p - (Decimal::ONE - p) / payoff;
+        if kelly <= Decimal::ZERO {
+            return Decimal::ZERO;
+        }
+
+        let size = (self.config.max_position_size * self.config.kelly_fraction * kelly)
+            .min(self.config.max_position_size);
+        self.config.clamp_to_hard_cap(size)
     }
 
     /// Record a trade outcome
     pub async fn record_trade(&mut self, outcome: TradeOutcome) {
+        let today = Utc::now().date_naive();
+        if today != self.trading_day {
+            self.start_day(today);
+        }
+
         if outcome.profit_loss < Decimal::ZERO {
             self.last_loss_time = Some(outcome.timestamp);
-            self.circuit_breaker.record_failure().await;
-        } else {
+        }
+        // A failed trade counts against the breaker even if it cost nothing
+        if outcome.was_successful && outcome.profit_loss >= Decimal::ZERO {
             self.circuit_breaker.record_success().await;
+        } else {
+            self.circuit_breaker.record_failure().await;
         }
 
         self.daily_trades.push(outcome);
 
-        // Check if daily loss limit exceeded
-        let daily_pnl: Decimal = self.daily_trades.iter().map(|t| t.profit_loss).sum();
-        if daily_pnl < -self.config.max_daily_loss {
-            // Force open circuit breaker
-            // In a real impl, we'd have a specific method for this
-            // For now, we simulate by recording enough failures
-            for _ in 0..3 {
-                self.circuit_breaker.record_failure().await;
+        // Halt for the rest of the day once a loss limit is hit
+        let daily_pnl = self.daily_pnl();
+        self.peak_pnl = self.peak_pnl.max(daily_pnl);
+        let drawdown = self.peak_pnl - daily_pnl;
+        if self.kill_switch.is_none() {
+            if daily_pnl < -self.config.max_daily_loss {
+                self.kill_switch = Some(format!(
+                    "Daily loss limit hit: ${} lost (limit ${})",
+                    -daily_pnl, self.config.max_daily_loss
+                ));
+            } else if self.config.max_drawdown > Decimal::ZERO
+                && drawdown > self.config.max_drawdown
+            {
+                self.kill_switch = Some(format!(
+                    "Drawdown limit hit: ${} below today's peak (limit ${})",
+                    drawdown, self.config.max_drawdown
+                ));
+            }
+            if let Some(reason) = &self.kill_switch {
+                tracing::error!("🛑 {} - trading halted until UTC midnight", reason);
+                self.circuit_breaker.trip().await;
             }
         }
+        self.persist();
     }
 
     /// Update position tracking
@@ -223,14 +483,55 @@ impl RiskManager {
         }
     }
 
+    /// Start tracking trade `id` on `pair` as open from now on
+    pub fn open_position(&mut self, id: impl Into<String>, pair: &str, size: Decimal) {
+        let id = id.into();
+        self.open_positions.insert(
+            id.clone(),
+            OpenPosition {
+                id,
+                pair: pair.to_string(),
+                size,
+                opened_at: Utc::now(),
+            },
+        );
+    }
+
+    /// Stop tracking trade `id` once all its legs have settled
+    pub fn close_position(&mut self, id: &str) -> Option<OpenPosition> {
+        self.open_positions.remove(id)
+    }
+
+    /// Positions open for longer than `max_age`, oldest first: legs that never
+    /// completed and may need flattening by hand
+    pub fn stale_positions(&self, max_age: Duration) -> Vec<OpenPosition> {
+        self.stale_positions_at(Utc::now(), max_age)
+    }
+
+    /// [`RiskManager::stale_positions`] as of `now`
+    pub fn stale_positions_at(&self, now: DateTime<Utc>, max_age: Duration) -> Vec<OpenPosition> {
+        let mut stale: Vec<OpenPosition> = self
+            .open_positions
+            .values()
+            .filter(|position| now - position.opened_at > max_age)
+            .cloned()
+            .collect();
+        stale.sort_by_key(|position| position.opened_at);
+        stale
+    }
+
     /// Get current total exposure
     pub fn total_exposure(&self) -> Decimal {
         self.positions.values().sum()
     }
 
-    /// Get daily P&L
+    /// Get daily P&L, including trades recorded before a restart
     pub fn daily_pnl(&self) -> Decimal {
-        self.daily_trades.iter().map(|t| t.profit_loss).sum()
+        if self.trading_day != Utc::now().date_naive() {
+            return Decimal::ZERO;
+        }
+        let recorded: Decimal = self.daily_trades.iter().map(|t| t.profit_loss).sum();
+        self.carried_pnl + recorded
     }
 
     /// Update price data for volatility tracking
@@ -245,7 +546,8 @@ impl RiskManager {
 
     /// Reset daily statistics (call at start of new trading day)
     pub async fn reset_daily(&mut self) {
-        self.daily_trades.clear();
+        self.start_day(Utc::now().date_naive());
+        self.persist();
         // Note: Circuit breaker state is persistent across days unless manually reset
         // Here we might want to reset it if it was triggered by daily loss
         // For now, allow it to remain as is
@@ -253,22 +555,32 @@ impl RiskManager {
 
     /// Check if trading is currently paused
     pub async fn is_paused(&self) -> bool {
-        !self.circuit_breaker.can_execute().await
+        !self.circuit_breaker.can_execute().await || self.active_kill_switch(Utc::now()).is_some()
     }
 
     /// Get current risk status
     pub async fn status(&self) -> RiskStatus {
-        let var = self
+        let (var, unestimated) = self
             .var_calculator
             .calculate_portfolio_var(&self.positions, &self.volatility_tracker);
+        if !unestimated.is_empty() {
+            tracing::debug!("VaR excludes pairs without volatility: {:?}", unestimated);
+        }
+
+        let mut open_positions: Vec<OpenPosition> = self.open_positions.values().cloned().collect();
+        open_positions.sort_by_key(|position| position.opened_at);
 
         RiskStatus {
             total_exposure: self.total_exposure(),
             daily_pnl: self.daily_pnl(),
             portfolio_var: var,
+            var_confidence_level: self.var_calculator.confidence_level(),
+            var_unestimated_pairs: unestimated,
             trades_today: self.daily_trades.len(),
             is_paused: self.is_paused().await,
+            kill_switch: self.active_kill_switch(Utc::now()).cloned(),
             positions: self.positions.clone(),
+            open_positions,
         }
     }
 }
@@ -281,15 +593,135 @@ pub enum TradeDecision {
     Rejected { reason: String },
 }
 
-/// Current risk status
+/// Point-in-time view of the risk manager, taken once per tick
 #[derive(Debug, Clone)]
+pub struct RiskSnapshot {
+    pub taken_at: DateTime<Utc>,
+    /// Why trading is halted until UTC midnight, if a daily loss limit was hit
+    pub kill_switch: Option<String>,
+    pub total_exposure: Decimal,
+    pub daily_pnl: Decimal,
+    pub circuit_state: circuit_breaker::CircuitState,
+    /// Circuit breaker is refusing trades
+    pub trading_halted: bool,
+    /// Time until an open circuit breaker allows a test trade
+    pub circuit_cooldown_remaining: Option<std::time::Duration>,
+    /// Reduced-size trades left after the breaker closed (0 when not recovering)
+    pub recovery_trades_remaining: usize,
+    cooldown_until: Option<DateTime<Utc>>,
+    config: RiskConfig,
+}
+
+impl RiskSnapshot {
+    /// Capital that can still be committed before hitting the total exposure limit
+    pub fn available_exposure(&self) -> Decimal {
+        (self.config.max_total_exposure - self.total_exposure).max(Decimal::ZERO)
+    }
+
+    /// Decide on a trade using only the snapshot. Exposure may have moved since it was
+    /// taken, so the final say belongs to [`RiskManager::commit`].
+    pub fn evaluate(&self, requested_size: Decimal) -> TradeDecision {
+        // The hard cap has the last word, whatever the other limits allowed
+        let cap = self.config.hard_max_trade_usd;
+        match self.evaluate_limits(requested_size) {
+            TradeDecision::Approved { size } | TradeDecision::Reduced { new_size: size, .. }
+                if size > cap =>
+            {
+                TradeDecision::Reduced {
+                    new_size: self.config.clamp_to_hard_cap(size),
+                    reason: format!("Size clamped to hard trade cap ${}", cap),
+                }
+            }
+            decision => decision,
+        }
+    }
+
+    fn evaluate_limits(&self, requested_size: Decimal) -> TradeDecision {
+        if let Some(reason) = &self.kill_switch {
+            return TradeDecision::Rejected {
+                reason: reason.clone(),
+            };
+        }
+
+        // Check circuit breaker
+        if self.trading_halted {
+            return TradeDecision::Rejected {
+                reason: "Circuit breaker OPEN - trading halted".to_string(),
+            };
+        }
+
+        // Check cooldown after loss
+        if let Some(until) = self.cooldown_until {
+            if self.taken_at < until {
+                let remaining = (until - self.taken_at).num_seconds();
+                return TradeDecision::Rejected {
+                    reason: format!("Cooldown active - {} seconds remaining", remaining),
+                };
+            }
+        }
+
+        // Scale down while the circuit breaker is still in its recovery period
+        let (size, recovery_reason) = if self.recovery_trades_remaining > 0 {
+            (
+                requested_size * self.config.recovery_size_fraction,
+                Some(format!(
+                    "Size scaled by {} during circuit breaker recovery - {} trades remaining",
+                    self.config.recovery_size_fraction, self.recovery_trades_remaining
+                )),
+            )
+        } else {
+            (requested_size, None)
+        };
+
+        // Check position size limit
+        if size > self.config.max_position_size {
+            return TradeDecision::Reduced {
+                new_size: self.config.max_position_size,
+                reason: "Size reduced to max position limit".to_string(),
+            };
+        }
+
+        // Check total exposure
+        if self.total_exposure + size > self.config.max_total_exposure {
+            let available = self.config.max_total_exposure - self.total_exposure;
+            if available <= Decimal::ZERO {
+                return TradeDecision::Rejected {
+                    reason: "Maximum exposure limit reached".to_string(),
+                };
+            }
+            return TradeDecision::Reduced {
+                new_size: available,
+                reason: "Size reduced due to exposure limit".to_string(),
+            };
+        }
+
+        match recovery_reason {
+            Some(reason) => TradeDecision::Reduced {
+                new_size: size,
+                reason,
+            },
+            None => TradeDecision::Approved { size },
+        }
+    }
+}
+
+/// Current risk status
+#[derive(Debug, Clone, Serialize)]
 pub struct RiskStatus {
     pub total_exposure: Decimal,
     pub daily_pnl: Decimal,
     pub portfolio_var: Decimal,
+    /// Confidence level `portfolio_var` is computed at
+    pub var_confidence_level: f64,
+    /// Open pairs with no volatility estimate yet, left out of `portfolio_var`
+    pub var_unestimated_pairs: Vec<String>,
     pub trades_today: usize,
     pub is_paused: bool,
+    /// Why trading is halted until UTC midnight, if a daily loss limit was hit
+    pub kill_switch: Option<String>,
     pub positions: HashMap<String, Decimal>,
+    /// Trades not yet settled, oldest first
+    pub open_positions: Vec<OpenPosition>,
 }
 
 impl Default for RiskManager {
@@ -341,6 +773,188 @@ mod tests {
         assert!(matches!(decision, TradeDecision::Rejected { .. }));
     }
 
+    #[tokio::test]
+    async fn test_recovery_scales_size_after_circuit_closes() {
+        let config = RiskConfig {
+            recovery_trades: 2,
+            ..Default::default()
+        };
+        let mut manager = RiskManager::new(config);
+        // Zero timeout so the breaker goes straight to HalfOpen on the next check
+        manager.circuit_breaker =
+            circuit_breaker::CircuitBreaker::new(3, 5, 0).with_recovery_trades(2);
+
+        for _ in 0..3 {
+            manager.circuit_breaker.record_failure().await;
+        }
+        assert!(manager.circuit_breaker.can_execute().await); // Open -> HalfOpen
+        for _ in 0..5 {
+            manager.circuit_breaker.record_success().await;
+        }
+        assert!(manager.circuit_breaker.in_recovery().await);
+
+        let decision = manager.can_trade("SOL/USDC", Decimal::from(500)).await;
+        match decision {
+            TradeDecision::Reduced { new_size, .. } => assert_eq!(new_size, Decimal::from(250)),
+            other => panic!("expected reduced size during recovery, got {:?}", other),
+        }
+
+        manager.circuit_breaker.record_success().await;
+        manager.circuit_breaker.record_success().await;
+        assert!(!manager.circuit_breaker.in_recovery().await);
+
+        let decision = manager.can_trade("SOL/USDC", Decimal::from(500)).await;
+        assert!(matches!(decision, TradeDecision::Approved { size } if size == Decimal::from(500)));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_evaluation_and_commit() {
+        let config = RiskConfig {
+            max_position_size: Decimal::from(600),
+            max_total_exposure: Decimal::from(1000),
+            ..Default::default()
+        };
+        let mut manager = RiskManager::new(config);
+
+        // Both candidates look fine against the same snapshot...
+        let snapshot = manager.snapshot().await;
+        assert!(!snapshot.trading_halted);
+        assert!(matches!(snapshot.evaluate(Decimal::from(600)), TradeDecision::Approved { .. }));
+        assert!(matches!(snapshot.evaluate(Decimal::from(600)), TradeDecision::Approved { .. }));
+
+        // ...but the live commit only has room left for part of the second one
+        let first = manager.commit("SOL/USDC", Decimal::from(600)).await;
+        assert!(matches!(first, TradeDecision::Approved { .. }));
+        let second = manager.commit("RAY/USDC", Decimal::from(600)).await;
+        match second {
+            TradeDecision::Reduced { new_size, .. } => assert_eq!(new_size, Decimal::from(400)),
+            other => panic!("expected size reduced to remaining exposure, got {:?}", other),
+        }
+        assert_eq!(manager.total_exposure(), Decimal::from(1000));
+        assert!(matches!(
+            manager.commit("SOL/USDC", Decimal::from(1)).await,
+            TradeDecision::Rejected { .. }
+        ));
+
+        manager.release("SOL/USDC", Decimal::from(600));
+        manager.release("RAY/USDC", Decimal::from(400));
+        assert_eq!(manager.total_exposure(), Decimal::ZERO);
+        assert_eq!(manager.snapshot().await.total_exposure, Decimal::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_hard_cap_overrides_other_sizing() {
+        let config = RiskConfig {
+            max_position_size: Decimal::from(1_000_000),
+            max_total_exposure: Decimal::from(10_000_000),
+            hard_max_trade_usd: Decimal::from(250),
+            ..Default::default()
+        };
+        let mut manager = RiskManager::new(config);
+
+        // Huge profit and liquidity would otherwise size at the (huge) position limit
+        let size = manager.calculate_position_size(
+            "SOL/USDC",
+            Decimal::from(50),
+            Decimal::from(1_000_000_000),
+        );
+        assert_eq!(size, Decimal::from(250));
+
+        match manager.commit("SOL/USDC", Decimal::from(900_000)).await {
+            TradeDecision::Reduced { new_size, .. } => assert_eq!(new_size, Decimal::from(250)),
+            other => panic!("expected size clamped to hard cap, got {:?}", other),
+        }
+        assert_eq!(manager.total_exposure(), Decimal::from(250));
+        assert!(matches!(
+            manager.can_trade("SOL/USDC", Decimal::from(100)).await,
+            TradeDecision::Approved { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_kelly_sizing() {
+        let mut manager = RiskManager::new(RiskConfig::default());
+
+        // No edge, no bet
+        assert_eq!(manager.calculate_kelly_size("SOL/USDC", Decimal::ZERO, 0.9), Decimal::ZERO);
+        assert_eq!(
+            manager.calculate_kelly_size("SOL/USDC", Decimal::from(-1), 0.9),
+            Decimal::ZERO
+        );
+
+        // 2% win vs 1% loss at 75%: f* = 0.75 - 0.25 / 2 = 0.625, halved
+        let size = manager.calculate_kelly_size("SOL/USDC", Decimal::TWO, 0.75);
+        assert_eq!(size, Decimal::new(3125, 1));
+
+        // A losing record on the pair pulls the estimate down
+        for _ in 0..10 {
+            manager
+                .record_trade(TradeOutcome {
+                    timestamp: Utc::now(),
+                    pair: "SOL/USDC".to_string(),
+                    profit_loss: Decimal::from(-1),
+                    was_successful: false,
+                })
+                .await;
+        }
+        assert!(manager.calculate_kelly_size("SOL/USDC", Decimal::TWO, 0.75) < size);
+        assert_eq!(manager.calculate_kelly_size("RAY/USDC", Decimal::TWO, 0.75), size);
+
+        // Over-levered fractions never exceed the position limit
+        let manager = RiskManager::new(RiskConfig {
+            kelly_fraction: Decimal::from(3),
+            ..Default::default()
+        });
+        assert_eq!(
+            manager.calculate_kelly_size("SOL/USDC", Decimal::from(50), 1.0),
+            Decimal::from(1000)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_portfolio_var_scales_with_volatility() {
+        let price = |pair: &str, mid: i64| {
+            let (base, quote) = pair.split_once('/').unwrap();
+            crate::PriceData::new(
+                crate::DexType::Raydium,
+                crate::TokenPair::new(base, quote),
+                Decimal::new(mid, 2),
+                Decimal::new(mid, 2),
+            )
+        };
+        let var_with_moves = |moves: &[i64], confidence_level: f64| {
+            let mut manager = RiskManager::new(RiskConfig {
+                confidence_level,
+                ..Default::default()
+            });
+            for &mid in moves {
+                manager.update_prices(&[price("SOL/USDC", mid), price("RAY/USDC", mid)]);
+            }
+            manager.update_position("SOL/USDC", Decimal::from(600));
+            manager.update_position("RAY/USDC", Decimal::from(400));
+            manager.update_position("JUP/USDC", Decimal::from(500));
+            manager
+        };
+
+        let calm = var_with_moves(&[10000, 10010, 10000], 0.95).status().await;
+        let wild = var_with_moves(&[10000, 10500, 10000], 0.95).status().await;
+        let wild_99 = var_with_moves(&[10000, 10500, 10000], 0.99).status().await;
+
+        // Only the pairs with a price history contribute; JUP is flagged instead
+        assert_eq!(calm.var_unestimated_pairs, vec!["JUP/USDC".to_string()]);
+        let sol_vol = var_with_moves(&[10000, 10500, 10000], 0.95)
+            .volatility_tracker
+            .get_volatility("SOL/USDC")
+            .unwrap();
+        let expected = Decimal::from(1000) * sol_vol * Decimal::new(1645, 3);
+        assert!((wild.portfolio_var - expected).abs() < Decimal::new(1, 6));
+
+        assert!(calm.portfolio_var > Decimal::ZERO);
+        assert!(wild.portfolio_var > calm.portfolio_var * Decimal::from(10));
+        assert!(wild_99.portfolio_var > wild.portfolio_var);
+        assert_eq!(wild_99.var_confidence_level, 0.99);
+    }
+
     #[test]
     fn test_position_tracking() {
         let mut manager = RiskManager::default();
@@ -350,4 +964,105 @@ mod tests {
 
         assert_eq!(manager.total_exposure(), Decimal::from(1500));
     }
+
+    #[tokio::test]
+    async fn test_drawdown_from_peak_halts_trading() {
+        let mut manager = RiskManager::new(RiskConfig {
+            max_drawdown: Decimal::from(200),
+            loss_cooldown_seconds: 0,
+            ..Default::default()
+        });
+        let trade = |profit_loss: i64| TradeOutcome {
+            timestamp: Utc::now(),
+            pair: "SOL/USDC".to_string(),
+            profit_loss: Decimal::from(profit_loss),
+            was_successful: true,
+        };
+
+        // Up $150, then down $210: only $60 lost on the day, but $210 off the peak
+        manager.record_trade(trade(150)).await;
+        manager.record_trade(trade(-150)).await;
+        assert!(!manager.is_paused().await);
+        manager.record_trade(trade(-60)).await;
+        assert_eq!(manager.daily_pnl(), Decimal::from(-60));
+        let snapshot = manager.snapshot().await;
+        assert!(snapshot.trading_halted);
+        assert!(snapshot.kill_switch.unwrap().contains("Drawdown"));
+
+        manager.reset_daily().await;
+        assert!(manager.snapshot().await.kill_switch.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_consecutive_failures_halt_trading() {
+        let mut manager = RiskManager::new(RiskConfig {
+            circuit_failure_threshold: 4,
+            circuit_timeout_seconds: 60,
+            ..Default::default()
+        });
+        let failed = TradeOutcome {
+            timestamp: Utc::now(),
+            pair: "SOL/USDC".to_string(),
+            profit_loss: Decimal::ZERO,
+            was_successful: false,
+        };
+
+        for _ in 0..3 {
+            manager.record_trade(failed.clone()).await;
+        }
+        let snapshot = manager.snapshot().await;
+        assert!(!snapshot.trading_halted);
+        assert!(snapshot.circuit_cooldown_remaining.is_none());
+
+        manager.record_trade(failed).await;
+        let snapshot = manager.snapshot().await;
+        assert!(snapshot.trading_halted);
+        let remaining = snapshot.circuit_cooldown_remaining.unwrap();
+        assert!(remaining > std::time::Duration::from_secs(55));
+        assert!(matches!(
+            manager.can_trade("SOL/USDC", Decimal::from(100)).await,
+            TradeDecision::Rejected { .. }
+        ));
+
+        // Disabled, the same failures never halt trading
+        let mut manager = RiskManager::new(RiskConfig {
+            circuit_breaker_enabled: false,
+            ..Default::default()
+        });
+        for _ in 0..10 {
+            manager
+                .record_trade(TradeOutcome {
+                    timestamp: Utc::now(),
+                    pair: "SOL/USDC".to_string(),
+                    profit_loss: Decimal::ZERO,
+                    was_successful: false,
+                })
+                .await;
+        }
+        assert!(!manager.snapshot().await.trading_halted);
+    }
+
+    #[tokio::test]
+    async fn test_open_position_goes_stale() {
+        let mut manager = RiskManager::default();
+        manager.open_position("stuck", "SOL/USDC", Decimal::from(250));
+        manager.open_position("settled", "RAY/USDC", Decimal::from(100));
+        let max_age = Duration::seconds(60);
+
+        assert!(manager.stale_positions(max_age).is_empty());
+        assert_eq!(manager.status().await.open_positions.len(), 2);
+
+        // Two minutes later only the unsettled trade is left, and it is stale
+        let settled = manager.close_position("settled").unwrap();
+        assert_eq!(settled.pair, "RAY/USDC");
+        let later = Utc::now() + Duration::minutes(2);
+        let stale = manager.stale_positions_at(later, max_age);
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].id, "stuck");
+        assert_eq!(stale[0].size, Decimal::from(250));
+
+        assert!(manager.close_position("stuck").is_some());
+        assert!(manager.stale_positions_at(later, max_age).is_empty());
+        assert!(manager.status().await.open_positions.is_empty());
+    }
 }
@@ -20,6 +20,10 @@ pub struct CircuitBreaker {
     consecutive_failures: Arc<RwLock<usize>>,
     consecutive_successes: Arc<RwLock<usize>>,
     last_failure_time: Arc<RwLock<Option<Instant>>>,
+
+    // Reduced-size trades still owed after HalfOpen -> Closed
+    recovery_trades: usize,
+    recovery_remaining: Arc<RwLock<usize>>,
     event_bus: Arc<RwLock<Option<Arc<EventBus>>>>,
 }
 
@@ -33,15 +37,50 @@ impl CircuitBreaker {
             consecutive_failures: Arc::new(RwLock::new(0)),
             consecutive_successes: Arc::new(RwLock::new(0)),
             last_failure_time: Arc::new(RwLock::new(None)),
+            recovery_trades: 0,
+            recovery_remaining: Arc::new(RwLock::new(0)),
             event_bus: Arc::new(RwLock::new(None)),
         }
     }
 
+    /// Number of trades after a HalfOpen -> Closed transition that are
+    /// still considered part of the recovery period.
+    pub fn with_recovery_trades(mut self, trades: usize) -> Self {
+        self.recovery_trades = trades;
+        self
+    }
+
+    /// Current breaker state.
+    pub async fn state(&self) -> CircuitState {
+        self.state.read().await.clone()
+    }
+
+    /// Trades left in the current recovery period (0 when fully recovered).
+    pub async fn recovery_trades_remaining(&self) -> usize {
+        *self.recovery_remaining.read().await
+    }
+
+    /// Whether the breaker recently closed and trading should stay at reduced size.
+    pub async fn in_recovery(&self) -> bool {
+        matches!(*self.state.read().await, CircuitState::Closed)
+            && self.recovery_trades_remaining().await > 0
+    }
+
+    /// Count a trade taken while closed against the recovery period.
+    async fn consume_recovery_trade(&self) {
+        if matches!(*self.state.read().await, CircuitState::Closed) {
+            let mut remaining = self.recovery_remaining.write().await;
+            *remaining = remaining.saturating_sub(1);
+        }
+    }
+
     pub async fn set_event_bus(&self, bus: Arc<EventBus>) {
         *self.event_bus.write().await = Some(bus);
     }
 
     pub async fn record_success(&self) {
+        self.consume_recovery_trade().await;
+
         let mut successes = self.consecutive_successes.write().await;
         *successes += 1;
 
@@ -53,7 +92,11 @@ impl CircuitBreaker {
             let mut state = self.state.write().await;
             if matches!(*state, CircuitState::HalfOpen) {
                 *state = CircuitState::Closed;
-                tracing::info!("Circuit breaker CLOSED - system recovered");
+                *self.recovery_remaining.write().await = self.recovery_trades;
+                tracing::info!(
+                    "Circuit breaker CLOSED - system recovered ({} reduced-size trades)",
+                    self.recovery_trades
+                );
                 
                 if let Some(bus) = self.event_bus.read().await.as_ref() {
                     bus.publish(TradingEvent::CircuitBreakerStateChanged {
@@ -66,6 +109,8 @@ impl CircuitBreaker {
     }
 
     pub async fn record_failure(&self) {
+        self.consume_recovery_trade().await;
+
         let mut failures = self.consecutive_failures.write().await;
         *failures += 1;
 
@@ -76,19 +121,59 @@ impl CircuitBreaker {
 
         // Open circuit if threshold exceeded
         if *failures >= self.failure_threshold {
-            let mut state = self.state.write().await;
-            *state = CircuitState::Open;
-            tracing::error!(
-                "Circuit breaker OPEN - trading halted"
-            );
-
-            if let Some(bus) = self.event_bus.read().await.as_ref() {
-                bus.publish(TradingEvent::CircuitBreakerStateChanged {
-                    old_state: "Closed".to_string(), // Could refer to previous state, but record_failure usually from Closed/HalfOpen
-                    new_state: "Open".to_string(),
-                });
-            }
+            self.open().await;
+        }
+    }
+
+    /// Open the breaker now, whatever the failure count (e.g. on a daily loss
+    /// limit breach); the timeout runs from this call
+    pub async fn trip(&self) {
+        *self.last_failure_time.write().await = Some(Instant::now());
+        self.open().await;
+    }
+
+    /// Close the breaker now, e.g. once the condition it was tripped for has
+    /// cleared; failure counts start over
+    pub async fn close(&self) {
+        let mut state = self.state.write().await;
+        if matches!(*state, CircuitState::Closed) {
+            return;
+        }
+        let old_state = format!("{:?}", *state);
+        *state = CircuitState::Closed;
+        *self.consecutive_failures.write().await = 0;
+        *self.consecutive_successes.write().await = 0;
+        tracing::info!("Circuit breaker CLOSED - trading resumed");
+
+        if let Some(bus) = self.event_bus.read().await.as_ref() {
+            bus.publish(TradingEvent::CircuitBreakerStateChanged {
+                old_state,
+                new_state: "Closed".to_string(),
+            });
+        }
+    }
+
+    async fn open(&self) {
+        let mut state = self.state.write().await;
+        *state = CircuitState::Open;
+        *self.recovery_remaining.write().await = 0;
+        tracing::error!("Circuit breaker OPEN - trading halted");
+
+        if let Some(bus) = self.event_bus.read().await.as_ref() {
+            bus.publish(TradingEvent::CircuitBreakerStateChanged {
+                old_state: "Closed".to_string(), // Could refer to previous state, but record_failure usually from Closed/HalfOpen
+                new_state: "Open".to_string(),
+            });
+        }
+    }
+
+    /// Time until an open breaker lets a test trade through (`None` unless open)
+    pub async fn cooldown_remaining(&self) -> Option<Duration> {
+        if !matches!(*self.state.read().await, CircuitState::Open) {
+            return None;
         }
+        let last_failure = (*self.last_failure_time.read().await)?;
+        Some(self.timeout.saturating_sub(last_failure.elapsed()))
     }
 
     pub async fn can_execute(&self) -> bool {
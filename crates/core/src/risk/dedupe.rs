@@ -0,0 +1,137 @@
+//! Restart-safe execution deduplication
+//!
+//! Remembers which opportunities were recently executed (the seen-set) and which
+//! routes are quarantined after an executor failure (the poison-pill blacklist).
+//! Both sets are timestamped, persisted to disk and reloaded on startup so a
+//! restart cannot immediately re-execute something the bot just acted on.
+
+use crate::types::ArbitrageOpportunity;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// On-disk representation of the dedupe state
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DedupeSnapshot {
+    seen: HashMap<String, DateTime<Utc>>,
+    blacklist: HashMap<String, DateTime<Utc>>,
+}
+
+/// Seen-set and blacklist with time-based expiry and optional file persistence
+pub struct ExecutionDedupe {
+    window: Duration,
+    seen: HashMap<String, DateTime<Utc>>,
+    blacklist: HashMap<String, DateTime<Utc>>,
+    path: Option<PathBuf>,
+}
+
+impl ExecutionDedupe {
+    /// In-memory dedupe; entries expire after `window_seconds`
+    pub fn new(window_seconds: i64) -> Self {
+        Self {
+            window: Duration::seconds(window_seconds),
+            seen: HashMap::new(),
+            blacklist: HashMap::new(),
+            path: None,
+        }
+    }
+
+    /// Load persisted state from `path` (if present), dropping expired entries.
+    /// Subsequent changes are written back to the same file.
+    pub fn load(path: impl AsRef<Path>, window_seconds: i64) -> Self {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+
+        let snapshot = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring corrupt dedupe state {}: {}", path.display(), e);
+                DedupeSnapshot::default()
+            }),
+            Err(_) => DedupeSnapshot::default(),
+        };
+
+        let mut dedupe = Self {
+            seen: snapshot.seen,
+            blacklist: snapshot.blacklist,
+            path: Some(path),
+            ..Self::new(window_seconds)
+        };
+        dedupe.prune(Utc::now());
+        dedupe
+    }
+
+    /// Key identifying one concrete opportunity (route plus quoted prices)
+    pub fn fingerprint(opp: &ArbitrageOpportunity) -> String {
+        format!(
+            "{}|{}|{}",
+            Self::route_key(opp),
+            opp.buy_price.normalize(),
+            opp.sell_price.normalize()
+        )
+    }
+
+    /// Key identifying the route regardless of price (used for the blacklist)
+    pub fn route_key(opp: &ArbitrageOpportunity) -> String {
+        format!("{}:{}->{}", opp.pair.symbol(), opp.buy_dex, opp.sell_dex)
+    }
+
+    /// Whether the opportunity was executed recently or its route is blacklisted
+    pub fn is_duplicate(&self, opp: &ArbitrageOpportunity) -> bool {
+        let now = Utc::now();
+        let live = |map: &HashMap<String, DateTime<Utc>>, key: &str| {
+            map.get(key).is_some_and(|at| now - *at < self.window)
+        };
+        live(&self.seen, &Self::fingerprint(opp)) || live(&self.blacklist, &Self::route_key(opp))
+    }
+
+    /// Remember that the opportunity was executed
+    pub fn mark_executed(&mut self, opp: &ArbitrageOpportunity) {
+        self.seen.insert(Self::fingerprint(opp), Utc::now());
+        self.persist();
+    }
+
+    /// Quarantine the opportunity's route for the dedupe window
+    pub fn blacklist(&mut self, opp: &ArbitrageOpportunity) {
+        self.blacklist.insert(Self::route_key(opp), Utc::now());
+        self.persist();
+    }
+
+    /// Drop entries older than the window
+    pub fn prune(&mut self, now: DateTime<Utc>) {
+        let window = self.window;
+        self.seen.retain(|_, at| now - *at < window);
+        self.blacklist.retain(|_, at| now - *at < window);
+    }
+
+    /// Number of seen and blacklisted entries
+    pub fn counts(&self) -> (usize, usize) {
+        (self.seen.len(), self.blacklist.len())
+    }
+
+    fn persist(&mut self) {
+        self.prune(Utc::now());
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let snapshot = DedupeSnapshot {
+            seen: self.seen.clone(),
+            blacklist: self.blacklist.clone(),
+        };
+        let result = serde_json::to_string(&snapshot)
+            .map_err(std::io::Error::other)
+            .and_then(|json| {
+                // Write then rename so a crash mid-write never leaves a truncated file
+                let tmp = path.with_extension("tmp");
+                fs::write(&tmp, json)?;
+                fs::rename(&tmp, path)
+            });
+        if let Err(e) = result {
+            tracing::warn!("Failed to persist dedupe state {}: {}", path.display(), e);
+        }
+    }
+}
@@ -3,7 +3,6 @@ use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 
 /// Value at Risk (VaR) Calculator
-#[allow(dead_code)]
 pub struct VarCalculator {
     /// Confidence level (e.g., 0.95 or 0.99)
     confidence_level: f64,
@@ -28,6 +27,10 @@ impl VarCalculator {
         }
     }
 
+    pub fn confidence_level(&self) -> f64 {
+        self.confidence_level
+    }
+
     /// Calculate VaR for a single position
     /// VaR = Position Value * Volatility * Z-Score
     pub fn calculate_var(&self, position_value: Decimal, volatility: Decimal) -> Decimal {
@@ -39,25 +42,33 @@ impl VarCalculator {
         Decimal::try_from(var).unwrap_or(Decimal::ZERO)
     }
 
-    /// Calculate Portfolio VaR (assuming perfect correlation for worst-case)
+    /// Calculate parametric Portfolio VaR (assuming perfect correlation for worst-case):
+    /// VaR = (sum of position-weighted volatilities) * Total Exposure * Z-Score
     /// In reality, we should use covariance matrix, but for arbitrage (SOL-based),
     /// pairs are highly correlated.
+    ///
+    /// Pairs without a volatility estimate yet contribute nothing; they are
+    /// returned alongside the VaR so callers can flag the figure as partial.
     pub fn calculate_portfolio_var(
         &self,
         positions: &std::collections::HashMap<String, Decimal>,
         vol_tracker: &VolatilityTracker,
-    ) -> Decimal {
-        let mut total_var = Decimal::ZERO;
+    ) -> (Decimal, Vec<String>) {
+        let exposure: Decimal = positions.values().sum();
+        let mut missing = Vec::new();
+        if exposure.is_zero() {
+            return (Decimal::ZERO, missing);
+        }
 
+        let mut weighted_vol = Decimal::ZERO;
         for (pair, &size) in positions {
-            if let Some(vol) = vol_tracker.get_volatility(pair) {
-                total_var += self.calculate_var(size, vol);
-            } else {
-                // Fallback minimal volatility if unknown
-                total_var += self.calculate_var(size, Decimal::new(1, 2)); // 1%
+            match vol_tracker.get_volatility(pair) {
+                Some(vol) => weighted_vol += size / exposure * vol,
+                None => missing.push(pair.clone()),
             }
         }
+        missing.sort();
 
-        total_var
+        (self.calculate_var(exposure, weighted_vol), missing)
     }
 }
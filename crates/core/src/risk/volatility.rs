@@ -1,7 +1,8 @@
-use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use std::collections::HashMap;
 
+use crate::math::decimal_sqrt;
+
 /// Tracks volatility for different trading pairs using EWMA
 pub struct VolatilityTracker {
     /// Map of pair symbol to current volatility (std dev estimate)
@@ -43,11 +44,8 @@ impl VolatilityTracker {
             let new_vol_sq = self.decay * ret_sq + (Decimal::ONE - self.decay) * current_vol_sq;
 
             // Store volatility (sqrt of variance)
-            // Decimal doesn't have sqrt, convert to f64 and back
-            if let Some(vol_sq_f64) = new_vol_sq.to_f64() {
-                let vol = Decimal::try_from(vol_sq_f64.sqrt()).unwrap_or(Decimal::ZERO);
-                self.volatilities.insert(pair.to_string(), vol);
-            }
+            self.volatilities
+                .insert(pair.to_string(), decimal_sqrt(new_vol_sq));
         }
 
         self.last_prices.insert(pair.to_string(), price);
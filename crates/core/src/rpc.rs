@@ -0,0 +1,145 @@
+//! Batched account reads over RPC
+//!
+//! Reading pool accounts one `getAccountInfo` at a time costs a round trip per
+//! pool. [`AccountFetcher`] reads them with `getMultipleAccounts` instead, split
+//! into chunks of the RPC's 100-account limit, so a provider fetches every pool
+//! it needs for a tick in one or two calls.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures_util::future::try_join_all;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use crate::{ArbitrageError, ArbitrageResult};
+
+/// Most accounts a single `getMultipleAccounts` call accepts
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Sends one `getMultipleAccounts` request
+#[async_trait]
+pub trait AccountsTransport: Send + Sync {
+    /// The accounts at `pubkeys`, in order; `None` for those that don't exist
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> ArbitrageResult<Vec<Option<Account>>>;
+}
+
+#[async_trait]
+impl AccountsTransport for RpcClient {
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> ArbitrageResult<Vec<Option<Account>>> {
+        RpcClient::get_multiple_accounts(self, pubkeys)
+            .await
+            .map_err(|e| ArbitrageError::RpcError(e.to_string()))
+    }
+}
+
+/// Reads any number of accounts in as few RPC calls as the limit allows
+#[derive(Clone)]
+pub struct AccountFetcher {
+    transport: Arc<dyn AccountsTransport>,
+}
+
+impl AccountFetcher {
+    pub fn new(rpc: Arc<RpcClient>) -> Self {
+        Self::with_transport(rpc)
+    }
+
+    /// Fetcher sending its requests through `transport`
+    pub fn with_transport(transport: Arc<dyn AccountsTransport>) -> Self {
+        Self { transport }
+    }
+
+    /// The accounts at `pubkeys`, in input order, fetched in concurrent chunks
+    /// of [`MAX_MULTIPLE_ACCOUNTS`]. Fails if any chunk does.
+    pub async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> ArbitrageResult<Vec<Option<Account>>> {
+        let requests = pubkeys
+            .chunks(MAX_MULTIPLE_ACCOUNTS)
+            .map(|chunk| self.fetch_chunk(chunk));
+        let chunks = try_join_all(requests).await?;
+        Ok(chunks.into_iter().flatten().collect())
+    }
+
+    /// One request for at most [`MAX_MULTIPLE_ACCOUNTS`] keys
+    async fn fetch_chunk(&self, chunk: &[Pubkey]) -> ArbitrageResult<Vec<Option<Account>>> {
+        let accounts = self.transport.get_multiple_accounts(chunk).await?;
+        if accounts.len() != chunk.len() {
+            return Err(ArbitrageError::RpcError(format!(
+                "getMultipleAccounts returned {} accounts for {} keys",
+                accounts.len(),
+                chunk.len()
+            )));
+        }
+        Ok(accounts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Answers each request with accounts whose lamports are the key's index,
+    /// recording the size of every request
+    struct RecordingTransport {
+        keys: Vec<Pubkey>,
+        requests: Mutex<Vec<usize>>,
+    }
+
+    #[async_trait]
+    impl AccountsTransport for RecordingTransport {
+        async fn get_multiple_accounts(
+            &self,
+            pubkeys: &[Pubkey],
+        ) -> ArbitrageResult<Vec<Option<Account>>> {
+            self.requests.lock().unwrap().push(pubkeys.len());
+            Ok(pubkeys
+                .iter()
+                .map(|key| {
+                    let index = self.keys.iter().position(|k| k == key)?;
+                    Some(Account {
+                        lamports: index as u64,
+                        ..Account::default()
+                    })
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_accounts_are_chunked_and_kept_in_order() {
+        let keys: Vec<Pubkey> = (0..250).map(|_| Pubkey::new_unique()).collect();
+        let transport = Arc::new(RecordingTransport {
+            keys: keys.clone(),
+            requests: Mutex::new(Vec::new()),
+        });
+        let fetcher = AccountFetcher::with_transport(transport.clone());
+
+        let accounts = fetcher.get_multiple_accounts(&keys).await.unwrap();
+
+        let mut requests = transport.requests.lock().unwrap().clone();
+        requests.sort_unstable();
+        assert_eq!(requests, vec![50, 100, 100]);
+        let lamports: Vec<u64> = accounts
+            .iter()
+            .map(|a| a.as_ref().unwrap().lamports)
+            .collect();
+        assert_eq!(lamports, (0..250).collect::<Vec<u64>>());
+
+        // Missing accounts come back as `None` in their place
+        let missing = [keys[3], Pubkey::new_unique(), keys[7]];
+        let accounts = fetcher.get_multiple_accounts(&missing).await.unwrap();
+        assert_eq!(
+            accounts.iter().map(Option::is_some).collect::<Vec<_>>(),
+            [true, false, true]
+        );
+    }
+}
@@ -1,78 +1,191 @@
-use crate::types::{DexType, PriceData, TokenPair};
+use crate::types::{DexType, PriceData, TokenPair, DEFAULT_SYNTHETIC_SPREAD_BPS};
 use futures_util::{SinkExt, StreamExt};
 use rust_decimal::Decimal;
 use serde_json::json;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 
+/// Pacing for WebSocket reconnects, so a server that drops us instantly can't
+/// trigger a reconnect storm
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// Minimum time between two connection attempts, however fast the server drops us
+    pub min_interval: Duration,
+    /// Backoff after the first drop; doubles with each consecutive reconnect
+    pub initial_backoff: Duration,
+    /// Upper bound on the backoff
+    pub max_backoff: Duration,
+    /// Consecutive reconnects before giving up and falling back to HTTP for the DEX
+    pub max_consecutive_reconnects: u32,
+    /// A connection that stays up this long resets the consecutive count
+    pub stable_after: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::from_secs(2),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+            max_consecutive_reconnects: 10,
+            stable_after: Duration::from_secs(60),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// How long to wait before reconnect number `consecutive` (1-based), given how
+    /// long the previous attempt (connect + session) lasted
+    pub fn delay_before_reconnect(&self, consecutive: u32, attempt_lasted: Duration) -> Duration {
+        let exponent = consecutive.saturating_sub(1).min(31);
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff);
+        backoff.max(self.min_interval.saturating_sub(attempt_lasted))
+    }
+}
+
 pub struct WebSocketManager {
     price_tx: mpsc::Sender<PriceData>,
-    reconnect_delay_ms: u64,
-    max_reconnect_attempts: u32,
+    reconnect: ReconnectPolicy,
+    synthetic_spread_bps: u32,
+    /// DEXs whose stream gave up reconnecting; they are served by HTTP polling only
+    http_only: Arc<Mutex<HashSet<DexType>>>,
+    /// Per-DEX feed URLs replacing the built-in ones
+    endpoints: HashMap<DexType, String>,
 }
 
 impl WebSocketManager {
     pub fn new(price_tx: mpsc::Sender<PriceData>) -> Self {
         Self {
             price_tx,
-            reconnect_delay_ms: 1000,
-            max_reconnect_attempts: 10,
+            reconnect: ReconnectPolicy::default(),
+            synthetic_spread_bps: DEFAULT_SYNTHETIC_SPREAD_BPS,
+            http_only: Arc::new(Mutex::new(HashSet::new())),
+            endpoints: HashMap::new(),
         }
     }
 
+    /// Stream `dex` prices from `url` instead of its built-in feed
+    pub fn with_endpoint(mut self, dex: DexType, url: impl Into<String>) -> Self {
+        self.endpoints.insert(dex, url.into());
+        self
+    }
+
+    /// Spread to synthesize around single-price messages (no bid/ask on the wire)
+    pub fn with_synthetic_spread_bps(mut self, spread_bps: u32) -> Self {
+        self.synthetic_spread_bps = spread_bps;
+        self
+    }
+
     pub fn with_reconnect(mut self, delay_ms: u64, max_attempts: u32) -> Self {
-        self.reconnect_delay_ms = delay_ms;
-        self.max_reconnect_attempts = max_attempts;
+        self.reconnect.initial_backoff = Duration::from_millis(delay_ms);
+        self.reconnect.max_consecutive_reconnects = max_attempts;
         self
     }
 
+    pub fn with_reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = policy;
+        self
+    }
+
+    /// Whether `dex` has fallen back to HTTP-only after exhausting its reconnects
+    pub fn is_http_only(&self, dex: DexType) -> bool {
+        self.http_only.lock().unwrap().contains(&dex)
+    }
+
+    /// DEXs that have fallen back to HTTP-only
+    pub fn http_only_dexes(&self) -> Vec<DexType> {
+        self.http_only.lock().unwrap().iter().copied().collect()
+    }
+
     /// Start a WebSocket subscription with automatic reconnection on disconnect.
+    ///
+    /// Reconnects are paced by the [`ReconnectPolicy`]; after too many consecutive
+    /// reconnects the DEX is marked HTTP-only and this returns.
     pub async fn start_with_reconnection(&self, dex: DexType, pair: TokenPair) {
-        let mut attempt = 0u32;
-        let mut delay = self.reconnect_delay_ms;
+        if self.ws_url(dex, &pair).is_none() {
+            self.fall_back_to_http(dex, "no WebSocket feed");
+            return;
+        }
+
+        let policy = &self.reconnect;
+        let mut consecutive = 0u32;
 
         loop {
-            tracing::info!(
-                "🔌 WS connection attempt {}/{} for {} on {:?}",
-                attempt + 1,
-                self.max_reconnect_attempts,
-                pair,
-                dex
-            );
+            if self.is_http_only(dex) {
+                tracing::debug!("{:?} already HTTP-only, stopping WS for {}", dex, pair);
+                return;
+            }
 
-            self.subscribe_to_pair(dex, pair.clone()).await;
+            let attempt_start = Instant::now();
+            self.run_session(dex, pair.clone(), consecutive > 0).await;
+            let lasted = attempt_start.elapsed();
 
-            attempt += 1;
-            if attempt >= self.max_reconnect_attempts {
-                tracing::error!(
-                    "❌ Exceeded max reconnect attempts ({}) for {} on {:?}",
-                    self.max_reconnect_attempts,
-                    pair,
-                    dex
+            // A long-lived session means the endpoint is healthy again
+            if lasted >= policy.stable_after {
+                consecutive = 0;
+            }
+            consecutive += 1;
+
+            if consecutive > policy.max_consecutive_reconnects {
+                self.fall_back_to_http(
+                    dex,
+                    &format!("{} consecutive reconnects for {}", consecutive - 1, pair),
                 );
-                break;
+                return;
             }
 
+            let delay = policy.delay_before_reconnect(consecutive, lasted);
             tracing::warn!(
-                "🔄 Reconnecting in {}ms (attempt {})",
-                delay,
-                attempt
+                "🔄 Reconnecting {} on {:?} in {}ms (attempt {}/{})",
+                pair,
+                dex,
+                delay.as_millis(),
+                consecutive,
+                policy.max_consecutive_reconnects
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    fn fall_back_to_http(&self, dex: DexType, reason: &str) {
+        if self.http_only.lock().unwrap().insert(dex) {
+            tracing::error!(
+                "❌ Giving up on WS for {:?} ({}); falling back to HTTP only",
+                dex,
+                reason
             );
-            tokio::time::sleep(tokio::time::Duration::from_millis(delay)).await;
+        }
+    }
 
-            // Exponential backoff: 1s → 2s → 4s → 8s → capped at 30s
-            delay = (delay * 2).min(30_000);
+    fn ws_url(&self, dex: DexType, pair: &TokenPair) -> Option<String> {
+        if let Some(url) = self.endpoints.get(&dex) {
+            return Some(url.clone());
+        }
+        match dex {
+            DexType::Jupiter => Some("wss://quote-api.jup.ag/v6/quote-ws".to_string()),
+            DexType::Raydium => Some(format!(
+                "wss://api.raydium.io/v2/main/price/{}",
+                pair.symbol()
+            )),
+            _ => None,
         }
     }
 
+    /// Connect, subscribe and forward prices until the connection drops
     pub async fn subscribe_to_pair(&self, dex: DexType, pair: TokenPair) {
-        let url = match dex {
-            DexType::Jupiter => "wss://quote-api.jup.ag/v6/quote-ws".to_string(),
-            DexType::Raydium => {
-                format!("wss://api.raydium.io/v2/main/price/{}", pair.symbol())
-            }
-            _ => return,
+        self.run_session(dex, pair, false).await;
+    }
+
+    async fn run_session(&self, dex: DexType, pair: TokenPair, reconnecting: bool) {
+        let Some(url) = self.ws_url(dex, &pair) else {
+            return;
         };
 
         let result = connect_async(url.as_str()).await;
@@ -94,15 +207,24 @@ impl WebSocketManager {
                     tracing::error!("Failed to send subscribe message: {}", e);
                     return;
                 }
+                if reconnecting {
+                    tracing::info!("✅ Re-subscribed to {} on {:?} after reconnect", pair, dex);
+                }
 
                 let price_tx = self.price_tx.clone();
                 let pair_clone = pair.clone();
+                let spread_bps = self.synthetic_spread_bps;
 
                 // Process messages until disconnect
                 while let Some(msg_result) = read.next().await {
                     match msg_result {
                         Ok(Message::Text(text)) => {
-                            match Self::parse_price_message(&text, dex, &pair_clone) {
+                            match Self::parse_price_message(
+                                &text,
+                                dex,
+                                &pair_clone,
+                                spread_bps,
+                            ) {
                                 Ok(Some(price_data)) => {
                                     if let Err(e) = price_tx.send(price_data).await {
                                         tracing::error!(
@@ -165,19 +287,27 @@ impl WebSocketManager {
     }
 
     /// Parse a WebSocket text message into a `PriceData`, returning `Ok(None)` for
-    /// non-price messages (heartbeats, subscription acks, etc.).
+    /// non-price messages (heartbeats, subscription acks, etc.). Single-price
+    /// messages get `spread_bps` of synthetic spread via [`PriceData::from_mid`].
     fn parse_price_message(
         text: &str,
         dex: DexType,
         pair: &TokenPair,
+        spread_bps: u32,
     ) -> Result<Option<PriceData>, String> {
         let json: serde_json::Value =
             serde_json::from_str(text).map_err(|e| format!("Invalid JSON: {}", e))?;
 
         // Check for heartbeat / subscription ack messages
-        if json.get("type").and_then(|t| t.as_str()) == Some("heartbeat")
-            || json.get("type").and_then(|t| t.as_str()) == Some("subscribed")
-        {
+        if matches!(
+            json.get("type").and_then(|t| t.as_str()),
+            Some("heartbeat" | "subscribed" | "unsubscribed" | "ping" | "pong")
+        ) {
+            return Ok(None);
+        }
+
+        // JSON-RPC style ack for our `subscribe` call: {"id": .., "result": ..}
+        if json.get("result").is_some() && json.get("id").is_some() {
             return Ok(None);
         }
 
@@ -192,11 +322,22 @@ impl WebSocketManager {
 
         // Try different field-name patterns used by various DEXs:
         //   Jupiter: { "bid": "...", "ask": "..." } or { "inAmount": ..., "outAmount": ... }
-        //   Raydium: { "price": "..." } (single mid-price)
+        //   Raydium: { "price": "..." } or { "lastPrice": "..." } (single mid-price),
+        //            possibly as a notification { "method": ..., "params": { ... } }
         //   Generic: { "data": { "bid": ..., "ask": ... } }
 
-        // Pattern 1: explicit bid/ask at top level or inside "data"
-        let data_obj = json.get("data").unwrap_or(&json);
+        // Pattern 1: explicit bid/ask at top level or inside "data"/"params"
+        let data_obj = json
+            .get("data")
+            .or_else(|| json.get("params").filter(|p| p.is_object()))
+            .unwrap_or(&json);
+
+        // Multiplexed feeds tag each tick with its pair; skip other pairs' ticks
+        if let Some(symbol) = data_obj.get("symbol").and_then(|s| s.as_str()) {
+            if symbol.replace(['-', '_'], "/") != pair.symbol() {
+                return Ok(None);
+            }
+        }
 
         if let (Some(bid_val), Some(ask_val)) = (data_obj.get("bid"), data_obj.get("ask")) {
             let bid = parse_decimal_value(bid_val)
@@ -206,11 +347,14 @@ impl WebSocketManager {
             return Ok(Some(PriceData::new(dex, pair.clone(), bid, ask)));
         }
 
-        // Pattern 2: single "price" field → use as both bid and ask (spread = 0)
-        if let Some(price_val) = data_obj.get("price") {
+        // Pattern 2: single price field → mid price with a synthetic spread
+        if let Some((field, price_val)) = ["price", "lastPrice", "last"]
+            .into_iter()
+            .find_map(|field| data_obj.get(field).map(|v| (field, v)))
+        {
             let price = parse_decimal_value(price_val)
-                .ok_or_else(|| "Cannot parse 'price' field".to_string())?;
-            return Ok(Some(PriceData::new(dex, pair.clone(), price, price)));
+                .ok_or_else(|| format!("Cannot parse '{}' field", field))?;
+            return Ok(Some(PriceData::from_mid(dex, pair.clone(), price, spread_bps)));
         }
 
         // Pattern 3: Jupiter quote-style with inAmount/outAmount
@@ -226,7 +370,7 @@ impl WebSocketManager {
                 return Err("inAmount is zero".to_string());
             }
             let price = out_amount / in_amount;
-            return Ok(Some(PriceData::new(dex, pair.clone(), price, price)));
+            return Ok(Some(PriceData::from_mid(dex, pair.clone(), price, spread_bps)));
         }
 
         // Unrecognized format – not necessarily an error, could be metadata
@@ -252,11 +396,14 @@ fn parse_decimal_value(val: &serde_json::Value) -> Option<Decimal> {
 mod tests {
     use super::*;
 
+    const SPREAD_BPS: u32 = DEFAULT_SYNTHETIC_SPREAD_BPS;
+
     #[test]
     fn test_parse_bid_ask_message() {
         let msg = r#"{"bid": "100.5", "ask": "101.0"}"#;
         let pair = TokenPair::new("SOL", "USDC");
-        let result = WebSocketManager::parse_price_message(msg, DexType::Jupiter, &pair);
+        let result =
+            WebSocketManager::parse_price_message(msg, DexType::Jupiter, &pair, SPREAD_BPS);
         assert!(result.is_ok());
         let price = result.unwrap().expect("Should produce PriceData");
         assert_eq!(price.bid, Decimal::from_str("100.5").unwrap());
@@ -268,28 +415,62 @@ mod tests {
     fn test_parse_single_price_message() {
         let msg = r#"{"price": 42.5}"#;
         let pair = TokenPair::new("RAY", "USDC");
-        let result = WebSocketManager::parse_price_message(msg, DexType::Raydium, &pair);
+        let result =
+            WebSocketManager::parse_price_message(msg, DexType::Raydium, &pair, SPREAD_BPS);
         assert!(result.is_ok());
         let price = result.unwrap().expect("Should produce PriceData");
-        assert_eq!(price.bid, price.ask); // single price → same bid and ask
+        // single price → synthetic spread around the mid, flagged one-sided
+        assert!(price.one_sided);
+        assert!(price.bid < price.ask);
+        assert_eq!(price.mid_price, Decimal::from_str("42.5").unwrap());
     }
 
     #[test]
     fn test_parse_nested_data_message() {
         let msg = r#"{"type": "update", "data": {"bid": 99, "ask": 101}}"#;
         let pair = TokenPair::new("SOL", "USDC");
-        let result = WebSocketManager::parse_price_message(msg, DexType::Jupiter, &pair);
+        let result =
+            WebSocketManager::parse_price_message(msg, DexType::Jupiter, &pair, SPREAD_BPS);
         assert!(result.is_ok());
         let price = result.unwrap().expect("Should produce PriceData");
         assert_eq!(price.bid, Decimal::from(99));
         assert_eq!(price.ask, Decimal::from(101));
     }
 
+    #[test]
+    fn test_parse_raydium_feed_frames() {
+        let pair = TokenPair::new("SOL", "USDC");
+        let parse = |msg: &str| {
+            WebSocketManager::parse_price_message(msg, DexType::Raydium, &pair, SPREAD_BPS)
+        };
+
+        // Ack for our subscribe call carries no price
+        assert!(parse(r#"{"id": 1, "result": true}"#).unwrap().is_none());
+
+        let tick = r#"{"method": "price",
+            "params": {"symbol": "SOL-USDC", "bid": "150.10", "ask": "150.20"}}"#;
+        let price = parse(tick).unwrap().expect("Should produce PriceData");
+        assert_eq!(price.bid, Decimal::from_str("150.10").unwrap());
+        assert_eq!(price.ask, Decimal::from_str("150.20").unwrap());
+        assert_eq!(price.dex, DexType::Raydium);
+        assert!(!price.one_sided);
+
+        let last = r#"{"type": "trade", "data": {"symbol": "SOL/USDC", "lastPrice": 150.5}}"#;
+        let price = parse(last).unwrap().expect("Should produce PriceData");
+        assert_eq!(price.mid_price, Decimal::from_str("150.5").unwrap());
+        assert!(price.bid < price.ask);
+
+        // Ticks for other pairs on a shared feed are skipped
+        let other = r#"{"data": {"symbol": "RAY-USDC", "price": "2.1"}}"#;
+        assert!(parse(other).unwrap().is_none());
+    }
+
     #[test]
     fn test_parse_heartbeat_ignored() {
         let msg = r#"{"type": "heartbeat"}"#;
         let pair = TokenPair::new("SOL", "USDC");
-        let result = WebSocketManager::parse_price_message(msg, DexType::Jupiter, &pair);
+        let result =
+            WebSocketManager::parse_price_message(msg, DexType::Jupiter, &pair, SPREAD_BPS);
         assert!(result.is_ok());
         assert!(result.unwrap().is_none(), "Heartbeat should produce None");
     }
@@ -298,7 +479,8 @@ mod tests {
     fn test_parse_subscription_ack_ignored() {
         let msg = r#"{"type": "subscribed", "channel": "prices"}"#;
         let pair = TokenPair::new("SOL", "USDC");
-        let result = WebSocketManager::parse_price_message(msg, DexType::Jupiter, &pair);
+        let result =
+            WebSocketManager::parse_price_message(msg, DexType::Jupiter, &pair, SPREAD_BPS);
         assert!(result.is_ok());
         assert!(result.unwrap().is_none());
     }
@@ -307,7 +489,8 @@ mod tests {
     fn test_parse_error_message() {
         let msg = r#"{"type": "error", "message": "rate limited"}"#;
         let pair = TokenPair::new("SOL", "USDC");
-        let result = WebSocketManager::parse_price_message(msg, DexType::Jupiter, &pair);
+        let result =
+            WebSocketManager::parse_price_message(msg, DexType::Jupiter, &pair, SPREAD_BPS);
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("rate limited"));
     }
@@ -316,7 +499,8 @@ mod tests {
     fn test_parse_invalid_json() {
         let msg = "not json at all";
         let pair = TokenPair::new("SOL", "USDC");
-        let result = WebSocketManager::parse_price_message(msg, DexType::Jupiter, &pair);
+        let result =
+            WebSocketManager::parse_price_message(msg, DexType::Jupiter, &pair, SPREAD_BPS);
         assert!(result.is_err());
     }
 
@@ -324,7 +508,8 @@ mod tests {
     fn test_parse_unrecognized_format_returns_none() {
         let msg = r#"{"status": "ok", "info": "connected"}"#;
         let pair = TokenPair::new("SOL", "USDC");
-        let result = WebSocketManager::parse_price_message(msg, DexType::Jupiter, &pair);
+        let result =
+            WebSocketManager::parse_price_message(msg, DexType::Jupiter, &pair, SPREAD_BPS);
         assert!(result.is_ok());
         assert!(result.unwrap().is_none());
     }
@@ -333,7 +518,8 @@ mod tests {
     fn test_parse_jupiter_quote_style() {
         let msg = r#"{"inAmount": "1000000", "outAmount": "42500000"}"#;
         let pair = TokenPair::new("USDC", "SOL");
-        let result = WebSocketManager::parse_price_message(msg, DexType::Jupiter, &pair);
+        let result =
+            WebSocketManager::parse_price_message(msg, DexType::Jupiter, &pair, SPREAD_BPS);
         assert!(result.is_ok());
         let price = result.unwrap().expect("Should produce PriceData");
         // 42500000 / 1000000 = 42.5
@@ -360,4 +546,104 @@ mod tests {
         let val = serde_json::json!(true);
         assert!(parse_decimal_value(&val).is_none());
     }
+
+    #[test]
+    fn test_reconnect_delay_backoff_and_min_interval() {
+        let policy = ReconnectPolicy {
+            min_interval: Duration::from_secs(5),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(8),
+            ..Default::default()
+        };
+        let long_session = Duration::from_secs(120);
+
+        // Exponential backoff, capped
+        assert_eq!(
+            policy.delay_before_reconnect(1, long_session),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            policy.delay_before_reconnect(3, long_session),
+            Duration::from_secs(4)
+        );
+        assert_eq!(
+            policy.delay_before_reconnect(10, long_session),
+            Duration::from_secs(8)
+        );
+        assert_eq!(
+            policy.delay_before_reconnect(u32::MAX, long_session),
+            Duration::from_secs(8)
+        );
+
+        // An instant drop still waits out the minimum interval
+        assert_eq!(
+            policy.delay_before_reconnect(1, Duration::ZERO),
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            policy.delay_before_reconnect(1, Duration::from_secs(3)),
+            Duration::from_secs(2)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reconnects_after_server_drop() {
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        // First session is dropped right after the subscribe; the second gets a tick
+        let server = tokio::spawn(async move {
+            let mut subscribes = 0;
+            for session in 0..2 {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                if let Some(Ok(Message::Text(_))) = ws.next().await {
+                    subscribes += 1;
+                }
+                if session == 1 {
+                    let tick = r#"{"bid": "100.5", "ask": "101.0"}"#;
+                    ws.send(Message::Text(tick.to_string())).await.unwrap();
+                }
+            }
+            subscribes
+        });
+
+        let (tx, mut rx) = mpsc::channel(1);
+        let manager = WebSocketManager::new(tx)
+            .with_endpoint(DexType::Raydium, url)
+            .with_reconnect_policy(ReconnectPolicy {
+                min_interval: Duration::ZERO,
+                initial_backoff: Duration::from_millis(10),
+                ..Default::default()
+            });
+        let client = tokio::spawn(async move {
+            manager
+                .start_with_reconnection(DexType::Raydium, TokenPair::new("SOL", "USDC"))
+                .await
+        });
+
+        let price = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("no price after reconnect")
+            .unwrap();
+        assert_eq!(price.bid, Decimal::from_str("100.5").unwrap());
+        assert_eq!(server.await.unwrap(), 2);
+        client.abort();
+    }
+
+    #[tokio::test]
+    async fn test_dex_without_feed_falls_back_to_http() {
+        let (tx, _rx) = mpsc::channel(1);
+        let manager = WebSocketManager::new(tx);
+
+        manager
+            .start_with_reconnection(DexType::Orca, TokenPair::new("SOL", "USDC"))
+            .await;
+
+        assert!(manager.is_http_only(DexType::Orca));
+        assert!(!manager.is_http_only(DexType::Raydium));
+        assert_eq!(manager.http_only_dexes(), vec![DexType::Orca]);
+    }
 }
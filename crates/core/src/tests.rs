@@ -48,6 +48,55 @@ mod types_tests {
         let price = make_price(DexType::Orca, "SOL", "USDC", 100.0, 102.0);
         assert_eq!(price.mid_price, Decimal::from(101));
     }
+
+    #[test]
+    fn test_price_data_from_mid() {
+        let price = PriceData::from_mid(
+            DexType::Jupiter,
+            TokenPair::new("SOL", "USDC"),
+            Decimal::from(100),
+            20,
+        );
+        // 20 bps total spread → 0.10 either side of the mid
+        assert_eq!(price.bid, Decimal::new(999, 1));
+        assert_eq!(price.ask, Decimal::new(1001, 1));
+        assert_eq!(price.mid_price, Decimal::from(100));
+        assert!(price.one_sided);
+        assert!(!make_price(DexType::Orca, "SOL", "USDC", 100.0, 102.0).one_sided);
+    }
+
+    #[test]
+    fn test_opportunity_profit_usd() {
+        let opp = crate::types::ArbitrageOpportunity {
+            id: crate::types::Uuid::new_v4(),
+            pair: TokenPair::new("SOL", "USDC"),
+            buy_dex: DexType::Raydium,
+            sell_dex: DexType::Orca,
+            buy_price: Decimal::from(100),
+            sell_price: Decimal::from(101),
+            gross_profit_pct: Decimal::ONE,
+            net_profit_pct: Decimal::new(45, 2),
+            estimated_profit_usd: None,
+            recommended_size: None,
+            detected_at: chrono::Utc::now(),
+            expired_at: None,
+            path: None,
+        };
+        let size = Decimal::from(1000);
+        assert_eq!(opp.gross_profit_usd(size).unwrap(), Decimal::from(10));
+        assert_eq!(opp.net_profit_usd(size).unwrap(), Decimal::new(45, 1));
+        assert_eq!(
+            opp.net_profit_after_costs_usd(size, Decimal::new(5, 1))
+                .unwrap(),
+            Decimal::from(4)
+        );
+
+        let wide = crate::types::ArbitrageOpportunity {
+            net_profit_pct: Decimal::from(150),
+            ..opp
+        };
+        assert!(wide.net_profit_usd(Decimal::MAX).is_err());
+    }
 }
 
 #[cfg(test)]
@@ -119,6 +168,93 @@ mod arbitrage_tests {
         );
     }
 
+    #[test]
+    fn test_borderline_opportunity_not_lost_to_display_rounding() {
+        let mut detector = ArbitrageDetector::new(ArbitrageConfig {
+            min_profit_threshold: Decimal::new(5, 1), // 0.5%
+            ..Default::default()
+        });
+        let pair = TokenPair::new("SOL", "USDC");
+
+        // Net edge of 0.504% after 0.55% of fees: above the threshold, but displays as 0.50
+        detector.update_price(make_price(DexType::Raydium, "SOL", "USDC", 99.9, 100.0));
+        detector.update_price(make_price(DexType::Orca, "SOL", "USDC", 101.054, 101.1));
+
+        let opportunities = detector.find_opportunities(&pair);
+        assert_eq!(
+            opportunities.len(),
+            1,
+            "borderline opportunity should be detected"
+        );
+
+        let net = opportunities[0].net_profit_pct;
+        assert_eq!(net, Decimal::new(504, 3));
+        // Rounded for display it no longer clears the threshold, which is why
+        // comparisons must never use the rounded value
+        let displayed = crate::math::DisplayRounding::default().pct(net);
+        assert_eq!(displayed.to_string(), "0.50");
+        assert!(displayed <= Decimal::new(5, 1));
+    }
+
+    #[test]
+    fn test_extreme_prices_do_not_panic() {
+        let mut detector = create_detector_with_low_threshold();
+        let pair = TokenPair::new("SOL", "USDC");
+
+        // A dust ask against a huge bid overflows the profit percentage
+        detector.update_price(PriceData::new(
+            DexType::Raydium,
+            pair.clone(),
+            Decimal::new(1, 28),
+            Decimal::new(1, 28),
+        ));
+        detector.update_price(PriceData::new(
+            DexType::Orca,
+            pair.clone(),
+            Decimal::from(10u128.pow(20)),
+            Decimal::from(10u128.pow(20)),
+        ));
+
+        let opportunities = detector.find_opportunities(&pair);
+        assert!(opportunities
+            .iter()
+            .all(|o| !(o.buy_dex == DexType::Raydium && o.sell_dex == DexType::Orca)));
+    }
+
+    #[test]
+    fn test_one_sided_quotes_require_extra_edge() {
+        let config = ArbitrageConfig {
+            min_profit_threshold: Decimal::new(5, 1),     // 0.5%
+            one_sided_extra_edge_pct: Decimal::new(2, 1), // +0.2% per synthetic leg
+            ..Default::default()
+        };
+        let pair = TokenPair::new("SOL", "USDC");
+        let buy = make_price(DexType::Raydium, "SOL", "USDC", 99.9, 100.0);
+        // 0.6% net: clears the base threshold but not threshold + extra edge
+        let sell_mid = Decimal::new(10115, 2);
+
+        let mut two_sided = ArbitrageDetector::new(config.clone());
+        two_sided.update_price(buy.clone());
+        two_sided.update_price(PriceData::new(
+            DexType::Orca,
+            pair.clone(),
+            sell_mid,
+            sell_mid + Decimal::ONE,
+        ));
+        assert_eq!(two_sided.find_opportunities(&pair).len(), 1);
+
+        let mut one_sided = ArbitrageDetector::new(config);
+        one_sided.update_price(buy);
+        // Zero synthetic spread keeps the bid identical; only the one-sided flag differs
+        one_sided.update_price(PriceData::from_mid(
+            DexType::Orca,
+            pair.clone(),
+            sell_mid,
+            0,
+        ));
+        assert!(one_sided.find_opportunities(&pair).is_empty());
+    }
+
     #[test]
     fn test_multiple_dexs() {
         let mut detector = create_detector_with_low_threshold();
@@ -186,11 +322,50 @@ mod arbitrage_tests {
             "Stale prices should not contribute to opportunities"
         );
     }
+
+    #[test]
+    fn test_expire_stale_opportunities() {
+        let mut detector = create_detector_with_low_threshold();
+        let pair = TokenPair::new("SOL", "USDC");
+
+        let mut raydium_price = make_price(DexType::Raydium, "SOL", "USDC", 99.9, 100.0);
+        raydium_price.timestamp -= Duration::seconds(10);
+        detector.update_price(raydium_price);
+        detector.update_price(make_price(DexType::Orca, "SOL", "USDC", 102.0, 102.1));
+
+        let opportunities = detector.find_opportunities(&pair);
+        assert_eq!(opportunities.len(), 1);
+        detector.track(&opportunities);
+
+        // Prices still cached and the opportunity is young: nothing expires
+        let max_age = std::time::Duration::from_secs(60);
+        assert!(detector.expire_stale(max_age).is_empty());
+        assert_eq!(detector.active_opportunities().count(), 1);
+
+        // The Raydium leg ages out of the cache
+        detector.clear_stale_prices(5);
+        let expired = detector.expire_stale(max_age);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, opportunities[0].id);
+        assert!(!expired[0].is_active());
+        assert_eq!(detector.active_opportunities().count(), 0);
+
+        // Old enough opportunities expire even with their prices cached
+        detector.update_price(make_price(DexType::Jupiter, "SOL", "USDC", 99.9, 100.0));
+        let opportunities = detector.find_opportunities(&pair);
+        detector.track(&opportunities);
+        assert!(detector.expire_stale(max_age).is_empty());
+        assert_eq!(
+            detector.expire_stale(std::time::Duration::ZERO).len(),
+            opportunities.len()
+        );
+    }
 }
 
 #[cfg(test)]
 mod config_tests {
     use crate::config::Config;
+    use crate::error::ArbitrageError;
 
     #[test]
     fn test_default_config() {
@@ -200,4 +375,397 @@ mod config_tests {
         assert_eq!(config.max_price_age_seconds, 5);
         assert!(config.solana_rpc_url.contains("solana"));
     }
+
+    #[test]
+    fn test_default_http_timeouts() {
+        use crate::http::pool::HttpTimeouts;
+        use std::time::Duration;
+
+        let timeouts = HttpTimeouts::from_config(&Config::default());
+        assert_eq!(timeouts.price_fetch, Duration::from_millis(500));
+        assert!(timeouts.quote > timeouts.price_fetch);
+        assert!(timeouts.swap_build > timeouts.quote);
+        assert_eq!(timeouts.connect, Duration::from_millis(2000));
+        assert_eq!(timeouts.retries, 1);
+        assert_eq!(timeouts, HttpTimeouts::default());
+    }
+
+    #[test]
+    fn test_parse_list_handles_empty_and_whitespace() {
+        use crate::config::parse_list;
+
+        assert!(parse_list("").is_empty());
+        assert!(parse_list("  ,  , ").is_empty());
+        assert_eq!(
+            parse_list(" SOL/USDC ,RAY / USDC,, "),
+            vec!["SOL/USDC", "RAY/USDC"]
+        );
+    }
+
+    #[test]
+    fn test_enabled_pairs_and_blacklisted_dexes() {
+        use crate::config::parse_dexes;
+        use crate::types::{DexType, TokenPair};
+
+        let mut config = Config::default();
+        assert!(config.is_pair_enabled(&TokenPair::new("BONK", "USDC")));
+        assert!(config.is_dex_enabled(DexType::Meteora));
+
+        config.enabled_pairs = vec!["sol/usdc".to_string()];
+        assert!(config.is_pair_enabled(&TokenPair::new("SOL", "USDC")));
+        assert!(!config.is_pair_enabled(&TokenPair::new("RAY", "USDC")));
+
+        config.blacklisted_dexes = parse_dexes("meteora, Phoenix, NotADex");
+        assert_eq!(
+            config.blacklisted_dexes,
+            vec![DexType::Meteora, DexType::Phoenix]
+        );
+        assert!(!config.is_dex_enabled(DexType::Meteora));
+        assert!(config.is_dex_enabled(DexType::Raydium));
+    }
+
+    #[test]
+    fn test_config_file_round_trip_and_env_precedence() {
+        use crate::types::DexType;
+
+        let config = Config {
+            slippage_bps: 30,
+            min_profit_threshold: 0.8,
+            enabled_pairs: vec!["SOL/USDC".to_string()],
+            blacklisted_dexes: vec![DexType::Meteora],
+            paper_starting_balances: vec![("USDC".to_string(), 500.0), ("SOL".to_string(), 2.0)],
+            telegram_webhook_url: Some("https://example.com/hook".to_string()),
+            ..Config::default()
+        };
+        let contents = toml::to_string(&config).unwrap();
+        assert_eq!(Config::from_toml(&contents, |_| None).unwrap(), config);
+
+        // Keys left out of the file keep their defaults
+        let partial = Config::from_toml("max_retries = 7\n", |_| None).unwrap();
+        assert_eq!(partial.max_retries, 7);
+        assert_eq!(partial.slippage_bps, Config::default().slippage_bps);
+
+        // The environment wins over the file
+        let env = |key: &str| (key == "MAX_RETRIES").then(|| "9".to_string());
+        assert_eq!(Config::from_toml("max_retries = 7\n", env).unwrap().max_retries, 9);
+
+        assert!(Config::from_toml("max_retries = \"many\"\n", |_| None).is_err());
+
+        // Reading the file itself; bad contents are reported with its path
+        let path = std::env::temp_dir().join(format!("arb-config-{}.toml", std::process::id()));
+        std::fs::write(&path, "max_retries = \"many\"\n").unwrap();
+        let err = Config::from_file(&path).unwrap_err().to_string();
+        std::fs::remove_file(&path).unwrap();
+        assert!(err.contains(path.to_str().unwrap()), "{}", err);
+    }
+
+    #[test]
+    fn test_config_rejects_non_positive_trade_cap() {
+        assert!(Config::default().validated().is_ok());
+        for cap in ["0", "-100"] {
+            let env = |key: &str| (key == "HARD_MAX_TRADE_USD").then(|| cap.to_string());
+            assert!(matches!(
+                Config::default().with_env_overrides(env).validated(),
+                Err(ArbitrageError::Config(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_config_rejects_recovery_fraction_outside_unit_interval() {
+        let with_fraction = |fraction: &str| {
+            let env = |key: &str| (key == "RECOVERY_SIZE_FRACTION").then(|| fraction.to_string());
+            Config::default().with_env_overrides(env).validated()
+        };
+        assert!(with_fraction("1").is_ok());
+        assert!(with_fraction("0.25").is_ok());
+        for fraction in ["0", "-0.5", "1.5", "NaN"] {
+            assert!(matches!(with_fraction(fraction), Err(ArbitrageError::Config(_))));
+        }
+    }
+
+    #[test]
+    fn test_config_rejects_smoothing_factor_outside_unit_interval() {
+        let with_factor = |factor: &str| {
+            let env = |key: &str| (key == "PRICE_SMOOTHING_FACTOR").then(|| factor.to_string());
+            Config::default().with_env_overrides(env).validated()
+        };
+        for factor in ["0", "0.3", "1"] {
+            assert!(with_factor(factor).is_ok(), "{}", factor);
+        }
+        for factor in ["-0.1", "1.5", "NaN"] {
+            assert!(matches!(with_factor(factor), Err(ArbitrageError::Config(_))));
+        }
+    }
+}
+
+#[cfg(test)]
+mod history_tests {
+    use crate::history::TradeRecord;
+
+    #[test]
+    fn test_trade_record_without_latency_still_parses() {
+        let line = r#"{"timestamp":"2024-01-01T00:00:00Z","session_id":"S","trade_type":"REAL","pair":"SOL/USDC","buy_dex":"Raydium","sell_dex":"Orca","size_usd":"100.00","profit_usd":"1.0000","profit_pct":"1.00","tx_signature":null,"success":true,"error":null}"#;
+        let record: TradeRecord = serde_json::from_str(line).unwrap();
+        assert!(record.quote_ms.is_none());
+        assert!(record.confirm_ms.is_none());
+    }
+
+    #[test]
+    fn test_trade_record_latency_roundtrip() {
+        let line = r#"{"timestamp":"2024-01-01T00:00:00Z","session_id":"S","trade_type":"REAL","pair":"SOL/USDC","buy_dex":"Raydium","sell_dex":"Orca","size_usd":"100.00","profit_usd":"1.0000","profit_pct":"1.00","tx_signature":null,"success":true,"error":null,"quote_ms":120,"submit_ms":40}"#;
+        let record: TradeRecord = serde_json::from_str(line).unwrap();
+        assert_eq!(record.quote_ms, Some(120));
+        assert_eq!(record.submit_ms, Some(40));
+
+        let json = serde_json::to_string(&record).unwrap();
+        assert!(json.contains("\"quote_ms\":120"));
+        assert!(!json.contains("build_ms"));
+    }
+
+    #[test]
+    fn test_history_reader_round_trip() {
+        use crate::history::{HistoryReader, HistoryRecorder};
+        use crate::types::{ArbitrageOpportunity, DexType, TokenPair};
+        use rust_decimal::Decimal;
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join(format!("history-{}.jsonl", uuid::Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+
+        let opp = |base: &str| ArbitrageOpportunity {
+            id: uuid::Uuid::new_v4(),
+            pair: TokenPair::new(base, "USDC"),
+            buy_dex: DexType::Raydium,
+            sell_dex: DexType::Orca,
+            buy_price: Decimal::new(100, 0),
+            sell_price: Decimal::new(101, 0),
+            gross_profit_pct: Decimal::ONE,
+            net_profit_pct: Decimal::new(9, 1),
+            estimated_profit_usd: None,
+            recommended_size: None,
+            detected_at: chrono::Utc::now(),
+            expired_at: None,
+            path: None,
+        };
+        let size = Decimal::new(100, 0);
+
+        let record = |recorder: &HistoryRecorder, base: &str, profit: i64, success: bool| {
+            let profit = Decimal::new(profit, 2);
+            recorder.record_trade(&opp(base), size, profit, success, None, None, true);
+        };
+        let first = HistoryRecorder::new(path, "S1");
+        record(&first, "SOL", 250, true);
+        record(&first, "SOL", 0, false);
+        let second = HistoryRecorder::new(path, "S2");
+        record(&second, "RAY", -50, true);
+        record(&second, "SOL", 100, true);
+
+        // A torn write and a record with a garbled amount
+        let mut file = std::fs::OpenOptions::new().append(true).open(path).unwrap();
+        writeln!(file, r#"{{"timestamp":"2024-01-01T00:0"#).unwrap();
+        let garbled = r#"{"timestamp":"2024-01-01T00:00:00Z","session_id":"S2","trade_type":"REAL","pair":"SOL/USDC","buy_dex":"Raydium","sell_dex":"Orca","size_usd":"abc","profit_usd":"1","profit_pct":"1","tx_signature":null,"success":true,"error":null}"#;
+        writeln!(file, "{}", garbled).unwrap();
+        writeln!(file).unwrap();
+
+        let history = HistoryReader::load(path).unwrap();
+        let _ = std::fs::remove_file(path);
+
+        assert_eq!(history.entries().len(), 4);
+        assert_eq!(history.skipped_lines(), 2);
+        assert_eq!(history.total_pnl(), Decimal::new(300, 2));
+        assert_eq!(history.win_rate(), 0.5);
+
+        let s1 = history.filter_by_session("S1");
+        assert_eq!(s1.total_pnl(), Decimal::new(250, 2));
+        assert_eq!(s1.win_rate(), 0.5);
+
+        let sol = history.filter_by_pair("SOL/USDC");
+        assert_eq!(sol.entries().len(), 3);
+        assert_eq!(sol.total_pnl(), Decimal::new(350, 2));
+        assert!(history.filter_by_pair("JUP/USDC").entries().is_empty());
+        assert_eq!(history.filter_by_pair("JUP/USDC").win_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_sol_quoted_profit_recorded_in_both_currencies() {
+        use crate::arbitrage::ArbitrageDetector;
+        use crate::history::{HistoryReader, HistoryRecorder};
+        use crate::types::{ArbitrageOpportunity, DexType, TokenPair};
+        use rust_decimal::Decimal;
+
+        let path = std::env::temp_dir().join(format!("history-{}.jsonl", uuid::Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+        let opp = ArbitrageOpportunity {
+            id: uuid::Uuid::new_v4(),
+            pair: TokenPair::new("RAY", "SOL"),
+            buy_dex: DexType::Raydium,
+            sell_dex: DexType::Orca,
+            buy_price: Decimal::new(1, 2),
+            sell_price: Decimal::new(101, 4),
+            gross_profit_pct: Decimal::ONE,
+            net_profit_pct: Decimal::new(9, 1),
+            estimated_profit_usd: None,
+            recommended_size: None,
+            detected_at: chrono::Utc::now(),
+            expired_at: None,
+            path: None,
+        };
+
+        // SOL is worth 150 USDC by the cached prices; RAY has no USD price at all
+        let mut detector = ArbitrageDetector::default();
+        detector.update_price(super::make_price(
+            DexType::Raydium,
+            "SOL",
+            "USDC",
+            149.9,
+            150.1,
+        ));
+        let sol_usd = detector.usd_price("SOL");
+        assert_eq!(sol_usd, Some(Decimal::from(150)));
+        assert_eq!(detector.usd_price("USDT"), Some(Decimal::ONE));
+        assert_eq!(detector.usd_price("RAY"), None);
+
+        // 0.02 SOL of profit is 3 USD, and only USD with a price to convert at
+        let profit = Decimal::new(2, 2);
+        assert_eq!(opp.quote_to_usd(profit, sol_usd), Some(Decimal::from(3)));
+        assert_eq!(opp.quote_to_usd(profit, None), None);
+
+        let recorder = HistoryRecorder::new(path, "S1");
+        let size = Decimal::from(10);
+        let priced =
+            recorder.build_record(&opp, size, profit, sol_usd, true, None, None, true, None);
+        assert_eq!(priced.profit_quote.as_deref(), Some("0.02"));
+        let usd = priced
+            .profit_usd
+            .as_deref()
+            .map(|usd| usd.parse::<Decimal>().unwrap());
+        assert_eq!(usd, Some(Decimal::from(3)));
+        recorder.append(&priced);
+        let unpriced =
+            recorder.build_record(&opp, size, profit, None, true, None, None, true, None);
+        assert_eq!(unpriced.profit_usd, None);
+        recorder.append(&unpriced);
+
+        // Both count as wins, but only the converted one adds to the USD total
+        let history = HistoryReader::load(path).unwrap();
+        let _ = std::fs::remove_file(path);
+        assert_eq!(history.entries().len(), 2);
+        assert_eq!(history.entries()[1].profit_quote, profit);
+        assert_eq!(history.total_pnl(), Decimal::from(3));
+        assert_eq!(history.win_rate(), 1.0);
+    }
+}
+
+#[cfg(test)]
+mod dedupe_tests {
+    use super::*;
+    use crate::risk::dedupe::ExecutionDedupe;
+    use crate::types::ArbitrageOpportunity;
+
+    fn detect_opportunity(sell_bid: f64) -> ArbitrageOpportunity {
+        let mut detector = ArbitrageDetector::new(ArbitrageConfig {
+            min_profit_threshold: Decimal::new(1, 3),
+            ..Default::default()
+        });
+        detector.update_price(make_price(DexType::Raydium, "SOL", "USDC", 99.9, 100.0));
+        detector.update_price(make_price(
+            DexType::Orca,
+            "SOL",
+            "USDC",
+            sell_bid,
+            sell_bid + 0.1,
+        ));
+        detector
+            .find_opportunities(&TokenPair::new("SOL", "USDC"))
+            .remove(0)
+    }
+
+    #[test]
+    fn test_executed_opportunity_survives_restart() {
+        let path = std::env::temp_dir().join(format!("dedupe-{}.json", uuid::Uuid::new_v4()));
+        let opp = detect_opportunity(102.0);
+
+        let mut dedupe = ExecutionDedupe::load(&path, 60);
+        assert!(!dedupe.is_duplicate(&opp));
+        dedupe.mark_executed(&opp);
+
+        // Re-detected with a new id but the same prices is still a duplicate after reload
+        let reloaded = ExecutionDedupe::load(&path, 60);
+        assert!(reloaded.is_duplicate(&detect_opportunity(102.0)));
+        // A changed spread on the same route is a new opportunity
+        assert!(!reloaded.is_duplicate(&detect_opportunity(103.0)));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_blacklisted_route_survives_restart() {
+        let path = std::env::temp_dir().join(format!("dedupe-{}.json", uuid::Uuid::new_v4()));
+
+        let mut dedupe = ExecutionDedupe::load(&path, 60);
+        dedupe.blacklist(&detect_opportunity(102.0));
+
+        let reloaded = ExecutionDedupe::load(&path, 60);
+        assert!(reloaded.is_duplicate(&detect_opportunity(103.0)));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_daily_loss_limit_survives_restart() {
+        use crate::risk::{RiskConfig, RiskManager, TradeDecision, TradeOutcome};
+        use rust_decimal::Decimal;
+
+        let path = std::env::temp_dir().join(format!("risk-{}.json", uuid::Uuid::new_v4()));
+        let config = RiskConfig {
+            max_daily_loss: Decimal::from(50),
+            loss_cooldown_seconds: 0,
+            ..Default::default()
+        };
+        let loss = |amount: i64| TradeOutcome {
+            timestamp: chrono::Utc::now(),
+            pair: "SOL/USDC".to_string(),
+            profit_loss: Decimal::from(-amount),
+            was_successful: true,
+        };
+
+        // $30 lost, then a restart: the next $30 loss still breaches the $50 limit
+        let mut manager = RiskManager::new(config.clone()).with_state_file(&path);
+        manager.record_trade(loss(30)).await;
+        let mut manager = RiskManager::new(config.clone()).with_state_file(&path);
+        assert_eq!(manager.daily_pnl(), Decimal::from(-30));
+        assert!(!manager.is_paused().await);
+        manager.record_trade(loss(30)).await;
+        assert!(manager.is_paused().await);
+
+        // Restarting again doesn't lift the halt, even with a fresh circuit breaker
+        let manager = RiskManager::new(config).with_state_file(&path);
+        let status = manager.status().await;
+        assert!(status.is_paused);
+        assert!(status.kill_switch.unwrap().contains("Daily loss limit"));
+        assert!(matches!(
+            manager.can_trade("SOL/USDC", Decimal::from(10)).await,
+            TradeDecision::Rejected { .. }
+        ));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_expired_entries_dropped_on_load() {
+        let path = std::env::temp_dir().join(format!("dedupe-{}.json", uuid::Uuid::new_v4()));
+        let opp = detect_opportunity(102.0);
+
+        let mut dedupe = ExecutionDedupe::load(&path, 60);
+        dedupe.mark_executed(&opp);
+        dedupe.blacklist(&opp);
+
+        // A zero-second window treats everything on disk as expired
+        let reloaded = ExecutionDedupe::load(&path, 0);
+        assert_eq!(reloaded.counts(), (0, 0));
+        assert!(!reloaded.is_duplicate(&opp));
+
+        let _ = std::fs::remove_file(&path);
+    }
 }
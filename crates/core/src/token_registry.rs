@@ -0,0 +1,206 @@
+//! Token mints and decimals
+//!
+//! Amounts cross the Jupiter API and the chain in atoms, so every quote and
+//! trade needs each token's decimals as well as its mint. [`TokenRegistry`] holds both,
+//! seeded from the static [`TOKEN_REGISTRY`] and extended at runtime with
+//! mints whose decimals are read from chain.
+
+use crate::{ArbitrageError, ArbitrageResult};
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+// Token Mints (Mainnet)
+pub const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+pub const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+pub const RAY_MINT: &str = "4k3Dyjzvzp8eMZWUXbBCjEvwSkkk59S5iCNLY3QrkX6R";
+pub const ORCA_MINT: &str = "orcaEKTdK7LKz57vaAYr9QeNsVEPfiu6QeMU1kektZE";
+pub const BONK_MINT: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+pub const USDT_MINT: &str = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
+pub const JUP_MINT: &str = "JUPyiwrYJFskUPiHa7hkeR8VUtAe6poCFFRLnWo6vCN";
+
+/// Solend's devnet USDC, which stands in for USDC on devnet
+pub const DEVNET_USDC_MINT: &str = "zVzi5VAf4qMEwzv7NXECVx5v2pQ7xnqVVjCXZwS9XzA";
+
+/// Static metadata for a supported token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenInfo {
+    pub symbol: &'static str,
+    pub mint: &'static str,
+    pub decimals: u32,
+}
+
+/// Registry of supported tokens (Mainnet mints and SPL decimals).
+#[rustfmt::skip]
+pub const TOKEN_REGISTRY: &[TokenInfo] = &[
+    TokenInfo { symbol: "SOL", mint: SOL_MINT, decimals: 9 },
+    TokenInfo { symbol: "USDC", mint: USDC_MINT, decimals: 6 },
+    TokenInfo { symbol: "USDT", mint: USDT_MINT, decimals: 6 },
+    TokenInfo { symbol: "RAY", mint: RAY_MINT, decimals: 6 },
+    TokenInfo { symbol: "ORCA", mint: ORCA_MINT, decimals: 6 },
+    TokenInfo { symbol: "BONK", mint: BONK_MINT, decimals: 5 },
+    TokenInfo { symbol: "JUP", mint: JUP_MINT, decimals: 6 },
+];
+
+/// Looks up a token in the registry by symbol.
+pub fn token_info(symbol: &str) -> Option<&'static TokenInfo> {
+    TOKEN_REGISTRY.iter().find(|t| t.symbol == symbol)
+}
+
+/// Size of an SPL Token mint account (Token-2022 mints with extensions are longer)
+const MINT_ACCOUNT_LEN: usize = 82;
+/// Offset of `decimals` in a mint: after the mint authority option and supply
+const MINT_DECIMALS_OFFSET: usize = 44;
+/// Offset of the `is_initialized` flag, right after `decimals`
+const MINT_INITIALIZED_OFFSET: usize = 45;
+
+/// Reads `decimals` from the data of an initialized SPL Token mint account.
+pub fn parse_mint_decimals(data: &[u8]) -> Option<u32> {
+    if data.len() < MINT_ACCOUNT_LEN || data[MINT_INITIALIZED_OFFSET] != 1 {
+        return None;
+    }
+    Some(u32::from(data[MINT_DECIMALS_OFFSET]))
+}
+
+/// A token's mint and decimals
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisteredToken {
+    pub mint: String,
+    pub decimals: u32,
+}
+
+/// Symbol → mint and decimals for every token the executor can trade
+#[derive(Debug, Clone, Default)]
+pub struct TokenRegistry {
+    tokens: HashMap<String, RegisteredToken>,
+}
+
+impl TokenRegistry {
+    /// Registry of the static mainnet tokens
+    pub fn mainnet() -> Self {
+        let mut registry = Self::default();
+        for token in TOKEN_REGISTRY {
+            registry.insert(token.symbol, token.mint, token.decimals);
+        }
+        registry
+    }
+
+    /// Registry for devnet: USDC is Solend's devnet mint. Other tokens keep
+    /// their mainnet mints, which may not exist on devnet.
+    pub fn devnet() -> Self {
+        let mut registry = Self::mainnet();
+        registry.insert("USDC", DEVNET_USDC_MINT, 6);
+        registry
+    }
+
+    /// Adds `symbol`, replacing any token already registered under it
+    pub fn insert(&mut self, symbol: impl Into<String>, mint: impl Into<String>, decimals: u32) {
+        self.tokens.insert(
+            symbol.into(),
+            RegisteredToken {
+                mint: mint.into(),
+                decimals,
+            },
+        );
+    }
+
+    /// Mint and decimals of `symbol`, if it is registered
+    pub fn get(&self, symbol: &str) -> Option<&RegisteredToken> {
+        self.tokens.get(symbol)
+    }
+
+    /// Mint address of `symbol`
+    pub fn mint(&self, symbol: &str) -> ArbitrageResult<&str> {
+        self.lookup(symbol).map(|t| t.mint.as_str())
+    }
+
+    /// Mint of `symbol` as a pubkey
+    pub fn pubkey(&self, symbol: &str) -> ArbitrageResult<Pubkey> {
+        let mint = self.mint(symbol)?;
+        Pubkey::from_str(mint)
+            .map_err(|e| ArbitrageError::InvalidPubkey(format!("{} mint {}: {}", symbol, mint, e)))
+    }
+
+    /// Decimals of `symbol`'s mint
+    pub fn decimals(&self, symbol: &str) -> ArbitrageResult<u32> {
+        self.lookup(symbol).map(|t| t.decimals)
+    }
+
+    fn lookup(&self, symbol: &str) -> ArbitrageResult<&RegisteredToken> {
+        self.tokens
+            .get(symbol)
+            .ok_or_else(|| ArbitrageError::InvalidOpportunity(format!("Unknown token: {}", symbol)))
+    }
+
+    /// Registers `symbol` with decimals read from its mint account on chain,
+    /// returning them.
+    pub async fn register_from_chain(
+        &mut self,
+        rpc: &RpcClient,
+        symbol: &str,
+        mint: &str,
+    ) -> ArbitrageResult<u32> {
+        let address =
+            Pubkey::from_str(mint).map_err(|e| ArbitrageError::InvalidPubkey(e.to_string()))?;
+        let data = rpc
+            .get_account_data(&address)
+            .await
+            .map_err(|e| ArbitrageError::RpcError(e.to_string()))?;
+        let decimals = parse_mint_decimals(&data).ok_or_else(|| {
+            ArbitrageError::InvalidOpportunity(format!("{} ({}) is not a token mint", symbol, mint))
+        })?;
+        self.insert(symbol, mint, decimals);
+        Ok(decimals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::to_atoms;
+    use rust_decimal::Decimal;
+
+    /// Data of an initialized mint account with `decimals`
+    fn mint_account(decimals: u8) -> Vec<u8> {
+        let mut data = vec![0u8; MINT_ACCOUNT_LEN];
+        data[MINT_DECIMALS_OFFSET] = decimals;
+        data[MINT_INITIALIZED_OFFSET] = 1;
+        data
+    }
+
+    #[test]
+    fn test_amounts_scale_by_each_tokens_decimals() {
+        let registry = TokenRegistry::mainnet();
+        let one = Decimal::ONE;
+
+        // BONK (5), USDC (6) and SOL (9) each scale one whole token differently
+        let atoms = |symbol| to_atoms(one, registry.decimals(symbol).unwrap()).unwrap();
+        assert_eq!(atoms("BONK"), 100_000);
+        assert_eq!(atoms("USDC"), 1_000_000);
+        assert_eq!(atoms("SOL"), 1_000_000_000);
+
+        assert_eq!(registry.mint("BONK").unwrap(), BONK_MINT);
+        assert!(registry.decimals("NOPE").is_err());
+        assert_eq!(
+            TokenRegistry::devnet().mint("USDC").unwrap(),
+            DEVNET_USDC_MINT
+        );
+    }
+
+    #[test]
+    fn test_parse_mint_decimals() {
+        assert_eq!(parse_mint_decimals(&mint_account(5)), Some(5));
+        assert_eq!(parse_mint_decimals(&mint_account(9)), Some(9));
+
+        // Token-2022 mints carry extensions after the base layout
+        let mut extended = mint_account(6);
+        extended.extend_from_slice(&[0u8; 100]);
+        assert_eq!(parse_mint_decimals(&extended), Some(6));
+
+        let mut uninitialized = mint_account(6);
+        uninitialized[MINT_INITIALIZED_OFFSET] = 0;
+        assert_eq!(parse_mint_decimals(&uninitialized), None);
+        assert_eq!(parse_mint_decimals(&[0u8; 40]), None);
+    }
+}
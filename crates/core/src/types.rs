@@ -61,6 +61,27 @@ impl std::fmt::Display for DexType {
     }
 }
 
+impl std::str::FromStr for DexType {
+    type Err = String;
+
+    /// Parse a DEX by its display name, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DexType::all()
+            .iter()
+            .copied()
+            .find(|dex| dex.display_name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("unknown DEX '{}'", s))
+    }
+}
+
+/// Quote tokens whose amounts are taken as USD without conversion
+pub const USD_STABLECOINS: &[&str] = &["USDC", "USDT"];
+
+/// Whether `symbol` is one of the [`USD_STABLECOINS`]
+pub fn is_usd_stablecoin(symbol: &str) -> bool {
+    USD_STABLECOINS.contains(&symbol)
+}
+
 /// Represents a trading pair of tokens
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct TokenPair {
@@ -82,6 +103,11 @@ impl TokenPair {
     pub fn symbol(&self) -> String {
         format!("{}/{}", self.base, self.quote)
     }
+
+    /// Whether amounts in the quote token are already USD
+    pub fn is_usd_quoted(&self) -> bool {
+        is_usd_stablecoin(&self.quote)
+    }
 }
 
 impl std::fmt::Display for TokenPair {
@@ -105,12 +131,23 @@ pub struct PriceData {
     pub mid_price: Decimal,
     /// 24-hour trading volume in quote currency
     pub volume_24h: Option<Decimal>,
-    /// Available liquidity depth
+    /// Available liquidity depth, in units of the quote token
     pub liquidity: Option<Decimal>,
     /// Timestamp when this price was recorded
     pub timestamp: DateTime<Utc>,
+    /// Identity of the underlying pool/route this price was quoted from
+    /// (e.g. AMM id or whirlpool address), if the provider exposes it
+    #[serde(default)]
+    pub route_id: Option<String>,
+    /// True when bid/ask were synthesized around a single quoted price
+    /// (see [`PriceData::from_mid`]) rather than observed on the book
+    #[serde(default)]
+    pub one_sided: bool,
 }
 
+/// Synthetic spread applied to single-price sources that don't specify their own
+pub const DEFAULT_SYNTHETIC_SPREAD_BPS: u32 = 10;
+
 impl PriceData {
     pub fn new(dex: DexType, pair: TokenPair, bid: Decimal, ask: Decimal) -> Self {
         let mid_price = (bid + ask) / Decimal::from(2);
@@ -123,6 +160,39 @@ impl PriceData {
             volume_24h: None,
             liquidity: None,
             timestamp: Utc::now(),
+            route_id: None,
+            one_sided: false,
+        }
+    }
+
+    /// Build a price from a single quoted mid price, placing bid and ask half of
+    /// `synthetic_spread_bps` either side of it. The result is flagged `one_sided`
+    /// so the detector can demand extra edge instead of trusting a fake spread.
+    pub fn from_mid(
+        dex: DexType,
+        pair: TokenPair,
+        mid: Decimal,
+        synthetic_spread_bps: u32,
+    ) -> Self {
+        let half_spread = mid * Decimal::from(synthetic_spread_bps) / Decimal::from(20_000);
+        Self {
+            one_sided: true,
+            ..Self::new(dex, pair, mid - half_spread, mid + half_spread)
+        }
+    }
+
+    /// Attach the underlying pool/route identity to this price
+    pub fn with_route(mut self, route_id: impl Into<String>) -> Self {
+        self.route_id = Some(route_id.into());
+        self
+    }
+
+    /// Whether this price and `other` are quoted from the same underlying pool/route.
+    /// Returns false when either side does not expose a route identity.
+    pub fn shares_route_with(&self, other: &PriceData) -> bool {
+        match (&self.route_id, &other.route_id) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
         }
     }
 
@@ -135,6 +205,112 @@ impl PriceData {
     }
 }
 
+/// One price level of an order book
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookLevel {
+    pub price: Decimal,
+    /// Base-token quantity resting at `price`
+    pub size: Decimal,
+}
+
+/// Order book snapshot for a pair, best levels first on each side
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderBook {
+    /// Highest price first
+    pub bids: Vec<BookLevel>,
+    /// Lowest price first
+    pub asks: Vec<BookLevel>,
+}
+
+impl OrderBook {
+    /// Build a book from unordered levels. Levels with a non-positive price or
+    /// size are dropped and levels at the same price are merged.
+    pub fn new(bids: Vec<BookLevel>, asks: Vec<BookLevel>) -> Self {
+        fn aggregate(mut levels: Vec<BookLevel>, descending: bool) -> Vec<BookLevel> {
+            levels.retain(|l| l.price > Decimal::ZERO && l.size > Decimal::ZERO);
+            levels.sort_by(|a, b| {
+                if descending {
+                    b.price.cmp(&a.price)
+                } else {
+                    a.price.cmp(&b.price)
+                }
+            });
+            let mut merged: Vec<BookLevel> = Vec::with_capacity(levels.len());
+            for level in levels {
+                match merged.last_mut() {
+                    Some(last) if last.price == level.price => last.size += level.size,
+                    _ => merged.push(level),
+                }
+            }
+            merged
+        }
+
+        Self {
+            bids: aggregate(bids, true),
+            asks: aggregate(asks, false),
+        }
+    }
+
+    pub fn best_bid(&self) -> Option<&BookLevel> {
+        self.bids.first()
+    }
+
+    pub fn best_ask(&self) -> Option<&BookLevel> {
+        self.asks.first()
+    }
+
+    /// True when either side has no levels
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty() || self.asks.is_empty()
+    }
+
+    /// The book cut down to the best `levels` on each side
+    pub fn top(&self, levels: usize) -> Self {
+        Self {
+            bids: self.bids.iter().take(levels).copied().collect(),
+            asks: self.asks.iter().take(levels).copied().collect(),
+        }
+    }
+
+    /// Quote-currency value resting on both sides of the book
+    pub fn depth_quote(&self) -> Decimal {
+        self.bids
+            .iter()
+            .chain(&self.asks)
+            .map(|l| l.price * l.size)
+            .sum()
+    }
+}
+
+/// Direction of a trade, relative to a pair's base token
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Side {
+    /// Spend the quote token to receive the base token
+    Buy,
+    /// Spend the base token to receive the quote token
+    Sell,
+}
+
+/// What a DEX would give for a specific trade size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Quote {
+    /// Amount received, after fees, in UI units of the token received
+    pub out_amount: Decimal,
+    /// How far the trade moves the price against itself (1.0 = 1%)
+    pub price_impact_pct: Decimal,
+    /// Fees paid, in UI units of the token received
+    pub fee: Decimal,
+}
+
+impl Quote {
+    /// Fees as a fraction of what the trade would receive without them, in
+    /// `DexType::fee_percentage` units; `None` for an empty quote
+    pub fn fee_rate(&self) -> Option<Decimal> {
+        let gross = self.out_amount + self.fee;
+        (!gross.is_zero()).then(|| self.fee / gross)
+    }
+}
+
 /// An arbitrage opportunity between two DEXs
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ArbitrageOpportunity {
@@ -154,7 +330,9 @@ pub struct ArbitrageOpportunity {
     pub gross_profit_pct: Decimal,
     /// Net profit percentage after fees
     pub net_profit_pct: Decimal,
-    /// Estimated profit in quote currency for a given trade size
+    /// Estimated profit for the recommended size, in the pair's quote token.
+    /// Only USD for stablecoin-quoted pairs, despite the name (kept for stored
+    /// records); see [`Self::quote_to_usd`].
     pub estimated_profit_usd: Option<Decimal>,
     /// Recommended trade size in base currency
     pub recommended_size: Option<Decimal>,
@@ -162,6 +340,10 @@ pub struct ArbitrageOpportunity {
     pub detected_at: DateTime<Utc>,
     /// When this opportunity expired (filled or price changed)
     pub expired_at: Option<DateTime<Utc>>,
+    /// Legs of a multi-hop (e.g. triangular) opportunity in execution order;
+    /// `None` for a simple buy-on-one-DEX, sell-on-another trade
+    #[serde(default)]
+    pub path: Option<Vec<(DexType, TokenPair)>>,
 }
 
 impl ArbitrageOpportunity {
@@ -175,6 +357,147 @@ impl ArbitrageOpportunity {
         let end = self.expired_at.unwrap_or_else(Utc::now);
         end - self.detected_at
     }
+
+    /// Net profit in quote currency for a trade of `size`: `size * net_profit_pct / 100`.
+    /// Despite the name this is USD only for stablecoin quotes.
+    /// Errors instead of panicking if the product overflows.
+    pub fn net_profit_usd(&self, size: Decimal) -> crate::ArbitrageResult<Decimal> {
+        crate::math::checked_pct_of(size, self.net_profit_pct)
+    }
+
+    /// Gross profit (before DEX fees) in quote currency for a trade of `size`
+    pub fn gross_profit_usd(&self, size: Decimal) -> crate::ArbitrageResult<Decimal> {
+        crate::math::checked_pct_of(size, self.gross_profit_pct)
+    }
+
+    /// `amount` of the pair's quote token in USD, given `quote_usd_price`, the
+    /// USD value of one quote token.
+    ///
+    /// Stablecoin-quoted amounts need no price; others are `None` without one.
+    pub fn quote_to_usd(
+        &self,
+        amount: Decimal,
+        quote_usd_price: Option<Decimal>,
+    ) -> Option<Decimal> {
+        if self.pair.is_usd_quoted() {
+            return Some(amount);
+        }
+        quote_usd_price.and_then(|price| amount.checked_mul(price))
+    }
+
+    /// [`net_profit_usd`](Self::net_profit_usd) less a per-trade transaction cost
+    /// (network + priority fees) already converted to quote currency
+    pub fn net_profit_after_costs_usd(
+        &self,
+        size: Decimal,
+        tx_cost_usd: Decimal,
+    ) -> crate::ArbitrageResult<Decimal> {
+        let net = self.net_profit_usd(size)?;
+        net.checked_sub(tx_cost_usd).ok_or_else(|| {
+            crate::ArbitrageError::ArithmeticOverflow(format!("{} - {}", net, tx_cost_usd))
+        })
+    }
+}
+
+/// What landing one trade costs in SOL, on top of the DEX fees already in
+/// `net_profit_pct` (the base network fee is [`ArbitrageConfig::solana_tx_fee`])
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxCosts {
+    /// Priority fee in micro-lamports per compute unit
+    pub priority_fee_micro_lamports: u64,
+    /// Compute units the priority fee is paid for
+    pub compute_unit_limit: u32,
+    /// Tip paid to Jito per bundle, in lamports (0 when not using Jito)
+    pub jito_tip_lamports: u64,
+}
+
+impl TxCosts {
+    /// Priority fee for the whole transaction, in lamports (rounded up)
+    pub fn priority_fee_lamports(&self) -> u64 {
+        let micro_lamports =
+            u128::from(self.priority_fee_micro_lamports) * u128::from(self.compute_unit_limit);
+        u64::try_from(micro_lamports.div_ceil(1_000_000)).unwrap_or(u64::MAX)
+    }
+}
+
+/// A trade's profit from the quoted spread down to what is left once the
+/// transaction has landed, all in quote currency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfitBreakdown {
+    /// Trade size the figures are for
+    pub size: Decimal,
+    /// Profit at the quoted prices (`gross_profit_pct`)
+    pub gross_profit: Decimal,
+    /// DEX fees and expected slippage (the gap between gross and `net_profit_pct`)
+    pub dex_fees: Decimal,
+    /// Base network fee
+    pub base_fee: Decimal,
+    /// Priority fee
+    pub priority_fee: Decimal,
+    /// Jito tip
+    pub jito_tip: Decimal,
+    /// What is left after everything above
+    pub net_profit: Decimal,
+}
+
+impl ProfitBreakdown {
+    /// Costs paid in SOL: base fee, priority fee and tip
+    pub fn tx_costs(&self) -> Decimal {
+        self.base_fee + self.priority_fee + self.jito_tip
+    }
+
+    /// Fully-loaded net profit as a percentage of size (0 for a zero size)
+    pub fn net_profit_pct(&self) -> Decimal {
+        self.net_profit
+            .checked_div(self.size)
+            .map(|ratio| ratio * Decimal::ONE_HUNDRED)
+            .unwrap_or_default()
+    }
+}
+
+/// Both legs of a two-DEX arbitrage simulated in sequence: the sell leg
+/// sells exactly the base the buy leg returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundTripResult {
+    /// Quote currency spent on the buy leg
+    pub capital: Decimal,
+    /// Buy leg, in base token received
+    pub buy: Quote,
+    /// Sell leg, in quote currency received
+    pub sell: Quote,
+}
+
+impl RoundTripResult {
+    /// Quote currency gained (negative for a loss) once both legs have filled
+    pub fn profit(&self) -> Decimal {
+        self.sell.out_amount - self.capital
+    }
+
+    /// DEX fees of both legs in quote currency, the buy leg's valued at the
+    /// sell leg's average fill
+    pub fn fees(&self) -> Decimal {
+        let sell_price = (self.sell.out_amount + self.sell.fee)
+            .checked_div(self.buy.out_amount)
+            .unwrap_or_default();
+        self.buy.fee * sell_price + self.sell.fee
+    }
+
+    /// [`Self::profit`] as a percentage of capital (0 for no capital)
+    pub fn profit_pct(&self) -> Decimal {
+        self.pct_of_capital(self.profit())
+    }
+
+    /// Profit before DEX fees, after slippage, as a percentage of capital
+    pub fn gross_profit_pct(&self) -> Decimal {
+        self.pct_of_capital(self.profit() + self.fees())
+    }
+
+    fn pct_of_capital(&self, amount: Decimal) -> Decimal {
+        amount
+            .checked_div(self.capital)
+            .map(|ratio| ratio * Decimal::ONE_HUNDRED)
+            .unwrap_or_default()
+    }
 }
 
 /// Configuration for arbitrage detection
@@ -188,15 +511,34 @@ pub struct ArbitrageConfig {
     pub slippage_tolerance: Decimal,
     /// Solana transaction fee in SOL
     pub solana_tx_fee: Decimal,
+    /// Extra net profit percentage required for each leg priced from a one-sided
+    /// (synthetic-spread) quote, on top of `min_profit_threshold`
+    pub one_sided_extra_edge_pct: Decimal,
+    /// Compare Jupiter's price against the other DEXs. Jupiter routes through those
+    /// same venues, so its quote mostly mirrors them; when false it is execution-only.
+    pub jupiter_price_source: bool,
+    /// Percentage a DEX's mid price may sit from the median of the DEXs quoting
+    /// the same pair before it is dropped as an outlier
+    pub max_price_deviation_pct: Decimal,
+    /// Liquidity (USD) both pools of an opportunity must hold; zero disables the check
+    pub min_liquidity_usd: Decimal,
+    /// Whether a pool whose liquidity is unreported, or can't be valued in USD,
+    /// passes the `min_liquidity_usd` check
+    pub allow_unknown_liquidity: bool,
 }
 
 impl Default for ArbitrageConfig {
     fn default() -> Self {
         Self {
-            min_profit_threshold: Decimal::new(50, 4), // 0.5%
-            max_position_size: Decimal::from(1000),    // $1,000
-            slippage_tolerance: Decimal::new(100, 4),  // 1%
-            solana_tx_fee: Decimal::new(5, 6),         // 0.000005 SOL
+            min_profit_threshold: Decimal::new(50, 4),    // 0.5%
+            max_position_size: Decimal::from(1000),       // $1,000
+            slippage_tolerance: Decimal::new(100, 4),     // 1%
+            solana_tx_fee: Decimal::new(5, 6),            // 0.000005 SOL
+            one_sided_extra_edge_pct: Decimal::new(5, 2), // 0.05% per synthetic leg
+            jupiter_price_source: true,
+            max_price_deviation_pct: Decimal::TEN, // 10%
+            min_liquidity_usd: Decimal::ZERO,      // off
+            allow_unknown_liquidity: true,
         }
     }
 }
@@ -210,12 +552,36 @@ pub struct TradeResult {
     pub signature: Option<String>,
     /// Whether the trade was successful
     pub success: bool,
-    /// Actual profit/loss in quote currency
-    pub actual_profit: Decimal,
+    /// Realized profit/loss in quote currency, read from the confirmed transactions'
+    /// balance changes. `None` when it couldn't be measured (simulated, unconfirmed,
+    /// or the transaction couldn't be fetched or parsed).
+    pub actual_profit: Option<Decimal>,
     /// Execution timestamp
     pub executed_at: DateTime<Utc>,
     /// Error message if failed
     pub error: Option<String>,
+    /// Per-stage latency breakdown captured along the execution path
+    #[serde(default)]
+    pub latency: Option<LatencyBreakdown>,
+    /// Realized slippage in basis points: quoted output vs. the confirmed balance change
+    /// (positive = received less than quoted). `None` for simulated/unconfirmed results.
+    #[serde(default)]
+    pub realized_slippage: Option<Decimal>,
+}
+
+/// Where time went while executing a single trade
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyBreakdown {
+    /// When the opportunity was detected
+    pub detected_at: Option<DateTime<Utc>>,
+    /// Time spent fetching the quote
+    pub quote_ms: Option<u64>,
+    /// Time spent building the transaction (swap instructions, signing inputs)
+    pub build_ms: Option<u64>,
+    /// Time spent submitting the transaction
+    pub submit_ms: Option<u64>,
+    /// Time spent waiting for confirmation
+    pub confirm_ms: Option<u64>,
 }
 
 // Re-export uuid for convenience
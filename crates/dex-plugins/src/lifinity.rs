@@ -1,14 +1,30 @@
+//! Lifinity provider
+//!
+//! Lifinity pools are oracle-pegged: they quote around a Pyth price rather
+//! than along their reserves alone. A pool price far from the oracle is
+//! therefore a stale pool or one being pushed around, not an opportunity, so
+//! every pool price is checked against Pyth before it reaches the detector.
+//!
+//! Pool accounts aren't decoded yet, so the bot doesn't register this
+//! provider until it can price.
+
 use async_trait::async_trait;
+use rust_decimal::Decimal;
 use solana_arb_core::{
     dex::DexProvider,
     error::ArbitrageError,
+    pricing::oracle::PythPriceFeed,
     types::{DexType, PriceData, TokenPair},
     ArbitrageResult,
 };
 use tokio::sync::mpsc;
 
+/// Default for [`LifinityProvider::with_max_oracle_deviation_pct`]
+const DEFAULT_MAX_ORACLE_DEVIATION_PCT: Decimal = Decimal::ONE;
+
 pub struct LifinityProvider {
-    // Placeholder - in real impl, would have RPC client or API key
+    oracle: PythPriceFeed,
+    max_oracle_deviation_pct: Decimal,
 }
 
 impl Default for LifinityProvider {
@@ -19,28 +35,73 @@ impl Default for LifinityProvider {
 
 impl LifinityProvider {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            oracle: PythPriceFeed::new(),
+            max_oracle_deviation_pct: DEFAULT_MAX_ORACLE_DEVIATION_PCT,
+        }
     }
-}
 
-#[async_trait]
-impl DexProvider for LifinityProvider {
-    fn dex_type(&self) -> DexType {
-        DexType::Lifinity
+    /// Reject pool prices further than this from the oracle (1.0 = 1%)
+    pub fn with_max_oracle_deviation_pct(mut self, pct: Decimal) -> Self {
+        self.max_oracle_deviation_pct = pct;
+        self
     }
 
-    async fn get_price(&self, _pair: &TokenPair) -> ArbitrageResult<PriceData> {
-        // Placeholder implementation
-        // Real implementation would query Lifinity pools or API
+    /// Replaces the Pyth Hermes endpoint oracle prices are read from
+    pub fn with_oracle_url(mut self, url: impl Into<String>) -> Self {
+        self.oracle = self.oracle.with_url(url);
+        self
+    }
 
-        // For now, return error or dummy data if dry run logic was here
-        // We will return an error generally until implemented
+    /// Pyth's price of the pair's base token in its quote token, from the two
+    /// USD feeds
+    pub async fn oracle_price(&self, pair: &TokenPair) -> ArbitrageResult<Decimal> {
+        Ok(self.oracle.pair_price(pair).await?.price)
+    }
 
-        // But to pass tests or integration, we can simulate or return Err
+    /// Current pool price for `pair`
+    async fn pool_price(&self, _pair: &TokenPair) -> ArbitrageResult<PriceData> {
+        // Placeholder: pool accounts aren't decoded yet
         Err(ArbitrageError::PriceFetch(
             "Lifinity price fetching not implemented".to_string(),
         ))
     }
+}
+
+/// Pass `price` through if its mid is within `max_deviation_pct` of
+/// `oracle_price`; otherwise it is treated as stale or manipulated.
+pub fn validate_against_oracle(
+    price: PriceData,
+    oracle_price: Decimal,
+    max_deviation_pct: Decimal,
+) -> ArbitrageResult<PriceData> {
+    let deviation_pct = (price.mid_price - oracle_price)
+        .abs()
+        .checked_div(oracle_price)
+        .map(|d| d * Decimal::ONE_HUNDRED)
+        .ok_or_else(|| {
+            ArbitrageError::PriceFetch(format!("Invalid oracle price for {}", price.pair))
+        })?;
+    if deviation_pct > max_deviation_pct {
+        return Err(ArbitrageError::PriceFetch(format!(
+            "Lifinity {} price {} is {:.2}% from oracle {}, likely stale",
+            price.pair, price.mid_price, deviation_pct, oracle_price
+        )));
+    }
+    Ok(price)
+}
+
+#[async_trait]
+impl DexProvider for LifinityProvider {
+    fn dex_type(&self) -> DexType {
+        DexType::Lifinity
+    }
+
+    async fn get_price(&self, pair: &TokenPair) -> ArbitrageResult<PriceData> {
+        let price = self.pool_price(pair).await?;
+        let oracle_price = self.oracle_price(pair).await?;
+        validate_against_oracle(price, oracle_price, self.max_oracle_deviation_pct)
+    }
 
     async fn subscribe(
         &self,
@@ -55,3 +116,26 @@ impl DexProvider for LifinityProvider {
         Ok(true)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool_price(mid: Decimal) -> PriceData {
+        PriceData::from_mid(DexType::Lifinity, TokenPair::new("SOL", "USDC"), mid, 0)
+    }
+
+    #[test]
+    fn test_pool_price_is_checked_against_oracle() {
+        let oracle = Decimal::from(150);
+        let max = Decimal::ONE;
+
+        // 0.5% off the oracle: passed through untouched
+        let in_range = validate_against_oracle(pool_price(Decimal::new(15075, 2)), oracle, max);
+        assert_eq!(in_range.unwrap().mid_price, Decimal::new(15075, 2));
+
+        // 5% off: rejected before the detector sees it
+        let out_of_range = validate_against_oracle(pool_price(Decimal::new(1425, 1)), oracle, max);
+        assert!(matches!(out_of_range, Err(ArbitrageError::PriceFetch(_))));
+    }
+}
@@ -1,14 +1,59 @@
+//! Meteora DLMM provider
+//!
+//! Prices and fees come from Meteora's DLMM API, which lists every pool with its
+//! current price, liquidity and fee settings. A pair can have several pools (one
+//! per bin step); the most liquid one is used. The pool list is cached briefly
+//! so fee and price lookups for many pairs share one request.
+//!
+//! DLMM fees are a base fee plus a variable fee driven by the pool's on-chain
+//! volatility accumulator. The API only exposes the base fee, so that is what
+//! `current_fee_bps` reports; it is the floor of what a swap pays.
+
 use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::Deserialize;
 use solana_arb_core::{
-    dex::DexProvider,
+    arbitrage::FeeSchedule,
+    dex::{tvl_in_quote, DexProvider},
     error::ArbitrageError,
-    types::{DexType, PriceData, TokenPair},
+    types::{
+        is_usd_stablecoin, DexType, PriceData, TokenPair, DEFAULT_SYNTHETIC_SPREAD_BPS,
+        USD_STABLECOINS,
+    },
     ArbitrageResult,
 };
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
+const METEORA_DLMM_API: &str = "https://dlmm-api.meteora.ag/pair/all";
+
+/// How long a fetched pool list (and so its fees) is reused
+const POOL_CACHE_TTL: Duration = Duration::from_secs(5);
+
+/// One DLMM pool as listed by the API. Numeric fields arrive as either JSON
+/// numbers or strings, so they are kept raw and parsed on use.
+#[derive(Debug, Clone, Deserialize)]
+struct DlmmPool {
+    address: String,
+    /// e.g. "SOL-USDC"
+    name: String,
+    base_fee_percentage: serde_json::Value,
+    current_price: serde_json::Value,
+    #[serde(default)]
+    liquidity: serde_json::Value,
+}
+
+impl DlmmPool {
+    fn liquidity(&self) -> Decimal {
+        decimal(&self.liquidity).unwrap_or_default()
+    }
+}
+
 pub struct MeteoraProvider {
-    // Placeholder
+    client: reqwest::Client,
+    pools: Mutex<Option<(Instant, Arc<Vec<DlmmPool>>)>>,
 }
 
 impl Default for MeteoraProvider {
@@ -19,7 +64,99 @@ impl Default for MeteoraProvider {
 
 impl MeteoraProvider {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            client: reqwest::Client::new(),
+            pools: Mutex::new(None),
+        }
+    }
+
+    /// The pool's current fee in basis points (base fee; see module docs)
+    pub async fn current_fee_bps(&self, pair: &TokenPair) -> ArbitrageResult<Decimal> {
+        let pools = self.pools().await?;
+        let (pool, _) = find_pool(&pools, pair)?;
+        let fee_pct = decimal(&pool.base_fee_percentage).ok_or_else(|| {
+            ArbitrageError::PriceFetch(format!("Meteora pool {} has no fee", pool.address))
+        })?;
+        Ok(fee_pct * Decimal::ONE_HUNDRED)
+    }
+
+    /// Register the current fee of each pair's pool in `fees`, so the detector
+    /// charges it instead of the static Meteora default. Pairs without a pool
+    /// keep the default.
+    pub async fn update_fee_schedule(&self, fees: &mut FeeSchedule, pairs: &[TokenPair]) {
+        for pair in pairs {
+            match self.current_fee_bps(pair).await {
+                // FeeSchedule uses `DexType::fee_percentage` units (0.25% = 0.0025)
+                Ok(bps) => {
+                    fees.register(DexType::Meteora, pair.clone(), bps / Decimal::from(10_000))
+                }
+                Err(e) => tracing::debug!("No Meteora fee for {}: {}", pair, e),
+            }
+        }
+    }
+
+    /// The pool list, refetched once the cached copy is older than the TTL
+    async fn pools(&self) -> ArbitrageResult<Arc<Vec<DlmmPool>>> {
+        if let Some((fetched_at, pools)) = self.pools.lock().unwrap().as_ref() {
+            if fetched_at.elapsed() < POOL_CACHE_TTL {
+                return Ok(pools.clone());
+            }
+        }
+
+        let pools: Arc<Vec<DlmmPool>> = Arc::new(
+            self.client
+                .get(METEORA_DLMM_API)
+                .send()
+                .await?
+                .json()
+                .await?,
+        );
+        *self.pools.lock().unwrap() = Some((Instant::now(), pools.clone()));
+        Ok(pools)
+    }
+}
+
+/// The most liquid pool for `pair`, and whether it is listed the other way round
+fn find_pool<'a>(pools: &'a [DlmmPool], pair: &TokenPair) -> ArbitrageResult<(&'a DlmmPool, bool)> {
+    let name = format!("{}-{}", pair.base, pair.quote);
+    let reverse = format!("{}-{}", pair.quote, pair.base);
+    pools
+        .iter()
+        .filter_map(|pool| {
+            if pool.name.eq_ignore_ascii_case(&name) {
+                Some((pool, false))
+            } else if pool.name.eq_ignore_ascii_case(&reverse) {
+                Some((pool, true))
+            } else {
+                None
+            }
+        })
+        .max_by_key(|(pool, _)| pool.liquidity())
+        .ok_or_else(|| ArbitrageError::PriceFetch(format!("No Meteora pool for {}", pair)))
+}
+
+/// USD value of one `symbol`, from its deepest pool against a USD stablecoin
+fn usd_price(pools: &[DlmmPool], symbol: &str) -> Option<Decimal> {
+    if is_usd_stablecoin(symbol) {
+        return Some(Decimal::ONE);
+    }
+    USD_STABLECOINS.iter().find_map(|usd| {
+        let (pool, reversed) = find_pool(pools, &TokenPair::new(symbol, *usd)).ok()?;
+        let price = decimal(&pool.current_price)?;
+        if reversed {
+            Decimal::ONE.checked_div(price)
+        } else {
+            Some(price)
+        }
+    })
+}
+
+/// Parse a JSON number or numeric string
+fn decimal(value: &serde_json::Value) -> Option<Decimal> {
+    match value {
+        serde_json::Value::Number(n) => Decimal::from_str(&n.to_string()).ok(),
+        serde_json::Value::String(s) => Decimal::from_str(s).ok(),
+        _ => None,
     }
 }
 
@@ -29,10 +166,30 @@ impl DexProvider for MeteoraProvider {
         DexType::Meteora
     }
 
-    async fn get_price(&self, _pair: &TokenPair) -> ArbitrageResult<PriceData> {
-        Err(ArbitrageError::PriceFetch(
-            "Meteora price fetching not implemented".to_string(),
-        ))
+    async fn get_price(&self, pair: &TokenPair) -> ArbitrageResult<PriceData> {
+        let pools = self.pools().await?;
+        let (pool, reversed) = find_pool(&pools, pair)?;
+
+        let mut price = decimal(&pool.current_price)
+            .filter(|p| !p.is_zero())
+            .ok_or_else(|| {
+                ArbitrageError::PriceFetch(format!("Meteora pool {} has no price", pool.address))
+            })?;
+        if reversed {
+            price = Decimal::ONE / price;
+        }
+
+        // The API reports a single pool price; synthesize bid/ask around it
+        let mut price_data = PriceData::from_mid(
+            DexType::Meteora,
+            pair.clone(),
+            price,
+            DEFAULT_SYNTHETIC_SPREAD_BPS,
+        )
+        .with_route(pool.address.clone());
+        // The API reports USD TVL; liquidity is kept in the quote token
+        price_data.liquidity = tvl_in_quote(pool.liquidity(), usd_price(&pools, &pair.quote));
+        Ok(price_data)
     }
 
     async fn subscribe(
@@ -48,3 +205,46 @@ impl DexProvider for MeteoraProvider {
         Ok(true)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_pool_prefers_liquidity_and_handles_reverse() {
+        let pools: Vec<DlmmPool> = serde_json::from_str(
+            r#"[
+                {"address": "shallow", "name": "SOL-USDC", "base_fee_percentage": "0.1",
+                 "current_price": 150.0, "liquidity": "1000"},
+                {"address": "deep", "name": "SOL-USDC", "base_fee_percentage": "0.02",
+                 "current_price": 150.2, "liquidity": "250000.5"},
+                {"address": "jup", "name": "USDC-JUP", "base_fee_percentage": 0.25,
+                 "current_price": 2.0, "liquidity": 5000}
+            ]"#,
+        )
+        .unwrap();
+
+        let (pool, reversed) = find_pool(&pools, &TokenPair::new("SOL", "USDC")).unwrap();
+        assert_eq!(pool.address, "deep");
+        assert!(!reversed);
+        assert_eq!(decimal(&pool.base_fee_percentage), Some(Decimal::new(2, 2)));
+
+        let (pool, reversed) = find_pool(&pools, &TokenPair::new("JUP", "USDC")).unwrap();
+        assert_eq!(pool.address, "jup");
+        assert!(reversed);
+        assert_eq!(
+            decimal(&pool.base_fee_percentage),
+            Some(Decimal::new(25, 2))
+        );
+
+        assert!(matches!(
+            find_pool(&pools, &TokenPair::new("BONK", "SOL")),
+            Err(ArbitrageError::PriceFetch(_))
+        ));
+
+        // SOL is valued from the deepest SOL-USDC pool, JUP through its reversed one
+        assert_eq!(usd_price(&pools, "SOL"), Some(Decimal::new(1502, 1)));
+        assert_eq!(usd_price(&pools, "JUP"), Some(Decimal::new(5, 1)));
+        assert_eq!(usd_price(&pools, "BONK"), None);
+    }
+}
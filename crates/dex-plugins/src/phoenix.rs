@@ -1,14 +1,51 @@
+//! Phoenix order book provider
+//!
+//! Phoenix is a central limit order book, so besides a best bid/ask it exposes
+//! depth that matters for sizing. Given an RPC endpoint, books are read from
+//! each pair's market account (see [`parse_market`]); otherwise they are pushed
+//! in with [`PhoenixProvider::update_book`]. Either way the latest book is kept,
+//! prices are derived from its top and `liquidity` is the quote value of its
+//! best [`DEFAULT_DEPTH_LEVELS`] levels.
+
 use async_trait::async_trait;
+use rust_decimal::Decimal;
 use solana_arb_core::{
     dex::DexProvider,
     error::ArbitrageError,
-    types::{DexType, PriceData, TokenPair},
+    types::{BookLevel, DexType, OrderBook, PriceData, TokenPair},
     ArbitrageResult,
 };
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
 use tokio::sync::mpsc;
 
+/// Upper bound on levels returned per side by `get_order_book`
+pub const MAX_BOOK_LEVELS: usize = 50;
+
+/// Levels per side counted towards `PriceData::liquidity`
+pub const DEFAULT_DEPTH_LEVELS: usize = 10;
+
+/// Phoenix v1 program, owner of every market account
+pub const PHOENIX_PROGRAM_ID: &str = "PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY";
+
+/// Markets priced by default, by pair
+const DEFAULT_MARKETS: &[(&str, &str, &str)] = &[(
+    "SOL",
+    "USDC",
+    "4DoNfFBfF7UokCC2FQzriy7yHK6DY6NVdYpuekQ5pRgg",
+)];
+
 pub struct PhoenixProvider {
-    // Placeholder
+    /// Latest book per pair, read from chain or pushed in
+    books: RwLock<HashMap<TokenPair, OrderBook>>,
+    /// Market account per pair
+    markets: HashMap<TokenPair, Pubkey>,
+    /// Reads market accounts, when set
+    rpc_client: Option<Arc<RpcClient>>,
 }
 
 impl Default for PhoenixProvider {
@@ -19,8 +56,185 @@ impl Default for PhoenixProvider {
 
 impl PhoenixProvider {
     pub fn new() -> Self {
-        Self {}
+        let markets = DEFAULT_MARKETS
+            .iter()
+            .map(|(base, quote, market)| {
+                let market = Pubkey::from_str(market).expect("valid market address");
+                (TokenPair::new(*base, *quote), market)
+            })
+            .collect();
+        Self {
+            books: RwLock::new(HashMap::new()),
+            markets,
+            rpc_client: None,
+        }
+    }
+
+    /// Read books from market accounts at `rpc_url`
+    pub fn with_rpc(mut self, rpc_url: &str) -> Self {
+        self.rpc_client = Some(Arc::new(RpcClient::new_with_commitment(
+            rpc_url.to_string(),
+            CommitmentConfig::confirmed(),
+        )));
+        self
+    }
+
+    /// Read `pair`'s book from the market account at `market`
+    pub fn with_market(mut self, pair: TokenPair, market: Pubkey) -> Self {
+        self.markets.insert(pair, market);
+        self
+    }
+
+    /// Replace the stored book for `pair` with the given (unordered) levels
+    pub fn update_book(&self, pair: TokenPair, bids: Vec<BookLevel>, asks: Vec<BookLevel>) {
+        self.books
+            .write()
+            .unwrap()
+            .insert(pair, OrderBook::new(bids, asks));
+    }
+
+    /// The best `levels` bids and asks for `pair` (at most [`MAX_BOOK_LEVELS`]),
+    /// read from its market account when an RPC endpoint and the market are
+    /// known, else the last book pushed in. A market with no resting orders
+    /// yields an empty book rather than an error.
+    pub async fn get_order_book(
+        &self,
+        pair: &TokenPair,
+        levels: usize,
+    ) -> ArbitrageResult<OrderBook> {
+        if let (Some(rpc_client), Some(market)) = (&self.rpc_client, self.markets.get(pair)) {
+            let account = rpc_client
+                .get_account(market)
+                .await
+                .map_err(|e| ArbitrageError::RpcError(e.to_string()))?;
+            if account.owner.to_string() != PHOENIX_PROGRAM_ID {
+                return Err(ArbitrageError::PriceFetch(format!(
+                    "{} is not a Phoenix market",
+                    market
+                )));
+            }
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs() as i64);
+            let (bids, asks) = parse_market(&account.data, now)?;
+            self.update_book(pair.clone(), bids, asks);
+        }
+
+        let books = self.books.read().unwrap();
+        let book = books
+            .get(pair)
+            .ok_or_else(|| ArbitrageError::PriceFetch(format!("No Phoenix book for {}", pair)))?;
+        Ok(book.top(levels.min(MAX_BOOK_LEVELS)))
+    }
+}
+
+// Market account layout (Phoenix v1): a 576-byte `MarketHeader`, then the
+// `FIFOMarket` whose bids and asks are red-black trees of resting orders.
+const BIDS_SIZE_OFFSET: usize = 16;
+const ASKS_SIZE_OFFSET: usize = 24;
+const BASE_DECIMALS_OFFSET: usize = 40;
+const BASE_LOT_SIZE_OFFSET: usize = 112;
+const QUOTE_DECIMALS_OFFSET: usize = 120;
+const TICK_SIZE_OFFSET: usize = 200;
+const RAW_BASE_UNITS_OFFSET: usize = 312;
+const MARKET_HEADER_LEN: usize = 576;
+/// Padding and six `u64` counters precede the bids tree in `FIFOMarket`
+const BIDS_TREE_OFFSET: usize = MARKET_HEADER_LEN + 256 + 6 * 8;
+/// Root and padding, then the node allocator's size, bump index and free list
+const TREE_HEADER_LEN: usize = 32;
+/// Four registers (left, right, parent, color), the order id (price in ticks,
+/// sequence number) and the resting order (trader, base lots, expiry slot and
+/// expiry timestamp)
+const NODE_LEN: usize = 64;
+const RIGHT_REGISTER: usize = 4;
+const PRICE_IN_TICKS_OFFSET: usize = 16;
+const BASE_LOTS_OFFSET: usize = 40;
+const EXPIRY_TIMESTAMP_OFFSET: usize = 56;
+
+fn read_u32(data: &[u8], offset: usize) -> ArbitrageResult<u32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or_else(|| truncated(offset))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> ArbitrageResult<u64> {
+    data.get(offset..offset + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or_else(|| truncated(offset))
+}
+
+fn truncated(offset: usize) -> ArbitrageError {
+    ArbitrageError::PriceFetch(format!(
+        "Phoenix market account ends before byte {}",
+        offset
+    ))
+}
+
+/// Decode the bids and asks resting on a Phoenix market account, in UI units.
+/// Orders whose expiry timestamp is before `now` (Unix seconds) are left out.
+pub fn parse_market(data: &[u8], now: i64) -> ArbitrageResult<(Vec<BookLevel>, Vec<BookLevel>)> {
+    let bids_size = read_u64(data, BIDS_SIZE_OFFSET)? as usize;
+    let asks_size = read_u64(data, ASKS_SIZE_OFFSET)? as usize;
+    let base_decimals = read_u32(data, BASE_DECIMALS_OFFSET)?;
+    let quote_decimals = read_u32(data, QUOTE_DECIMALS_OFFSET)?;
+    let base_lot_size = read_u64(data, BASE_LOT_SIZE_OFFSET)?;
+    let tick_size = read_u64(data, TICK_SIZE_OFFSET)?;
+    let raw_base_units = read_u32(data, RAW_BASE_UNITS_OFFSET)?.max(1);
+
+    // Ticks are quote atoms per base unit, lots are base atoms
+    let level = |ticks: u64, lots: u64| BookLevel {
+        price: Decimal::from_i128_with_scale(ticks as i128 * tick_size as i128, quote_decimals)
+            / Decimal::from(raw_base_units),
+        size: Decimal::from_i128_with_scale(lots as i128 * base_lot_size as i128, base_decimals),
+    };
+    let orders = |tree: usize, capacity: usize| -> ArbitrageResult<Vec<BookLevel>> {
+        Ok(resting_orders(data, tree, capacity, now)?
+            .into_iter()
+            .map(|(ticks, lots)| level(ticks, lots))
+            .collect())
+    };
+
+    let asks_tree = BIDS_TREE_OFFSET + TREE_HEADER_LEN + bids_size * NODE_LEN;
+    Ok((
+        orders(BIDS_TREE_OFFSET, bids_size)?,
+        orders(asks_tree, asks_size)?,
+    ))
+}
+
+/// (price in ticks, base lots) of every live order in the tree at `tree`
+fn resting_orders(
+    data: &[u8],
+    tree: usize,
+    capacity: usize,
+    now: i64,
+) -> ArbitrageResult<Vec<(u64, u64)>> {
+    let nodes = tree + TREE_HEADER_LEN;
+    let mut orders = Vec::new();
+    // Nodes are numbered from 1; 0 is the empty leaf
+    let mut pending = vec![read_u32(data, tree)? as usize];
+    while let Some(index) = pending.pop() {
+        if index == 0 {
+            continue;
+        }
+        if index > capacity || orders.len() >= capacity {
+            return Err(ArbitrageError::PriceFetch(
+                "Phoenix order tree is corrupt".to_string(),
+            ));
+        }
+        let node = nodes + (index - 1) * NODE_LEN;
+        pending.push(read_u32(data, node)? as usize);
+        pending.push(read_u32(data, node + RIGHT_REGISTER)? as usize);
+
+        let expires_at = read_u64(data, node + EXPIRY_TIMESTAMP_OFFSET)? as i64;
+        if expires_at != 0 && expires_at < now {
+            continue;
+        }
+        orders.push((
+            read_u64(data, node + PRICE_IN_TICKS_OFFSET)?,
+            read_u64(data, node + BASE_LOTS_OFFSET)?,
+        ));
     }
+    Ok(orders)
 }
 
 #[async_trait]
@@ -29,10 +243,18 @@ impl DexProvider for PhoenixProvider {
         DexType::Phoenix
     }
 
-    async fn get_price(&self, _pair: &TokenPair) -> ArbitrageResult<PriceData> {
-        Err(ArbitrageError::PriceFetch(
-            "Phoenix price fetching not implemented".to_string(),
-        ))
+    async fn get_price(&self, pair: &TokenPair) -> ArbitrageResult<PriceData> {
+        let book = self.get_order_book(pair, DEFAULT_DEPTH_LEVELS).await?;
+        let (Some(bid), Some(ask)) = (book.best_bid(), book.best_ask()) else {
+            return Err(ArbitrageError::PriceFetch(format!(
+                "Phoenix book for {} has no bids or no asks",
+                pair
+            )));
+        };
+
+        let mut price = PriceData::new(DexType::Phoenix, pair.clone(), bid.price, ask.price);
+        price.liquidity = Some(book.depth_quote());
+        Ok(price)
     }
 
     async fn subscribe(
@@ -48,3 +270,142 @@ impl DexProvider for PhoenixProvider {
         Ok(true)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    fn level(price: i64, size: i64) -> BookLevel {
+        BookLevel {
+            price: Decimal::new(price, 2),
+            size: Decimal::from(size),
+        }
+    }
+
+    /// Market account for SOL (9 decimals, 0.001 SOL lots) / USDC (6 decimals,
+    /// 0.001 USDC ticks) with room for four orders a side. Orders are (price in
+    /// ticks, base lots, expiry timestamp), stored in node order; the first is
+    /// the root and the next two its children.
+    fn market_account(bids: &[(u64, u64, u64)], asks: &[(u64, u64, u64)]) -> Vec<u8> {
+        const CAPACITY: usize = 4;
+        let tree_len = TREE_HEADER_LEN + CAPACITY * NODE_LEN;
+        let mut data = vec![0u8; BIDS_TREE_OFFSET + 2 * tree_len];
+        let mut put =
+            |offset: usize, bytes: &[u8]| data[offset..offset + bytes.len()].copy_from_slice(bytes);
+        put(BIDS_SIZE_OFFSET, &(CAPACITY as u64).to_le_bytes());
+        put(ASKS_SIZE_OFFSET, &(CAPACITY as u64).to_le_bytes());
+        put(BASE_DECIMALS_OFFSET, &9u32.to_le_bytes());
+        put(QUOTE_DECIMALS_OFFSET, &6u32.to_le_bytes());
+        put(BASE_LOT_SIZE_OFFSET, &1_000_000u64.to_le_bytes());
+        put(TICK_SIZE_OFFSET, &1_000u64.to_le_bytes());
+        put(RAW_BASE_UNITS_OFFSET, &1u32.to_le_bytes());
+
+        for (tree, orders) in [
+            (BIDS_TREE_OFFSET, bids),
+            (BIDS_TREE_OFFSET + tree_len, asks),
+        ] {
+            if orders.is_empty() {
+                continue;
+            }
+            put(tree, &1u32.to_le_bytes());
+            for (i, (ticks, lots, expiry)) in orders.iter().enumerate() {
+                let node = tree + TREE_HEADER_LEN + i * NODE_LEN;
+                if i == 0 {
+                    let children = orders.len().min(3) as u32;
+                    put(node, &(if children > 1 { 2u32 } else { 0 }).to_le_bytes());
+                    put(
+                        node + RIGHT_REGISTER,
+                        &(if children > 2 { 3u32 } else { 0 }).to_le_bytes(),
+                    );
+                }
+                put(node + PRICE_IN_TICKS_OFFSET, &ticks.to_le_bytes());
+                put(node + BASE_LOTS_OFFSET, &lots.to_le_bytes());
+                put(node + EXPIRY_TIMESTAMP_OFFSET, &expiry.to_le_bytes());
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_parse_market_account() {
+        let now = 1_700_000_000;
+        let data = market_account(
+            // 99.99 for 2 SOL, 100.00 for 1.5 SOL, and one that expired
+            &[
+                (99_990, 2_000, 0),
+                (100_000, 1_500, now as u64 + 60),
+                (100_050, 9_000, now as u64 - 1),
+            ],
+            &[(100_100, 500, 0)],
+        );
+
+        let (bids, asks) = parse_market(&data, now).unwrap();
+        let book = OrderBook::new(bids, asks);
+        assert_eq!(
+            book.bids,
+            vec![
+                BookLevel {
+                    price: Decimal::new(100, 0),
+                    size: Decimal::new(15, 1)
+                },
+                BookLevel {
+                    price: Decimal::new(9999, 2),
+                    size: Decimal::TWO
+                },
+            ]
+        );
+        assert_eq!(
+            book.asks,
+            vec![BookLevel {
+                price: Decimal::new(1001, 1),
+                size: Decimal::new(5, 1)
+            }]
+        );
+
+        // An empty market is an empty book; a cut-off account is an error
+        let (bids, asks) = parse_market(&market_account(&[], &[]), now).unwrap();
+        assert!(bids.is_empty() && asks.is_empty());
+        assert!(parse_market(&data[..BIDS_TREE_OFFSET], now).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_order_book_levels_and_depth() {
+        let provider = PhoenixProvider::new();
+        let pair = TokenPair::new("SOL", "USDC");
+        provider.update_book(
+            pair.clone(),
+            vec![
+                level(9990, 2),
+                level(10000, 1),
+                level(9990, 3),
+                level(9900, 0),
+            ],
+            vec![level(10020, 4), level(10010, 1)],
+        );
+
+        let book = provider.get_order_book(&pair, 1).await.unwrap();
+        assert_eq!(book.bids, vec![level(10000, 1)]);
+        assert_eq!(book.asks, vec![level(10010, 1)]);
+
+        // Same-price levels merge, empty levels drop, huge requests are capped
+        let book = provider.get_order_book(&pair, usize::MAX).await.unwrap();
+        assert_eq!(book.bids, vec![level(10000, 1), level(9990, 5)]);
+        assert_eq!(book.asks, vec![level(10010, 1), level(10020, 4)]);
+
+        let price = provider.get_price(&pair).await.unwrap();
+        assert_eq!(price.bid, Decimal::new(100, 0));
+        assert_eq!(price.ask, Decimal::new(1001, 1));
+        assert_eq!(price.liquidity, Some(book.depth_quote()));
+        assert_eq!(book.depth_quote(), Decimal::new(11004, 1));
+
+        // An empty side is still a valid book, but not a price
+        let thin = TokenPair::new("BONK", "USDC");
+        provider.update_book(thin.clone(), vec![level(1, 10)], vec![]);
+        assert!(provider.get_order_book(&thin, 5).await.unwrap().is_empty());
+        assert!(matches!(
+            provider.get_price(&thin).await,
+            Err(ArbitrageError::PriceFetch(_))
+        ));
+    }
+}
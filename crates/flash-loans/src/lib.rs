@@ -1,7 +1,10 @@
 pub mod metrics;
+pub mod router;
 pub mod safety;
 pub mod solend;
 
+pub use router::FlashLoanRouter;
+
 use anyhow::Result;
 use async_trait::async_trait;
 use rust_decimal::Decimal;
@@ -30,4 +33,6 @@ pub trait FlashLoanProvider: Send + Sync {
 pub struct FlashLoanQuote {
     pub fee: Decimal,
     pub provider: String,
+    /// Amount the provider can currently lend, if it reports one
+    pub available_liquidity: Option<Decimal>,
 }
@@ -0,0 +1,151 @@
+use crate::{FlashLoanProvider, FlashLoanQuote};
+use rust_decimal::Decimal;
+use solana_arb_core::{ArbitrageError, ArbitrageResult};
+use solana_sdk::pubkey::Pubkey;
+use tracing::debug;
+
+/// Routes flash loans to whichever registered provider is cheapest.
+///
+/// Providers are asked for a quote in turn; ones that fail to quote or report
+/// less available liquidity than the amount are skipped. Providers that don't
+/// report liquidity are assumed able to lend the amount.
+#[derive(Default)]
+pub struct FlashLoanRouter {
+    providers: Vec<Box<dyn FlashLoanProvider>>,
+}
+
+impl FlashLoanRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a provider (e.g. Solend, Kamino, MarginFi)
+    pub fn with_provider(mut self, provider: Box<dyn FlashLoanProvider>) -> Self {
+        self.providers.push(provider);
+        self
+    }
+
+    pub fn providers(&self) -> &[Box<dyn FlashLoanProvider>] {
+        &self.providers
+    }
+
+    /// The lowest-fee quote among providers able to lend `amount` of `mint`,
+    /// with the name of the provider that gave it
+    pub async fn get_best_quote(
+        &self,
+        mint: Pubkey,
+        amount: Decimal,
+    ) -> ArbitrageResult<(String, FlashLoanQuote)> {
+        let mut best: Option<(String, FlashLoanQuote)> = None;
+        let mut rejections = Vec::new();
+
+        for provider in &self.providers {
+            let quote = match provider.get_quote(mint, amount).await {
+                Ok(quote) => quote,
+                Err(e) => {
+                    debug!("{} could not quote flash loan: {}", provider.name(), e);
+                    rejections.push(format!("{}: {}", provider.name(), e));
+                    continue;
+                }
+            };
+            if let Some(available) = quote.available_liquidity.filter(|a| *a < amount) {
+                rejections.push(format!("{}: only {} available", provider.name(), available));
+                continue;
+            }
+            if best.as_ref().is_none_or(|(_, b)| quote.fee < b.fee) {
+                best = Some((provider.name().to_string(), quote));
+            }
+        }
+
+        best.ok_or_else(|| {
+            let reasons = if rejections.is_empty() {
+                "no providers registered".to_string()
+            } else {
+                rejections.join("; ")
+            };
+            ArbitrageError::InsufficientLiquidity(format!(
+                "no flash loan provider can lend {} of {} ({})",
+                amount, mint, reasons
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use solana_sdk::instruction::Instruction;
+
+    struct MockProvider {
+        name: &'static str,
+        fee_bps: i64,
+        liquidity: Decimal,
+    }
+
+    #[async_trait]
+    impl FlashLoanProvider for MockProvider {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn borrow_instruction(&self, _amount: u64, _mint: &Pubkey) -> Result<Instruction> {
+            Err(anyhow::anyhow!("{} only quotes", self.name))
+        }
+
+        fn repay_instruction(&self, _amount: u64, _mint: &Pubkey) -> Result<Instruction> {
+            Err(anyhow::anyhow!("{} only quotes", self.name))
+        }
+
+        fn calculate_fee(&self, borrow_amount: u64) -> u64 {
+            borrow_amount * self.fee_bps as u64 / 10_000
+        }
+
+        async fn get_quote(&self, _mint: Pubkey, amount: Decimal) -> Result<FlashLoanQuote> {
+            Ok(FlashLoanQuote {
+                fee: amount * Decimal::new(self.fee_bps, 4),
+                provider: self.name.to_string(),
+                available_liquidity: Some(self.liquidity),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_best_quote_prefers_cheapest_with_liquidity() {
+        let router = FlashLoanRouter::new()
+            .with_provider(Box::new(MockProvider {
+                name: "Pricey",
+                fee_bps: 9,
+                liquidity: Decimal::from(1_000_000),
+            }))
+            .with_provider(Box::new(MockProvider {
+                name: "Cheap",
+                fee_bps: 3,
+                liquidity: Decimal::from(10_000),
+            }));
+        let mint = Pubkey::new_unique();
+
+        let (name, quote) = router
+            .get_best_quote(mint, Decimal::from(5_000))
+            .await
+            .unwrap();
+        assert_eq!(name, "Cheap");
+        assert_eq!(quote.fee, Decimal::new(15, 1));
+
+        // Too big for the cheap reserve
+        let (name, _) = router
+            .get_best_quote(mint, Decimal::from(50_000))
+            .await
+            .unwrap();
+        assert_eq!(name, "Pricey");
+
+        // Too big for anyone
+        let err = router
+            .get_best_quote(mint, Decimal::from(5_000_000))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ArbitrageError::InsufficientLiquidity(_)));
+        assert!(err.to_string().contains("Pricey"));
+    }
+}
@@ -128,6 +128,7 @@ impl FlashLoanProvider for SolendFlashLoan {
         Ok(super::FlashLoanQuote {
             fee,
             provider: Self::PROTOCOL_NAME.to_string(),
+            available_liquidity: None,
         })
     }
 }
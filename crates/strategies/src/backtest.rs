@@ -0,0 +1,112 @@
+//! Replaying recorded prices through strategies
+//!
+//! Extends the detector-only replay in `solana_arb_core::backtest` with the
+//! `Strategy` trait objects, fed the same way the bot feeds them: each price
+//! updates strategy state, then the whole snapshot is analyzed.
+
+use solana_arb_core::{
+    backtest::{BacktestSummary, Backtester},
+    types::PriceData,
+};
+
+use crate::Strategy;
+
+/// Replay `snapshots` through the detector and every strategy in `strategies`.
+/// A strategy that errors on a snapshot is skipped for that snapshot.
+pub async fn replay<'a>(
+    backtester: &'a mut Backtester,
+    snapshots: impl IntoIterator<Item = Vec<PriceData>>,
+    strategies: &[Box<dyn Strategy>],
+) -> &'a BacktestSummary {
+    for snapshot in snapshots {
+        backtester.step(snapshot.clone());
+
+        for strategy in strategies {
+            for price in &snapshot {
+                if let Err(e) = strategy.update_state(price).await {
+                    tracing::warn!("{} failed to update state: {}", strategy.name(), e);
+                }
+            }
+            match strategy.analyze(&snapshot).await {
+                Ok(opportunities) => backtester.record(opportunities),
+                Err(e) => tracing::warn!("{} failed during backtest: {}", strategy.name(), e),
+            }
+        }
+    }
+    backtester.summary()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use rust_decimal::Decimal;
+    use solana_arb_core::{
+        types::{ArbitrageOpportunity, DexType, TokenPair},
+        ArbitrageResult,
+    };
+
+    /// Signals a 2% Raydium -> Orca trade on every pair it sees
+    struct AlwaysSignal;
+
+    #[async_trait]
+    impl Strategy for AlwaysSignal {
+        fn name(&self) -> &'static str {
+            "AlwaysSignal"
+        }
+
+        async fn analyze(
+            &self,
+            prices: &[PriceData],
+        ) -> ArbitrageResult<Vec<ArbitrageOpportunity>> {
+            Ok(prices
+                .iter()
+                .map(|p| ArbitrageOpportunity {
+                    id: uuid::Uuid::new_v4(),
+                    pair: p.pair.clone(),
+                    buy_dex: DexType::Raydium,
+                    sell_dex: DexType::Orca,
+                    buy_price: p.ask,
+                    sell_price: p.bid,
+                    gross_profit_pct: Decimal::TWO,
+                    net_profit_pct: Decimal::TWO,
+                    estimated_profit_usd: None,
+                    recommended_size: None,
+                    detected_at: chrono::Utc::now(),
+                    expired_at: None,
+                    path: None,
+                })
+                .collect())
+        }
+
+        async fn update_state(&self, _price: &PriceData) -> ArbitrageResult<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_includes_strategy_opportunities() {
+        let tick = |bid: i64| {
+            PriceData::new(
+                DexType::Raydium,
+                TokenPair::new("JUP", "USDC"),
+                Decimal::new(bid, 2),
+                Decimal::new(bid + 1, 2),
+            )
+        };
+        let snapshots = vec![vec![tick(100)], vec![tick(101)]];
+        let strategies: Vec<Box<dyn Strategy>> = vec![Box::new(AlwaysSignal)];
+
+        let mut backtester = Backtester::new(Decimal::new(1, 1), Decimal::new(500, 0));
+        let summary = replay(&mut backtester, snapshots, &strategies).await;
+
+        // A single DEX gives the detector nothing; the signal stays open, so it counts once
+        assert_eq!(summary.snapshots, 2);
+        assert_eq!(summary.total_opportunities, 1);
+        assert_eq!(
+            summary.by_pair["JUP/USDC"].gross_profit_usd,
+            Decimal::new(10, 0)
+        );
+        assert!(summary.net_profit_usd < summary.gross_profit_usd);
+    }
+}
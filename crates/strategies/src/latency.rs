@@ -1,14 +1,58 @@
+//! Latency arbitrage: trading a DEX whose quote hasn't caught up yet
+//!
+//! When the market moves, DEXs reprice at different speeds (an AMM waits for
+//! someone to arb it, an oracle-priced pool for its next update). For each
+//! pair the strategy keeps the last quote seen from every DEX, with the time
+//! it was quoted and how fast its mid price was moving. The DEX that updated
+//! most recently while moving is the leader; any other DEX whose quote is
+//! older than the leader's (but no older than the lag window) and whose price
+//! diverges from it by more than the threshold is lagging, and is traded
+//! towards the leader: buy the lagging side when the leader moved up, sell it
+//! when the leader moved down.
+//!
+//! Assumptions:
+//! - `PriceData::timestamp` is when the quote was last updated at the source,
+//!   so a DEX that hasn't repriced keeps an older timestamp. Providers that
+//!   stamp every fetch with the current time never look lagged.
+//! - The leader's move is real and the lagging DEX will follow it, not the
+//!   other way round. Only moves in the direction of the gap count, so a gap
+//!   opened by the lagging DEX itself moving is not traded.
+//! - A quote older than the lag window is treated as dead rather than lagging;
+//!   it is left to the staleness checks elsewhere.
+
 use crate::Strategy;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 use solana_arb_core::{
-    types::{ArbitrageOpportunity, PriceData},
+    types::{ArbitrageOpportunity, DexType, PriceData, TokenPair},
     ArbitrageResult,
 };
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 use tokio::sync::RwLock;
 
+/// Default minimum gap between the leading and lagging mid prices, in percent
+const DEFAULT_MIN_DIVERGENCE_PCT: Decimal = Decimal::from_parts(2, 0, 0, false, 1);
+
+/// Default maximum age of the lagging quote relative to the leader's
+const DEFAULT_MAX_LAG: Duration = Duration::from_secs(5);
+
+/// The last quote seen from one DEX for one pair
+#[derive(Debug, Clone)]
+struct Observation {
+    bid: Decimal,
+    ask: Decimal,
+    mid: Decimal,
+    at: DateTime<Utc>,
+    /// Change in mid price since the previous quote, in percent per second
+    velocity_pct_per_sec: Option<Decimal>,
+}
+
 pub struct LatencyArbitrage {
-    // Track last update time to detect stale prices vs fresh updates
-    last_update: RwLock<std::collections::HashMap<String, i64>>,
+    observations: RwLock<HashMap<(DexType, TokenPair), Observation>>,
+    min_divergence_pct: Decimal,
+    max_lag: Duration,
 }
 
 impl Default for LatencyArbitrage {
@@ -18,11 +62,101 @@ impl Default for LatencyArbitrage {
 }
 
 impl LatencyArbitrage {
+    /// 0.2% divergence within a 5 second lag window
     pub fn new() -> Self {
+        Self::with_thresholds(DEFAULT_MIN_DIVERGENCE_PCT, DEFAULT_MAX_LAG)
+    }
+
+    /// Trade a lagging DEX once its mid price is at least `min_divergence_pct`
+    /// away from the leader's, provided its quote is at most `max_lag` older
+    pub fn with_thresholds(min_divergence_pct: Decimal, max_lag: Duration) -> Self {
         Self {
-            last_update: RwLock::new(std::collections::HashMap::new()),
+            observations: RwLock::new(HashMap::new()),
+            min_divergence_pct,
+            max_lag,
+        }
+    }
+
+    /// Opportunity to trade `lagger` towards `leader`, if the gap is wide
+    /// enough and the leader is moving into it
+    fn signal(
+        &self,
+        pair: &TokenPair,
+        (leader_dex, leader): (DexType, &Observation),
+        (lagger_dex, lagger): (DexType, &Observation),
+    ) -> Option<ArbitrageOpportunity> {
+        let lag = (leader.at - lagger.at).to_std().ok()?;
+        if lag.is_zero() || lag > self.max_lag || lagger.mid.is_zero() {
+            return None;
         }
+
+        let divergence_pct = (leader.mid - lagger.mid)
+            .checked_div(lagger.mid)?
+            .checked_mul(Decimal::ONE_HUNDRED)?;
+        let velocity = leader.velocity_pct_per_sec?;
+        if divergence_pct.abs() < self.min_divergence_pct
+            || velocity.is_zero()
+            || velocity.is_sign_positive() != divergence_pct.is_sign_positive()
+        {
+            return None;
+        }
+
+        // The lagging side is expected to move towards the leader
+        let (buy_dex, buy_price, sell_dex, sell_price) = if divergence_pct.is_sign_positive() {
+            (lagger_dex, lagger.ask, leader_dex, leader.bid)
+        } else {
+            (leader_dex, leader.ask, lagger_dex, lagger.bid)
+        };
+        if buy_price.is_zero() {
+            return None;
+        }
+        let gross_profit_pct = (sell_price - buy_price)
+            .checked_div(buy_price)?
+            .checked_mul(Decimal::ONE_HUNDRED)?;
+        let net_profit_pct =
+            gross_profit_pct - buy_dex.fee_percentage() - sell_dex.fee_percentage();
+        if net_profit_pct <= Decimal::ZERO {
+            return None;
+        }
+
+        tracing::debug!(
+            pair = %pair,
+            leader = %leader_dex,
+            lagger = %lagger_dex,
+            lag_ms = lag.as_millis() as u64,
+            divergence_pct = %divergence_pct.round_dp(4),
+            velocity_pct_per_sec = %velocity.round_dp(4),
+            "Latency arb signal"
+        );
+
+        Some(ArbitrageOpportunity {
+            id: uuid::Uuid::new_v4(),
+            pair: pair.clone(),
+            buy_dex,
+            sell_dex,
+            buy_price,
+            sell_price,
+            gross_profit_pct,
+            net_profit_pct,
+            estimated_profit_usd: None,
+            recommended_size: None,
+            detected_at: Utc::now(),
+            expired_at: None,
+            path: None,
+        })
+    }
+}
+
+/// Percent change per second between two quotes, if time has passed
+fn velocity(prev: &Observation, mid: Decimal, at: DateTime<Utc>) -> Option<Decimal> {
+    let elapsed_ms = (at - prev.at).num_milliseconds();
+    if elapsed_ms <= 0 {
+        return None;
     }
+    (mid - prev.mid)
+        .checked_div(prev.mid)?
+        .checked_mul(Decimal::from(100_000))?
+        .checked_div(Decimal::from(elapsed_ms))
 }
 
 #[async_trait]
@@ -32,19 +166,141 @@ impl Strategy for LatencyArbitrage {
     }
 
     async fn update_state(&self, price: &PriceData) -> ArbitrageResult<()> {
-        let mut last = self.last_update.write().await;
-        last.insert(price.pair.symbol(), price.timestamp.timestamp_millis());
+        let mut observations = self.observations.write().await;
+        let key = (price.dex, price.pair.clone());
+
+        let velocity_pct_per_sec = match observations.get(&key) {
+            // Same quote seen again (or out of order): nothing new to learn
+            Some(prev) if price.timestamp <= prev.at => return Ok(()),
+            Some(prev) => velocity(prev, price.mid_price, price.timestamp),
+            None => None,
+        };
+        observations.insert(
+            key,
+            Observation {
+                bid: price.bid,
+                ask: price.ask,
+                mid: price.mid_price,
+                at: price.timestamp,
+                velocity_pct_per_sec,
+            },
+        );
         Ok(())
     }
 
-    async fn analyze(&self, _prices: &[PriceData]) -> ArbitrageResult<Vec<ArbitrageOpportunity>> {
-        // Latency arb logic:
-        // Compare timestamps of same pair across different DEXs.
-        // If one DEX is significantly lagging (e.g., Oracle update pending), trade against it.
+    async fn has_sufficient_history(&self, pair: &TokenPair) -> bool {
+        // Needs quotes from two DEXs to compare
+        self.observations
+            .read()
+            .await
+            .keys()
+            .filter(|(_, p)| p == pair)
+            .nth(1)
+            .is_some()
+    }
+
+    async fn analyze(&self, prices: &[PriceData]) -> ArbitrageResult<Vec<ArbitrageOpportunity>> {
+        let observations = self.observations.read().await;
+        let pairs: HashSet<&TokenPair> = prices.iter().map(|p| &p.pair).collect();
+        let mut opportunities = Vec::new();
+
+        for pair in pairs {
+            let quotes: Vec<(DexType, &Observation)> = observations
+                .iter()
+                .filter(|((_, p), _)| p == pair)
+                .map(|((dex, _), obs)| (*dex, obs))
+                .collect();
+
+            // The freshest quote that is still moving leads
+            let Some(leader) = quotes
+                .iter()
+                .filter(|(_, obs)| obs.velocity_pct_per_sec.is_some_and(|v| !v.is_zero()))
+                .max_by_key(|(_, obs)| obs.at)
+                .copied()
+            else {
+                continue;
+            };
+
+            opportunities.extend(
+                quotes
+                    .iter()
+                    .filter(|(dex, _)| *dex != leader.0)
+                    .filter_map(|lagger| self.signal(pair, leader, *lagger)),
+            );
+        }
+
+        Ok(opportunities)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+
+    fn quote(dex: DexType, mid: i64, at: DateTime<Utc>) -> PriceData {
+        // 0.1 spread around `mid` hundredths
+        let mut price = PriceData::new(
+            dex,
+            TokenPair::new("SOL", "USDC"),
+            Decimal::new(mid * 10 - 5, 3),
+            Decimal::new(mid * 10 + 5, 3),
+        );
+        price.timestamp = at;
+        price
+    }
+
+    #[tokio::test]
+    async fn test_lagging_dex_is_traded_towards_leader() {
+        let strategy = LatencyArbitrage::new();
+        let pair = TokenPair::new("SOL", "USDC");
+        let t0 = Utc::now() - ChronoDuration::seconds(10);
+        let at = |ms: i64| t0 + ChronoDuration::milliseconds(ms);
+
+        // Orca climbs 100 -> 101 while Raydium's last quote stays at 100
+        let series = [
+            quote(DexType::Orca, 10_000, at(0)),
+            quote(DexType::Raydium, 10_000, at(0)),
+            quote(DexType::Orca, 10_050, at(1_000)),
+            quote(DexType::Raydium, 10_000, at(1_000)),
+            quote(DexType::Orca, 10_100, at(2_000)),
+        ];
+        for price in &series {
+            strategy.update_state(price).await.unwrap();
+        }
+        assert!(strategy.has_sufficient_history(&pair).await);
+
+        let opps = strategy.analyze(&series[3..]).await.unwrap();
+        assert_eq!(opps.len(), 1);
+        let opp = &opps[0];
+        assert_eq!(opp.buy_dex, DexType::Raydium);
+        assert_eq!(opp.sell_dex, DexType::Orca);
+        assert_eq!(opp.buy_price, Decimal::new(100_005, 3));
+        assert_eq!(opp.sell_price, Decimal::new(100_995, 3));
+        assert!(opp.net_profit_pct > Decimal::ZERO);
 
-        // This requires `prices` slice to contain multiple DEX prices for the same pair.
-        // Simplified implementation stub.
+        // Once Raydium catches up there is nothing left to trade
+        let caught_up = quote(DexType::Raydium, 10_100, at(2_500));
+        strategy.update_state(&caught_up).await.unwrap();
+        assert!(strategy.analyze(&[caught_up]).await.unwrap().is_empty());
+    }
 
-        Ok(Vec::new())
+    #[tokio::test]
+    async fn test_no_signal_outside_lag_window() {
+        let strategy =
+            LatencyArbitrage::with_thresholds(Decimal::new(2, 1), Duration::from_secs(1));
+        let t0 = Utc::now() - ChronoDuration::seconds(10);
+        let at = |ms: i64| t0 + ChronoDuration::milliseconds(ms);
+
+        // Raydium's quote is 3s behind: dead, not lagging
+        let series = [
+            quote(DexType::Raydium, 10_000, at(0)),
+            quote(DexType::Orca, 10_000, at(2_000)),
+            quote(DexType::Orca, 10_100, at(3_000)),
+        ];
+        for price in &series {
+            strategy.update_state(price).await.unwrap();
+        }
+        assert!(strategy.analyze(&series).await.unwrap().is_empty());
     }
 }
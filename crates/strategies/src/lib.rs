@@ -1,9 +1,10 @@
 use async_trait::async_trait;
 use solana_arb_core::{
-    types::{ArbitrageOpportunity, PriceData},
+    types::{ArbitrageOpportunity, PriceData, TokenPair, TradeResult},
     ArbitrageResult,
 };
 
+pub mod backtest;
 pub mod latency;
 pub mod statistical;
 pub mod plugin;
@@ -19,8 +20,25 @@ pub trait Strategy: Send + Sync {
     fn name(&self) -> &'static str;
 
     /// Analyze price data and generate arbitrage opportunities
+    ///
+    /// Contract: when there is not enough history for a pair (e.g. during warmup),
+    /// implementations skip that pair and return an empty vec rather than an error,
+    /// and must never panic or divide by zero on a partially filled window.
     async fn analyze(&self, prices: &[PriceData]) -> ArbitrageResult<Vec<ArbitrageOpportunity>>;
 
     /// Update internal state with new market data (e.g., for moving averages)
     async fn update_state(&self, price: &PriceData) -> ArbitrageResult<()>;
+
+    /// Whether enough history has accumulated for `pair` to produce signals.
+    /// Callers can use this to skip `analyze` during warmup. Stateless strategies
+    /// are always ready.
+    async fn has_sufficient_history(&self, _pair: &TokenPair) -> bool {
+        true
+    }
+
+    /// Called after the bot executes an opportunity, for successful and failed trades
+    /// alike. The bot notifies every strategy, so `opp_id` may belong to another
+    /// strategy or the detector; adaptive strategies should ignore ids they did not
+    /// produce. The default is a no-op.
+    async fn on_trade_result(&self, _opp_id: uuid::Uuid, _result: &TradeResult) {}
 }
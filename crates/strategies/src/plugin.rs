@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 use solana_arb_core::{
-    types::{ArbitrageOpportunity, PriceData},
+    types::{ArbitrageOpportunity, PriceData, TradeResult},
     ArbitrageResult,
 };
 use crate::Strategy;
@@ -89,6 +89,16 @@ impl StrategyRegistry {
         }
     }
     
+    /// Forward an execution result to all enabled strategies
+    pub async fn notify_trade_result(&self, opp_id: uuid::Uuid, result: &TradeResult) {
+        let plugins = self.plugins.read().await;
+        for plugin in plugins.iter() {
+            if plugin.descriptor().enabled {
+                plugin.on_trade_result(opp_id, result).await;
+            }
+        }
+    }
+
     /// Get count of registered strategies
     pub async fn count(&self) -> usize {
         self.plugins.read().await.len()
@@ -106,6 +116,56 @@ mod tests {
         should_fail: bool,
     }
 
+    /// Records every trade result it is notified about
+    #[derive(Default)]
+    struct LearningStrategy {
+        results: Arc<std::sync::Mutex<Vec<(uuid::Uuid, bool)>>>,
+    }
+
+    #[async_trait]
+    impl Strategy for LearningStrategy {
+        fn name(&self) -> &'static str {
+            "LearningStrategy"
+        }
+
+        async fn analyze(&self, _prices: &[PriceData]) -> ArbitrageResult<Vec<ArbitrageOpportunity>> {
+            Ok(vec![])
+        }
+
+        async fn update_state(&self, _price: &PriceData) -> ArbitrageResult<()> {
+            Ok(())
+        }
+
+        async fn on_trade_result(&self, opp_id: uuid::Uuid, result: &TradeResult) {
+            self.results.lock().unwrap().push((opp_id, result.success));
+        }
+    }
+
+    #[async_trait]
+    impl StrategyPlugin for LearningStrategy {
+        fn descriptor(&self) -> StrategyDescriptor {
+            StrategyDescriptor {
+                name: "Learning".to_string(),
+                version: "1.0.0".to_string(),
+                description: "Records trade results".to_string(),
+                enabled: true,
+            }
+        }
+    }
+
+    fn trade_result(opp_id: uuid::Uuid, success: bool) -> TradeResult {
+        TradeResult {
+            opportunity_id: opp_id,
+            signature: None,
+            success,
+            actual_profit: Some(Decimal::ZERO),
+            executed_at: chrono::Utc::now(),
+            error: (!success).then(|| "failed".to_string()),
+            latency: None,
+            realized_slippage: None,
+        }
+    }
+
     #[async_trait]
     impl Strategy for MockStrategy {
         fn name(&self) -> &'static str {
@@ -134,6 +194,7 @@ mod tests {
                  recommended_size: Some(Decimal::new(1000, 0)),
                  detected_at: chrono::Utc::now(),
                  expired_at: None,
+                 path: None,
              };
              
              Ok(vec![opp])
@@ -184,6 +245,25 @@ mod tests {
         assert_eq!(opps.len(), 1);
     }
     
+    #[tokio::test]
+    async fn test_trade_results_reach_strategies() {
+        let registry = StrategyRegistry::new();
+        let strategy = LearningStrategy::default();
+        let results = strategy.results.clone();
+        registry.register(Box::new(strategy)).await.unwrap();
+        // Strategies without an override keep the no-op default
+        registry.register(Box::new(MockStrategy {
+            name: "S1".to_string(),
+            should_fail: false,
+        })).await.unwrap();
+
+        let (won, lost) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+        registry.notify_trade_result(won, &trade_result(won, true)).await;
+        registry.notify_trade_result(lost, &trade_result(lost, false)).await;
+
+        assert_eq!(*results.lock().unwrap(), vec![(won, true), (lost, false)]);
+    }
+
     #[tokio::test]
     async fn test_failed_strategy_handling() {
         let registry = StrategyRegistry::new();
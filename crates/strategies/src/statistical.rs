@@ -3,16 +3,17 @@ use async_trait::async_trait;
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use solana_arb_core::{
-    types::{ArbitrageOpportunity, DexType, PriceData},
+    math::decimal_sqrt,
+    types::{ArbitrageOpportunity, PriceData, TokenPair},
     ArbitrageResult,
 };
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use tokio::sync::RwLock;
 
 pub struct StatisticalArbitrage {
     // Sliding window of price ratios for pairs
     // Key: Pair symbol, Value: Queue of (price_ratio, timestamp)
-    history: RwLock<std::collections::HashMap<String, VecDeque<(Decimal, i64)>>>,
+    history: RwLock<HashMap<String, VecDeque<(Decimal, i64)>>>,
     window_size: usize,
     z_score_threshold: Decimal,
 }
@@ -20,7 +21,7 @@ pub struct StatisticalArbitrage {
 impl StatisticalArbitrage {
     pub fn new(window_size: usize, z_score_threshold: Decimal) -> Self {
         Self {
-            history: RwLock::new(std::collections::HashMap::new()),
+            history: RwLock::new(HashMap::new()),
             window_size,
             z_score_threshold,
         }
@@ -31,7 +32,8 @@ impl StatisticalArbitrage {
         value: Decimal,
         history: &VecDeque<(Decimal, i64)>,
     ) -> Option<Decimal> {
-        if history.len() < self.window_size {
+        // An empty history (e.g. window_size == 0) has no mean to compare against
+        if history.is_empty() || history.len() < self.window_size {
             return None;
         }
 
@@ -46,11 +48,7 @@ impl StatisticalArbitrage {
         }
 
         let variance = variance_sum / count;
-        // Decimal sqrt is not standard, convert to f64
-        let std_dev = variance
-            .to_f64()
-            .map(|f| f.sqrt())
-            .and_then(Decimal::from_f64_retain)?;
+        let std_dev = decimal_sqrt(variance);
 
         if std_dev.is_zero() {
             return Some(Decimal::ZERO);
@@ -80,91 +78,122 @@ impl Strategy for StatisticalArbitrage {
         Ok(())
     }
 
+    async fn has_sufficient_history(&self, pair: &TokenPair) -> bool {
+        self.history
+            .read()
+            .await
+            .get(&pair.symbol())
+            .is_some_and(|queue| !queue.is_empty() && queue.len() >= self.window_size)
+    }
+
     async fn analyze(&self, prices: &[PriceData]) -> ArbitrageResult<Vec<ArbitrageOpportunity>> {
         let history = self.history.read().await;
         let mut opportunities = Vec::new();
 
+        let mut by_pair: HashMap<&TokenPair, Vec<&PriceData>> = HashMap::new();
         for price in prices {
-            if let Some(queue) = history.get(&price.pair.symbol()) {
-                if let Some(z_score) = self.calculate_z_score(price.mid_price, queue) {
-                    // Mean reversion logic:
-                    // If Z-score > threshold, price is historically high -> SELL or SHORT
-                    // If Z-score < -threshold, price is historically low -> BUY or LONG
-
-                    if z_score.abs() > self.z_score_threshold {
-                        tracing::info!(
-                            "📈 StatArb signal: {} Z-score {} (Threshold {})",
-                            price.pair.symbol(),
-                            z_score,
-                            self.z_score_threshold
-                        );
-
-                        // Calculate the historical mean for profit estimation
-                        let sum: Decimal = queue.iter().map(|(v, _)| *v).sum();
-                        let mean = sum / Decimal::from(queue.len());
-
-                        // Determine trade direction:
-                        //  z > 0 → price above mean → expect reversion down → sell on this DEX, buy on another
-                        //  z < 0 → price below mean → expect reversion up → buy on this DEX, sell on another
-                        let (buy_dex, sell_dex, buy_price, sell_price) = if z_score > Decimal::ZERO {
-                            // Price is high: sell on current DEX at ask price, expect to buy at mean
-                            (DexType::Jupiter, price.dex, mean, price.ask)
-                        } else {
-                            // Price is low: buy on current DEX at ask price, expect to sell at mean
-                            (price.dex, DexType::Jupiter, price.ask, mean)
-                        };
-
-                        // Gross profit as percentage of buy price
-                        let gross_profit_pct = if buy_price.is_zero() {
-                            Decimal::ZERO
-                        } else {
-                            ((sell_price - buy_price) / buy_price) * Decimal::from(100)
-                        };
-
-                        // Net profit after estimated fees
-                        let total_fees = buy_dex.fee_percentage() + sell_dex.fee_percentage();
-                        let net_profit_pct = gross_profit_pct - total_fees;
-
-                        // Only create opportunity if net profit is positive
-                        if net_profit_pct > Decimal::ZERO {
-                            // Confidence-based position sizing: higher |z-score| → more confidence
-                            let confidence = z_score.abs().to_f64().unwrap_or(0.0);
-                            let base_size = Decimal::from(100); // $100 base
-                            let recommended_size = base_size * Decimal::from_f64_retain(confidence.min(5.0))
-                                .unwrap_or(Decimal::ONE);
-
-                            let estimated_profit = recommended_size * net_profit_pct / Decimal::from(100);
-
-                            let opp = ArbitrageOpportunity {
-                                id: uuid::Uuid::new_v4(),
-                                pair: price.pair.clone(),
-                                buy_dex,
-                                sell_dex,
-                                buy_price,
-                                sell_price,
-                                gross_profit_pct,
-                                net_profit_pct,
-                                estimated_profit_usd: Some(estimated_profit),
-                                recommended_size: Some(recommended_size),
-                                detected_at: chrono::Utc::now(),
-                                expired_at: None,
-                            };
-
-                            tracing::info!(
-                                "💡 StatArb opportunity: {} buy@{} on {:?}, sell@{} on {:?} (net {:.4}%)",
-                                price.pair.symbol(),
-                                buy_price,
-                                buy_dex,
-                                sell_price,
-                                sell_dex,
-                                net_profit_pct
-                            );
-
-                            opportunities.push(opp);
-                        }
-                    }
-                }
+            by_pair.entry(&price.pair).or_default().push(price);
+        }
+
+        for (pair, quotes) in by_pair {
+            // The window holds every DEX's quotes, so its mean is the cross-DEX mean
+            let Some(queue) = history.get(&pair.symbol()) else {
+                continue;
+            };
+            let scored: Vec<(&PriceData, Decimal)> = quotes
+                .into_iter()
+                .filter_map(|p| Some((p, self.calculate_z_score(p.mid_price, queue)?)))
+                .collect();
+
+            // Trade the DEX that deviates most against the one closest to the mean
+            let Some(&(extreme, z_score)) = scored.iter().max_by_key(|(_, z)| z.abs()) else {
+                continue;
+            };
+            if z_score.abs() <= self.z_score_threshold {
+                continue;
+            }
+            tracing::info!(
+                "📈 StatArb signal: {} on {} Z-score {} (Threshold {})",
+                pair,
+                extreme.dex,
+                z_score,
+                self.z_score_threshold
+            );
+            let Some(&(anchor, _)) = scored
+                .iter()
+                .filter(|(p, _)| p.dex != extreme.dex)
+                .min_by_key(|(_, z)| z.abs())
+            else {
+                tracing::debug!("StatArb: no second DEX quoting {} to trade against", pair);
+                continue;
+            };
+
+            // Mean reversion:
+            //  z > 0 → the extreme DEX is rich → sell there, buy on the anchor DEX
+            //  z < 0 → the extreme DEX is cheap → buy there, sell on the anchor DEX
+            let (buy, sell) = if z_score > Decimal::ZERO {
+                (anchor, extreme)
+            } else {
+                (extreme, anchor)
+            };
+            let (buy_price, sell_price) = (buy.ask, sell.bid);
+            if buy_price.is_zero() || sell_price <= buy_price {
+                continue;
             }
+
+            // Expected profit is the extreme DEX reverting to the mean
+            let sum: Decimal = queue.iter().map(|(v, _)| *v).sum();
+            let mean = sum / Decimal::from(queue.len());
+            let Some(gross_profit_pct) = (extreme.mid_price - mean)
+                .abs()
+                .checked_div(mean)
+                .and_then(|r| r.checked_mul(Decimal::ONE_HUNDRED))
+            else {
+                continue;
+            };
+
+            // Net profit after estimated fees
+            let total_fees = buy.dex.fee_percentage() + sell.dex.fee_percentage();
+            let net_profit_pct = gross_profit_pct - total_fees;
+            if net_profit_pct <= Decimal::ZERO {
+                continue;
+            }
+
+            // Confidence-based position sizing: higher |z-score| → more confidence
+            let confidence = z_score.abs().to_f64().unwrap_or(0.0);
+            let base_size = Decimal::from(100); // $100 base
+            let recommended_size =
+                base_size * Decimal::from_f64_retain(confidence.min(5.0)).unwrap_or(Decimal::ONE);
+
+            let mut opp = ArbitrageOpportunity {
+                id: uuid::Uuid::new_v4(),
+                pair: pair.clone(),
+                buy_dex: buy.dex,
+                sell_dex: sell.dex,
+                buy_price,
+                sell_price,
+                gross_profit_pct,
+                net_profit_pct,
+                estimated_profit_usd: None,
+                recommended_size: Some(recommended_size),
+                detected_at: chrono::Utc::now(),
+                expired_at: None,
+                path: None,
+            };
+            // Left unset rather than panicking if the estimate overflows
+            opp.estimated_profit_usd = opp.net_profit_usd(recommended_size).ok();
+
+            tracing::info!(
+                "💡 StatArb opportunity: {} buy@{} on {:?}, sell@{} on {:?} (net {:.4}%)",
+                pair,
+                buy_price,
+                buy.dex,
+                sell_price,
+                sell.dex,
+                net_profit_pct
+            );
+
+            opportunities.push(opp);
         }
 
         Ok(opportunities)
@@ -174,7 +203,7 @@ impl Strategy for StatisticalArbitrage {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use solana_arb_core::TokenPair;
+    use solana_arb_core::{types::DexType, TokenPair};
     use std::collections::VecDeque;
 
     fn build_history(values: &[f64]) -> VecDeque<(Decimal, i64)> {
@@ -228,22 +257,34 @@ mod tests {
             strat.update_state(&price).await.unwrap();
         }
 
-        // Now present a far-outlier price
+        // Now present a far-outlier price, with Orca still quoting near the mean
         let outlier = PriceData::new(
             DexType::Raydium,
             TokenPair::new("SOL", "USDC"),
             Decimal::from(120), // bid well above mean
             Decimal::from(121), // ask well above mean
         );
+        let anchor = PriceData::new(
+            DexType::Orca,
+            TokenPair::new("SOL", "USDC"),
+            Decimal::new(1000, 1),
+            Decimal::new(1001, 1),
+        );
 
-        let opps = strat.analyze(&[outlier]).await.unwrap();
-        assert!(
-            !opps.is_empty(),
+        let opps = strat.analyze(&[anchor, outlier]).await.unwrap();
+        assert_eq!(
+            opps.len(),
+            1,
             "Should create opportunity when z-score exceeds threshold"
         );
 
+        // Raydium spiked: sell it there, buy on Orca
         let opp = &opps[0];
         assert_eq!(opp.pair.base, "SOL");
+        assert_eq!(opp.sell_dex, DexType::Raydium);
+        assert_eq!(opp.buy_dex, DexType::Orca);
+        assert_eq!(opp.sell_price, Decimal::from(120));
+        assert_eq!(opp.buy_price, Decimal::new(1001, 1));
         assert!(opp.net_profit_pct > Decimal::ZERO);
         assert!(opp.estimated_profit_usd.is_some());
         assert!(opp.recommended_size.is_some());
@@ -303,11 +344,60 @@ mod tests {
             Decimal::from(81),
         );
 
-        let opps = strat.analyze(&[low]).await.unwrap();
+        let anchor = PriceData::new(
+            DexType::Raydium,
+            TokenPair::new("SOL", "USDC"),
+            Decimal::from(100),
+            Decimal::new(1001, 1),
+        );
+
+        let opps = strat.analyze(&[low.clone(), anchor]).await.unwrap();
         assert!(!opps.is_empty());
 
         let opp = &opps[0];
-        // When price is low: buy on current DEX, sell on Jupiter (target mean)
+        // When price is low: buy on the crashed DEX, sell where it is near the mean
         assert_eq!(opp.buy_dex, DexType::Orca);
+        assert_eq!(opp.sell_dex, DexType::Raydium);
+
+        // With no other DEX quoting the pair there is nothing to trade against
+        assert!(strat.analyze(&[low]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_analyze_empty_history_returns_empty() {
+        let strat = StatisticalArbitrage::new(5, Decimal::from(2));
+        let pair = TokenPair::new("SOL", "USDC");
+        let price = PriceData::new(DexType::Raydium, pair.clone(), Decimal::from(100), Decimal::from(101));
+
+        assert!(!strat.has_sufficient_history(&pair).await);
+        assert!(strat.analyze(std::slice::from_ref(&price)).await.unwrap().is_empty());
+        assert!(strat.analyze(&[]).await.unwrap().is_empty());
+
+        // Partially filled window: still warming up, still no error
+        for _ in 0..3 {
+            strat.update_state(&price).await.unwrap();
+        }
+        assert!(!strat.has_sufficient_history(&pair).await);
+        assert!(strat.analyze(std::slice::from_ref(&price)).await.unwrap().is_empty());
+
+        for _ in 0..2 {
+            strat.update_state(&price).await.unwrap();
+        }
+        assert!(strat.has_sufficient_history(&pair).await);
+    }
+
+    #[tokio::test]
+    async fn test_zero_window_does_not_divide_by_zero() {
+        let strat = StatisticalArbitrage::new(0, Decimal::from(2));
+        let empty = VecDeque::new();
+        assert!(strat.calculate_z_score(Decimal::from(100), &empty).is_none());
+
+        let price = PriceData::new(
+            DexType::Raydium,
+            TokenPair::new("SOL", "USDC"),
+            Decimal::from(100),
+            Decimal::from(101),
+        );
+        assert!(strat.analyze(&[price]).await.unwrap().is_empty());
     }
 }
@@ -0,0 +1,22 @@
+-- Every opportunity detected by the collector, with full-precision prices.
+-- Decimals are stored as NUMERIC so analytics see the exact values the engine used.
+CREATE TABLE IF NOT EXISTS arbitrage_opportunities (
+    id UUID NOT NULL,
+    base_token VARCHAR(50) NOT NULL,
+    quote_token VARCHAR(50) NOT NULL,
+    buy_dex VARCHAR(20) NOT NULL,
+    sell_dex VARCHAR(20) NOT NULL,
+    buy_price NUMERIC NOT NULL,
+    sell_price NUMERIC NOT NULL,
+    gross_profit_pct NUMERIC NOT NULL,
+    net_profit_pct NUMERIC NOT NULL,
+    estimated_profit_usd NUMERIC,
+    recommended_size NUMERIC,
+    detected_at TIMESTAMPTZ NOT NULL,
+    expired_at TIMESTAMPTZ,
+    PRIMARY KEY (id, detected_at)
+);
+
+SELECT create_hypertable('arbitrage_opportunities', 'detected_at', if_not_exists => TRUE);
+CREATE INDEX IF NOT EXISTS idx_arb_opps_pair_time
+    ON arbitrage_opportunities (base_token, quote_token, detected_at DESC);
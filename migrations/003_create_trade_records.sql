@@ -0,0 +1,26 @@
+-- Trade history as written by the bot (one row per `TradeRecord`).
+-- Plain Postgres so it also works without TimescaleDB; applied on startup by
+-- `PgHistoryRecorder` as well.
+CREATE TABLE IF NOT EXISTS trade_records (
+    id BIGSERIAL PRIMARY KEY,
+    recorded_at TIMESTAMPTZ NOT NULL,
+    session_id VARCHAR(64) NOT NULL,
+    trade_type VARCHAR(20) NOT NULL,  -- SIMULATION or REAL
+    pair VARCHAR(50) NOT NULL,
+    buy_dex VARCHAR(20) NOT NULL,
+    sell_dex VARCHAR(20) NOT NULL,
+    size_usd NUMERIC NOT NULL,
+    profit_usd NUMERIC NOT NULL,
+    profit_pct NUMERIC NOT NULL,
+    tx_signature VARCHAR(100),
+    success BOOLEAN NOT NULL,
+    error TEXT,
+    detected_at TIMESTAMPTZ,
+    quote_ms BIGINT,
+    build_ms BIGINT,
+    submit_ms BIGINT,
+    confirm_ms BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS idx_trade_records_time ON trade_records (recorded_at DESC);
+CREATE INDEX IF NOT EXISTS idx_trade_records_session ON trade_records (session_id, recorded_at);
@@ -0,0 +1,5 @@
+-- Profit in the pair's quote token alongside its USD value, which is left
+-- NULL for pairs not quoted in a USD stablecoin when no reference price was
+-- available. Applied on startup by `PgHistoryRecorder` after 003.
+ALTER TABLE trade_records ADD COLUMN IF NOT EXISTS profit_quote NUMERIC;
+ALTER TABLE trade_records ALTER COLUMN profit_usd DROP NOT NULL;